@@ -0,0 +1,13 @@
+-- Migration 003: Stop running session on daily reset
+-- Lets a daily reset pause the running timer and record the in-progress session as abandoned
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN stop_session_on_daily_reset BOOLEAN NOT NULL DEFAULT FALSE;
+
+-- Unix timestamp at which an unfinished session was abandoned (NULL if completed or still active)
+ALTER TABLE timer_sessions
+ADD COLUMN abandoned_at INTEGER;
+
+COMMIT;
@@ -0,0 +1,10 @@
+-- Migration 004: Record skipped sessions
+-- Lets stats tell skipped sessions apart from completed and abandoned ones
+
+BEGIN;
+
+-- Unix timestamp at which a session was skipped before finishing (NULL otherwise)
+ALTER TABLE timer_sessions
+ADD COLUMN skipped_at INTEGER;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 005: Track work sessions since the last long break
+-- Backs the optional cap on consecutive work sessions before a forced long break
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN work_sessions_since_long_break INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
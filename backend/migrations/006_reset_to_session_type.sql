@@ -0,0 +1,9 @@
+-- Migration 006: Per-user session type adopted after a reset
+-- One of work, short_break or long_break; NULL leaves the timer alone at the reset
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN reset_to_session_type TEXT;
+
+COMMIT;
@@ -0,0 +1,12 @@
+-- Migration 007: Optionally start a work session on the first connection of the day
+-- last_auto_start_utc records when the automation last fired so it runs at most once per local day
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN auto_start_on_first_connect BOOLEAN NOT NULL DEFAULT FALSE;
+
+ALTER TABLE user_configurations
+ADD COLUMN last_auto_start_utc INTEGER;
+
+COMMIT;
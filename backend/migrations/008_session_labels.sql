@@ -0,0 +1,12 @@
+-- Migration 008: Optional label describing what a session was spent on
+-- Carried on the live timer state and stored on each recorded session
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN label TEXT;
+
+ALTER TABLE timer_sessions
+ADD COLUMN label TEXT;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 009: User-defined display names for session types
+-- Stored as a JSON object keyed by session type id
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN session_type_labels TEXT;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 010: Minutes past the hour for the 'hour' daily reset type
+-- NULL keeps resetting on the hour
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN daily_reset_time_minute INTEGER;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 011: Queued session plan followed instead of the default cycle
+-- Stored as JSON: {"steps": [{"session_type", "duration"}], "current"}
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN session_plan TEXT;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 012: Remember the work session length a break interrupted
+-- Restored when work resumes for users with resume_work_duration on (migration 022)
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN pre_break_work_duration INTEGER;
+
+COMMIT;
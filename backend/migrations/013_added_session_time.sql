@@ -0,0 +1,12 @@
+-- Migration 013: Track time added to a session beyond its planned duration
+-- Carried on the live timer state and stored on each recorded session
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN added_seconds INTEGER NOT NULL DEFAULT 0;
+
+ALTER TABLE timer_sessions
+ADD COLUMN added_seconds INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 014: Record which user each session belongs to
+-- Sessions recorded before this migration have no user and are left out of history
+
+BEGIN;
+
+ALTER TABLE timer_sessions
+ADD COLUMN user_id TEXT;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 015: Store each timer's long break frequency
+-- Work sessions between long breaks, set through the settings API
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN long_break_frequency INTEGER NOT NULL DEFAULT 4;
+
+COMMIT;
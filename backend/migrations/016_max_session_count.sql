@@ -0,0 +1,8 @@
+-- Migration 016: Per-user ceiling on the daily session count
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN max_session_count INTEGER NOT NULL DEFAULT 1000;
+
+COMMIT;
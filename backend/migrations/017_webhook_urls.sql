@@ -0,0 +1,13 @@
+-- Migration 017: Allow several webhook URLs per user
+-- Stored as a JSON array; an existing single webhook_url becomes its only entry
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN webhook_urls TEXT NOT NULL DEFAULT '[]';
+
+UPDATE user_configurations
+SET webhook_urls = json_array(webhook_url)
+WHERE webhook_url IS NOT NULL AND webhook_url != '';
+
+COMMIT;
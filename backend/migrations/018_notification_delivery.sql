@@ -0,0 +1,15 @@
+-- Migration 018: Track webhook delivery outcomes
+-- Each completion notification records its user, destination and the attempts it took
+
+BEGIN;
+
+ALTER TABLE notification_events
+ADD COLUMN user_id TEXT;
+
+ALTER TABLE notification_events
+ADD COLUMN webhook_url TEXT;
+
+ALTER TABLE notification_events
+ADD COLUMN attempts INTEGER NOT NULL DEFAULT 0;
+
+COMMIT;
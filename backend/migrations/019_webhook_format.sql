@@ -0,0 +1,12 @@
+-- Migration 019: Let users shape their webhook bodies
+-- webhook_format is raw, slack, discord or custom; custom uses webhook_template
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN webhook_format TEXT NOT NULL DEFAULT 'raw';
+
+ALTER TABLE user_configurations
+ADD COLUMN webhook_template TEXT;
+
+COMMIT;
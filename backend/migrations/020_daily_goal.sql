@@ -0,0 +1,8 @@
+-- Migration 020: Optional per-user daily goal of completed work sessions
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN daily_goal INTEGER;
+
+COMMIT;
@@ -0,0 +1,9 @@
+-- Migration 021: Store when a running timer's session ends
+-- Lets a restarted server resume the countdown where it left off
+
+BEGIN;
+
+ALTER TABLE timer_state
+ADD COLUMN session_ends_at INTEGER;
+
+COMMIT;
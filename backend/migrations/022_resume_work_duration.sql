@@ -0,0 +1,9 @@
+-- Migration 022: Per-user choice to resume the pre-break work duration
+-- When set, work after a break gets the length of the work session before it
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN resume_work_duration BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMIT;
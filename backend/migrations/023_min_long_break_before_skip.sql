@@ -0,0 +1,9 @@
+-- Migration 023: Per-user minimum seconds of a long break before it can be skipped
+-- NULL (the default) allows skipping at any time
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN min_long_break_before_skip INTEGER;
+
+COMMIT;
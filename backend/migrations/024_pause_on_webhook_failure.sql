@@ -0,0 +1,9 @@
+-- Migration 024: Per-user choice to pause the timer when a completion webhook fails
+-- Replaces the server-wide ROMA_TIMER_PAUSE_ON_WEBHOOK_FAILURE setting
+
+BEGIN;
+
+ALTER TABLE user_configurations
+ADD COLUMN pause_on_webhook_failure BOOLEAN NOT NULL DEFAULT FALSE;
+
+COMMIT;
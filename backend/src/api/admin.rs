@@ -0,0 +1,522 @@
+//! Admin API Endpoints
+//!
+//! Database backups, server statistics and the dev-only day simulator.
+
+use std::env;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+
+use crate::api::json::ApiJson;
+use crate::database::DatabaseManager;
+use crate::models::session_reset_event::SessionResetTriggerSource;
+use crate::models::timer_state::SharedState;
+use crate::models::user_configuration::WebhookFormat;
+use crate::services::time_provider::now_unix;
+use crate::services::timer_control_service::complete_session;
+use crate::services::webhook_service::{
+    get_webhook_retry_policy, post_webhook, send_webhook_notification,
+};
+use crate::websocket::manager::{SharedWsManager, WebSocketManager};
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::Json,
+};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+
+#[derive(Debug, Serialize)]
+pub struct AdminStatsResponse {
+    pub total_users: i64,
+    pub active_connections: usize,
+    pub running_timers: usize,
+    pub sessions_completed_today: i64,
+    pub pending_scheduled_tasks: i64,
+    pub webhooks_sent: u64,
+    pub webhooks_failed: u64,
+    /// Failed share of session-complete webhooks since startup, 0.0 when none were sent
+    pub webhook_failure_rate: f64,
+}
+
+/// Most work sessions one simulated day may run
+const MAX_SIMULATED_WORK_SESSIONS: u32 = 16;
+
+fn default_simulated_work_sessions() -> u32 {
+    4
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SimulateDayRequest {
+    pub user_id: String,
+    #[serde(default = "default_simulated_work_sessions")]
+    pub work_sessions: u32,
+}
+
+/// One step of a simulated day, at its simulated Unix time
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum SimulatedEvent {
+    SessionComplete {
+        at: i64,
+        session_type: String,
+        session_count: u32,
+    },
+    DailyReset {
+        at: i64,
+        previous_count: i64,
+    },
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulateDayResponse {
+    pub events: Vec<SimulatedEvent>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BackupResponse {
+    pub path: String,
+    pub size_bytes: u64,
+}
+
+/// Enable development-only endpoints such as `/api/admin/simulate-day`
+fn get_dev_tools_enabled() -> bool {
+    env::var("ROMA_TIMER_DEV_TOOLS")
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Admin token guarding `/api/admin/*`; admin endpoints are disabled when unset
+fn get_admin_token() -> Option<String> {
+    env::var("ROMA_TIMER_ADMIN_TOKEN").ok().filter(|token| !token.is_empty())
+}
+
+const DEFAULT_BACKUP_DIR: &str = "./data/backups";
+
+const DEFAULT_BACKUP_RETENTION: usize = 7;
+
+pub(crate) fn get_backup_dir() -> std::path::PathBuf {
+    env::var("ROMA_TIMER_BACKUP_DIR")
+        .map(std::path::PathBuf::from)
+        .unwrap_or_else(|_| std::path::PathBuf::from(DEFAULT_BACKUP_DIR))
+}
+
+/// Number of backups kept in the backup directory; older ones are deleted
+pub(crate) fn get_backup_retention() -> usize {
+    env::var("ROMA_TIMER_BACKUP_RETENTION")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_BACKUP_RETENTION)
+}
+
+/// Check the request carries the configured admin token
+fn authenticate_admin(
+    headers: &axum::http::HeaderMap,
+    admin_token: Option<&str>,
+) -> Result<(), StatusCode> {
+    let admin_token = admin_token.ok_or(StatusCode::FORBIDDEN)?;
+    let provided = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(admin_token.as_bytes())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    mac.update(b"roma-timer-admin");
+    let expected = mac.finalize().into_bytes();
+    let mut mac = Hmac::<Sha256>::new_from_slice(provided.as_bytes())
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    mac.update(b"roma-timer-admin");
+    mac.verify_slice(&expected).map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+/// Back up the database into `backup_dir` under a timestamped name, then delete
+/// all but the newest `retention` backups
+pub(crate) async fn create_backup(
+    database: &DatabaseManager,
+    backup_dir: &std::path::Path,
+    retention: usize,
+) -> anyhow::Result<BackupResponse> {
+    std::fs::create_dir_all(backup_dir)?;
+
+    let file_name = format!(
+        "roma-timer-{}.db",
+        chrono::Utc::now().format("%Y%m%d-%H%M%S-%3f")
+    );
+    let path = backup_dir.join(file_name);
+    let size_bytes = database.backup_to(&path).await?;
+
+    let mut backups: Vec<_> = std::fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.starts_with("roma-timer-") && name.ends_with(".db"))
+        })
+        .collect();
+    // Timestamped names sort chronologically
+    backups.sort();
+    let excess = backups.len().saturating_sub(retention.max(1));
+    for old_backup in &backups[..excess] {
+        if let Err(e) = std::fs::remove_file(old_backup) {
+            tracing::warn!("Failed to remove old backup {}: {e}", old_backup.display());
+        }
+    }
+
+    Ok(BackupResponse {
+        path: path.to_string_lossy().to_string(),
+        size_bytes,
+    })
+}
+
+pub async fn backup_database(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    headers: axum::http::HeaderMap,
+) -> Result<Json<BackupResponse>, StatusCode> {
+    authenticate_admin(&headers, get_admin_token().as_deref())?;
+
+    let backup = create_backup(&ws_manager.database, &get_backup_dir(), get_backup_retention())
+        .await
+        .map_err(|e| {
+            tracing::error!("Database backup failed: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    tracing::info!("Database backed up to {} ({} bytes)", backup.path, backup.size_bytes);
+    Ok(Json(backup))
+}
+
+/// Aggregate server stats from the managers and cheap database counts;
+/// "today" starts at midnight UTC
+async fn collect_admin_stats(
+    state: &SharedState,
+    ws_manager: &WebSocketManager,
+    now: chrono::DateTime<chrono::Utc>,
+) -> anyhow::Result<AdminStatsResponse> {
+    let day_start = now
+        .date_naive()
+        .and_hms_opt(0, 0, 0)
+        .expect("midnight is a valid time")
+        .and_utc()
+        .timestamp();
+    let counts = ws_manager.database.operator_counts(day_start).await?;
+    let active_connections = ws_manager.connections.lock().await.len();
+    let running_timers = state.lock().await.iter().filter(|(_, timer)| timer.is_running).count();
+
+    let webhooks_sent = ws_manager.webhooks_sent.load(Ordering::Relaxed);
+    let webhooks_failed = ws_manager.webhooks_failed.load(Ordering::Relaxed);
+    let webhook_failure_rate = if webhooks_sent == 0 {
+        0.0
+    } else {
+        webhooks_failed as f64 / webhooks_sent as f64
+    };
+
+    Ok(AdminStatsResponse {
+        total_users: counts.total_users,
+        active_connections,
+        running_timers,
+        sessions_completed_today: counts.sessions_completed_since,
+        pending_scheduled_tasks: counts.pending_scheduled_tasks,
+        webhooks_sent,
+        webhooks_failed,
+        webhook_failure_rate,
+    })
+}
+
+pub async fn admin_stats(
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    headers: axum::http::HeaderMap,
+) -> Result<Json<AdminStatsResponse>, StatusCode> {
+    authenticate_admin(&headers, get_admin_token().as_deref())?;
+
+    let stats = collect_admin_stats(&state, &ws_manager, chrono::Utc::now())
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to collect admin stats: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(Json(stats))
+}
+
+/// Fast-forward through a day for `user_id` on `time_provider`'s clock: run
+/// `work_sessions` work sessions with their breaks on a copy of the timer,
+/// counting each and sending its completion webhook, then perform the next
+/// scheduled reset. The live timer is not touched.
+async fn simulate_day(
+    ws_manager: &WebSocketManager,
+    user_id: &str,
+    work_sessions: u32,
+    webhook_url: Option<&str>,
+    time_provider: Arc<crate::services::time_provider::MockTimeProvider>,
+) -> Result<Vec<SimulatedEvent>, crate::error::AppError> {
+    use crate::services::time_provider::TimeProvider;
+
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        time_provider.clone(),
+        ws_manager.database.clone(),
+    )
+    .with_min_reset_interval(chrono::Duration::zero());
+
+    let resume_work_duration = ws_manager.user_configuration(user_id).await.resume_work_duration;
+    let mut state = ws_manager.timer_states.lock().await.get(user_id);
+    state.session_type = "work".to_string();
+    state.session_count = 1;
+    state.work_sessions_since_long_break = 0;
+    state.plan = None;
+    state.rewind();
+
+    let mut events = Vec::new();
+    let mut completed_work = 0;
+    while completed_work < work_sessions {
+        time_provider.advance(chrono::Duration::seconds(state.remaining_seconds as i64));
+        let (session_type, session_count) = complete_session(&mut state, ws_manager.max_consecutive_work_sessions, resume_work_duration);
+        if session_type == "work" {
+            completed_work += 1;
+            if let Err(e) = service.increment_session_count(user_id).await {
+                tracing::warn!("Simulated day could not count session for {user_id}: {e}");
+            }
+        }
+        if let Some(url) = webhook_url {
+            let result = send_webhook_notification(url, &WebhookFormat::Raw, None, &session_type, session_count, get_webhook_retry_policy())
+                .await
+                .map_err(|e| e.to_string());
+            if let Err(reason) = result {
+                tracing::warn!("Simulated session webhook failed: {reason}");
+            }
+        }
+        events.push(SimulatedEvent::SessionComplete {
+            at: time_provider.now_timestamp(),
+            session_type,
+            session_count,
+        });
+    }
+
+    let config = service
+        .find_user_configuration(user_id)
+        .await?
+        .ok_or(crate::error::AppError::ConfigurationNotFound)?;
+    let next_reset = service.calculate_next_reset_time(&config)?;
+    if next_reset > time_provider.now_utc() {
+        time_provider.set_time(next_reset);
+    }
+    let reset = service
+        .perform_daily_reset(&config, SessionResetTriggerSource::BackgroundService)
+        .await?;
+    if let Some(url) = webhook_url {
+        let payload = serde_json::json!({
+            "title": "Roma Timer",
+            "message": format!("Daily reset: {} sessions cleared", reset.previous_count),
+            "event": "daily_reset",
+            "previous_count": reset.previous_count,
+            "timestamp": now_unix()
+        });
+        let result = post_webhook(url, &payload, get_webhook_retry_policy())
+            .await
+            .map_err(|e| e.to_string());
+        if let Err(reason) = result {
+            tracing::warn!("Simulated reset webhook failed: {reason}");
+        }
+    }
+    events.push(SimulatedEvent::DailyReset {
+        at: reset.reset_timestamp_utc,
+        previous_count: reset.previous_count,
+    });
+
+    Ok(events)
+}
+
+pub async fn simulate_day_endpoint(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    headers: axum::http::HeaderMap,
+    ApiJson(request): ApiJson<SimulateDayRequest>,
+) -> Result<Json<SimulateDayResponse>, StatusCode> {
+    if !get_dev_tools_enabled() {
+        return Err(StatusCode::NOT_FOUND);
+    }
+    authenticate_admin(&headers, get_admin_token().as_deref())?;
+    if !(1..=MAX_SIMULATED_WORK_SESSIONS).contains(&request.work_sessions) {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let webhook_url = env::var("ROMA_TIMER_WEBHOOK_URL").ok();
+    let time_provider = Arc::new(crate::services::time_provider::MockTimeProvider::new_from_now());
+    let events = simulate_day(
+        &ws_manager,
+        &request.user_id,
+        request.work_sessions,
+        webhook_url.as_deref(),
+        time_provider,
+    )
+    .await
+    .map_err(|e| {
+        tracing::warn!("Day simulation for {} failed: {e}", request.user_id);
+        e.status_code()
+    })?;
+
+    tracing::info!("Simulated a day of {} events for {}", events.len(), request.user_id);
+    Ok(Json(SimulateDayResponse { events }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::sync::Mutex as StdMutex;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use axum::Router;
+    use axum::routing::post;
+
+    #[tokio::test]
+    async fn test_backup_produces_restorable_copy_and_rotates() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_, ws_manager) = test_app_state(&temp_dir).await;
+        let user_id = ws_manager.database.create_user("alice", "hash", "salt").await.unwrap();
+        let backup_dir = temp_dir.path().join("backups");
+
+        let backup = create_backup(&ws_manager.database, &backup_dir, 1).await.unwrap();
+        assert!(backup.size_bytes > 0);
+        assert!(backup.path.ends_with(".db"));
+
+        let restored = DatabaseManager::new(&format!("sqlite:{}", backup.path)).await.unwrap();
+        let user = restored.get_user_by_username("alice").await.unwrap().unwrap();
+        assert_eq!(user.id, user_id);
+
+        // Older backups beyond the retention count are removed
+        tokio::time::sleep(Duration::from_millis(5)).await;
+        let newer = create_backup(&ws_manager.database, &backup_dir, 1).await.unwrap();
+        let remaining: Vec<_> = std::fs::read_dir(&backup_dir).unwrap().collect();
+        assert_eq!(remaining.len(), 1);
+        assert!(std::path::Path::new(&newer.path).exists());
+    }
+
+    #[test]
+    fn test_admin_endpoints_require_admin_token() {
+        let mut headers = axum::http::HeaderMap::new();
+        assert_eq!(authenticate_admin(&headers, None), Err(StatusCode::FORBIDDEN));
+        assert_eq!(authenticate_admin(&headers, Some("admin-secret")), Err(StatusCode::UNAUTHORIZED));
+
+        headers.insert(header::AUTHORIZATION, "Bearer wrong".parse().unwrap());
+        assert_eq!(authenticate_admin(&headers, Some("admin-secret")), Err(StatusCode::UNAUTHORIZED));
+
+        headers.insert(header::AUTHORIZATION, "Bearer admin-secret".parse().unwrap());
+        assert_eq!(authenticate_admin(&headers, Some("admin-secret")), Ok(()));
+    }
+
+    #[tokio::test]
+    async fn test_admin_stats_aggregate_counts() {
+        use crate::models::scheduled_task::ScheduledTask;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let database = &ws_manager.database;
+        let now = chrono::Utc::now();
+
+        database.create_user("alice", "hash", "salt").await.unwrap();
+        database.create_user("bob", "hash", "salt").await.unwrap();
+        for (session_type, completed_at) in [
+            ("work", now.timestamp()),
+            ("short_break", now.timestamp()),
+            ("work", now.timestamp() - 2 * 86_400),
+        ] {
+            database
+                .record_completed_session("alice", session_type, 1500, 0, "laptop", completed_at, None)
+                .await
+                .unwrap();
+        }
+        let active = ScheduledTask::daily_reset_task("alice".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        let mut inactive = ScheduledTask::daily_reset_task("bob".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        inactive.is_active = false;
+        database.save_scheduled_task(&active).await.unwrap();
+        database.save_scheduled_task(&inactive).await.unwrap();
+
+        let (first, _first_rx) = mpsc::unbounded_channel();
+        let (second, _second_rx) = mpsc::unbounded_channel();
+        ws_manager.add_connection("phone".to_string(), "alice", None, first).await;
+        ws_manager.add_connection("laptop".to_string(), "alice", None, second).await;
+        state.lock().await.user("alice").is_running = true;
+        ws_manager.webhooks_sent.store(4, Ordering::Relaxed);
+        ws_manager.webhooks_failed.store(1, Ordering::Relaxed);
+
+        let stats = collect_admin_stats(&state, &ws_manager, now).await.unwrap();
+        assert_eq!(stats.total_users, 2);
+        assert_eq!(stats.active_connections, 2);
+        assert_eq!(stats.running_timers, 1);
+        assert_eq!(stats.sessions_completed_today, 2);
+        assert_eq!(stats.pending_scheduled_tasks, 1);
+        assert_eq!(stats.webhook_failure_rate, 0.25);
+    }
+
+    #[tokio::test]
+    async fn test_simulate_day_sends_ordered_events() {
+        use crate::services::time_provider::TimeProvider;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, timezone, daily_reset_enabled, created_at, updated_at) VALUES ('alice', 'UTC', TRUE, 0, 0)"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let received = Arc::new(StdMutex::new(Vec::<serde_json::Value>::new()));
+        let app = Router::new().route("/hook", post({
+            let received = received.clone();
+            move |Json(payload): Json<serde_json::Value>| async move {
+                received.lock().unwrap().push(payload);
+                StatusCode::OK
+            }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let time_provider = Arc::new(crate::services::time_provider::MockTimeProvider::new_from_ymd_hms(2025, 3, 3, 9, 0, 0).unwrap());
+        let start = time_provider.now_timestamp();
+        let events = simulate_day(&ws_manager, "alice", 2, Some(&format!("http://{addr}/hook")), time_provider)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            events,
+            [
+                SimulatedEvent::SessionComplete { at: start + 1500, session_type: "work".to_string(), session_count: 1 },
+                SimulatedEvent::SessionComplete { at: start + 1800, session_type: "short_break".to_string(), session_count: 1 },
+                SimulatedEvent::SessionComplete { at: start + 3300, session_type: "work".to_string(), session_count: 2 },
+                // Next midnight UTC
+                SimulatedEvent::DailyReset { at: start + 15 * 3600, previous_count: 2 },
+            ]
+        );
+        let webhook_events: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|payload| {
+                payload["event"]
+                    .as_str()
+                    .or(payload["session_type"].as_str())
+                    .unwrap()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(webhook_events, ["work", "short_break", "work", "daily_reset"]);
+        // The live timer is untouched
+        assert_eq!(state.lock().await.user("alice").session_count, 1);
+
+        // Without ROMA_TIMER_DEV_TOOLS the endpoint does not exist
+        let response = simulate_day_endpoint(
+            State((state.clone(), ws_manager.clone())),
+            axum::http::HeaderMap::new(),
+            ApiJson(SimulateDayRequest { user_id: "alice".to_string(), work_sessions: 2 }),
+        )
+        .await;
+        assert_eq!(response.unwrap_err(), StatusCode::NOT_FOUND);
+    }
+}
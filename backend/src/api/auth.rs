@@ -0,0 +1,675 @@
+//! Authentication API Endpoints
+//!
+//! Registration, login, logout, token refresh and device pairing.
+
+use std::env;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use crate::api::json::ApiJson;
+use crate::database::DatabaseManager;
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::{
+    CurrentUser, PAIRING_CODE_TTL_SECS, authenticate, device_fingerprint, generate_auth_token,
+    generate_pairing_code, generate_salt, get_pepper, get_refresh_token_ttl, hash_password,
+    hash_refresh_token, mark_token_revoked, needs_rehash, token_revocation_id, verify_auth_token,
+    verify_password,
+};
+use crate::services::time_provider::now_unix;
+use crate::websocket::manager::{CountChangeLimit, SharedWsManager};
+
+use axum::{
+    extract::{ConnectInfo, State},
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use base64::{engine::general_purpose, Engine as _};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+#[derive(Debug, Deserialize)]
+pub struct LoginRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RegisterRequest {
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AuthResponse {
+    /// Short-lived access token
+    pub token: String,
+    pub user_id: String,
+    pub username: String,
+    pub expires_at: u64,
+    /// Exchange at `/api/auth/refresh` for a new access token; single use
+    pub refresh_token: String,
+    pub refresh_expires_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RefreshRequest {
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PairingCodeResponse {
+    pub code: String,
+    pub deep_link: String,
+    pub expires_at: u64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RedeemPairingRequest {
+    pub code: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RegisterResponse {
+    pub message: String,
+    pub user_id: String,
+    pub username: String,
+}
+
+/// Pairing-code redemption attempts allowed per client address per minute, from
+/// `ROMA_TIMER_PAIR_REDEEM_ATTEMPTS_PER_MINUTE`. Defaults to 10; zero disables the limit.
+pub fn get_redeem_attempt_limit() -> Option<CountChangeLimit> {
+    let max_changes = env::var("ROMA_TIMER_PAIR_REDEEM_ATTEMPTS_PER_MINUTE")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(10);
+    (max_changes > 0).then_some(CountChangeLimit {
+        max_changes,
+        window: Duration::from_secs(60),
+    })
+}
+
+/// Base URL clients reach the server on, from `ROMA_TIMER_PUBLIC_URL`
+/// (e.g. `https://timer.example.com`). Unset leaves pairing deep links relative.
+pub fn get_public_base_url() -> Option<String> {
+    env::var("ROMA_TIMER_PUBLIC_URL")
+        .ok()
+        .map(|value| value.trim().trim_end_matches('/').to_string())
+        .filter(|value| !value.is_empty())
+}
+
+pub async fn register_user(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    ApiJson(request): ApiJson<RegisterRequest>,
+) -> Result<Json<RegisterResponse>, StatusCode> {
+    let database = &ws_manager.database;
+
+    // Validate input
+    if request.username.len() < 3 || request.password.len() < 6 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    // Generate salt and hash password
+    let salt = generate_salt();
+    let pepper = get_pepper();
+
+    let password_hash = match hash_password(&request.password, &salt, &pepper) {
+        Ok(hash) => hash,
+        Err(_) => return Err(StatusCode::INTERNAL_SERVER_ERROR),
+    };
+
+    // Create user
+    match database.create_user(&request.username, &password_hash, &salt).await {
+        Ok(user_id) => {
+            println!("✅ User registered successfully: {}", request.username);
+            Ok(Json(RegisterResponse {
+                message: "User registered successfully".to_string(),
+                user_id,
+                username: request.username.clone(),
+            }))
+        }
+        Err(e) => {
+            eprintln!("❌ Failed to register user: {e}");
+            if e.to_string().contains("Username already exists") {
+                return Err(StatusCode::CONFLICT);
+            }
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+pub async fn login_user(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    headers: axum::http::HeaderMap,
+    ApiJson(request): ApiJson<LoginRequest>,
+) -> Result<Json<AuthResponse>, StatusCode> {
+    let database = &ws_manager.database;
+
+    // Get user by username
+    match database.get_user_by_username(&request.username).await {
+        Ok(Some(user)) => {
+            // Verify password
+            let pepper = get_pepper();
+            if verify_password(&request.password, &user.salt, &pepper, &user.password_hash) {
+                if needs_rehash(&user.password_hash) {
+                    rehash_password(database, &user.id, &request.password, &user.salt, &pepper).await;
+                }
+
+                let fingerprint = device_fingerprint(&headers);
+                let response = issue_auth_response(database, &user.id, user.username, &fingerprint, None).await?;
+                tracing::debug!("User logged in: {}", request.username);
+                Ok(Json(response))
+            } else {
+                tracing::debug!("Login failed for {}", request.username);
+                Err(StatusCode::UNAUTHORIZED)
+            }
+        }
+        // Logged the same as a wrong password, so the log doesn't reveal which usernames exist
+        Ok(None) => {
+            tracing::debug!("Login failed for {}", request.username);
+            Err(StatusCode::UNAUTHORIZED)
+        }
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR),
+    }
+}
+
+/// Revoke the token the request was made with, so it stops working before it
+/// expires, along with the refresh tokens issued to the same device so it
+/// can't mint new ones
+pub async fn logout_user(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    headers: axum::http::HeaderMap,
+) -> Result<StatusCode, StatusCode> {
+    let claims = authenticate(&headers)?;
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let token_id = token_revocation_id(&claims, token);
+    if let Err(e) = ws_manager.database.revoke_token(&token_id, claims.exp as i64).await {
+        tracing::error!("Failed to revoke token for {}: {e}", claims.sub);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+    mark_token_revoked(token_id, claims.exp);
+
+    if let Err(e) = ws_manager
+        .database
+        .revoke_device_refresh_tokens(&claims.sub, &device_fingerprint(&headers))
+        .await
+    {
+        tracing::error!("Failed to revoke refresh tokens for {}: {e}", claims.sub);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    tracing::info!("User logged out: {}", claims.sub);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Store a fresh Argon2id hash for a user who just logged in with an outdated
+/// one. Failures are logged; the login itself still succeeds.
+async fn rehash_password(database: &DatabaseManager, user_id: &str, password: &str, salt: &str, pepper: &str) {
+    let password_hash = match hash_password(password, salt, pepper) {
+        Ok(hash) => hash,
+        Err(e) => {
+            tracing::error!("Failed to re-hash password for {user_id}: {e}");
+            return;
+        }
+    };
+    match database.update_password_hash(user_id, &password_hash).await {
+        Ok(()) => tracing::info!("Upgraded password hash for {user_id}"),
+        Err(e) => tracing::error!("Failed to store re-hashed password for {user_id}: {e}"),
+    }
+}
+
+pub async fn create_pairing_code(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<PairingCodeResponse>, StatusCode> {
+    let code = generate_pairing_code();
+    let expires_at = now_unix()
+        + PAIRING_CODE_TTL_SECS;
+
+    ws_manager
+        .database
+        .create_pairing_code(&code, &claims.sub, expires_at as i64)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to create pairing code: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    // Never derived from the request's Host header, which the client controls
+    let base_url = ws_manager.public_base_url.as_deref().unwrap_or_default();
+
+    Ok(Json(PairingCodeResponse {
+        deep_link: format!("{base_url}/?pair_code={code}"),
+        code,
+        expires_at,
+    }))
+}
+
+/// Exchange a pairing code for tokens. Attempts are limited per client address
+/// so short codes can't be brute-forced within their lifetime.
+pub async fn redeem_pairing_code(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    connect_info: Option<ConnectInfo<SocketAddr>>,
+    headers: axum::http::HeaderMap,
+    ApiJson(request): ApiJson<RedeemPairingRequest>,
+) -> Result<Response, StatusCode> {
+    // Without connection info every caller shares one allowance
+    let client = connect_info.map_or_else(|| "unknown".to_string(), |ConnectInfo(addr)| addr.ip().to_string());
+    if let Err(retry_after) = ws_manager.acquire_redeem_attempt(&client).await {
+        tracing::warn!("Rate limited pairing code redemption from {client}");
+        let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        return Ok((
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(serde_json::json!({
+                "error": "rate_limited",
+                "message": format!("Too many pairing attempts; try again in {retry_after_secs}s"),
+            })),
+        )
+            .into_response());
+    }
+
+    let database = &ws_manager.database;
+    let now = now_unix();
+
+    let user_id = database
+        .redeem_pairing_code(request.code.trim(), now as i64)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let user = database
+        .get_user_by_id(&user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    tracing::info!("Paired new device for user: {}", user.username);
+    let response =
+        issue_auth_response(database, &user_id, user.username, &device_fingerprint(&headers), None).await?;
+    Ok(Json(response).into_response())
+}
+
+/// Exchange a refresh token for a new access token and a new refresh token.
+/// The old refresh token stops working; presenting it again is treated as
+/// theft and revokes every token descended from the same login.
+pub async fn refresh_auth_token(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    headers: axum::http::HeaderMap,
+    ApiJson(request): ApiJson<RefreshRequest>,
+) -> Result<Json<AuthResponse>, StatusCode> {
+    let database = &ws_manager.database;
+    let now = now_unix();
+    let token_hash = hash_refresh_token(request.refresh_token.trim());
+
+    let consumed = database
+        .consume_refresh_token(&token_hash, now as i64)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+    let Some(refresh) = consumed else {
+        let family = database
+            .get_refresh_token_family(&token_hash)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        if let Some(family_id) = family {
+            tracing::warn!("Refresh token reused; revoking token family {family_id}");
+            database
+                .revoke_refresh_token_family(&family_id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        }
+        return Err(StatusCode::UNAUTHORIZED);
+    };
+
+    if refresh.expires_at < now as i64 {
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+    if refresh.device_fingerprint != device_fingerprint(&headers) {
+        tracing::warn!("Refresh token for {} presented from another device; revoking", refresh.user_id);
+        database
+            .revoke_refresh_token_family(&refresh.family_id)
+            .await
+            .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        return Err(StatusCode::UNAUTHORIZED);
+    }
+
+    let user = database
+        .get_user_by_id(&refresh.user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    let response = issue_auth_response(
+        database,
+        &refresh.user_id,
+        user.username,
+        &refresh.device_fingerprint,
+        Some(refresh.family_id),
+    )
+    .await?;
+    Ok(Json(response))
+}
+
+/// Issue an access token and a refresh token for `user_id`. The refresh token
+/// joins `family_id` when rotating, or starts a new family on login.
+async fn issue_auth_response(
+    database: &DatabaseManager,
+    user_id: &str,
+    username: String,
+    fingerprint: &str,
+    family_id: Option<String>,
+) -> Result<AuthResponse, StatusCode> {
+    let (token, expires_at) = {
+        let token = generate_auth_token(user_id).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        let claims = verify_auth_token(&token).map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+        (token, claims.exp)
+    };
+
+    let refresh_token = {
+        let mut rng = rand::thread_rng();
+        let bytes: [u8; 32] = rng.gen();
+        general_purpose::URL_SAFE_NO_PAD.encode(bytes)
+    };
+    let refresh_expires_at = now_unix() + get_refresh_token_ttl();
+    let family_id = family_id.unwrap_or_else(|| Uuid::new_v4().to_string());
+    database
+        .create_refresh_token(
+            &hash_refresh_token(&refresh_token),
+            user_id,
+            fingerprint,
+            &family_id,
+            refresh_expires_at as i64,
+        )
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to store refresh token for {user_id}: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(AuthResponse {
+        token,
+        user_id: user_id.to_string(),
+        username,
+        expires_at,
+        refresh_token,
+        refresh_expires_at,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use crate::services::auth_service::{DEFAULT_ACCESS_TOKEN_TTL_SECS, cleanup_revoked_tokens, get_token_leeway, legacy_hash_password};
+    use crate::websocket::manager::WebSocketManager;
+    use axum::Router;
+    use axum::routing::get;
+
+    #[tokio::test]
+    async fn test_pairing_code_is_single_use_and_expires() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let user_id = ws_manager.database.create_user("alice", "hash", "salt").await.unwrap();
+
+        let Json(pairing) = create_pairing_code(State((state.clone(), ws_manager.clone())), current_user(&user_id))
+            .await
+            .unwrap();
+        assert!(pairing.deep_link.contains(&pairing.code));
+
+        let redeem = |code: String| {
+            redeem_pairing_code(
+                State((state.clone(), ws_manager.clone())),
+                None,
+                axum::http::HeaderMap::new(),
+                ApiJson(RedeemPairingRequest { code }),
+            )
+        };
+
+        let auth: serde_json::Value = response_json(redeem(pairing.code.clone()).await.unwrap()).await;
+        assert_eq!(auth["user_id"], user_id.as_str());
+        assert_eq!(verify_auth_token(auth["token"].as_str().unwrap()).unwrap().sub, user_id);
+
+        // Second redemption of the same code fails
+        assert_eq!(redeem(pairing.code).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        // Expired codes are rejected
+        let expired_at = chrono::Utc::now().timestamp() - 1;
+        ws_manager.database.create_pairing_code("EXPIRED1", &user_id, expired_at).await.unwrap();
+        assert_eq!(redeem("EXPIRED1".to_string()).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_pairing_deep_link_uses_public_url_not_host_header() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let user_id = ws_manager.database.create_user("alice", "hash", "salt").await.unwrap();
+
+        // Unconfigured, the link is relative to wherever the client loaded the app from
+        let Json(pairing) = create_pairing_code(State((state.clone(), ws_manager.clone())), current_user(&user_id))
+            .await
+            .unwrap();
+        assert_eq!(pairing.deep_link, format!("/?pair_code={}", pairing.code));
+
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_public_base_url(Some("https://timer.example.com".to_string())),
+        );
+        let app = Router::new()
+            .route("/api/auth/pair", get(create_pairing_code))
+            .with_state((state, ws_manager));
+        let response = tower::ServiceExt::oneshot(
+            app,
+            axum::http::Request::builder()
+                .uri("/api/auth/pair")
+                .header(header::HOST, "attacker.example")
+                .header(header::AUTHORIZATION, format!("Bearer {}", generate_auth_token(&user_id).unwrap()))
+                .body(axum::body::Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+        let pairing: serde_json::Value = response_json(response).await;
+        let code = pairing["code"].as_str().unwrap();
+        assert_eq!(pairing["deep_link"], format!("https://timer.example.com/?pair_code={code}"));
+    }
+
+    #[tokio::test]
+    async fn test_pairing_code_redemption_is_rate_limited_per_client() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone()).with_redeem_attempt_limit(Some(
+                CountChangeLimit {
+                    max_changes: 3,
+                    window: Duration::from_secs(60),
+                },
+            )),
+        );
+        let user_id = ws_manager.database.create_user("alice", "hash", "salt").await.unwrap();
+        let Json(pairing) = create_pairing_code(State((state.clone(), ws_manager.clone())), current_user(&user_id))
+            .await
+            .unwrap();
+
+        let redeem = |ip: [u8; 4], code: &str| {
+            redeem_pairing_code(
+                State((state.clone(), ws_manager.clone())),
+                Some(ConnectInfo(SocketAddr::from((ip, 40000)))),
+                axum::http::HeaderMap::new(),
+                ApiJson(RedeemPairingRequest { code: code.to_string() }),
+            )
+        };
+
+        let attacker = [203, 0, 113, 7];
+        for guess in ["AAAAAAAA", "BBBBBBBB", "CCCCCCCC"] {
+            assert_eq!(redeem(attacker, guess).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+        }
+        // Once over the limit even the right code is refused, without being consumed
+        let response = redeem(attacker, &pairing.code).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+
+        // Another client has its own allowance
+        let response = redeem([198, 51, 100, 2], &pairing.code).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_password_hash_upgraded_at_login() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let salt = generate_salt();
+        let pepper = get_pepper();
+        let legacy_hash = legacy_hash_password("hunter22", &salt, &pepper).unwrap();
+        assert!(needs_rehash(&legacy_hash));
+        let user_id = ws_manager.database.create_user("alice", &legacy_hash, &salt).await.unwrap();
+
+        let login = || {
+            login_user(
+                State((state.clone(), ws_manager.clone())),
+                axum::http::HeaderMap::new(),
+                ApiJson(LoginRequest {
+                    username: "alice".to_string(),
+                    password: "hunter22".to_string(),
+                }),
+            )
+        };
+
+        let Json(auth) = login().await.unwrap();
+        assert_eq!(auth.user_id, user_id);
+
+        let stored = ws_manager.database.get_user_by_id(&user_id).await.unwrap().unwrap();
+        assert!(stored.password_hash.starts_with("$argon2id$"));
+        assert!(!needs_rehash(&stored.password_hash));
+
+        // The upgraded hash keeps working
+        let Json(again) = login().await.unwrap();
+        assert_eq!(again.user_id, user_id);
+    }
+
+    #[tokio::test]
+    async fn test_logout_revokes_token() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let salt = generate_salt();
+        let password_hash = hash_password("hunter22", &salt, &get_pepper()).unwrap();
+        ws_manager.database.create_user("alice", &password_hash, &salt).await.unwrap();
+
+        let mut device = axum::http::HeaderMap::new();
+        device.insert(header::USER_AGENT, "roma-timer-test/1.0".parse().unwrap());
+        let Json(auth) = login_user(
+            State((state.clone(), ws_manager.clone())),
+            device.clone(),
+            ApiJson(LoginRequest {
+                username: "alice".to_string(),
+                password: "hunter22".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        let mut headers = device.clone();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", auth.token).parse().unwrap());
+
+        assert!(authenticate(&headers).is_ok());
+        assert_eq!(
+            logout_user(State((state.clone(), ws_manager.clone())), headers.clone()).await,
+            Ok(StatusCode::NO_CONTENT)
+        );
+        assert_eq!(authenticate(&headers).unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        // The device's refresh token can't mint a new access token either
+        let refreshed = refresh_auth_token(
+            State((state.clone(), ws_manager.clone())),
+            device.clone(),
+            ApiJson(RefreshRequest { refresh_token: auth.refresh_token.clone() }),
+        )
+        .await;
+        assert_eq!(refreshed.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        // Other tokens for the same user keep working
+        assert!(authenticate(&auth_headers(&auth.user_id)).is_ok());
+
+        // The revocation is stored until the token would have expired
+        let now = now_unix();
+        let stored = ws_manager.database.get_revoked_tokens(now as i64).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        cleanup_revoked_tokens(&ws_manager.database, auth.expires_at + get_token_leeway() + 1).await;
+        assert!(ws_manager.database.get_revoked_tokens(now as i64).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_refresh_token_rotation_and_reuse_detection() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let salt = generate_salt();
+        let password_hash = hash_password("hunter22", &salt, &get_pepper()).unwrap();
+        ws_manager.database.create_user("alice", &password_hash, &salt).await.unwrap();
+
+        let mut device = axum::http::HeaderMap::new();
+        device.insert(header::USER_AGENT, "roma-timer-test/1.0".parse().unwrap());
+
+        let Json(login) = login_user(
+            State((state.clone(), ws_manager.clone())),
+            device.clone(),
+            ApiJson(LoginRequest {
+                username: "alice".to_string(),
+                password: "hunter22".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        let claims = verify_auth_token(&login.token).unwrap();
+        assert_eq!(claims.exp - claims.iat, DEFAULT_ACCESS_TOKEN_TTL_SECS);
+        assert!(login.refresh_expires_at > login.expires_at);
+
+        let refresh = |headers: axum::http::HeaderMap, refresh_token: String| {
+            refresh_auth_token(
+                State((state.clone(), ws_manager.clone())),
+                headers,
+                ApiJson(RefreshRequest { refresh_token }),
+            )
+        };
+
+        // A refresh token only works from the device it was issued to
+        let other_device = axum::http::HeaderMap::new();
+        let Json(rotated) = refresh(device.clone(), login.refresh_token.clone()).await.unwrap();
+        assert_eq!(rotated.user_id, login.user_id);
+        assert_ne!(rotated.refresh_token, login.refresh_token);
+        assert_eq!(verify_auth_token(&rotated.token).unwrap().sub, login.user_id);
+
+        // Reusing the rotated-out token revokes the whole family, including
+        // the token that replaced it
+        assert_eq!(
+            refresh(device.clone(), login.refresh_token.clone()).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            refresh(device.clone(), rotated.refresh_token.clone()).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+
+        // A fresh login starts a new family, which a different device can't use
+        let Json(second) = login_user(
+            State((state.clone(), ws_manager.clone())),
+            device.clone(),
+            ApiJson(LoginRequest {
+                username: "alice".to_string(),
+                password: "hunter22".to_string(),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            refresh(other_device, second.refresh_token.clone()).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+        assert_eq!(
+            refresh(device, "not-a-refresh-token".to_string()).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+}
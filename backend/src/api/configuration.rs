@@ -2,7 +2,9 @@
 //!
 //! REST API endpoints for managing user configuration settings.
 
+use super::json::ApiJson;
 use crate::models::user_configuration::UserConfiguration;
+use crate::services::time_provider::now_unix;
 use crate::services::configuration_service::{ConfigurationService, ConfigurationServiceError, ConfigurationUpdate};
 use axum::{
     extract::State,
@@ -28,10 +30,7 @@ impl ApiError {
         Self {
             error: error.to_string(),
             message: message.to_string(),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            timestamp: now_unix(),
         }
     }
 }
@@ -50,10 +49,7 @@ impl ValidationError {
         Self {
             error: error.to_string(),
             message: message.to_string(),
-            timestamp: std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs(),
+            timestamp: now_unix(),
             details,
         }
     }
@@ -91,7 +87,7 @@ pub async fn get_configuration(
 /// Updates user configuration settings with validation.
 pub async fn update_configuration(
     State(configuration_service): State<Arc<ConfigurationService>>,
-    Json(update): Json<ConfigurationUpdate>,
+    ApiJson(update): ApiJson<ConfigurationUpdate>,
 ) -> Result<Json<UserConfiguration>, (StatusCode, Json<Value>)> {
     debug!("PUT /api/configuration - Updating configuration: {:?}", update);
 
@@ -171,13 +167,12 @@ mod tests {
         Router,
     };
     use serde_json::json;
-    use sqlx::SqlitePool;
     use tower::ServiceExt;
 
     async fn create_test_app() -> Router {
-        let pool = SqlitePool::connect(":memory:").await.unwrap();
-        let websocket_service = crate::services::websocket_service::WebSocketService::new(pool.clone());
-        let configuration_service = ConfigurationService::new(pool, websocket_service)
+        let database = Arc::new(crate::database::DatabaseManager::new("sqlite::memory:").await.unwrap());
+        database.migrate().await.unwrap();
+        let configuration_service = ConfigurationService::without_broadcast(database)
             .await
             .unwrap();
 
@@ -0,0 +1,642 @@
+//! Daily Reset API Endpoints
+//!
+//! Daily reset status, configuration, manual resets and the reset event log.
+
+use std::sync::Arc;
+
+use crate::api::json::ApiJson;
+use crate::api::stats::user_timezone;
+use crate::database::DatabaseManager;
+use crate::models::session_reset_event::SessionResetTriggerSource;
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::CurrentUser;
+use crate::websocket::manager::{SharedWsManager, WebSocketManager, WsMessage};
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+};
+use serde::{Deserialize, Serialize};
+
+/// The caller's daily reset status: session count and override, the next reset
+/// in UTC and their timezone, and whether daily reset is enabled
+pub async fn get_daily_reset_status(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<crate::services::daily_reset_service::DailyResetStatusSnapshot>, StatusCode> {
+    let status = daily_reset_status(ws_manager.database.clone(), &claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to get daily reset status for {}: {e}", claims.sub);
+            e.status_code()
+        })?;
+    Ok(Json(status))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyResetTimeRequest {
+    #[serde(rename = "type")]
+    pub time_type: crate::models::user_configuration::DailyResetTimeType,
+    pub hour: Option<u8>,
+    #[serde(default)]
+    pub minute: Option<u8>,
+    pub time: Option<String>,
+}
+
+impl From<DailyResetTimeRequest> for crate::models::user_configuration::DailyResetTime {
+    fn from(request: DailyResetTimeRequest) -> Self {
+        Self {
+            time_type: request.time_type,
+            hour: request.hour,
+            minute: request.minute,
+            time: request.time,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyResetConfigRequest {
+    pub enabled: bool,
+    pub timezone: String,
+    pub reset_time: DailyResetTimeRequest,
+}
+
+/// Save the caller's daily reset configuration and return the resulting
+/// status. Disabling daily reset cancels their scheduled reset tasks.
+pub async fn update_daily_reset_config(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    ApiJson(request): ApiJson<DailyResetConfigRequest>,
+) -> Result<Json<crate::services::daily_reset_service::DailyResetStatusSnapshot>, StatusCode> {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    service
+        .configure_daily_reset(&claims.sub, request.enabled, request.reset_time.into(), &request.timezone)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to update daily reset configuration for {}: {e}", claims.sub);
+            e.status_code()
+        })?;
+
+    let status = daily_reset_status(ws_manager.database.clone(), &claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to get daily reset status for {}: {e}", claims.sub);
+            e.status_code()
+        })?;
+
+    // Let the user's devices show the new schedule
+    ws_manager
+        .broadcast_message(&claims.sub, WsMessage::DailyResetStatus(status.clone()))
+        .await;
+
+    Ok(Json(status))
+}
+
+/// Most reset events returned by one history request
+const MAX_RESET_EVENTS_PAGE: u32 = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct ResetEventsQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub reset_type: Option<crate::models::session_reset_event::SessionResetEventType>,
+    pub device_id: Option<String>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ResetEventsResponse {
+    pub events: Vec<crate::models::session_reset_event::SessionResetEvent>,
+    /// Events matching the filters across all pages
+    pub total: i64,
+    pub limit: u32,
+    pub offset: u32,
+}
+
+/// Reset the caller's daily session count now
+pub async fn reset_daily_sessions(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<crate::models::session_reset_event::SessionResetEvent>, StatusCode> {
+    let event = reset_daily_sessions_for(&ws_manager, &claims.sub, SessionResetTriggerSource::ApiCall)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Daily session reset for {} failed: {e}", claims.sub);
+            e.status_code()
+        })?;
+    Ok(Json(event))
+}
+
+/// The user's session reset history, newest first, optionally narrowed to a
+/// local date range, a reset type and the device that triggered it, one page
+/// at a time along with the total number of matching events
+pub async fn list_reset_events(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<ResetEventsQuery>,
+) -> Result<Json<ResetEventsResponse>, StatusCode> {
+    let database = &ws_manager.database;
+
+    let limit = query.limit.unwrap_or(50).min(MAX_RESET_EVENTS_PAGE);
+    let offset = query.offset.unwrap_or(0);
+    let mut filter = crate::models::session_reset_event::SessionResetEventQuery::new()
+        .for_user(claims.sub.clone())
+        .limit(limit)
+        .offset(offset);
+    if let Some(reset_type) = query.reset_type {
+        filter = filter.with_reset_type(reset_type);
+    }
+    if let Some(device_id) = query.device_id {
+        filter = filter.with_device_id(device_id);
+    }
+    if query.from.is_some() || query.to.is_some() {
+        let timezone = user_timezone(database, &claims.sub).await?;
+        let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+        let from = query
+            .from
+            .unwrap_or(chrono::DateTime::UNIX_EPOCH.date_naive());
+        let to = query
+            .to
+            .unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).date_naive());
+        if from > to {
+            return Err(StatusCode::BAD_REQUEST);
+        }
+        let (since, until) = crate::services::stats_service::local_day_bounds(tz, from, to);
+        let start = chrono::DateTime::from_timestamp(since, 0).ok_or(StatusCode::BAD_REQUEST)?;
+        let end = chrono::DateTime::from_timestamp(until, 0).ok_or(StatusCode::BAD_REQUEST)?;
+        filter = filter.between_dates(start, end);
+    }
+
+    let events = database.get_session_reset_events(&filter).await.map_err(|e| {
+        tracing::error!("Failed to load reset events for {}: {}", claims.sub, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+    let total = database.count_session_reset_events(&filter).await.map_err(|e| {
+        tracing::error!("Failed to count reset events for {}: {}", claims.sub, e);
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(ResetEventsResponse {
+        events,
+        total,
+        limit,
+        offset,
+    }))
+}
+
+/// Reset `user_id`'s daily session count on request, recording `trigger` as
+/// where the request came from, and broadcast the new status to their clients
+pub async fn reset_daily_sessions_for(
+    ws_manager: &WebSocketManager,
+    user_id: &str,
+    trigger: SessionResetTriggerSource,
+) -> Result<crate::models::session_reset_event::SessionResetEvent, crate::error::AppError> {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    let config = service
+        .find_user_configuration(user_id)
+        .await?
+        .ok_or(crate::error::AppError::ConfigurationNotFound)?;
+    let event = service.perform_daily_reset(&config, trigger).await?;
+
+    let status = daily_reset_status_message(ws_manager.database.clone(), user_id).await;
+    ws_manager.broadcast_message(user_id, status).await;
+    Ok(event)
+}
+
+/// The user's daily reset status, using the defaults if they have never saved
+/// a configuration
+async fn daily_reset_status(
+    database: Arc<DatabaseManager>,
+    user_id: &str,
+) -> Result<crate::services::daily_reset_service::DailyResetStatusSnapshot, crate::error::AppError> {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        database,
+    );
+
+    let config = service.find_user_configuration(user_id).await?.unwrap_or_else(|| {
+        crate::models::user_configuration::UserConfiguration::with_id(user_id.to_string())
+    });
+    service.status_snapshot(&config)
+}
+
+/// Build the reply to a `GetDailyResetStatus` request for the given user. Users
+/// without a stored configuration get the status of the default configuration.
+pub async fn daily_reset_status_message(database: Arc<DatabaseManager>, user_id: &str) -> WsMessage {
+    match daily_reset_status(database, user_id).await {
+        Ok(snapshot) => WsMessage::DailyResetStatus(snapshot),
+        Err(e) => {
+            tracing::warn!(target: "roma::ws", "Failed to get daily reset status for {user_id}: {e}");
+            WsMessage::Error {
+                code: e.error_code().to_string(),
+                message: e.to_string(),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use crate::api::timer_control::{TimerRequest, control_timer};
+    use crate::services::auth_service::generate_auth_token;
+    use crate::websocket::server::websocket_handler;
+    use axum::Router;
+    use axum::extract::ws::Message;
+    use axum::routing::get;
+    use futures_util::SinkExt;
+
+    #[tokio::test]
+    async fn test_get_daily_reset_status_message() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, daily_reset_time_type, daily_reset_time_hour, daily_reset_enabled, today_session_count, manual_session_override, created_at, updated_at)
+            VALUES ('alice', 'Europe/Paris', 'hour', 6, TRUE, 3, 5, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let request: WsMessage = serde_json::from_str(r#"{"type":"GetDailyResetStatus"}"#).unwrap();
+        assert!(matches!(request, WsMessage::GetDailyResetStatus));
+
+        let reply = daily_reset_status_message(ws_manager.database.clone(), "alice").await;
+        let json = serde_json::to_value(&reply).unwrap();
+        assert_eq!(json["type"], "DailyResetStatus");
+
+        let data = &json["data"];
+        assert_eq!(data["user_id"], "alice");
+        assert_eq!(data["enabled"], true);
+        assert_eq!(data["timezone"], "Europe/Paris");
+        assert_eq!(data["current_session_count"], 5);
+        assert_eq!(data["manual_session_override"], 5);
+        assert_eq!(data["is_due"], true);
+
+        let next_utc = data["next_reset_time_utc"].as_i64().unwrap();
+        assert!(next_utc > chrono::Utc::now().timestamp());
+        let next_local = chrono::DateTime::parse_from_rfc3339(data["next_reset_time_local"].as_str().unwrap()).unwrap();
+        assert_eq!(next_local.timestamp(), next_utc);
+        assert_eq!(chrono::Timelike::hour(&next_local), 6);
+    }
+
+    #[tokio::test]
+    async fn test_daily_reset_status_endpoint() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, daily_reset_time_type, daily_reset_time_hour, daily_reset_enabled, today_session_count, manual_session_override, created_at, updated_at)
+            VALUES ('alice', 'America/New_York', 'hour', 4, TRUE, 7, 2, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let status = get_daily_reset_status(State((state.clone(), ws_manager.clone())), current_user("alice"))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(status.user_id, "alice");
+        assert!(status.enabled);
+        assert_eq!(status.timezone, "America/New_York");
+        assert_eq!(status.current_session_count, 2);
+        assert_eq!(status.manual_session_override, Some(2));
+
+        let next_utc = status.next_reset_time_utc.unwrap();
+        assert!(next_utc > chrono::Utc::now().timestamp());
+        let next_local = chrono::DateTime::parse_from_rfc3339(&status.next_reset_time_local.unwrap()).unwrap();
+        assert_eq!(next_local.timestamp(), next_utc);
+        assert_eq!(chrono::Timelike::hour(&next_local), 4);
+
+        // Users without a saved configuration get the defaults
+        let status = get_daily_reset_status(State((state, ws_manager)), current_user("bob"))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(status.user_id, "bob");
+        assert_eq!(status.current_session_count, 0);
+        assert_eq!(status.manual_session_override, None);
+    }
+
+    #[tokio::test]
+    async fn test_update_daily_reset_config() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let update = |body: serde_json::Value| {
+            update_daily_reset_config(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(serde_json::from_value(body).unwrap()),
+            )
+        };
+
+        // The first save creates the configuration
+        let status = update(serde_json::json!({
+            "enabled": true,
+            "timezone": "Asia/Tokyo",
+            "reset_time": {"type": "custom", "time": "05:30"},
+        }))
+        .await
+        .unwrap()
+        .0;
+        assert!(status.enabled);
+        assert_eq!(status.timezone, "Asia/Tokyo");
+        let next_local = chrono::DateTime::parse_from_rfc3339(&status.next_reset_time_local.unwrap()).unwrap();
+        assert_eq!((chrono::Timelike::hour(&next_local), chrono::Timelike::minute(&next_local)), (5, 30));
+
+        let status = update(serde_json::json!({
+            "enabled": true,
+            "timezone": "UTC",
+            "reset_time": {"type": "hour", "hour": 7},
+        }))
+        .await
+        .unwrap()
+        .0;
+        let next_local = chrono::DateTime::parse_from_rfc3339(&status.next_reset_time_local.unwrap()).unwrap();
+        assert_eq!(chrono::Timelike::hour(&next_local), 7);
+
+        // Bad input is rejected and leaves the saved configuration alone
+        let invalid_timezone = update(serde_json::json!({
+            "enabled": true,
+            "timezone": "Mars/Olympus_Mons",
+            "reset_time": {"type": "midnight"},
+        }))
+        .await
+        .unwrap_err();
+        assert_eq!(invalid_timezone, StatusCode::BAD_REQUEST);
+        let invalid_hour = update(serde_json::json!({
+            "enabled": true,
+            "timezone": "UTC",
+            "reset_time": {"type": "hour", "hour": 24},
+        }))
+        .await
+        .unwrap_err();
+        assert_eq!(invalid_hour, StatusCode::BAD_REQUEST);
+        let status = daily_reset_status(ws_manager.database.clone(), "alice").await.unwrap();
+        assert_eq!(status.timezone, "UTC");
+    }
+
+    #[tokio::test]
+    async fn test_disabling_daily_reset_cancels_scheduled_tasks() {
+        use crate::models::scheduled_task::ScheduledTask;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let update = |enabled: bool| {
+            update_daily_reset_config(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(serde_json::from_value(serde_json::json!({
+                    "enabled": enabled,
+                    "timezone": "UTC",
+                    "reset_time": {"type": "midnight"},
+                })).unwrap()),
+            )
+        };
+
+        let Json(enabled) = update(true).await.unwrap();
+        assert!(enabled.enabled);
+        let own_task = ScheduledTask::daily_reset_task("alice".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        let other_task = ScheduledTask::daily_reset_task("bob".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        ws_manager.database.save_scheduled_task(&own_task).await.unwrap();
+        ws_manager.database.save_scheduled_task(&other_task).await.unwrap();
+
+        let status = update(false).await.unwrap().0;
+        assert!(!status.enabled);
+        assert_eq!(status.next_reset_time_utc, None);
+
+        let cancelled = ws_manager.database.get_scheduled_task(&own_task.id).await.unwrap().unwrap();
+        assert!(!cancelled.is_active);
+        let untouched = ws_manager.database.get_scheduled_task(&other_task.id).await.unwrap().unwrap();
+        assert!(untouched.is_active);
+    }
+
+    #[tokio::test]
+    async fn test_reset_events_filtered_by_device() {
+        use crate::models::session_reset_event::SessionResetEvent;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let now = chrono::Utc::now();
+        for (device_id, hours_ago) in [("laptop", 3), ("phone", 2), ("laptop", 1)] {
+            let event = SessionResetEvent::manual_reset(
+                "alice".to_string(),
+                4,
+                0,
+                now - chrono::Duration::hours(hours_ago),
+                "UTC".to_string(),
+                device_id.to_string(),
+            );
+            ws_manager.database.insert_session_reset_event(&event).await.unwrap();
+        }
+        let other_user = SessionResetEvent::manual_reset("bob".to_string(), 1, 0, now, "UTC".to_string(), "laptop".to_string());
+        ws_manager.database.insert_session_reset_event(&other_user).await.unwrap();
+
+        let Json(ResetEventsResponse { events, .. }) = list_reset_events(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            axum::extract::Query(ResetEventsQuery {
+                from: Some(now.date_naive() - chrono::Duration::days(1)),
+                to: None,
+                reset_type: Some(crate::models::session_reset_event::SessionResetEventType::ManualReset),
+                device_id: Some("laptop".to_string()),
+                limit: None,
+                offset: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(events.len(), 2);
+        assert!(events.iter().all(|event| event.device_id.as_deref() == Some("laptop")));
+        assert!(events.iter().all(|event| event.user_configuration_id == "alice"));
+        assert!(events[0].reset_timestamp_utc > events[1].reset_timestamp_utc);
+
+        let Json(ResetEventsResponse { events: all, .. }) = list_reset_events(
+            State((state, ws_manager)),
+            current_user("alice"),
+            axum::extract::Query(ResetEventsQuery {
+                from: None,
+                to: None,
+                reset_type: None,
+                device_id: None,
+                limit: None,
+                offset: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(all.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_reset_events_filtered_by_type_and_paged() {
+        use crate::models::session_reset_event::{SessionResetEvent, SessionResetEventType};
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let now = chrono::Utc::now();
+        for days_ago in 1..=5 {
+            let at = now - chrono::Duration::days(days_ago);
+            let scheduled = SessionResetEvent::scheduled_daily_reset("alice".to_string(), 4, at, "UTC".to_string());
+            ws_manager.database.insert_session_reset_event(&scheduled).await.unwrap();
+        }
+        let manual = SessionResetEvent::manual_reset("alice".to_string(), 2, 0, now, "UTC".to_string(), "laptop".to_string());
+        ws_manager.database.insert_session_reset_event(&manual).await.unwrap();
+
+        let list = |reset_type: Option<SessionResetEventType>, limit: Option<u32>, offset: Option<u32>| {
+            list_reset_events(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                axum::extract::Query(ResetEventsQuery {
+                    from: None,
+                    to: None,
+                    reset_type,
+                    device_id: None,
+                    limit,
+                    offset,
+                }),
+            )
+        };
+
+        let Json(manual_only) = list(Some(SessionResetEventType::ManualReset), None, None).await.unwrap();
+        assert_eq!(manual_only.total, 1);
+        assert_eq!(manual_only.events.len(), 1);
+        assert_eq!(manual_only.events[0].id, manual.id);
+
+        // Pages of two through the five scheduled resets, newest first
+        let mut seen = Vec::new();
+        for (offset, expected_len) in [(0, 2), (2, 2), (4, 1), (6, 0)] {
+            let Json(page) = list(Some(SessionResetEventType::ScheduledDaily), Some(2), Some(offset)).await.unwrap();
+            assert_eq!(page.total, 5);
+            assert_eq!((page.limit, page.offset), (2, offset));
+            assert_eq!(page.events.len(), expected_len);
+            assert!(page.events.iter().all(|event| event.reset_type == SessionResetEventType::ScheduledDaily));
+            seen.extend(page.events.into_iter().map(|event| event.reset_timestamp_utc));
+        }
+        assert_eq!(seen.len(), 5);
+        assert!(seen.windows(2).all(|pair| pair[0] > pair[1]));
+
+        let Json(everything) = list(None, None, None).await.unwrap();
+        assert_eq!(everything.total, 6);
+    }
+
+    #[tokio::test]
+    async fn test_reset_broadcasts_only_on_change() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        let reset = || {
+            control_timer(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(TimerRequest { action: "reset".to_string(), label: None }),
+            )
+        };
+
+        assert!(state.lock().await.user("alice").is_reset());
+        let Json(unchanged) = reset().await.unwrap();
+        assert_eq!(unchanged.remaining_seconds, unchanged.work_duration);
+        assert!(receiver.try_recv().is_err());
+
+        state.lock().await.user("alice").remaining_seconds = 600;
+        let Json(after) = reset().await.unwrap();
+        assert_eq!(after.remaining_seconds, after.work_duration);
+        assert!(matches!(receiver.try_recv(), Ok(Message::Text(_))));
+
+        // Stopping a running timer that is still at full duration is a change too
+        state.lock().await.user("alice").is_running = true;
+        let Json(stopped) = reset().await.unwrap();
+        assert!(!stopped.is_running);
+        assert!(matches!(receiver.try_recv(), Ok(Message::Text(_))));
+        assert!(!state.lock().await.user("alice").is_running);
+    }
+
+    #[tokio::test]
+    async fn test_manual_resets_record_their_entry_point() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, daily_reset_enabled, today_session_count, created_at, updated_at)
+            VALUES ('alice', 'UTC', TRUE, 3, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let trigger_sources = || async {
+            let rows: Vec<(String,)> = sqlx::query_as(
+                "SELECT trigger_source FROM session_reset_events WHERE user_configuration_id = 'alice' ORDER BY created_at, rowid",
+            )
+            .fetch_all(pool)
+            .await
+            .unwrap();
+            rows.into_iter().map(|(source,)| source).collect::<Vec<_>>()
+        };
+
+        // HTTP
+        let Json(event) = reset_daily_sessions(State((state.clone(), ws_manager.clone())), current_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(event.trigger_source, SessionResetTriggerSource::ApiCall);
+        assert_eq!(trigger_sources().await, vec!["api_call"]);
+
+        // WebSocket
+        let app = Router::new()
+            .route("/ws", get(websocket_handler))
+            .with_state((state.clone(), ws_manager.clone()));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let token = generate_auth_token("alice").unwrap();
+        let encoded: String = url::form_urlencoded::byte_serialize(token.as_bytes()).collect();
+        let (mut socket, _) = tokio_tungstenite::connect_async(format!("ws://{addr}/ws?token={encoded}"))
+            .await
+            .unwrap();
+        socket
+            .send(tokio_tungstenite::tungstenite::Message::Text(r#"{"type":"ResetDailySessions"}"#.to_string()))
+            .await
+            .unwrap();
+
+        let mut sources = Vec::new();
+        for _ in 0..60 {
+            sources = trigger_sources().await;
+            if sources.len() == 2 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sources, vec!["api_call", "websocket_message"]);
+    }
+}
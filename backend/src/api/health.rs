@@ -0,0 +1,164 @@
+//! Health API Endpoints
+//!
+//! Liveness, readiness and health checks.
+
+use std::env;
+use std::sync::atomic::Ordering;
+
+use crate::models::timer_state::SharedState;
+use crate::websocket::manager::SharedWsManager;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+
+/// Liveness probe: answering at all means the process is up
+pub async fn liveness_check() -> &'static str {
+    "OK"
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReadinessResponse {
+    pub ready: bool,
+    /// The database answers a trivial query
+    pub database: bool,
+    /// Migrations have been applied
+    pub migrations: bool,
+    /// The scheduled task runner is running
+    pub scheduler: bool,
+}
+
+/// Readiness probe: `200` once migrations have run, the scheduler has started
+/// and the database is reachable, `503` until then
+pub async fn readiness_check(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+) -> Response {
+    let migrations = ws_manager.database.is_migrated();
+    let scheduler = ws_manager.scheduler_running.load(Ordering::Acquire);
+    let database = ws_manager.database.test_connection().await.is_ok();
+    let ready = migrations && scheduler && database;
+
+    let status_code = if ready { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status_code, Json(ReadinessResponse { ready, database, migrations, scheduler })).into_response()
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct HealthResponse {
+    /// `ok`, or `unavailable` when a dependency is down
+    pub status: String,
+    /// `ok` or `down`
+    pub database: String,
+    pub uptime_seconds: u64,
+    pub version: String,
+}
+
+/// Report whether the server can do its job: `200` if the database answers a
+/// trivial query, `503` if it doesn't
+pub async fn health_check(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+) -> Response {
+    let database_ok = match ws_manager.database.test_connection().await {
+        Ok(()) => true,
+        Err(e) => {
+            tracing::warn!("Health check failed: {e}");
+            false
+        }
+    };
+
+    let status_code = if database_ok { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    let body = HealthResponse {
+        status: if database_ok { "ok" } else { "unavailable" }.to_string(),
+        database: if database_ok { "ok" } else { "down" }.to_string(),
+        uptime_seconds: ws_manager.started_at.elapsed().as_secs(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+    (status_code, Json(body)).into_response()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::sync::Arc;
+    use std::time::Duration;
+    use tokio::sync::{Mutex, watch};
+    use crate::database::DatabaseManager;
+    use crate::models::timer_state::TimerStates;
+    use crate::services::background_service::scheduled_task_runner;
+    use crate::websocket::manager::WebSocketManager;
+
+    #[tokio::test]
+    async fn test_health_check_reports_database() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let response = health_check(State((state.clone(), ws_manager.clone()))).await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let health = response_json::<HealthResponse>(response).await;
+        assert_eq!(health.status, "ok");
+        assert_eq!(health.database, "ok");
+        assert_eq!(health.version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[tokio::test]
+    async fn test_health_check_unavailable_when_database_down() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        ws_manager.database.pool.sqlite().unwrap().close().await;
+
+        let response = health_check(State((state, ws_manager))).await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let health = response_json::<HealthResponse>(response).await;
+        assert_eq!(health.status, "unavailable");
+        assert_eq!(health.database, "down");
+    }
+
+    #[tokio::test]
+    async fn test_readyz_waits_for_migrations_and_scheduler() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_ready.db");
+        let database = Arc::new(
+            DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy()))
+                .await
+                .unwrap(),
+        );
+        let state = SharedState::new(Mutex::new(TimerStates::new(test_timer_state())));
+        let ws_manager = SharedWsManager::new(WebSocketManager::new(state.clone(), database.clone()));
+        let readiness = || readiness_check(State((state.clone(), ws_manager.clone())));
+
+        assert_eq!(liveness_check().await, "OK");
+
+        let response = readiness().await;
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = response_json::<ReadinessResponse>(response).await;
+        assert!(body.database && !body.migrations && !body.ready);
+
+        database.migrate().await.unwrap();
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let runner = tokio::spawn(scheduled_task_runner(
+            ws_manager.clone(),
+            Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+            Duration::from_secs(60),
+            shutdown_rx,
+        ));
+        for _ in 0..50 {
+            if ws_manager.scheduler_running.load(Ordering::Acquire) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+
+        let response = readiness().await;
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response_json::<ReadinessResponse>(response).await;
+        assert!(body.ready && body.migrations && body.scheduler);
+
+        // Not ready again once the scheduler stops
+        shutdown_tx.send(true).unwrap();
+        runner.await.unwrap();
+        assert_eq!(readiness().await.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -0,0 +1,58 @@
+//! JSON body extractor for API handlers
+//!
+//! Wraps axum's `Json` so malformed or mistyped request bodies get the
+//! structured `{ error: { code, message } }` shape instead of axum's
+//! plain-text rejection.
+
+use axum::{
+    async_trait,
+    extract::{rejection::JsonRejection, FromRequest, Request},
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    Json,
+};
+use serde::de::DeserializeOwned;
+use serde_json::json;
+
+/// Drop-in replacement for `axum::Json` as a request body extractor
+#[derive(Debug)]
+pub struct ApiJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for ApiJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response;
+
+    async fn from_request(req: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(req, state).await {
+            Ok(Json(value)) => Ok(ApiJson(value)),
+            Err(rejection) => Err(json_rejection_response(rejection)),
+        }
+    }
+}
+
+/// Structured error response for a rejected JSON body. Syntax errors are a
+/// 400, well-formed JSON of the wrong shape a 422, a missing
+/// `Content-Type: application/json` a 415.
+pub fn json_rejection_response(rejection: JsonRejection) -> Response {
+    let (status, code) = match &rejection {
+        JsonRejection::JsonSyntaxError(_) => (StatusCode::BAD_REQUEST, "invalid_json"),
+        JsonRejection::JsonDataError(_) => (StatusCode::UNPROCESSABLE_ENTITY, "invalid_json"),
+        JsonRejection::MissingJsonContentType(_) => {
+            (StatusCode::UNSUPPORTED_MEDIA_TYPE, "unsupported_media_type")
+        }
+        _ => (rejection.status(), "invalid_json"),
+    };
+
+    let body = Json(json!({
+        "error": {
+            "code": code,
+            "message": rejection.body_text(),
+        }
+    }));
+
+    (status, body).into_response()
+}
@@ -0,0 +1,224 @@
+//! HTTP Middleware
+//!
+//! Request ids, service worker cache busting and authentication for protected API paths.
+
+
+use crate::services::auth_service::authenticate;
+
+use axum::http::{header, StatusCode};
+use uuid::Uuid;
+
+// Service worker cache busting middleware
+pub async fn sw_cache_middleware(
+    req: axum::extract::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let path = req.uri().path();
+
+    if path == "/sw.js" {
+        let mut response = next.run(req).await;
+
+        // Set cache-busting headers for service worker
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            "no-cache, no-store, must-revalidate".parse().unwrap()
+        );
+        response.headers_mut().insert(
+            header::PRAGMA,
+            "no-cache".parse().unwrap()
+        );
+        response.headers_mut().insert(
+            header::EXPIRES,
+            "0".parse().unwrap()
+        );
+
+        response
+    } else {
+        next.run(req).await
+    }
+}
+
+/// Header carrying the id that ties a request to its log lines
+pub const REQUEST_ID_HEADER: &str = "x-request-id";
+
+/// Longest client-supplied request id kept; longer ones are replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+/// Give every request an `X-Request-Id`, keeping a usable one sent by the
+/// client and generating one otherwise, and echo it on the response. Runs
+/// before `TraceLayer`, which records the id on the request's span.
+pub async fn request_id_middleware(
+    mut req: axum::extract::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    let request_id = req
+        .headers()
+        .get(REQUEST_ID_HEADER)
+        .filter(|value| {
+            let bytes = value.as_bytes();
+            !bytes.is_empty() && bytes.len() <= MAX_REQUEST_ID_LEN && bytes.iter().all(u8::is_ascii_graphic)
+        })
+        .cloned()
+        .unwrap_or_else(|| {
+            axum::http::HeaderValue::from_str(&Uuid::new_v4().to_string())
+                .expect("a UUID is a valid header value")
+        });
+    req.headers_mut().insert(REQUEST_ID_HEADER, request_id.clone());
+
+    let mut response = next.run(req).await;
+    response.headers_mut().insert(REQUEST_ID_HEADER, request_id);
+    response
+}
+
+/// API paths reachable without a user token: the health probes, signing in,
+/// and the admin endpoints, which check the admin token themselves
+const PUBLIC_API_PATHS: &[&str] = &[
+    "/api/health",
+    "/api/livez",
+    "/api/readyz",
+    "/api/auth/register",
+    "/api/auth/login",
+    "/api/auth/refresh",
+    "/api/auth/pair/redeem",
+];
+
+/// Whether `path` needs a user token. Static assets don't, and neither does
+/// the WebSocket, which authenticates its own handshake.
+fn requires_auth(path: &str) -> bool {
+    path.starts_with("/api/")
+        && !path.starts_with("/api/admin/")
+        && !PUBLIC_API_PATHS.contains(&path)
+}
+
+/// Reject requests to protected paths without a valid Bearer token, and hand
+/// the token's `AuthClaims` to the handler as a request extension
+pub async fn auth_middleware(
+    mut req: axum::extract::Request<axum::body::Body>,
+    next: axum::middleware::Next,
+) -> Result<axum::response::Response, StatusCode> {
+    if requires_auth(req.uri().path()) {
+        let claims = authenticate(req.headers())?;
+        req.extensions_mut().insert(claims);
+    }
+    Ok(next.run(req).await)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use tokio::net::TcpListener;
+    use crate::api::health::liveness_check;
+    use crate::api::timer_control::{control_timer, get_timer};
+    use crate::services::auth_service::generate_auth_token;
+    use axum::routing::{get, post};
+    use axum::{Router, middleware};
+    use reqwest::Client;
+
+    #[tokio::test]
+    async fn test_request_id_echoed_or_generated() {
+        use tower::ServiceExt;
+
+        let app: Router = Router::new()
+            .route("/api/ping", get(|| async { "pong" }))
+            .layer(middleware::from_fn(request_id_middleware));
+        let request_id = |request: axum::http::Request<axum::body::Body>| {
+            let app = app.clone();
+            async move {
+                let response = app.oneshot(request).await.unwrap();
+                response.headers().get(REQUEST_ID_HEADER).map(|value| value.to_str().unwrap().to_string())
+            }
+        };
+
+        let supplied = axum::http::Request::get("/api/ping")
+            .header("X-Request-Id", "trace-123")
+            .body(axum::body::Body::empty())
+            .unwrap();
+        assert_eq!(request_id(supplied).await.as_deref(), Some("trace-123"));
+
+        let missing = axum::http::Request::get("/api/ping").body(axum::body::Body::empty()).unwrap();
+        let generated = request_id(missing).await.expect("no request id generated");
+        assert!(Uuid::parse_str(&generated).is_ok(), "{generated}");
+
+        // Unusable ids are replaced rather than echoed
+        let too_long = axum::http::Request::get("/api/ping")
+            .header("X-Request-Id", "x".repeat(MAX_REQUEST_ID_LEN + 1))
+            .body(axum::body::Body::empty())
+            .unwrap();
+        let replaced = request_id(too_long).await.unwrap();
+        assert!(Uuid::parse_str(&replaced).is_ok(), "{replaced}");
+    }
+
+    #[tokio::test]
+    async fn test_auth_middleware_protects_api_paths_only() {
+        use tower::ServiceExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let app_state = test_app_state(&temp_dir).await;
+        let app: Router = Router::new()
+            .route("/api/timer", get(get_timer))
+            .route("/api/livez", get(liveness_check))
+            .fallback(|| async { "static asset" })
+            .layer(middleware::from_fn(auth_middleware))
+            .with_state(app_state);
+        let status = |uri: &str, token: Option<String>| {
+            let mut request = axum::http::Request::get(uri);
+            if let Some(token) = token {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {token}"));
+            }
+            let request = request.body(axum::body::Body::empty()).unwrap();
+            let app = app.clone();
+            async move { app.oneshot(request).await.unwrap().status() }
+        };
+
+        assert_eq!(status("/api/timer", None).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status("/api/timer", Some("not-a-token".to_string())).await, StatusCode::UNAUTHORIZED);
+        assert_eq!(status("/api/timer", Some(generate_auth_token("alice").unwrap())).await, StatusCode::OK);
+
+        assert_eq!(status("/api/livez", None).await, StatusCode::OK);
+        assert_eq!(status("/index.html", None).await, StatusCode::OK);
+
+        assert!(requires_auth("/api/settings"));
+        assert!(requires_auth("/api/auth/logout"));
+        for public in ["/api/auth/login", "/api/auth/pair/redeem", "/api/admin/stats", "/ws", "/sw.js"] {
+            assert!(!requires_auth(public), "{public} should be public");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_malformed_json_gets_structured_error() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let app_state = test_app_state(&temp_dir).await;
+        let app = Router::new()
+            .route("/api/timer", post(control_timer))
+            .layer(middleware::from_fn(auth_middleware))
+            .with_state(app_state);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let token = generate_auth_token("alice").unwrap();
+        let post_body = |body: &'static str| {
+            Client::new()
+                .post(format!("http://{addr}/api/timer"))
+                .bearer_auth(&token)
+                .header("content-type", "application/json")
+                .body(body)
+                .send()
+        };
+
+        let response = post_body("{\"action\": \"start\"").await.unwrap();
+        assert_eq!(response.status().as_u16(), StatusCode::BAD_REQUEST.as_u16());
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "invalid_json");
+        assert!(!body["error"]["message"].as_str().unwrap().is_empty());
+
+        // Valid JSON of the wrong shape is rejected the same way
+        let response = post_body("{\"action\": 42}").await.unwrap();
+        assert_eq!(response.status().as_u16(), StatusCode::UNPROCESSABLE_ENTITY.as_u16());
+        let body: serde_json::Value = response.json().await.unwrap();
+        assert_eq!(body["error"]["code"], "invalid_json");
+    }
+}
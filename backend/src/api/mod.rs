@@ -3,6 +3,19 @@
 //! Contains all REST API endpoints and routing.
 
 pub mod configuration;
+pub mod json;
 pub mod timer;
+pub mod admin;
+pub mod auth;
+pub mod daily_reset;
+pub mod health;
+pub mod middleware;
+pub mod sessions;
+pub mod settings;
+pub mod stats;
+pub mod tasks;
+pub mod timer_control;
+pub mod user_data;
+pub mod webhook;
 
 // Re-export commonly used API components
@@ -0,0 +1,360 @@
+//! Session API Endpoints
+//!
+//! Completed session history and manual session count changes.
+
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::daily_reset::daily_reset_status_message;
+use crate::api::json::ApiJson;
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::CurrentUser;
+use crate::websocket::manager::{CountChangeLimit, SharedWsManager};
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+
+/// Manual session-count changes allowed per user per minute, from
+/// `ROMA_TIMER_SESSION_COUNT_CHANGES_PER_MINUTE`. Defaults to 10; zero disables the limit.
+pub fn get_count_change_limit() -> Option<CountChangeLimit> {
+    let max_changes = env::var("ROMA_TIMER_SESSION_COUNT_CHANGES_PER_MINUTE")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(10);
+    (max_changes > 0).then_some(CountChangeLimit {
+        max_changes,
+        window: Duration::from_secs(60),
+    })
+}
+
+/// Upper bound on sessions returned by one `/api/sessions` request, whatever
+/// `limit` the client asks for
+fn get_max_sessions_per_response() -> u32 {
+    env::var("ROMA_TIMER_MAX_SESSIONS_PER_RESPONSE")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|max| *max > 0)
+        .unwrap_or(500)
+}
+
+/// Window covered by `/api/sessions` when the client gives no start
+const DEFAULT_SESSIONS_WINDOW_SECS: i64 = 7 * 24 * 3600;
+
+#[derive(Debug, Deserialize)]
+pub struct CompletedSessionsQuery {
+    /// Unix seconds, inclusive; defaults to seven days before `end`
+    pub start: Option<i64>,
+    /// Unix seconds, exclusive; defaults to now
+    pub end: Option<i64>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CompletedSessionsResponse {
+    pub start: i64,
+    pub end: i64,
+    pub sessions: Vec<crate::models::timer_session::SessionHistoryEntry>,
+    /// More sessions match; narrow the range or request the next page with `offset`
+    pub has_more: bool,
+}
+
+/// How `PUT /api/sessions/count` changes today's session count
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SessionCountMode {
+    /// Mask the count with a manual override
+    #[default]
+    Set,
+    /// Drop the override, revealing the underlying count
+    Clear,
+    /// Make the override the real count and drop it
+    Merge,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionCountQuery {
+    #[serde(default)]
+    pub mode: SessionCountMode,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct SessionCountRequest {
+    pub count: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SessionCountResponse {
+    pub today_session_count: u32,
+    pub manual_session_override: Option<u32>,
+    pub current_session_count: u32,
+}
+
+/// Change today's session count. Changes beyond the per-user limit are rejected
+/// with `rate_limited`, leaving the last accepted value in place.
+pub async fn update_session_count(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<SessionCountQuery>,
+    request: Option<ApiJson<SessionCountRequest>>,
+) -> Result<Response, StatusCode> {
+    let request = request.map(|ApiJson(request)| request).unwrap_or_default();
+    if query.mode == SessionCountMode::Set && request.count.is_none() {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    if let Err(retry_after) = ws_manager.acquire_count_change(&claims.sub).await {
+        tracing::debug!("Rate limited session count change for {}", claims.sub);
+        let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+        return Ok((
+            StatusCode::TOO_MANY_REQUESTS,
+            [(header::RETRY_AFTER, retry_after_secs.to_string())],
+            Json(serde_json::json!({
+                "error": "rate_limited",
+                "message": format!("Too many session count changes; try again in {retry_after_secs}s"),
+            })),
+        )
+            .into_response());
+    }
+
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    let result = match query.mode {
+        SessionCountMode::Set => {
+            let count = request.count.ok_or(StatusCode::BAD_REQUEST)?;
+            service.set_manual_session_override(&claims.sub, Some(count)).await
+        }
+        SessionCountMode::Clear => service.set_manual_session_override(&claims.sub, None).await,
+        SessionCountMode::Merge => service.merge_manual_session_override(&claims.sub).await,
+    };
+
+    let config = result.map_err(|e| {
+        tracing::warn!("Failed to update session count for {}: {e}", claims.sub);
+        e.status_code()
+    })?;
+
+    // Let the user's devices show the new count
+    let status = daily_reset_status_message(ws_manager.database.clone(), &claims.sub).await;
+    ws_manager.broadcast_message(&claims.sub, status).await;
+
+    Ok(Json(SessionCountResponse {
+        today_session_count: config.today_session_count,
+        manual_session_override: config.manual_session_override,
+        current_session_count: config.get_current_session_count(),
+    })
+    .into_response())
+}
+
+/// The user's completed and skipped sessions in a time range, newest first.
+/// Without a range only the last seven days are returned, and pages never
+/// exceed the configured maximum.
+pub async fn list_completed_sessions(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<CompletedSessionsQuery>,
+) -> Result<Json<CompletedSessionsResponse>, StatusCode> {
+    let end = query.end.unwrap_or_else(|| chrono::Utc::now().timestamp() + 1);
+    let start = query.start.unwrap_or(end - DEFAULT_SESSIONS_WINDOW_SECS);
+    if start > end {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let max = get_max_sessions_per_response();
+    let limit = query.limit.unwrap_or(max).clamp(1, max);
+    let offset = query.offset.unwrap_or(0);
+
+    // Fetch one extra row to tell the client whether there is more
+    let mut sessions = ws_manager
+        .database
+        .get_session_history(&claims.sub, start, end, limit + 1, offset)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load session history: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    let has_more = sessions.len() > limit as usize;
+    sessions.truncate(limit as usize);
+
+    Ok(Json(CompletedSessionsResponse {
+        start,
+        end,
+        sessions,
+        has_more,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use tokio::sync::mpsc;
+    use crate::services::timer_control_service::count_completed_work_session;
+    use crate::websocket::manager::{WebSocketManager, WsMessage};
+    use axum::extract::ws::Message;
+
+    #[tokio::test]
+    async fn test_completed_sessions_default_window_and_cap() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let now = chrono::Utc::now().timestamp();
+        let database = &ws_manager.database;
+
+        database.record_completed_session("alice", "work", 1500, 0, "laptop", now - 8 * 86_400, None).await.unwrap();
+        let max = get_max_sessions_per_response() as i64;
+        for i in 0..max + 5 {
+            database.record_completed_session("alice", "work", 1500, 0, "laptop", now - 3600 - i, None).await.unwrap();
+        }
+
+        let Json(response) = list_completed_sessions(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            axum::extract::Query(CompletedSessionsQuery {
+                start: None,
+                end: None,
+                limit: Some(100_000),
+                offset: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.end - response.start, DEFAULT_SESSIONS_WINDOW_SECS);
+        assert_eq!(response.sessions.len() as i64, max);
+        assert!(response.has_more);
+        assert!(response.sessions.iter().all(|session| session.completed_at >= response.start));
+
+        // The remainder of the window is on the next page; the 8-day-old session never appears
+        let Json(next_page) = list_completed_sessions(
+            State((state, ws_manager)),
+            current_user("alice"),
+            axum::extract::Query(CompletedSessionsQuery {
+                start: None,
+                end: None,
+                limit: None,
+                offset: Some(max as u32),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(next_page.sessions.len(), 5);
+        assert!(!next_page.has_more);
+    }
+
+    #[tokio::test]
+    async fn test_merge_session_count_override() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, daily_reset_enabled, today_session_count, created_at, updated_at)
+            VALUES ('alice', 'UTC', TRUE, 3, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let update = |mode: SessionCountMode, count: Option<u32>| {
+            update_session_count(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                axum::extract::Query(SessionCountQuery { mode }),
+                Some(ApiJson(SessionCountRequest { count })),
+            )
+        };
+
+        let masked: SessionCountResponse = response_json(update(SessionCountMode::Set, Some(10)).await.unwrap()).await;
+        assert_eq!(masked.today_session_count, 3);
+        assert_eq!(masked.current_session_count, 10);
+
+        let merged: SessionCountResponse = response_json(update(SessionCountMode::Merge, None).await.unwrap()).await;
+        assert_eq!(merged.today_session_count, 10);
+        assert_eq!(merged.manual_session_override, None);
+        assert_eq!(merged.current_session_count, 10);
+
+        // Automated counting continues from the merged value
+        assert_eq!(count_completed_work_session(&ws_manager, "alice").await, Some(11));
+
+        // Clearing, unlike merging, reveals the underlying count
+        update(SessionCountMode::Set, Some(2)).await.unwrap();
+        let cleared: SessionCountResponse = response_json(update(SessionCountMode::Clear, None).await.unwrap()).await;
+        assert_eq!(cleared.current_session_count, 11);
+
+        assert_eq!(update(SessionCountMode::Set, None).await.unwrap_err(), StatusCode::BAD_REQUEST);
+    }
+
+    #[tokio::test]
+    async fn test_session_count_changes_are_rate_limited() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone()).with_count_change_limit(Some(
+                CountChangeLimit {
+                    max_changes: 3,
+                    window: Duration::from_secs(60),
+                },
+            )),
+        );
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, timezone, daily_reset_enabled, created_at, updated_at) VALUES ('alice', 'UTC', TRUE, 0, 0)"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        let mut statuses = Vec::new();
+        for count in 1..=10 {
+            let response = update_session_count(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                axum::extract::Query(SessionCountQuery { mode: SessionCountMode::Set }),
+                Some(ApiJson(SessionCountRequest { count: Some(count) })),
+            )
+            .await
+            .unwrap();
+            statuses.push(response.status());
+            if response.status() == StatusCode::TOO_MANY_REQUESTS {
+                let body: serde_json::Value = response_json(response).await;
+                assert_eq!(body["error"], "rate_limited");
+            }
+        }
+        assert!(statuses[..3].iter().all(|status| *status == StatusCode::OK));
+        assert!(statuses[3..].iter().all(|status| *status == StatusCode::TOO_MANY_REQUESTS));
+
+        // The last accepted value is kept and was the last one broadcast
+        let (manual_override,): (Option<i64>,) =
+            sqlx::query_as("SELECT manual_session_override FROM user_configurations WHERE id = 'alice'")
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        assert_eq!(manual_override, Some(3));
+
+        let broadcast_counts: Vec<u32> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                Message::Text(text) => match serde_json::from_str::<WsMessage>(&text) {
+                    Ok(WsMessage::DailyResetStatus(status)) => Some(status.current_session_count),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(broadcast_counts, [1, 2, 3]);
+
+        // Other users have their own allowance
+        assert!(ws_manager.acquire_count_change("bob").await.is_ok());
+    }
+}
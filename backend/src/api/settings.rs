@@ -0,0 +1,91 @@
+//! Settings API Endpoints
+//!
+//! Reads and updates the current user's timer settings.
+
+use crate::api::json::ApiJson;
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::CurrentUser;
+use crate::services::settings_service::{SettingsOutcome, SettingsRequest, submit_settings_update};
+use crate::websocket::manager::SharedWsManager;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+
+pub async fn get_settings(
+    State((state, _)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<serde_json::Value>, StatusCode> {
+    let timer_state = state.lock().await.get(&claims.sub);
+    Ok(Json(serde_json::json!({
+        "work_duration": timer_state.work_duration,
+        "short_break_duration": timer_state.short_break_duration,
+        "long_break_duration": timer_state.long_break_duration,
+        "long_break_frequency": timer_state.long_break_frequency,
+        "session_type_labels": timer_state.session_type_labels,
+    })))
+}
+
+pub async fn update_settings(
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    ApiJson(request): ApiJson<SettingsRequest>,
+) -> Result<Response, StatusCode> {
+    match submit_settings_update(&state, &ws_manager, &claims.sub, request).await {
+        SettingsOutcome::Applied(updated_state) => Ok(Json(updated_state).into_response()),
+        SettingsOutcome::Rejected(reason) => Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "invalid_settings", "message": reason })),
+        )
+            .into_response()),
+        SettingsOutcome::Deferred { retry_after } => {
+            // Accepted but coalesced: the merged values are applied when the window ends
+            let current_state = state.lock().await.get(&claims.sub);
+            let retry_after_secs = retry_after.as_secs_f64().ceil().max(1.0) as u64;
+            Ok((
+                StatusCode::ACCEPTED,
+                [(header::RETRY_AFTER, retry_after_secs.to_string())],
+                Json(current_state),
+            )
+                .into_response())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn test_long_break_frequency_setting_is_applied_and_persisted() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let response = update_settings(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(SettingsRequest {
+                work_duration: None,
+                short_break_duration: None,
+                long_break_duration: None,
+                long_break_frequency: Some(6),
+                session_type_labels: None,
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let Json(settings) = get_settings(State((state.clone(), ws_manager.clone())), current_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(settings["long_break_frequency"], 6);
+        assert_eq!(state.lock().await.get("alice").long_break_every(None), Some(6));
+
+        let persisted = ws_manager.database.get_timer_state("alice").await.unwrap().unwrap();
+        assert_eq!(persisted.long_break_frequency, 6);
+    }
+}
@@ -0,0 +1,453 @@
+//! Statistics API Endpoints
+//!
+//! Daily, weekly, label, streak and goal statistics plus CSV/JSON export.
+
+use std::env;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+
+use crate::database::DatabaseManager;
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::CurrentUser;
+use crate::websocket::manager::SharedWsManager;
+
+use axum::{
+    extract::State,
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use futures_util::StreamExt;
+use serde::Deserialize;
+
+/// Focus score weights, overridable per deployment
+fn get_focus_score_weights() -> crate::services::stats_service::FocusScoreWeights {
+    let defaults = crate::services::stats_service::FocusScoreWeights::default();
+    let weight = |name: &str, default: f64| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse::<f64>().ok())
+            .filter(|value| value.is_finite() && *value >= 0.0)
+            .unwrap_or(default)
+    };
+
+    crate::services::stats_service::FocusScoreWeights {
+        skip_penalty: weight("ROMA_TIMER_FOCUS_SKIP_PENALTY", defaults.skip_penalty),
+        abandon_penalty: weight("ROMA_TIMER_FOCUS_ABANDON_PENALTY", defaults.abandon_penalty),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DailyStatsQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+}
+
+/// The user's configured timezone, UTC if they have no configuration
+pub async fn user_timezone(database: &Arc<DatabaseManager>, user_id: &str) -> Result<String, StatusCode> {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        database.clone(),
+    );
+    Ok(service
+        .find_user_configuration(user_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?
+        .map(|config| config.timezone)
+        .unwrap_or_else(|| "UTC".to_string()))
+}
+
+/// `user_id`'s work-session metrics for the local days `from` through `to`
+async fn session_metrics_for_range(
+    database: &DatabaseManager,
+    user_id: &str,
+    tz: chrono_tz::Tz,
+    from: chrono::NaiveDate,
+    to: chrono::NaiveDate,
+) -> Result<std::collections::BTreeMap<chrono::NaiveDate, crate::services::stats_service::SessionMetrics>, StatusCode> {
+    let (since, until) = crate::services::stats_service::local_day_bounds(tz, from, to);
+    let sessions = database
+        .get_finished_sessions_between(user_id, since, until)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load sessions: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+    Ok(crate::services::stats_service::session_metrics_by_day(&sessions, tz))
+}
+
+pub async fn daily_stats(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<DailyStatsQuery>,
+) -> Result<Json<Vec<crate::services::stats_service::DailyStats>>, StatusCode> {
+    let database = &ws_manager.database;
+
+    let timezone = user_timezone(database, &claims.sub).await?;
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+
+    // Default to the last week, ending today in the user's timezone
+    let to = query
+        .to
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).date_naive());
+    let from = query.from.unwrap_or(to - chrono::Duration::days(6));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let metrics = session_metrics_for_range(database, &claims.sub, tz, from, to).await?;
+    Ok(Json(crate::services::stats_service::daily_stats(&metrics, &get_focus_score_weights())))
+}
+
+/// Focus time per session label over local days `from` through `to`
+/// (default: the last week)
+pub async fn label_stats(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<DailyStatsQuery>,
+) -> Result<Json<Vec<crate::database::connection::LabelFocusRow>>, StatusCode> {
+    let database = &ws_manager.database;
+
+    let timezone = user_timezone(database, &claims.sub).await?;
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    let to = query
+        .to
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).date_naive());
+    let from = query.from.unwrap_or(to - chrono::Duration::days(6));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let (since, until) = crate::services::stats_service::local_day_bounds(tz, from, to);
+    let rows = database.focus_by_label(&claims.sub, since, until).await.map_err(|e| {
+        tracing::error!("Failed to load label stats: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    })?;
+
+    Ok(Json(rows))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WeeklyStatsQuery {
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub week_start: Option<crate::services::stats_service::WeekStart>,
+}
+
+pub async fn weekly_stats(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<WeeklyStatsQuery>,
+) -> Result<Json<Vec<crate::services::stats_service::WeeklyStats>>, StatusCode> {
+    use crate::services::stats_service::{weekly_rollup, WeekStart};
+
+    let database = ws_manager.database.clone();
+
+    let timezone = user_timezone(&database, &claims.sub).await?;
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+
+    let week_start = query
+        .week_start
+        .unwrap_or_else(|| WeekStart::default_for_timezone(&timezone));
+    // Default to the last four weeks, ending today in the user's timezone
+    let to = query
+        .to
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).date_naive());
+    let from = query
+        .from
+        .unwrap_or_else(|| week_start.week_containing(to) - chrono::Duration::weeks(3));
+    if from > to {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let daily_stats = database
+        .get_daily_session_stats_range(&claims.sub, from, to)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to load daily stats: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    let metrics = session_metrics_for_range(&database, &claims.sub, tz, from, to).await?;
+
+    Ok(Json(weekly_rollup(
+        &daily_stats,
+        &metrics,
+        week_start,
+        &get_focus_score_weights(),
+    )))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AggregatedStatsQuery {
+    #[serde(default)]
+    pub granularity: crate::services::stats_service::Granularity,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+}
+
+/// Archived work totals per day, week or month over local days `from` through
+/// `to` (default: the last 30 days)
+pub async fn aggregated_stats(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<AggregatedStatsQuery>,
+) -> Result<Json<Vec<crate::services::stats_service::AggregatedStats>>, StatusCode> {
+    let timezone = user_timezone(&ws_manager.database, &claims.sub).await?;
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    let to = query
+        .to
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).date_naive());
+    let from = query.from.unwrap_or(to - chrono::Duration::days(29));
+
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    let stats = service
+        .get_aggregated_statistics(&claims.sub, from, to, query.granularity)
+        .await
+        .map_err(|e| {
+            tracing::warn!("Failed to aggregate stats for {}: {e}", claims.sub);
+            e.status_code()
+        })?;
+
+    Ok(Json(stats))
+}
+
+/// The caller's current and longest daily streaks
+pub async fn streak_stats(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<crate::services::stats_service::Streaks>, StatusCode> {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    let streaks = service.get_streaks(&claims.sub).await.map_err(|e| {
+        tracing::warn!("Failed to compute streaks for {}: {e}", claims.sub);
+        e.status_code()
+    })?;
+
+    Ok(Json(streaks))
+}
+
+/// The caller's session count today against their daily goal
+pub async fn goal_stats(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<crate::services::stats_service::GoalProgress>, StatusCode> {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    let progress = service.get_goal_progress(&claims.sub).await.map_err(|e| {
+        tracing::warn!("Failed to get goal progress for {}: {e}", claims.sub);
+        e.status_code()
+    })?;
+
+    Ok(Json(progress))
+}
+
+/// Export formats for session statistics
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StatsExportFormat {
+    #[default]
+    Csv,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct StatsExportQuery {
+    #[serde(default)]
+    pub format: StatsExportFormat,
+    pub start: Option<chrono::NaiveDate>,
+    pub end: Option<chrono::NaiveDate>,
+}
+
+/// Rows buffered between the database reader and the response body
+const STATS_EXPORT_BUFFER_ROWS: usize = 64;
+
+/// Download the caller's archived daily statistics for local days `start`
+/// through `end` (default: everything up to today). Rows are streamed to the
+/// client as they are read.
+pub async fn export_stats(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Query(query): axum::extract::Query<StatsExportQuery>,
+) -> Result<Response, StatusCode> {
+    use crate::services::stats_service::{daily_stats_csv_row, DAILY_STATS_CSV_HEADER};
+
+    let StatsExportFormat::Csv = query.format;
+
+    let timezone = user_timezone(&ws_manager.database, &claims.sub).await?;
+    let tz: chrono_tz::Tz = timezone.parse().unwrap_or(chrono_tz::UTC);
+    let end = query
+        .end
+        .unwrap_or_else(|| chrono::Utc::now().with_timezone(&tz).date_naive());
+    let start = query
+        .start
+        .unwrap_or(chrono::DateTime::UNIX_EPOCH.date_naive());
+    if start > end {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let (sender, receiver) = mpsc::channel::<String>(STATS_EXPORT_BUFFER_ROWS);
+    let database = ws_manager.database.clone();
+    let user_id = claims.sub.clone();
+    tokio::spawn(async move {
+        if sender.send(DAILY_STATS_CSV_HEADER.to_string()).await.is_err() {
+            return;
+        }
+        let mut rows = database.stream_daily_session_stats_range(&user_id, start, end);
+        while let Some(row) = rows.next().await {
+            match row {
+                Ok(stats) => {
+                    // The client went away
+                    if sender.send(daily_stats_csv_row(&stats)).await.is_err() {
+                        return;
+                    }
+                }
+                Err(e) => {
+                    // Headers are already sent; ending early is all that's left
+                    tracing::error!("Stats export for {user_id} failed partway: {e}");
+                    return;
+                }
+            }
+        }
+    });
+
+    let body = futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver
+            .recv()
+            .await
+            .map(|chunk| (Ok::<_, std::convert::Infallible>(chunk), receiver))
+    });
+    let filename = format!("roma-timer-stats-{start}-to-{end}.csv");
+    Ok((
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{filename}\"")),
+        ],
+        axum::body::Body::from_stream(body),
+    )
+        .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn test_label_stats_only_include_own_sessions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let now = chrono::Utc::now().timestamp();
+        for (user_id, label) in [("alice", "writing"), ("alice", "writing"), ("bob", "secret project")] {
+            ws_manager
+                .database
+                .record_completed_session(user_id, "work", 1500, 0, "server", now, Some(label))
+                .await
+                .unwrap();
+        }
+
+        let Json(rows) = label_stats(
+            State((state, ws_manager)),
+            current_user("alice"),
+            axum::extract::Query(DailyStatsQuery { from: None, to: None }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].label.as_deref(), Some("writing"));
+        assert_eq!((rows[0].sessions, rows[0].focus_seconds), (2, 3000));
+    }
+
+    #[tokio::test]
+    async fn test_daily_stats_only_include_own_sessions() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let now = chrono::Utc::now().timestamp();
+        for (user_id, duration) in [("alice", 1500), ("bob", 3000), ("bob", 3000)] {
+            ws_manager
+                .database
+                .record_completed_session(user_id, "work", duration, 0, "server", now, None)
+                .await
+                .unwrap();
+        }
+
+        let Json(days) = daily_stats(
+            State((state, ws_manager)),
+            current_user("alice"),
+            axum::extract::Query(DailyStatsQuery { from: None, to: None }),
+        )
+        .await
+        .unwrap();
+        let sessions: i64 = days.iter().map(|day| day.sessions).sum();
+        let focus_seconds: i64 = days.iter().map(|day| day.focus_seconds).sum();
+        assert_eq!((sessions, focus_seconds), (1, 1500));
+    }
+
+    #[tokio::test]
+    async fn test_stats_csv_export() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        for (id, user, date, sessions) in [
+            ("s1", "alice", "2024-03-08", 2),
+            ("s2", "alice", "2024-03-09", 5),
+            ("s3", "alice", "2024-04-01", 1),
+            ("s4", "bob", "2024-03-09", 7),
+        ] {
+            sqlx::query(
+                r#"
+                INSERT INTO daily_session_stats (id, user_configuration_id, date, timezone, work_sessions_completed,
+                    total_work_seconds, total_break_seconds, manual_overrides, final_session_count, created_at, updated_at)
+                VALUES (?, ?, ?, 'Europe/Paris', ?, ?, 300, 1, ?, 0, 0)
+                "#,
+            )
+            .bind(id)
+            .bind(user)
+            .bind(date)
+            .bind(sessions)
+            .bind(sessions * 1500)
+            .bind(sessions)
+            .execute(pool)
+            .await
+            .unwrap();
+        }
+
+        let response = export_stats(
+            State((state, ws_manager)),
+            current_user("alice"),
+            axum::extract::Query(StatsExportQuery {
+                format: StatsExportFormat::Csv,
+                start: chrono::NaiveDate::from_ymd_opt(2024, 3, 1),
+                end: chrono::NaiveDate::from_ymd_opt(2024, 3, 31),
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "text/csv; charset=utf-8");
+        assert_eq!(
+            response.headers()[header::CONTENT_DISPOSITION],
+            "attachment; filename=\"roma-timer-stats-2024-03-01-to-2024-03-31.csv\""
+        );
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<_> = csv.lines().collect();
+        assert_eq!(
+            lines,
+            vec![
+                "date,timezone,work_sessions,total_work_seconds,break_seconds,manual_overrides,final_count",
+                "2024-03-08,Europe/Paris,2,3000,300,1,2",
+                "2024-03-09,Europe/Paris,5,7500,300,1,5",
+            ]
+        );
+    }
+}
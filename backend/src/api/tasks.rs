@@ -0,0 +1,126 @@
+//! Scheduled Task API Endpoints
+//!
+//! Lists and cancels the current user's scheduled tasks.
+
+
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::CurrentUser;
+use crate::websocket::manager::SharedWsManager;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::Json,
+};
+use serde::Serialize;
+
+#[derive(Debug, Serialize)]
+pub struct ScheduledTaskSummary {
+    pub id: String,
+    pub task_type: String,
+    pub next_run_utc: i64,
+    pub is_active: bool,
+    pub run_count: i64,
+    pub failure_count: i64,
+}
+
+pub async fn list_tasks(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<Vec<ScheduledTaskSummary>>, StatusCode> {
+    let tasks = ws_manager
+        .database
+        .get_scheduled_tasks_for_user(&claims.sub)
+        .await
+        .map_err(|e| {
+            tracing::error!("Failed to list scheduled tasks: {e}");
+            StatusCode::INTERNAL_SERVER_ERROR
+        })?;
+
+    Ok(Json(
+        tasks
+            .into_iter()
+            .map(|task| ScheduledTaskSummary {
+                task_type: task.task_type_str().to_string(),
+                id: task.id,
+                next_run_utc: task.next_run_utc,
+                is_active: task.is_active,
+                run_count: task.run_count,
+                failure_count: task.failure_count,
+            })
+            .collect(),
+    ))
+}
+
+pub async fn cancel_task(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    axum::extract::Path(task_id): axum::extract::Path<String>,
+) -> Result<StatusCode, StatusCode> {
+    let database = &ws_manager.database;
+
+    let task = database
+        .get_scheduled_task(&task_id)
+        .await
+        .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+
+    // Tasks owned by other users are reported as missing so ids can't be probed
+    match task {
+        Some(task) if task.user_configuration_id.as_deref() == Some(claims.sub.as_str()) => {
+            database
+                .deactivate_scheduled_task(&task_id)
+                .await
+                .map_err(|_| StatusCode::INTERNAL_SERVER_ERROR)?;
+            tracing::info!("Cancelled scheduled task {task_id} for user {}", claims.sub);
+            Ok(StatusCode::NO_CONTENT)
+        }
+        _ => Err(StatusCode::NOT_FOUND),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn test_list_and_cancel_tasks() {
+        use crate::models::scheduled_task::ScheduledTask;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let own_task = ScheduledTask::daily_reset_task("alice".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        let other_task = ScheduledTask::daily_reset_task("bob".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        ws_manager.database.save_scheduled_task(&own_task).await.unwrap();
+        ws_manager.database.save_scheduled_task(&other_task).await.unwrap();
+
+        let Json(tasks) = list_tasks(State((state.clone(), ws_manager.clone())), current_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(tasks.len(), 1);
+        assert_eq!(tasks[0].id, own_task.id);
+        assert!(tasks[0].is_active);
+
+        let status = cancel_task(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            axum::extract::Path(own_task.id.clone()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(status, StatusCode::NO_CONTENT);
+        let cancelled = ws_manager.database.get_scheduled_task(&own_task.id).await.unwrap().unwrap();
+        assert!(!cancelled.is_active);
+
+        let result = cancel_task(
+            State((state, ws_manager.clone())),
+            current_user("alice"),
+            axum::extract::Path(other_task.id.clone()),
+        )
+        .await;
+        assert_eq!(result, Err(StatusCode::NOT_FOUND));
+        let untouched = ws_manager.database.get_scheduled_task(&other_task.id).await.unwrap().unwrap();
+        assert!(untouched.is_active);
+    }
+}
@@ -5,6 +5,7 @@
 
 use crate::services::timer_service::{TimerService, TimerServiceError, TimerState};
 use crate::models::timer_session::TimerSession;
+use crate::services::time_provider::now_unix;
 use axum::{
     extract::{Path, State},
     http::StatusCode,
@@ -140,10 +141,7 @@ impl axum::response::IntoResponse for ApiError {
         let body = Json(json!({
             "error": status.as_str(),
             "message": error_message,
-            "timestamp": std::time::SystemTime::now()
-                .duration_since(std::time::UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
+            "timestamp": now_unix()
         }));
 
         (status, body).into_response()
@@ -0,0 +1,336 @@
+//! Timer Control API Endpoints
+//!
+//! REST endpoints for the shared per-user timer: actions, added time and session plans.
+
+use std::env;
+
+use crate::api::json::ApiJson;
+use crate::models::timer_state::{
+    PlanStep, SessionPlan, SharedState, TimerState, validate_plan_steps,
+};
+use crate::services::auth_service::CurrentUser;
+use crate::services::time_provider::now_unix;
+use crate::services::timer_control_service::control_user_timer;
+use crate::websocket::manager::SharedWsManager;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerRequest {
+    pub action: String,
+    /// Label for the session being started; an empty label clears it
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Most seconds add-time may extend a single session by, from
+/// `ROMA_TIMER_MAX_ADDED_SECONDS`. Defaults to 30 minutes; zero disables the cap.
+pub fn get_max_added_seconds() -> Option<u32> {
+    let max_added_seconds = env::var("ROMA_TIMER_MAX_ADDED_SECONDS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(30 * 60);
+    (max_added_seconds > 0).then_some(max_added_seconds)
+}
+
+pub async fn get_timer(
+    State((state, _)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<TimerState>, StatusCode> {
+    let timer_state = state.lock().await.get(&claims.sub);
+    Ok(Json(timer_state))
+}
+
+pub async fn control_timer(
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    ApiJson(request): ApiJson<TimerRequest>,
+) -> Result<Json<TimerState>, StatusCode> {
+    let updated_state = control_user_timer(&state, &ws_manager, &claims.sub, request)
+        .await
+        .map_err(|e| {
+            tracing::info!("Rejected timer action: {e}");
+            e.status_code()
+        })?;
+    Ok(Json(updated_state))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddTimeRequest {
+    /// Seconds to add to the current session
+    pub seconds: u32,
+}
+
+/// Extend the current session by `request.seconds`. Rejected once the total
+/// added to the session would pass the configured cap.
+pub async fn add_session_time(
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    ApiJson(request): ApiJson<AddTimeRequest>,
+) -> Result<Response, StatusCode> {
+    if request.seconds == 0 {
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut states = state.lock().await;
+    let timer_state = states.user(&claims.sub);
+    let added_seconds = timer_state.added_seconds.saturating_add(request.seconds);
+    if let Some(max_added_seconds) = ws_manager.max_added_seconds.filter(|max| added_seconds > *max) {
+        let allowance = max_added_seconds.saturating_sub(timer_state.added_seconds);
+        return Ok((
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({
+                "error": "add_time_limit",
+                "message": format!(
+                    "At most {max_added_seconds}s can be added to a session; {allowance}s remaining"
+                ),
+            })),
+        )
+            .into_response());
+    }
+
+    timer_state.added_seconds = added_seconds;
+    timer_state.remaining_seconds = timer_state.remaining_seconds.saturating_add(request.seconds);
+    if let Some(ends_at) = timer_state.session_ends_at.as_mut() {
+        *ends_at += request.seconds as u64;
+    }
+    timer_state.last_updated = now_unix();
+
+    let updated_state = timer_state.clone();
+    drop(states);
+
+    ws_manager.update_timer_state(&claims.sub, updated_state.clone()).await;
+    Ok(Json(updated_state).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetRemainingRequest {
+    pub remaining_seconds: u32,
+}
+
+/// Jump the current session to `request.remaining_seconds`. The value must be
+/// between one second and the session's duration, including any time added.
+pub async fn set_remaining_time(
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    ApiJson(request): ApiJson<SetRemainingRequest>,
+) -> Result<Response, StatusCode> {
+    let mut states = state.lock().await;
+    let timer_state = states.user(&claims.sub);
+    let max_seconds = timer_state.extended_duration();
+    if request.remaining_seconds == 0 || request.remaining_seconds > max_seconds {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "invalid_remaining",
+                "message": format!(
+                    "Remaining time must be between 1 and {max_seconds} seconds for a {} session",
+                    timer_state.session_type
+                ),
+            })),
+        )
+            .into_response());
+    }
+
+    let now = now_unix();
+    timer_state.remaining_seconds = request.remaining_seconds;
+    if timer_state.is_running {
+        timer_state.session_ends_at = Some(now + request.remaining_seconds as u64);
+    }
+    timer_state.last_updated = now;
+
+    let updated_state = timer_state.clone();
+    drop(states);
+
+    ws_manager.update_timer_state(&claims.sub, updated_state.clone()).await;
+    Ok(Json(updated_state).into_response())
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SessionPlanRequest {
+    pub steps: Vec<PlanStep>,
+}
+
+/// Queue a plan of sessions and load its first step. Rejected while the timer
+/// is running so the session in progress isn't cut short.
+pub async fn set_session_plan(
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    ApiJson(request): ApiJson<SessionPlanRequest>,
+) -> Result<Response, StatusCode> {
+    if let Err(reason) = validate_plan_steps(&request.steps) {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "invalid_plan", "message": reason })),
+        )
+            .into_response());
+    }
+
+    let mut states = state.lock().await;
+    let timer_state = states.user(&claims.sub);
+    if timer_state.is_running {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let first_step = request.steps[0].clone();
+    timer_state.plan = Some(SessionPlan {
+        steps: request.steps,
+        current: 0,
+    });
+    timer_state.session_type = first_step.session_type;
+    timer_state.remaining_seconds = first_step.duration;
+    timer_state.added_seconds = 0;
+    timer_state.last_updated = now_unix();
+
+    let updated_state = timer_state.clone();
+    drop(states);
+
+    ws_manager.update_timer_state(&claims.sub, updated_state.clone()).await;
+    Ok(Json(updated_state).into_response())
+}
+
+/// Drop the queued plan and go back to the default cycle. A session in
+/// progress keeps running, capped at its type's configured duration.
+pub async fn clear_session_plan(
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<TimerState>, StatusCode> {
+    let mut states = state.lock().await;
+    let timer_state = states.user(&claims.sub);
+    if timer_state.plan.take().is_none() {
+        return Ok(Json(timer_state.clone()));
+    }
+    if !timer_state.is_running {
+        timer_state.rewind();
+    }
+    if timer_state.normalize() && timer_state.is_running {
+        timer_state.start(now_unix());
+    }
+    timer_state.last_updated = now_unix();
+
+    let updated_state = timer_state.clone();
+    drop(states);
+
+    ws_manager.update_timer_state(&claims.sub, updated_state.clone()).await;
+    Ok(Json(updated_state))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use crate::services::timer_control_service::start_timer;
+    use crate::websocket::manager::{WebSocketManager, WsMessage};
+    use axum::extract::ws::Message;
+
+    #[tokio::test]
+    async fn test_add_time_is_capped_and_recorded() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone()).with_max_added_seconds(Some(600)),
+        );
+
+        let add_time = |seconds: u32| {
+            add_session_time(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(AddTimeRequest { seconds }),
+            )
+        };
+
+        // Up to the cap is allowed
+        for _ in 0..2 {
+            let response = add_time(300).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(add_time(0).await.unwrap_err(), StatusCode::BAD_REQUEST);
+        let extended = state.lock().await.get("alice");
+        assert_eq!(extended.added_seconds, 600);
+        assert_eq!(extended.remaining_seconds, 25 * 60 + 600);
+        assert_eq!(extended.extended_duration(), 25 * 60 + 600);
+
+        // Beyond it is rejected and changes nothing
+        let rejected = add_time(1).await.unwrap();
+        assert_eq!(rejected.status(), StatusCode::CONFLICT);
+        let body: serde_json::Value = response_json(rejected).await;
+        assert_eq!(body["error"], "add_time_limit");
+        assert_eq!(state.lock().await.get("alice").remaining_seconds, 25 * 60 + 600);
+
+        // The completed session records the planned duration and the time added
+        state.lock().await.user("alice").remaining_seconds = 1;
+        start_timer(&state, &ws_manager, "alice".to_string(), None).await;
+
+        let now = chrono::Utc::now().timestamp();
+        let mut sessions = Vec::new();
+        for _ in 0..60 {
+            sessions = ws_manager
+                .database
+                .get_completed_sessions("alice", now - 60, now + 60, 10, 0)
+                .await
+                .unwrap();
+            if !sessions.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].duration, 25 * 60);
+        assert_eq!(sessions[0].added_seconds, 600);
+        assert_eq!(sessions[0].elapsed, 25 * 60 + 600);
+
+        // The next session starts without any added time
+        let next = state.lock().await.get("alice");
+        assert_eq!(next.session_type, "short_break");
+        assert_eq!(next.added_seconds, 0);
+    }
+
+    #[tokio::test]
+    async fn test_set_remaining_time_is_bounded_by_session_duration() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("alice-phone".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        let set_remaining = |remaining_seconds: u32| {
+            set_remaining_time(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(SetRemainingRequest { remaining_seconds }),
+            )
+        };
+
+        let response = set_remaining(12 * 60 + 30).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let updated: TimerState = response_json(response).await;
+        assert_eq!(updated.remaining_seconds, 12 * 60 + 30);
+        assert_eq!(state.lock().await.get("alice").remaining_seconds, 12 * 60 + 30);
+
+        let broadcast = std::iter::from_fn(|| receiver.try_recv().ok()).find_map(|message| match message {
+            Message::Text(text) => match serde_json::from_str::<WsMessage>(&text) {
+                Ok(WsMessage::TimerStateUpdate(broadcast)) => Some(broadcast),
+                _ => None,
+            },
+            _ => None,
+        });
+        assert_eq!(broadcast.unwrap().state.remaining_seconds, 12 * 60 + 30);
+
+        // Longer than the work session, or zero, is rejected and changes nothing
+        for remaining_seconds in [25 * 60 + 1, 0] {
+            let rejected = set_remaining(remaining_seconds).await.unwrap();
+            assert_eq!(rejected.status(), StatusCode::BAD_REQUEST);
+            let body: serde_json::Value = response_json(rejected).await;
+            assert_eq!(body["error"], "invalid_remaining");
+        }
+        assert_eq!(state.lock().await.get("alice").remaining_seconds, 12 * 60 + 30);
+        assert!(receiver.try_recv().is_err());
+    }
+}
@@ -0,0 +1,217 @@
+//! User Data API Endpoints
+//!
+//! Exports and imports a user's configuration and session history.
+
+use std::sync::Arc;
+
+use crate::api::daily_reset::daily_reset_status_message;
+use crate::api::json::ApiJson;
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::CurrentUser;
+use crate::websocket::manager::SharedWsManager;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+
+/// Everything stored for `user_id`, in the export document format
+async fn user_data_export(
+    ws_manager: &SharedWsManager,
+    user_id: &str,
+) -> Result<crate::models::user_data_export::UserDataExport, StatusCode> {
+    let database = &ws_manager.database;
+    let internal_error = |what: &str, e: &dyn std::fmt::Display| {
+        tracing::error!("Failed to export {what} for {user_id}: {e}");
+        StatusCode::INTERNAL_SERVER_ERROR
+    };
+
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        database.clone(),
+    );
+    let configuration = service
+        .find_user_configuration(user_id)
+        .await
+        .map_err(|e| internal_error("configuration", &e))?
+        .unwrap_or_else(|| crate::models::user_configuration::UserConfiguration::with_id(user_id.to_string()));
+    let sessions = database
+        .get_user_sessions(user_id)
+        .await
+        .map_err(|e| internal_error("sessions", &e))?;
+    let last_day = chrono::NaiveDate::from_ymd_opt(9999, 12, 31).ok_or(StatusCode::INTERNAL_SERVER_ERROR)?;
+    let daily_stats = database
+        .get_daily_session_stats_range(user_id, chrono::DateTime::UNIX_EPOCH.date_naive(), last_day)
+        .await
+        .map_err(|e| internal_error("daily stats", &e))?;
+    let reset_events = database
+        .get_session_reset_events(&crate::models::session_reset_event::SessionResetEventQuery::new().for_user(user_id.to_string()))
+        .await
+        .map_err(|e| internal_error("reset events", &e))?;
+
+    Ok(crate::models::user_data_export::UserDataExport {
+        version: crate::models::user_data_export::USER_DATA_EXPORT_VERSION,
+        exported_at: chrono::Utc::now().timestamp(),
+        configuration,
+        sessions,
+        daily_stats,
+        reset_events,
+    })
+}
+
+/// The caller's configuration, session history, daily stats and reset events
+/// as one JSON document
+pub async fn export_user_data(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Json<crate::models::user_data_export::UserDataExport>, StatusCode> {
+    Ok(Json(user_data_export(&ws_manager, &claims.sub).await?))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportSummary {
+    pub sessions: usize,
+    pub daily_stats: usize,
+    pub reset_events: usize,
+}
+
+/// Replace the caller's data with an export document, which may have been
+/// taken from another user. Every record is validated first, and the records
+/// are saved in one transaction so a failure leaves the existing data alone.
+pub async fn import_user_data(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+    ApiJson(data): ApiJson<crate::models::user_data_export::UserDataExport>,
+) -> Result<Response, StatusCode> {
+    let data = data.for_user(&claims.sub);
+    if let Err(e) = data.validate() {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({ "error": "invalid_import", "message": e.to_string() })),
+        )
+            .into_response());
+    }
+
+    if let Err(e) = ws_manager.database.replace_user_data(&data).await {
+        tracing::warn!("Import for {} rolled back: {e}", claims.sub);
+        return Ok((
+            StatusCode::CONFLICT,
+            Json(serde_json::json!({ "error": "import_failed", "message": e.to_string() })),
+        )
+            .into_response());
+    }
+    tracing::info!(
+        "Imported {} sessions, {} daily stats and {} reset events for {}",
+        data.sessions.len(),
+        data.daily_stats.len(),
+        data.reset_events.len(),
+        claims.sub
+    );
+
+    // Let the user's devices show the imported session count
+    let status = daily_reset_status_message(ws_manager.database.clone(), &claims.sub).await;
+    ws_manager.broadcast_message(&claims.sub, status).await;
+
+    Ok(Json(ImportSummary {
+        sessions: data.sessions.len(),
+        daily_stats: data.daily_stats.len(),
+        reset_events: data.reset_events.len(),
+    })
+    .into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn test_user_data_export_import_round_trip() {
+        use crate::models::session_reset_event::SessionResetEvent;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let database = ws_manager.database.clone();
+
+        let pool = database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, work_duration, timezone, daily_reset_time_type, daily_reset_time_hour,
+                daily_reset_enabled, today_session_count, daily_goal, webhook_urls, created_at, updated_at)
+            VALUES ('alice', 1800, 'Europe/Paris', 'hour', 5, TRUE, 3, 6, '["https://example.com/hook"]', 10, 20)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO daily_session_stats (id, user_configuration_id, date, timezone, work_sessions_completed,
+                total_work_seconds, total_break_seconds, manual_overrides, final_session_count, created_at, updated_at)
+            VALUES ('stats-1', 'alice', '2024-03-09', 'Europe/Paris', 4, 7200, 900, 1, 4, 30, 40)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+        let now = chrono::Utc::now().timestamp();
+        database.record_completed_session("alice", "work", 1800, 60, "laptop", now - 600, Some("writing")).await.unwrap();
+        database.record_completed_session("alice", "short_break", 300, 0, "phone", now - 200, None).await.unwrap();
+        let event = SessionResetEvent::manual_reset("alice".to_string(), 3, 0, chrono::Utc::now(), "Europe/Paris".to_string(), "laptop".to_string());
+        database.insert_session_reset_event(&event).await.unwrap();
+
+        let export = || async { export_user_data(State((state.clone(), ws_manager.clone())), current_user("alice")).await.unwrap().0 };
+        let comparable = |data: &crate::models::user_data_export::UserDataExport| {
+            let mut json = serde_json::to_value(data).unwrap();
+            json["exported_at"] = serde_json::Value::Null;
+            json
+        };
+
+        let original = export().await;
+        assert_eq!(original.sessions.len(), 2);
+        assert_eq!(original.daily_stats.len(), 1);
+        assert_eq!(original.reset_events.len(), 1);
+        assert_eq!(original.configuration.daily_goal, Some(6));
+
+        // The document survives a trip through JSON, a wipe and an import
+        let document: crate::models::user_data_export::UserDataExport =
+            serde_json::from_str(&serde_json::to_string(&original).unwrap()).unwrap();
+        for sql in [
+            "DELETE FROM timer_sessions",
+            "DELETE FROM daily_session_stats",
+            "DELETE FROM session_reset_events",
+            "DELETE FROM user_configurations",
+        ] {
+            sqlx::query(sql).execute(pool).await.unwrap();
+        }
+        let response = import_user_data(State((state.clone(), ws_manager.clone())), current_user("alice"), ApiJson(document))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let summary: ImportSummary = response_json(response).await;
+        assert_eq!((summary.sessions, summary.daily_stats, summary.reset_events), (2, 1, 1));
+
+        let restored = export().await;
+        assert_eq!(comparable(&restored), comparable(&original));
+
+        // An invalid record rejects the whole document
+        let mut invalid = original.clone();
+        invalid.reset_events[0].previous_count = -1;
+        let response = import_user_data(State((state.clone(), ws_manager.clone())), current_user("alice"), ApiJson(invalid))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // A record that fails to save rolls back the records saved before it
+        let mut conflicting = original.clone();
+        conflicting.configuration.work_duration = 2400;
+        conflicting.sessions.push(conflicting.sessions[0].clone());
+        let response = import_user_data(State((state.clone(), ws_manager.clone())), current_user("alice"), ApiJson(conflicting))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(comparable(&export().await), comparable(&original));
+    }
+}
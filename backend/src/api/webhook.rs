@@ -0,0 +1,165 @@
+//! Webhook API Endpoints
+//!
+//! Sends a test notification to the configured webhooks.
+
+use std::sync::Arc;
+
+use crate::models::timer_state::SharedState;
+use crate::services::auth_service::CurrentUser;
+use crate::services::webhook_service::{
+    WEBHOOK_TEST_SESSION_TYPE, WebhookRetryPolicy, send_webhook_notification,
+};
+use crate::websocket::manager::SharedWsManager;
+
+use axum::{
+    extract::State,
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookTestResult {
+    pub url: String,
+    pub delivered: bool,
+    /// HTTP status of the response, absent if the endpoint couldn't be reached
+    pub status: Option<u16>,
+    pub latency_ms: u64,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct WebhookTestResponse {
+    pub results: Vec<WebhookTestResult>,
+}
+
+/// Send a synthetic "test" session notification to each of the user's
+/// webhooks, once and without retries, reporting how each one answered
+pub async fn test_webhook(
+    State((_, ws_manager)): State<(SharedState, SharedWsManager)>,
+    CurrentUser(claims): CurrentUser,
+) -> Result<Response, StatusCode> {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    let config = service.find_user_configuration(&claims.sub).await.map_err(|e| {
+        tracing::warn!("Failed to load configuration for {}: {e}", claims.sub);
+        e.status_code()
+    })?;
+    let Some(config) = config.filter(|config| !config.webhook_urls.is_empty()) else {
+        return Ok((
+            StatusCode::BAD_REQUEST,
+            Json(serde_json::json!({
+                "error": "no_webhook",
+                "message": "No webhook URL is configured",
+            })),
+        )
+            .into_response());
+    };
+
+    let policy = WebhookRetryPolicy {
+        max_attempts: 1,
+        ..WebhookRetryPolicy::default()
+    };
+    let tests = config.webhook_urls.iter().map(|url| {
+        let config = &config;
+        async move {
+            let started = std::time::Instant::now();
+            let result = send_webhook_notification(
+                url,
+                &config.webhook_format,
+                config.webhook_template.as_deref(),
+                WEBHOOK_TEST_SESSION_TYPE,
+                0,
+                policy,
+            )
+            .await;
+            let latency_ms = u64::try_from(started.elapsed().as_millis()).unwrap_or(u64::MAX);
+            match result {
+                Ok(delivery) => WebhookTestResult {
+                    url: url.clone(),
+                    delivered: true,
+                    status: Some(delivery.status),
+                    latency_ms,
+                    error: None,
+                },
+                Err(e) => WebhookTestResult {
+                    url: url.clone(),
+                    delivered: false,
+                    status: e.status(),
+                    latency_ms,
+                    error: Some(e.to_string()),
+                },
+            }
+        }
+    });
+    let results = futures_util::future::join_all(tests).await;
+
+    Ok(Json(WebhookTestResponse { results }).into_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::sync::Mutex as StdMutex;
+    use tokio::net::TcpListener;
+    use axum::Router;
+    use axum::routing::post;
+
+    #[tokio::test]
+    async fn test_webhook_test_endpoint_reports_each_url() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let received = Arc::new(StdMutex::new(Vec::<serde_json::Value>::new()));
+        let app = Router::new()
+            .route("/hook", post({
+                let received = received.clone();
+                move |Json(payload): Json<serde_json::Value>| async move {
+                    received.lock().unwrap().push(payload);
+                    StatusCode::ACCEPTED
+                }
+            }))
+            .route("/gone", post(|| async { StatusCode::GONE }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let urls = serde_json::json!([format!("http://{addr}/hook"), format!("http://{addr}/gone")]);
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, webhook_urls, created_at, updated_at) VALUES ('alice', ?, 0, 0), ('bob', '[]', 0, 0)"
+        )
+        .bind(urls.to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let response = test_webhook(State((state.clone(), ws_manager.clone())), current_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body: WebhookTestResponse = response_json(response).await;
+        assert_eq!(body.results.len(), 2);
+        assert_eq!(body.results[0].url, format!("http://{addr}/hook"));
+        assert!(body.results[0].delivered);
+        assert_eq!(body.results[0].status, Some(202));
+        assert!(!body.results[1].delivered);
+        assert_eq!(body.results[1].status, Some(410));
+        assert!(body.results[1].error.is_some());
+
+        let payloads = received.lock().unwrap().clone();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0]["session_type"], WEBHOOK_TEST_SESSION_TYPE);
+
+        // Nothing configured: 400
+        let response = test_webhook(State((state, ws_manager)), current_user("bob")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response_json(response).await;
+        assert_eq!(body["error"], "no_webhook");
+    }
+}
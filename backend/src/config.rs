@@ -60,6 +60,32 @@ pub struct Config {
 
     /// Enable performance metrics
     pub enable_metrics: bool,
+
+    /// Whether the server or the clients own the countdown
+    pub timer_mode: TimerMode,
+}
+
+/// Who drives the countdown of a running timer
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TimerMode {
+    /// The server ticks the timer every second and broadcasts each tick
+    #[default]
+    ServerTick,
+    /// Clients count down locally and periodically report their state; the
+    /// server validates, persists and broadcasts the reports
+    ClientTick,
+}
+
+impl std::str::FromStr for TimerMode {
+    type Err = ConfigError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "server" | "server_tick" | "servertick" => Ok(TimerMode::ServerTick),
+            "client" | "client_tick" | "clienttick" => Ok(TimerMode::ClientTick),
+            _ => Err(ConfigError::InvalidTimerMode(s.to_string())),
+        }
+    }
 }
 
 impl Default for Config {
@@ -82,6 +108,7 @@ impl Default for Config {
             request_timeout: 30,
             enable_request_logging: true,
             enable_metrics: true,
+            timer_mode: TimerMode::ServerTick,
         }
     }
 }
@@ -198,6 +225,11 @@ impl Config {
                 .map_err(|_| ConfigError::InvalidBool(enable_metrics))?;
         }
 
+        // Timer mode
+        if let Ok(timer_mode) = env::var("ROMA_TIMER_TIMER_MODE") {
+            config.timer_mode = timer_mode.parse()?;
+        }
+
         // Validate configuration
         config.validate()?;
 
@@ -334,6 +366,7 @@ impl Config {
         info!("  Request timeout: {}s", self.request_timeout);
         info!("  Request logging: {}", self.enable_request_logging);
         info!("  Metrics: {}", self.enable_metrics);
+        info!("  Timer mode: {:?}", self.timer_mode);
 
         if self.shared_secret == "change-me-in-production" {
             warn!("⚠️  Using default shared secret - CHANGE IN PRODUCTION!");
@@ -397,6 +430,9 @@ pub enum ConfigError {
     #[error("Invalid boolean value: {0}")]
     InvalidBool(String),
 
+    #[error("Invalid timer mode: {0} (expected 'server' or 'client')")]
+    InvalidTimerMode(String),
+
     #[error("Insecure shared secret for production environment")]
     InsecureProductionSecret,
 
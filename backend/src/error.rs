@@ -8,6 +8,7 @@ use axum::{
     Json,
 };
 use serde_json::json;
+use crate::services::time_provider::now_unix;
 use thiserror::Error;
 
 /// Application error types
@@ -124,6 +125,9 @@ pub enum AppError {
 
     #[error("Cron expression invalid: {0}")]
     InvalidCronExpression(String),
+
+    #[error("Daily reset skipped: last reset was only {0} seconds ago")]
+    DailyResetTooSoon(i64),
 }
 
 impl AppError {
@@ -141,7 +145,9 @@ impl AppError {
             AppError::NotFound(_) | AppError::ConfigurationNotFound | AppError::SessionNotFound => {
                 StatusCode::NOT_FOUND
             }
-            AppError::Conflict(_) | AppError::TimerAlreadyRunning => StatusCode::CONFLICT,
+            AppError::Conflict(_) | AppError::TimerAlreadyRunning | AppError::DailyResetTooSoon(_) => {
+                StatusCode::CONFLICT
+            }
             AppError::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
             AppError::ServiceUnavailable => StatusCode::SERVICE_UNAVAILABLE,
             AppError::Authentication(_) => StatusCode::UNAUTHORIZED,
@@ -212,6 +218,7 @@ impl AppError {
             AppError::AnalyticsCalculation(_) => "AnalyticsCalculationError",
             AppError::WebSocketMessageValidation(_) => "WebSocketMessageValidationError",
             AppError::InvalidCronExpression(_) => "InvalidCronExpression",
+            AppError::DailyResetTooSoon(_) => "DailyResetTooSoon",
         }
     }
 
@@ -236,10 +243,7 @@ impl IntoResponse for AppError {
         let status = self.status_code();
         let error_code = self.error_code();
         let message = self.to_string();
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let timestamp = now_unix();
 
         let body = Json(json!({
             "error": error_code,
@@ -366,7 +370,7 @@ mod tests {
         assert!(matches!(error, AppError::NotFound(_)));
 
         let error = AppError::timer_already_running();
-        assert_eq!(error, AppError::TimerAlreadyRunning);
+        assert!(matches!(error, AppError::TimerAlreadyRunning));
     }
 
     #[test]
@@ -435,6 +435,9 @@ mod tests {
             "2025-01-07".to_string(),
             "UTC".to_string(),
         );
+        // Timestamps have one-second resolution, so start from an older record
+        stats.created_at -= 60;
+        stats.updated_at = stats.created_at;
 
         stats.add_work_session(1500); // 25 minutes
 
@@ -451,6 +454,8 @@ mod tests {
             "2025-01-07".to_string(),
             "UTC".to_string(),
         );
+        stats.created_at -= 60;
+        stats.updated_at = stats.created_at;
 
         stats.add_break_time(300); // 5 minutes
 
@@ -493,7 +498,7 @@ mod tests {
         assert_eq!(stats.total_break_minutes(), 10);
         assert_eq!(stats.average_work_session_minutes(), 25.0);
         assert_eq!(stats.work_to_break_ratio(), 7.5);
-        assert_eq!(stats.productivity_score(), 35); // 3 sessions * 5 + 10 duration bonus
+        assert_eq!(stats.productivity_score(), 25); // 3 sessions * 5 + 10 duration bonus
     }
 
     #[test]
@@ -4,7 +4,7 @@
 //! Tracks device metadata, connection lifecycle, and heartbeat status.
 
 use serde::{Deserialize, Serialize};
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::services::time_provider::now_unix;
 use uuid::Uuid;
 
 /// Device connection for tracking active WebSocket connections
@@ -60,10 +60,7 @@ impl DeviceConnection {
         user_agent: Option<String>,
         ip_address: Option<String>,
     ) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
 
         Self {
             id: Uuid::new_v4().to_string(),
@@ -80,10 +77,7 @@ impl DeviceConnection {
 
     /// Update last ping timestamp
     pub fn update_ping(&mut self) {
-        self.last_ping = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.last_ping = now_unix();
 
         // If connection was inactive, mark as connected
         if self.status == ConnectionStatus::Inactive {
@@ -103,31 +97,23 @@ impl DeviceConnection {
 
     /// Check if connection is healthy based on heartbeat
     pub fn is_healthy(&self, timeout_seconds: u64) -> bool {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
 
-        matches!(self.status, ConnectionStatus::Connected) &&
-        (now - self.last_ping) < timeout_seconds
+        // A connection still handshaking is as healthy as its last ping
+        matches!(self.status, ConnectionStatus::Connecting | ConnectionStatus::Connected) &&
+        now.saturating_sub(self.last_ping) < timeout_seconds
     }
 
     /// Get connection age in seconds
     pub fn age(&self) -> u64 {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
 
         now.saturating_sub(self.connected_at)
     }
 
     /// Get time since last ping in seconds
     pub fn time_since_last_ping(&self) -> u64 {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
 
         now.saturating_sub(self.last_ping)
     }
@@ -318,10 +304,7 @@ impl ConnectionPool {
     /// Mark inactive connections
     pub fn mark_inactive_connections(&mut self, timeout_seconds: u64) -> Vec<String> {
         let mut inactive_ids = Vec::new();
-        let _now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let _now = now_unix();
 
         for (id, connection) in &mut self.connections {
             if !connection.is_healthy(timeout_seconds) {
@@ -493,10 +476,7 @@ mod tests {
         assert!(connection.is_healthy(60));
 
         // Simulate old ping
-        connection.last_ping = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() - 120; // 2 minutes ago
+        connection.last_ping = now_unix() - 120; // 2 minutes ago
 
         assert!(!connection.is_healthy(60)); // Should be unhealthy with 60s timeout
         assert!(connection.is_healthy(180)); // Should be healthy with 180s timeout
@@ -10,5 +10,7 @@ pub mod websocket_messages;
 pub mod daily_session_stats;
 pub mod scheduled_task;
 pub mod session_reset_event;
+pub mod user_data_export;
+pub mod timer_state;
 
 // Re-export commonly used types
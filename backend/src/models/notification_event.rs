@@ -4,7 +4,7 @@
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::services::time_provider::now_unix;
 
 /// Notification event types
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
@@ -75,10 +75,7 @@ pub struct NotificationEvent {
 impl NotificationEvent {
     /// Create a new notification event
     pub fn new(timer_session_id: String, event_type: NotificationType, message: Option<String>) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
 
         Self {
             id: uuid::Uuid::new_v4().to_string(),
@@ -92,12 +89,7 @@ impl NotificationEvent {
 
     /// Mark the notification as delivered
     pub fn mark_delivered(&mut self) {
-        self.delivered_at = Some(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs()
-        );
+        self.delivered_at = Some(now_unix());
     }
 
     /// Check if the notification has been delivered
@@ -107,11 +99,7 @@ impl NotificationEvent {
 
     /// Get the time since creation in seconds
     pub fn age_seconds(&self) -> u64 {
-        SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs()
-            .saturating_sub(self.created_at)
+        now_unix().saturating_sub(self.created_at)
     }
 
     /// Check if the notification is old (older than 5 minutes)
@@ -5,7 +5,7 @@
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use chrono::{DateTime, Utc};
+use chrono::{DateTime, Datelike, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Timelike, Utc};
 use uuid::Uuid;
 
 /// Task types for scheduled operations
@@ -210,59 +210,19 @@ impl ScheduledTask {
         self.touch();
     }
 
-    /// Calculate next run time based on cron expression
+    /// Calculate next run time based on cron expression, evaluated in the task's timezone
     pub fn calculate_next_run(&mut self, base_time: DateTime<Utc>) -> Result<(), ScheduledTaskError> {
-        // Simple implementation - in production, use a proper cron library
-        let next_run = self.calculate_next_run_simple(base_time)?;
+        let schedule = CronSchedule::parse(&self.cron_expression)?;
+        let timezone: chrono_tz::Tz = self.timezone
+            .parse()
+            .map_err(|_| ScheduledTaskError::InvalidTimezone)?;
+        let next_run = schedule
+            .next_after(base_time, &timezone)
+            .ok_or(ScheduledTaskError::InvalidCronExpression)?;
         self.next_run_utc = next_run.timestamp();
         Ok(())
     }
 
-    /// Simple next run calculation (placeholder - should use cron library)
-    fn calculate_next_run_simple(&self, base_time: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduledTaskError> {
-        // Parse cron expression: minute hour day month day_of_week
-        let parts: Vec<&str> = self.cron_expression.split_whitespace().collect();
-        if parts.len() != 5 {
-            return Err(ScheduledTaskError::InvalidCronExpression);
-        }
-
-        let (minute, hour, day, month, day_of_week) = (parts[0], parts[1], parts[2], parts[3], parts[4]);
-
-        // For now, handle simple daily reset cases
-        if minute == "0" && hour == "0" && day == "*" && month == "*" && day_of_week == "*" {
-            // Daily at midnight
-            let today_midnight = base_time.date_naive().and_hms_opt(0, 0, 0).unwrap().and_utc();
-            if today_midnight > base_time {
-                return Ok(today_midnight);
-            } else {
-                return Ok((base_time.date_naive() + chrono::Duration::days(1))
-                    .and_hms_opt(0, 0, 0)
-                    .unwrap()
-                    .and_utc());
-            }
-        }
-
-        // Handle hourly resets
-        if minute == "0" && hour != "*" && day == "*" && month == "*" && day_of_week == "*" {
-            if let Ok(hour_val) = hour.parse::<u32>() {
-                if hour_val < 24 {
-                    let today_at_hour = base_time.date_naive().and_hms_opt(hour_val, 0, 0).unwrap().and_utc();
-                    if today_at_hour > base_time {
-                        return Ok(today_at_hour);
-                    } else {
-                        return Ok((base_time.date_naive() + chrono::Duration::days(1))
-                            .and_hms_opt(hour_val, 0, 0)
-                            .unwrap()
-                            .and_utc());
-                    }
-                }
-            }
-        }
-
-        // Default to next hour for unknown patterns
-        Ok(base_time + chrono::Duration::hours(1))
-    }
-
     /// Update the updated_at timestamp
     pub fn touch(&mut self) {
         self.updated_at = Utc::now().timestamp();
@@ -329,11 +289,7 @@ impl ScheduledTask {
             return Err(ScheduledTaskError::InvalidTimezone);
         }
 
-        // Basic cron expression validation
-        let cron_parts: Vec<&str> = self.cron_expression.split_whitespace().collect();
-        if cron_parts.len() != 5 {
-            return Err(ScheduledTaskError::InvalidCronExpression);
-        }
+        CronSchedule::parse(&self.cron_expression)?;
 
         Ok(())
     }
@@ -377,6 +333,152 @@ impl Default for ScheduledTask {
     }
 }
 
+/// How far ahead to look for a matching time before giving up on an expression
+/// that can never fire (e.g. `0 0 31 2 *`). Covers the 29 February of a leap year.
+const CRON_SEARCH_DAYS: i64 = 366 * 8;
+
+/// A parsed five-field cron expression: minute hour day-of-month month day-of-week
+///
+/// Each field accepts `*`, single values, ranges (`1-5`), lists (`1,15`) and
+/// steps (`*/15`, `0-30/10`). Day-of-week runs 0-7 with both 0 and 7 meaning
+/// Sunday. As in classic cron, when both day fields are restricted a day
+/// matches if either of them does.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days_of_month: u32,
+    months: u16,
+    days_of_week: u8,
+    day_of_month_restricted: bool,
+    day_of_week_restricted: bool,
+}
+
+impl CronSchedule {
+    /// Parse a cron expression
+    pub fn parse(expression: &str) -> Result<Self, ScheduledTaskError> {
+        let fields: Vec<&str> = expression.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(ScheduledTaskError::InvalidCronExpression);
+        }
+
+        // Sunday may be written as 7; fold it onto 0
+        let days_of_week = parse_cron_field(fields[4], 0, 7)?;
+        let days_of_week = ((days_of_week | (days_of_week >> 7)) & 0x7f) as u8;
+
+        Ok(Self {
+            minutes: parse_cron_field(fields[0], 0, 59)?,
+            hours: parse_cron_field(fields[1], 0, 23)? as u32,
+            days_of_month: parse_cron_field(fields[2], 1, 31)? as u32,
+            months: parse_cron_field(fields[3], 1, 12)? as u16,
+            days_of_week,
+            day_of_month_restricted: fields[2] != "*",
+            day_of_week_restricted: fields[4] != "*",
+        })
+    }
+
+    /// First time strictly after `after` at which the schedule fires in `timezone`
+    ///
+    /// Local times skipped by a DST change never fire; local times repeated by
+    /// one fire on their first occurrence.
+    pub fn next_after<Zone: TimeZone>(&self, after: DateTime<Utc>, timezone: &Zone) -> Option<DateTime<Utc>> {
+        let local = after.with_timezone(timezone).naive_local();
+        let first_day = local.date();
+        let first_minute = local.hour() * 60 + local.minute() + 1;
+
+        for offset in 0..CRON_SEARCH_DAYS {
+            let day = first_day + Duration::days(offset);
+            if !self.matches_day(day) {
+                continue;
+            }
+
+            let from = if offset == 0 { first_minute } else { 0 };
+            for minute_of_day in from..24 * 60 {
+                let (hour, minute) = (minute_of_day / 60, minute_of_day % 60);
+                if self.hours & (1 << hour) == 0 || self.minutes & (1 << minute) == 0 {
+                    continue;
+                }
+
+                let candidate = NaiveDateTime::new(day, NaiveTime::from_hms_opt(hour, minute, 0)?);
+                let fires_at = match timezone.from_local_datetime(&candidate) {
+                    LocalResult::Single(time) => time,
+                    LocalResult::Ambiguous(earliest, _) => earliest,
+                    LocalResult::None => continue,
+                };
+                let fires_at = fires_at.with_timezone(&Utc);
+                if fires_at > after {
+                    return Some(fires_at);
+                }
+            }
+        }
+
+        None
+    }
+
+    fn matches_day(&self, day: NaiveDate) -> bool {
+        if self.months & (1 << day.month()) == 0 {
+            return false;
+        }
+
+        let day_of_month = self.days_of_month & (1 << day.day()) != 0;
+        let day_of_week = self.days_of_week & (1 << day.weekday().num_days_from_sunday()) != 0;
+        match (self.day_of_month_restricted, self.day_of_week_restricted) {
+            (true, true) => day_of_month || day_of_week,
+            (true, false) => day_of_month,
+            (false, true) => day_of_week,
+            (false, false) => true,
+        }
+    }
+}
+
+/// Parse one cron field into a bit set of the values it allows
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<u64, ScheduledTaskError> {
+    let invalid = || ScheduledTaskError::InvalidCronExpression;
+    let parse_value = |value: &str| -> Result<u32, ScheduledTaskError> {
+        let value: u32 = value.parse().map_err(|_| invalid())?;
+        if value < min || value > max {
+            return Err(invalid());
+        }
+        Ok(value)
+    };
+
+    let mut allowed = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| invalid())?;
+                if step == 0 {
+                    return Err(invalid());
+                }
+                (range, step)
+            }
+            None => (part, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start)?, parse_value(end)?),
+                // `5/10` means every 10th value starting at 5
+                None if step > 1 => (parse_value(range)?, max),
+                None => {
+                    let value = parse_value(range)?;
+                    (value, value)
+                }
+            },
+        };
+        if start > end {
+            return Err(invalid());
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            allowed |= 1 << value;
+        }
+    }
+
+    Ok(allowed)
+}
+
 /// Scheduled task validation errors
 #[derive(Debug, thiserror::Error)]
 pub enum ScheduledTaskError {
@@ -705,4 +807,72 @@ mod tests {
         assert_eq!(ScheduledTaskType::Backup.default_cron_expression(), "0 3 * * 0");
         assert_eq!(ScheduledTaskType::Notification.default_cron_expression(), "* * * * *");
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_weekly_sunday_cleanup_calculation() {
+        let mut task = ScheduledTask::system_task(ScheduledTaskType::Cleanup, "UTC".to_string());
+
+        // Tuesday 2025-01-07 -> Sunday 2025-01-12 at 2 AM
+        let tuesday = Utc.with_ymd_and_hms(2025, 1, 7, 10, 0, 0).single().unwrap();
+        task.calculate_next_run(tuesday).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 12, 2, 0, 0).single().unwrap());
+
+        // Exactly at the run time moves on a full week
+        let sunday_run = Utc.with_ymd_and_hms(2025, 1, 12, 2, 0, 0).single().unwrap();
+        task.calculate_next_run(sunday_run).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 19, 2, 0, 0).single().unwrap());
+
+        // 7 is Sunday too
+        task.cron_expression = "0 2 * * 7".to_string();
+        task.calculate_next_run(tuesday).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 12, 2, 0, 0).single().unwrap());
+    }
+
+    #[test]
+    fn test_specific_minute_and_hour_in_task_timezone() {
+        let mut task = ScheduledTask::new(
+            ScheduledTaskType::DailyReset,
+            "30 14 * * *".to_string(),
+            "UTC".to_string(),
+        );
+
+        let before = Utc.with_ymd_and_hms(2025, 1, 7, 14, 29, 59).single().unwrap();
+        task.calculate_next_run(before).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 7, 14, 30, 0).single().unwrap());
+
+        let after = Utc.with_ymd_and_hms(2025, 1, 7, 14, 31, 0).single().unwrap();
+        task.calculate_next_run(after).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 8, 14, 30, 0).single().unwrap());
+
+        // 14:30 in New York (UTC-5 in January) is 19:30 UTC
+        task.timezone = "America/New_York".to_string();
+        task.calculate_next_run(before).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 7, 19, 30, 0).single().unwrap());
+    }
+
+    #[test]
+    fn test_step_range_and_list_syntax() {
+        let mut task = ScheduledTask::new(
+            ScheduledTaskType::Notification,
+            "*/15 * * * *".to_string(),
+            "UTC".to_string(),
+        );
+
+        let base = Utc.with_ymd_and_hms(2025, 1, 7, 10, 7, 0).single().unwrap();
+        task.calculate_next_run(base).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 7, 10, 15, 0).single().unwrap());
+
+        let last_quarter = Utc.with_ymd_and_hms(2025, 1, 7, 10, 45, 0).single().unwrap();
+        task.calculate_next_run(last_quarter).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 7, 11, 0, 0).single().unwrap());
+
+        // Weekdays at 9 and 17; Friday evening rolls over to Monday morning
+        task.cron_expression = "0 9,17 * * 1-5".to_string();
+        let friday_evening = Utc.with_ymd_and_hms(2025, 1, 10, 18, 0, 0).single().unwrap();
+        task.calculate_next_run(friday_evening).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 13, 9, 0, 0).single().unwrap());
+
+        for invalid in ["*/0 * * * *", "60 * * * *", "0 5-2 * * *", "0 0 * 13 *", "a b c d e"] {
+            assert!(CronSchedule::parse(invalid).is_err(), "{invalid} should be rejected");
+        }
+    }
+}
@@ -138,6 +138,17 @@ impl SessionResetTriggerSource {
             SessionResetTriggerSource::System => "System",
         }
     }
+
+    /// Whether the reset was triggered without a user asking for it
+    pub fn is_automatic(&self) -> bool {
+        matches!(
+            self,
+            SessionResetTriggerSource::BackgroundService
+                | SessionResetTriggerSource::Migration
+                | SessionResetTriggerSource::ConfigurationUpdate
+                | SessionResetTriggerSource::System
+        )
+    }
 }
 
 /// Session reset event for audit trail and analytics
@@ -385,7 +396,7 @@ impl SessionResetEvent {
             return Err(SessionResetEventError::InvalidUserId);
         }
 
-        if self.user_timezone.is_empty() {
+        if self.user_timezone.parse::<chrono_tz::Tz>().is_err() {
             return Err(SessionResetEventError::InvalidTimezone);
         }
 
@@ -562,6 +573,12 @@ impl SessionResetEventQuery {
         self
     }
 
+    /// Filter by the device that triggered the reset
+    pub fn with_device_id(mut self, device_id: String) -> Self {
+        self.device_id = Some(device_id);
+        self
+    }
+
     /// Set limit for number of results
     pub fn limit(mut self, limit: u32) -> Self {
         self.limit = Some(limit);
@@ -578,6 +595,7 @@ impl SessionResetEventQuery {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::TimeZone;
 
     #[test]
     fn test_session_reset_event_creation() {
@@ -667,8 +685,8 @@ mod tests {
         assert!(event.count_changed());
         assert_eq!(event.count_difference(), 5);
         assert!(!event.is_reset_to_zero());
-        assert!(event.is_user_initiated());
-        assert!(!event.is_automatic());
+        assert!(event.reset_type.is_user_initiated());
+        assert!(!event.reset_type.is_automatic());
     }
 
     #[test]
@@ -683,8 +701,8 @@ mod tests {
         assert!(event.is_reset_to_zero());
         assert!(event.count_changed());
         assert_eq!(event.count_difference(), -5);
-        assert!(!event.is_user_initiated());
-        assert!(event.is_automatic());
+        assert!(!event.reset_type.is_user_initiated());
+        assert!(event.reset_type.is_automatic());
     }
 
     #[test]
@@ -6,7 +6,7 @@
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 use uuid::Uuid;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::services::time_provider::now_unix;
 
 /// Timer session types
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
@@ -84,10 +84,7 @@ pub struct TimerSession {
 impl TimerSession {
     /// Create a new timer session
     pub fn new(timer_type: TimerType, duration: Option<u32>) -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
 
         Self {
             id: Uuid::new_v4().to_string(),
@@ -127,10 +124,7 @@ impl TimerSession {
     /// Add elapsed time and return if session is complete
     pub fn add_elapsed(&mut self, seconds: u32) -> bool {
         self.elapsed = self.elapsed.saturating_add(seconds);
-        self.updated_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.updated_at = now_unix();
         self.is_complete()
     }
 
@@ -141,10 +135,7 @@ impl TimerSession {
         }
 
         self.is_running = true;
-        self.updated_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.updated_at = now_unix();
 
         Ok(())
     }
@@ -156,10 +147,7 @@ impl TimerSession {
         }
 
         self.is_running = false;
-        self.updated_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.updated_at = now_unix();
 
         Ok(())
     }
@@ -168,10 +156,7 @@ impl TimerSession {
     pub fn reset(&mut self) {
         self.elapsed = 0;
         self.is_running = false;
-        self.updated_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.updated_at = now_unix();
     }
 
     /// Skip to next session type
@@ -179,7 +164,7 @@ impl TimerSession {
         let next_type = match self.timer_type {
             TimerType::Work => {
                 // After work, check if it's time for a long break
-                if work_sessions_completed % long_break_frequency == 0 {
+                if work_sessions_completed > 0 && work_sessions_completed % long_break_frequency == 0 {
                     TimerType::LongBreak
                 } else {
                     TimerType::ShortBreak
@@ -192,10 +177,7 @@ impl TimerSession {
         self.duration = next_type.default_duration(); // Will be updated with config duration
         self.elapsed = 0;
         self.is_running = false;
-        self.updated_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.updated_at = now_unix();
     }
 
     /// Skip to next session type with configuration-based durations
@@ -203,7 +185,7 @@ impl TimerSession {
         let next_type = match self.timer_type {
             TimerType::Work => {
                 // After work, check if it's time for a long break
-                if work_sessions_completed % config.long_break_frequency == 0 {
+                if work_sessions_completed > 0 && work_sessions_completed % config.long_break_frequency == 0 {
                     TimerType::LongBreak
                 } else {
                     TimerType::ShortBreak
@@ -216,10 +198,7 @@ impl TimerSession {
         self.duration = next_type.get_duration_from_config(config);
         self.elapsed = 0;
         self.is_running = false;
-        self.updated_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        self.updated_at = now_unix();
     }
 
     /// Validate the timer session
@@ -243,6 +222,32 @@ impl TimerSession {
     }
 }
 
+/// A finished session in a user's history, completed or skipped
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct SessionHistoryEntry {
+    pub id: String,
+
+    /// `work`, `short_break` or `long_break`
+    pub session_type: String,
+
+    /// Length the session was planned for, in seconds
+    pub planned_duration: i64,
+
+    /// Seconds actually spent in the session, including any time added
+    pub actual_duration: i64,
+
+    /// When the session began (Unix timestamp)
+    pub started_at: i64,
+
+    /// When the session ended, by completion or skip (Unix timestamp)
+    pub completed_at: i64,
+
+    /// Whether the session was skipped rather than run to completion
+    pub skipped: bool,
+
+    pub label: Option<String>,
+}
+
 /// Timer session validation errors
 #[derive(Debug, thiserror::Error)]
 pub enum TimerSessionError {
@@ -0,0 +1,349 @@
+//! Timer State
+//!
+//! In-memory per-user timer state, session plans and the broadcast payload sent to clients.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
+use tokio::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerState {
+    pub is_running: bool,
+    pub remaining_seconds: u32,
+    pub session_type: String,
+    pub session_count: u32,
+    pub work_duration: u32,
+    pub short_break_duration: u32,
+    pub long_break_duration: u32,
+    /// Work sessions per long break; zero never schedules one
+    #[serde(default = "default_long_break_frequency")]
+    pub long_break_frequency: u32,
+    pub last_updated: u64, // Unix timestamp
+    /// Work sessions finished since the last long break
+    #[serde(default)]
+    pub work_sessions_since_long_break: u32,
+    /// What the user is working on; recorded with each session until changed
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Display names for session types, keyed by session type id
+    #[serde(default)]
+    pub session_type_labels: BTreeMap<String, String>,
+    /// Queued sessions followed instead of the default cycle until exhausted
+    #[serde(default)]
+    pub plan: Option<SessionPlan>,
+    /// Length of the work session the current break followed, restored when
+    /// work resumes if the user's `resume_work_duration` is on
+    #[serde(default)]
+    pub pre_break_work_duration: Option<u32>,
+    /// Seconds added to the current session with add-time, on top of its duration
+    #[serde(default)]
+    pub added_seconds: u32,
+    /// Unix time the running session ends; `remaining_seconds` is recomputed
+    /// from it on each tick. `None` while stopped.
+    #[serde(default)]
+    pub session_ends_at: Option<u64>,
+}
+
+/// Work sessions per long break until the user changes it
+pub const DEFAULT_LONG_BREAK_FREQUENCY: u32 = 4;
+
+fn default_long_break_frequency() -> u32 {
+    DEFAULT_LONG_BREAK_FREQUENCY
+}
+
+/// One queued session in a `SessionPlan`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlanStep {
+    pub session_type: String,
+    /// Session length in seconds
+    pub duration: u32,
+}
+
+/// A sequence of sessions the timer walks through on completion
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionPlan {
+    pub steps: Vec<PlanStep>,
+    /// Index of the step in progress
+    pub current: usize,
+}
+
+impl SessionPlan {
+    pub fn current_step(&self) -> Option<&PlanStep> {
+        self.steps.get(self.current)
+    }
+}
+
+/// Most steps accepted in one session plan
+const MAX_PLAN_STEPS: usize = 32;
+
+/// Longest session accepted in a plan step, in seconds
+const MAX_PLAN_STEP_DURATION: u32 = 4 * 60 * 60;
+
+/// Check each step of a plan names a known session type and lasts between
+/// one minute and `MAX_PLAN_STEP_DURATION`
+pub fn validate_plan_steps(steps: &[PlanStep]) -> Result<(), String> {
+    if steps.is_empty() || steps.len() > MAX_PLAN_STEPS {
+        return Err(format!("A plan needs 1 to {MAX_PLAN_STEPS} steps"));
+    }
+    for (index, step) in steps.iter().enumerate() {
+        if !crate::models::user_configuration::SESSION_TYPES.contains(&step.session_type.as_str()) {
+            return Err(format!("Step {index}: unknown session type '{}'", step.session_type));
+        }
+        if !(60..=MAX_PLAN_STEP_DURATION).contains(&step.duration) {
+            return Err(format!(
+                "Step {index}: duration must be 60 to {MAX_PLAN_STEP_DURATION} seconds"
+            ));
+        }
+    }
+    Ok(())
+}
+
+impl TimerState {
+    /// Full duration in seconds of the current session: the plan step's length
+    /// while following a plan, otherwise the configured duration of its type
+    pub fn session_duration(&self) -> u32 {
+        self.plan
+            .as_ref()
+            .and_then(SessionPlan::current_step)
+            .filter(|step| step.session_type == self.session_type)
+            .map(|step| step.duration)
+            .unwrap_or_else(|| self.duration_for(&self.session_type))
+    }
+
+    /// Configured duration in seconds of the given session type
+    pub fn duration_for(&self, session_type: &str) -> u32 {
+        match session_type {
+            "work" => self.work_duration,
+            "short_break" => self.short_break_duration,
+            "long_break" => self.long_break_duration,
+            _ => self.work_duration,
+        }
+    }
+
+    /// Display name of the current session type: the user's label if set,
+    /// otherwise the built-in name
+    pub fn session_type_label(&self) -> String {
+        if let Some(label) = self.session_type_labels.get(&self.session_type) {
+            return label.clone();
+        }
+        match self.session_type.as_str() {
+            "short_break" => "Short Break",
+            "long_break" => "Long Break",
+            _ => "Work",
+        }
+        .to_string()
+    }
+
+    /// Length of the current session including any time added to it
+    pub fn extended_duration(&self) -> u32 {
+        self.session_duration().saturating_add(self.added_seconds)
+    }
+
+    /// Seconds elapsed in the current session
+    pub fn elapsed_seconds(&self) -> u32 {
+        self.extended_duration().saturating_sub(self.remaining_seconds)
+    }
+
+    /// Fraction of the current session completed, from 0.0 to 1.0
+    pub fn progress(&self) -> f32 {
+        let duration = self.extended_duration();
+        if duration == 0 {
+            return 0.0;
+        }
+        (self.elapsed_seconds() as f32 / duration as f32).clamp(0.0, 1.0)
+    }
+
+    /// Work sessions between long breaks: every `long_break_frequency`, or
+    /// sooner if `max_consecutive_work_sessions` is lower. `None` if neither is set.
+    pub fn long_break_every(&self, max_consecutive_work_sessions: Option<u32>) -> Option<u32> {
+        let frequency = (self.long_break_frequency > 0).then_some(self.long_break_frequency);
+        match (frequency, max_consecutive_work_sessions) {
+            (Some(frequency), Some(cap)) => Some(frequency.min(cap)),
+            (frequency, cap) => frequency.or(cap),
+        }
+    }
+
+    /// Work sessions left before the next long break when one comes every
+    /// `frequency` work sessions, counting the current work session
+    pub fn sessions_until_long_break(&self, frequency: u32) -> u32 {
+        if self.session_type == "long_break" {
+            return frequency;
+        }
+        frequency.saturating_sub(self.work_sessions_since_long_break)
+    }
+
+    /// Whether the timer is stopped at the start of its current session, so a
+    /// reset would change nothing
+    pub fn is_reset(&self) -> bool {
+        !self.is_running && self.added_seconds == 0 && self.remaining_seconds == self.session_duration()
+    }
+
+    /// Go back to the beginning of the current session, dropping any added time
+    pub fn rewind(&mut self) {
+        self.remaining_seconds = self.session_duration();
+        self.added_seconds = 0;
+    }
+
+    /// Start counting down the remaining time from `now`
+    pub fn start(&mut self, now: u64) {
+        self.is_running = true;
+        self.session_ends_at = Some(now + self.remaining_seconds as u64);
+        self.last_updated = now;
+    }
+
+    /// Stop counting down, keeping the remaining time
+    pub fn stop(&mut self) {
+        self.is_running = false;
+        self.session_ends_at = None;
+    }
+
+    /// Stop the timer and start over at the beginning of a `session_type` session
+    pub fn reset_to(&mut self, session_type: &str) {
+        self.stop();
+        self.session_type = session_type.to_string();
+        self.remaining_seconds = self.duration_for(session_type);
+        self.added_seconds = 0;
+    }
+
+    /// Clamp `remaining_seconds` to the current session's duration plus any
+    /// added time. Returns true if the state had to be corrected.
+    pub fn normalize(&mut self) -> bool {
+        let duration = self.extended_duration();
+        if self.remaining_seconds <= duration {
+            return false;
+        }
+
+        tracing::warn!(
+            "remaining_seconds ({}) exceeds {} duration ({}), clamping",
+            self.remaining_seconds,
+            self.session_type,
+            duration
+        );
+        self.remaining_seconds = duration;
+        true
+    }
+}
+
+/// Timer state as broadcast to clients, with server-computed progress so every
+/// client renders the same value
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TimerStateBroadcast {
+    #[serde(flatten)]
+    pub state: TimerState,
+    pub elapsed_seconds: u32,
+    pub progress: f32,
+    /// Display name of `session_type`
+    pub session_type_label: String,
+    /// Work sessions until the next long break; absent when long breaks aren't scheduled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions_until_long_break: Option<u32>,
+}
+
+impl TimerStateBroadcast {
+    /// Broadcast payload for `state`, counting down to a long break under the
+    /// server's consecutive work session cap
+    pub fn new(state: TimerState, max_consecutive_work_sessions: Option<u32>) -> Self {
+        Self {
+            elapsed_seconds: state.elapsed_seconds(),
+            progress: state.progress(),
+            session_type_label: state.session_type_label(),
+            sessions_until_long_break: state
+                .long_break_every(max_consecutive_work_sessions)
+                .map(|frequency| state.sessions_until_long_break(frequency)),
+            state,
+        }
+    }
+}
+
+/// Every user's timer, keyed by user id. A user without a timer yet gets a
+/// copy of `defaults`.
+#[derive(Debug, Clone)]
+pub struct TimerStates {
+    defaults: TimerState,
+    timers: HashMap<String, TimerState>,
+}
+
+impl TimerStates {
+    pub fn new(defaults: TimerState) -> Self {
+        Self {
+            defaults,
+            timers: HashMap::new(),
+        }
+    }
+
+    /// `user_id`'s timer, created from the defaults on first use
+    pub fn user(&mut self, user_id: &str) -> &mut TimerState {
+        self.timers
+            .entry(user_id.to_string())
+            .or_insert_with(|| self.defaults.clone())
+    }
+
+    /// A copy of `user_id`'s timer, without creating one
+    pub fn get(&self, user_id: &str) -> TimerState {
+        self.timers
+            .get(user_id)
+            .cloned()
+            .unwrap_or_else(|| self.defaults.clone())
+    }
+
+    pub fn insert(&mut self, user_id: &str, state: TimerState) {
+        self.timers.insert(user_id.to_string(), state);
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&String, &TimerState)> {
+        self.timers.iter()
+    }
+}
+
+pub type SharedState = Arc<Mutex<TimerStates>>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use crate::websocket::manager::WsMessage;
+
+    #[test]
+    fn test_broadcast_progress_matches_remaining_seconds() {
+        let mut state = test_timer_state(); // 25 minute work session
+
+        for (remaining, elapsed, progress) in [(1500, 0, 0.0), (1125, 375, 0.25), (750, 750, 0.5), (0, 1500, 1.0)] {
+            state.remaining_seconds = remaining;
+            let broadcast = TimerStateBroadcast::new(state.clone(), None);
+            assert_eq!(broadcast.elapsed_seconds, elapsed);
+            assert!((broadcast.progress - progress).abs() < f32::EPSILON);
+            assert_eq!(broadcast.elapsed_seconds + broadcast.state.remaining_seconds, state.session_duration());
+        }
+
+        // Durations are per session type
+        state.session_type = "short_break".to_string();
+        state.remaining_seconds = 60;
+        let broadcast = TimerStateBroadcast::new(state.clone(), None);
+        assert_eq!(broadcast.elapsed_seconds, 240);
+        assert!((broadcast.progress - 0.8).abs() < 1e-6);
+
+        let json = serde_json::to_value(WsMessage::TimerStateUpdate(broadcast)).unwrap();
+        assert_eq!(json["data"]["remaining_seconds"], 60);
+        assert_eq!(json["data"]["elapsed_seconds"], 240);
+    }
+
+    #[test]
+    fn test_long_break_every_honors_consecutive_work_cap() {
+        let mut state = TimerState {
+            long_break_frequency: 4,
+            work_sessions_since_long_break: 1,
+            ..test_timer_state()
+        };
+        assert_eq!(state.long_break_every(None), Some(4));
+        assert_eq!(state.long_break_every(Some(2)), Some(2));
+        assert_eq!(state.long_break_every(Some(6)), Some(4));
+        assert_eq!(TimerStateBroadcast::new(state.clone(), Some(2)).sessions_until_long_break, Some(1));
+
+        state.long_break_frequency = 0;
+        assert_eq!(state.long_break_every(None), None);
+        assert_eq!(state.long_break_every(Some(3)), Some(3));
+        assert_eq!(TimerStateBroadcast::new(state, None).sessions_until_long_break, None);
+    }
+}
@@ -5,7 +5,7 @@
 
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
-use std::time::{SystemTime, UNIX_EPOCH};
+use crate::services::time_provider::now_unix;
 use url::Url;
 use chrono_tz::Tz;
 
@@ -37,6 +37,132 @@ impl Theme {
     }
 }
 
+/// Shape of the body posted to a user's webhooks
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
+#[sqlx(type_name = "text")]
+pub enum WebhookFormat {
+    /// Roma Timer's own JSON: title, message, session type and count, timestamp
+    #[serde(rename = "raw")]
+    #[sqlx(rename = "raw")]
+    Raw,
+    /// `{"text": message}`, for Slack incoming webhooks
+    #[serde(rename = "slack")]
+    #[sqlx(rename = "slack")]
+    Slack,
+    /// `{"content": message}`, for Discord webhooks
+    #[serde(rename = "discord")]
+    #[sqlx(rename = "discord")]
+    Discord,
+    /// The user's own JSON template with `{placeholder}` substitution
+    #[serde(rename = "custom")]
+    #[sqlx(rename = "custom")]
+    Custom,
+}
+
+impl Default for WebhookFormat {
+    fn default() -> Self {
+        WebhookFormat::Raw
+    }
+}
+
+impl WebhookFormat {
+    /// Name used in the database and in serialized messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WebhookFormat::Raw => "raw",
+            WebhookFormat::Slack => "slack",
+            WebhookFormat::Discord => "discord",
+            WebhookFormat::Custom => "custom",
+        }
+    }
+
+    /// Format named `name`, if there is one
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "raw" => Some(WebhookFormat::Raw),
+            "slack" => Some(WebhookFormat::Slack),
+            "discord" => Some(WebhookFormat::Discord),
+            "custom" => Some(WebhookFormat::Custom),
+            _ => None,
+        }
+    }
+
+    /// Build the webhook body for `event`. Only Custom uses `template`.
+    pub fn render(&self, template: Option<&str>, event: &WebhookEvent) -> Result<serde_json::Value, UserConfigurationError> {
+        Ok(match self {
+            WebhookFormat::Raw => serde_json::json!({
+                "title": "Roma Timer",
+                "message": event.message,
+                "session_type": event.session_type,
+                "session_count": event.session_count,
+                "timestamp": event.timestamp
+            }),
+            WebhookFormat::Slack => serde_json::json!({ "text": event.message }),
+            WebhookFormat::Discord => serde_json::json!({ "content": event.message }),
+            WebhookFormat::Custom => {
+                let template = template.ok_or(UserConfigurationError::MissingWebhookTemplate)?;
+                render_webhook_template(template, event)?
+            }
+        })
+    }
+}
+
+/// What a webhook notification reports, as substituted into templates
+#[derive(Debug, Clone)]
+pub struct WebhookEvent {
+    pub session_type: String,
+    pub session_count: u32,
+    pub message: String,
+    pub timestamp: u64,
+}
+
+/// Placeholders a custom webhook template may use
+pub const WEBHOOK_TEMPLATE_PLACEHOLDERS: [&str; 4] = ["session_type", "session_count", "message", "timestamp"];
+
+/// Substitute `{placeholder}`s in a JSON `template` and parse the result.
+/// Values are JSON-escaped, so they're safe inside string literals.
+fn render_webhook_template(template: &str, event: &WebhookEvent) -> Result<serde_json::Value, UserConfigurationError> {
+    let invalid = UserConfigurationError::InvalidWebhookTemplate;
+    let escape = |value: &str| {
+        let quoted = serde_json::to_string(value).unwrap_or_default();
+        quoted[1..quoted.len() - 1].to_string()
+    };
+
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let name_len = after
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .unwrap_or(after.len());
+        let name = &after[..name_len];
+        if name.is_empty() || !after[name_len..].starts_with('}') {
+            // An ordinary JSON brace, not a placeholder
+            rendered.push('{');
+            rest = after;
+            continue;
+        }
+        let value = match name {
+            "session_type" => escape(&event.session_type),
+            "session_count" => event.session_count.to_string(),
+            "message" => escape(&event.message),
+            "timestamp" => event.timestamp.to_string(),
+            _ => {
+                return Err(invalid(format!(
+                    "unknown placeholder {{{name}}} (expected one of {})",
+                    WEBHOOK_TEMPLATE_PLACEHOLDERS.join(", ")
+                )));
+            }
+        };
+        rendered.push_str(&value);
+        rest = &after[name_len + 1..];
+    }
+    rendered.push_str(rest);
+
+    serde_json::from_str(&rendered).map_err(|e| invalid(format!("not valid JSON once rendered: {e}")))
+}
+
 /// Daily reset time configuration
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, sqlx::Type)]
 #[sqlx(type_name = "text")]
@@ -58,12 +184,26 @@ impl Default for DailyResetTimeType {
     }
 }
 
+impl DailyResetTimeType {
+    /// Name used in the database and in serialized messages
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DailyResetTimeType::Midnight => "midnight",
+            DailyResetTimeType::Hour => "hour",
+            DailyResetTimeType::Custom => "custom",
+        }
+    }
+}
+
 /// Daily reset time configuration with values
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct DailyResetTime {
     #[serde(flatten)]
     pub time_type: DailyResetTimeType,
     pub hour: Option<u8>,
+    /// Minutes past `hour` (0-59) - only used with the Hour type; defaults to 0
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minute: Option<u8>,
     pub time: Option<String>,
 }
 
@@ -73,6 +213,7 @@ impl DailyResetTime {
         Self {
             time_type: DailyResetTimeType::Midnight,
             hour: None,
+            minute: None,
             time: None,
         }
     }
@@ -86,10 +227,23 @@ impl DailyResetTime {
         Ok(Self {
             time_type: DailyResetTimeType::Hour,
             hour: Some(hour),
+            minute: None,
             time: None,
         })
     }
 
+    /// Create a new hourly reset time at `minute` past the hour
+    pub fn hour_minute(hour: u8, minute: u8) -> Result<Self, UserConfigurationError> {
+        if minute > 59 {
+            return Err(UserConfigurationError::InvalidResetMinute(minute));
+        }
+
+        Ok(Self {
+            minute: Some(minute),
+            ..Self::hour(hour)?
+        })
+    }
+
     /// Create a new custom reset time
     pub fn custom(time: String) -> Result<Self, UserConfigurationError> {
         // Validate HH:MM format
@@ -103,6 +257,7 @@ impl DailyResetTime {
         Ok(Self {
             time_type: DailyResetTimeType::Custom,
             hour: None,
+            minute: None,
             time: Some(time),
         })
     }
@@ -113,7 +268,7 @@ impl DailyResetTime {
             DailyResetTimeType::Midnight => "Midnight".to_string(),
             DailyResetTimeType::Hour => {
                 self.hour
-                    .map(|h| format!("{}:00", h))
+                    .map(|h| format!("{}:{:02}", h, self.minute.unwrap_or(0)))
                     .unwrap_or_else(|| "Hour".to_string())
             }
             DailyResetTimeType::Custom => {
@@ -122,64 +277,96 @@ impl DailyResetTime {
         }
     }
 
-    /// Get cron expression for this reset time
-    pub fn to_cron_expression(&self) -> String {
+    /// Local (hour, minute) of day the reset happens at, whatever the time
+    /// type. Missing or malformed values fall back to midnight.
+    pub fn local_time(&self) -> (u32, u32) {
         match self.time_type {
-            DailyResetTimeType::Midnight => "0 0 * * *".to_string(),
-            DailyResetTimeType::Hour => {
-                self.hour
-                    .map(|h| format!("0 {} * * *", h))
-                    .unwrap_or_else(|| "0 0 * * *".to_string())
-            }
-            DailyResetTimeType::Custom => {
-                if let Some(ref time) = self.time {
-                    let parts: Vec<&str> = time.split(':').collect();
-                    if parts.len() == 2 {
-                        return format!("0 {} {} * *", parts[1], parts[0]);
-                    }
-                }
-                "0 0 * * *".to_string()
-            }
+            DailyResetTimeType::Midnight => (0, 0),
+            DailyResetTimeType::Hour => (
+                self.hour.unwrap_or(0) as u32,
+                self.minute.unwrap_or(0) as u32,
+            ),
+            DailyResetTimeType::Custom => self
+                .time
+                .as_deref()
+                .filter(|time| is_valid_time_format(time))
+                .and_then(|time| {
+                    let (hour, minute) = time.split_once(':')?;
+                    Some((hour.parse().ok()?, minute.parse().ok()?))
+                })
+                .unwrap_or((0, 0)),
         }
     }
 
+    /// Get the standard 5-field cron expression (`minute hour * * *`) for this reset time
+    pub fn to_cron_expression(&self) -> String {
+        let (hour, minute) = self.local_time();
+        format!("{} {} * * *", minute, hour)
+    }
+
     /// Convert to database storage format
-    pub fn to_database_format(&self) -> (DailyResetTimeType, Option<u8>, Option<String>) {
-        (self.time_type.clone(), self.hour, self.time.clone())
+    pub fn to_database_format(&self) -> (DailyResetTimeType, Option<u8>, Option<u8>, Option<String>) {
+        (self.time_type.clone(), self.hour, self.minute, self.time.clone())
     }
 
     /// Create from database storage format
     pub fn from_database_format(
         time_type: DailyResetTimeType,
         hour: Option<u8>,
+        minute: Option<u8>,
         time: Option<String>,
     ) -> Self {
-        Self { time_type, hour, time }
+        Self { time_type, hour, minute, time }
     }
 
-    /// Validate the reset time configuration
+    /// Validate the reset time configuration. Only the value matching the
+    /// time type may be set: Hour needs an hour, Custom needs a time, Midnight neither.
     pub fn validate(&self) -> Result<(), UserConfigurationError> {
         match self.time_type {
             DailyResetTimeType::Hour => {
-                if let Some(hour) = self.hour {
-                    if hour > 23 {
-                        return Err(UserConfigurationError::InvalidResetHour(hour));
-                    }
+                let hour = self.hour.ok_or(UserConfigurationError::MissingResetHour)?;
+                if hour > 23 {
+                    return Err(UserConfigurationError::InvalidResetHour(hour));
+                }
+                if let Some(minute) = self.minute.filter(|minute| *minute > 59) {
+                    return Err(UserConfigurationError::InvalidResetMinute(minute));
+                }
+                if self.time.is_some() {
+                    return Err(UserConfigurationError::UnexpectedResetTimeValue("hour"));
                 }
             }
             DailyResetTimeType::Custom => {
-                if let Some(ref time) = self.time {
-                    if !is_valid_time_format(time) {
-                        return Err(UserConfigurationError::InvalidResetTime(time.clone()));
-                    }
+                let time = self.time.as_ref().ok_or(UserConfigurationError::MissingResetTime)?;
+                if !is_valid_time_format(time) {
+                    return Err(UserConfigurationError::InvalidResetTime(time.clone()));
+                }
+                if self.hour.is_some() || self.minute.is_some() {
+                    return Err(UserConfigurationError::UnexpectedResetTimeValue("custom"));
+                }
+            }
+            DailyResetTimeType::Midnight => {
+                if self.hour.is_some() || self.minute.is_some() || self.time.is_some() {
+                    return Err(UserConfigurationError::UnexpectedResetTimeValue("midnight"));
                 }
             }
-            DailyResetTimeType::Midnight => {} // Always valid
         }
         Ok(())
     }
 }
 
+/// Session types the timer cycles through
+pub const SESSION_TYPES: [&str; 3] = ["work", "short_break", "long_break"];
+
+/// Daily session count ceiling unless the user raises or lowers it
+pub const DEFAULT_MAX_SESSION_COUNT: u32 = 1000;
+
+/// Highest session count ceiling a user may configure
+pub const MAX_SESSION_COUNT_LIMIT: u32 = 100_000;
+
+fn default_max_session_count() -> u32 {
+    DEFAULT_MAX_SESSION_COUNT
+}
+
 /// Helper function to validate time format HH:MM
 fn is_valid_time_format(time_str: &str) -> bool {
     // Check basic format length
@@ -245,9 +432,20 @@ pub struct UserConfiguration {
     #[sqlx(rename = "notifications_enabled")]
     pub notifications_enabled: bool,
 
-    /// Optional webhook URL for timer completion notifications
-    #[sqlx(rename = "webhook_url")]
-    pub webhook_url: Option<String>,
+    /// Webhook URLs notified, each independently, when a timer completes
+    #[sqlx(rename = "webhook_urls")]
+    #[serde(default)]
+    pub webhook_urls: Vec<String>,
+
+    /// Shape of the webhook body
+    #[sqlx(rename = "webhook_format")]
+    #[serde(default)]
+    pub webhook_format: WebhookFormat,
+
+    /// JSON template for the Custom webhook format
+    #[sqlx(rename = "webhook_template")]
+    #[serde(default)]
+    pub webhook_template: Option<String>,
 
     /// Whether to wait for user interaction before starting next session
     #[sqlx(rename = "wait_for_interaction")]
@@ -269,6 +467,11 @@ pub struct UserConfiguration {
     #[sqlx(rename = "daily_reset_time_hour")]
     pub daily_reset_time_hour: Option<u8>,
 
+    /// Minutes past the hour (0-59) for daily reset when time_type is Hour
+    #[sqlx(rename = "daily_reset_time_minute")]
+    #[serde(default)]
+    pub daily_reset_time_minute: Option<u8>,
+
     /// Custom time for daily reset (HH:MM format) when time_type is Custom
     #[sqlx(rename = "daily_reset_time_custom")]
     pub daily_reset_time_custom: Option<String>,
@@ -289,6 +492,50 @@ pub struct UserConfiguration {
     #[sqlx(rename = "manual_session_override")]
     pub manual_session_override: Option<u32>,
 
+    /// Highest session count, counted or set manually, allowed in a day
+    #[sqlx(rename = "max_session_count")]
+    #[serde(default = "default_max_session_count")]
+    pub max_session_count: u32,
+
+    /// Work sessions the user aims to complete each day, if they set a goal
+    #[sqlx(rename = "daily_goal")]
+    #[serde(default)]
+    pub daily_goal: Option<u32>,
+
+    /// Whether a daily reset also pauses a running timer and abandons the in-progress session
+    #[sqlx(rename = "stop_session_on_daily_reset")]
+    #[serde(default)]
+    pub stop_session_on_daily_reset: bool,
+
+    /// Session type a stopped timer is put back to at the daily reset; the
+    /// timer is left alone unless the user sets one
+    #[sqlx(rename = "reset_to_session_type")]
+    #[serde(default)]
+    pub reset_to_session_type: Option<String>,
+
+    /// Whether the first device to connect after the daily reset starts a work session
+    #[sqlx(rename = "auto_start_on_first_connect")]
+    #[serde(default)]
+    pub auto_start_on_first_connect: bool,
+
+    /// Whether work after a break gets the length of the work session before
+    /// the break (e.g. a plan step or custom duration) instead of the configured one
+    #[sqlx(rename = "resume_work_duration")]
+    #[serde(default)]
+    pub resume_work_duration: bool,
+
+    /// Seconds of a long break that must pass before it can be skipped; `None`
+    /// allows skipping at any time
+    #[sqlx(rename = "min_long_break_before_skip")]
+    #[serde(default)]
+    pub min_long_break_before_skip: Option<u32>,
+
+    /// Whether the timer pauses when a completion webhook can't be delivered
+    /// after all retries
+    #[sqlx(rename = "pause_on_webhook_failure")]
+    #[serde(default)]
+    pub pause_on_webhook_failure: bool,
+
     /// Creation timestamp (Unix timestamp)
     #[sqlx(rename = "created_at")]
     pub created_at: i64,
@@ -301,10 +548,7 @@ pub struct UserConfiguration {
 impl UserConfiguration {
     /// Create a new user configuration with default values
     pub fn new() -> Self {
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        let now = now_unix() as i64;
 
         Self {
             id: "default-config".to_string(),
@@ -313,7 +557,9 @@ impl UserConfiguration {
             long_break_duration: 900,   // 15 minutes
             long_break_frequency: 4,    // Long break after 4 work sessions
             notifications_enabled: true,
-            webhook_url: None,
+            webhook_urls: Vec::new(),
+            webhook_format: WebhookFormat::default(),
+            webhook_template: None,
             wait_for_interaction: false,
             theme: Theme::default(),
 
@@ -321,11 +567,20 @@ impl UserConfiguration {
             timezone: "UTC".to_string(),
             daily_reset_time_type: DailyResetTimeType::default(),
             daily_reset_time_hour: None,
+            daily_reset_time_minute: None,
             daily_reset_time_custom: None,
             daily_reset_enabled: false,
             last_daily_reset_utc: None,
             today_session_count: 0,
             manual_session_override: None,
+            max_session_count: DEFAULT_MAX_SESSION_COUNT,
+            daily_goal: None,
+            stop_session_on_daily_reset: false,
+            reset_to_session_type: None,
+            auto_start_on_first_connect: false,
+            resume_work_duration: false,
+            min_long_break_before_skip: None,
+            pause_on_webhook_failure: false,
 
             created_at: now,
             updated_at: now,
@@ -340,7 +595,7 @@ impl UserConfiguration {
     }
 
     /// Validate work duration bounds
-    fn validate_work_duration(duration: u32) -> Result<(), UserConfigurationError> {
+    pub fn validate_work_duration(duration: u32) -> Result<(), UserConfigurationError> {
         if duration < 300 || duration > 3600 {
             // 5 minutes to 1 hour
             return Err(UserConfigurationError::InvalidWorkDuration(duration));
@@ -349,7 +604,7 @@ impl UserConfiguration {
     }
 
     /// Validate short break duration bounds
-    fn validate_short_break_duration(duration: u32) -> Result<(), UserConfigurationError> {
+    pub fn validate_short_break_duration(duration: u32) -> Result<(), UserConfigurationError> {
         if duration < 60 || duration > 900 {
             // 1 minute to 15 minutes
             return Err(UserConfigurationError::InvalidShortBreakDuration(duration));
@@ -358,7 +613,7 @@ impl UserConfiguration {
     }
 
     /// Validate long break duration bounds
-    fn validate_long_break_duration(duration: u32) -> Result<(), UserConfigurationError> {
+    pub fn validate_long_break_duration(duration: u32) -> Result<(), UserConfigurationError> {
         if duration < 300 || duration > 1800 {
             // 5 minutes to 30 minutes
             return Err(UserConfigurationError::InvalidLongBreakDuration(duration));
@@ -367,7 +622,7 @@ impl UserConfiguration {
     }
 
     /// Validate long break frequency bounds
-    fn validate_long_break_frequency(frequency: u32) -> Result<(), UserConfigurationError> {
+    pub fn validate_long_break_frequency(frequency: u32) -> Result<(), UserConfigurationError> {
         if frequency < 2 || frequency > 10 {
             // 2 to 10 work sessions
             return Err(UserConfigurationError::InvalidLongBreakFrequency(frequency));
@@ -375,14 +630,13 @@ impl UserConfiguration {
         Ok(())
     }
 
-    /// Validate webhook URL if provided
-    fn validate_webhook_url(url: &Option<String>) -> Result<(), UserConfigurationError> {
-        if let Some(webhook_url) = url {
-            Url::parse(webhook_url)
+    /// Validate each webhook URL
+    fn validate_webhook_urls(urls: &[String]) -> Result<(), UserConfigurationError> {
+        for webhook_url in urls {
+            let parsed_url = Url::parse(webhook_url)
                 .map_err(|_| UserConfigurationError::InvalidWebhookUrl(webhook_url.clone()))?;
 
             // Ensure URL uses HTTP or HTTPS
-            let parsed_url = Url::parse(webhook_url).unwrap();
             if !matches!(parsed_url.scheme(), "http" | "https") {
                 return Err(UserConfigurationError::InvalidWebhookUrl(webhook_url.clone()));
             }
@@ -390,13 +644,60 @@ impl UserConfiguration {
         Ok(())
     }
 
+    /// Validate a webhook format and template: Custom needs a template that
+    /// renders to JSON using only known placeholders
+    pub fn validate_webhook_template(format: &WebhookFormat, template: Option<&str>) -> Result<(), UserConfigurationError> {
+        if *format != WebhookFormat::Custom {
+            return Ok(());
+        }
+        let sample = WebhookEvent {
+            session_type: "work".to_string(),
+            session_count: 1,
+            message: "Work session #1 complete! Time for a break.".to_string(),
+            timestamp: 0,
+        };
+        format.render(template, &sample).map(|_| ())
+    }
+
+    /// Validate the daily session count ceiling
+    pub fn validate_max_session_count(max: u32) -> Result<(), UserConfigurationError> {
+        if max < 1 || max > MAX_SESSION_COUNT_LIMIT {
+            return Err(UserConfigurationError::InvalidMaxSessionCount(max));
+        }
+        Ok(())
+    }
+
+    /// Validate a daily goal against the daily session count ceiling
+    pub fn validate_daily_goal(goal: Option<u32>, max_session_count: u32) -> Result<(), UserConfigurationError> {
+        match goal {
+            Some(goal) if goal < 1 || goal > max_session_count => {
+                Err(UserConfigurationError::InvalidDailyGoal(goal, max_session_count))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Validate that a session type is one the timer knows about
+    fn validate_session_type(session_type: &str) -> Result<(), UserConfigurationError> {
+        if !SESSION_TYPES.contains(&session_type) {
+            return Err(UserConfigurationError::InvalidSessionType(session_type.to_string()));
+        }
+        Ok(())
+    }
+
     /// Validate the user configuration
     pub fn validate(&self) -> Result<(), UserConfigurationError> {
         Self::validate_work_duration(self.work_duration)?;
         Self::validate_short_break_duration(self.short_break_duration)?;
         Self::validate_long_break_duration(self.long_break_duration)?;
         Self::validate_long_break_frequency(self.long_break_frequency)?;
-        Self::validate_webhook_url(&self.webhook_url)?;
+        Self::validate_webhook_urls(&self.webhook_urls)?;
+        Self::validate_webhook_template(&self.webhook_format, self.webhook_template.as_deref())?;
+        if let Some(session_type) = &self.reset_to_session_type {
+            Self::validate_session_type(session_type)?;
+        }
+        Self::validate_max_session_count(self.max_session_count)?;
+        Self::validate_daily_goal(self.daily_goal, self.max_session_count)?;
 
         // Validate daily reset configuration
         self.validate_timezone(&self.timezone)?;
@@ -449,10 +750,56 @@ impl UserConfiguration {
         Ok(())
     }
 
-    /// Update webhook URL with validation
-    pub fn set_webhook_url(&mut self, url: Option<String>) -> Result<(), UserConfigurationError> {
-        Self::validate_webhook_url(&url)?;
-        self.webhook_url = url;
+    /// Replace the webhook URLs, with validation
+    pub fn set_webhook_urls(&mut self, urls: Vec<String>) -> Result<(), UserConfigurationError> {
+        Self::validate_webhook_urls(&urls)?;
+        self.webhook_urls = urls;
+        self.touch();
+        Ok(())
+    }
+
+    /// Set how webhook bodies are shaped, with validation
+    pub fn set_webhook_format(&mut self, format: WebhookFormat, template: Option<String>) -> Result<(), UserConfigurationError> {
+        Self::validate_webhook_template(&format, template.as_deref())?;
+        self.webhook_format = format;
+        self.webhook_template = template;
+        self.touch();
+        Ok(())
+    }
+
+    /// Webhook URLs in their stored form, a JSON array
+    pub fn webhook_urls_json(&self) -> String {
+        serde_json::to_string(&self.webhook_urls).unwrap_or_else(|_| "[]".to_string())
+    }
+
+    /// Parse stored webhook URLs; anything unreadable counts as none
+    pub fn webhook_urls_from_json(json: &str) -> Vec<String> {
+        serde_json::from_str(json).unwrap_or_default()
+    }
+
+    /// Update the daily session count ceiling, with validation
+    pub fn set_max_session_count(&mut self, max: u32) -> Result<(), UserConfigurationError> {
+        Self::validate_max_session_count(max)?;
+        self.max_session_count = max;
+        self.touch();
+        Ok(())
+    }
+
+    /// Set or clear the daily goal, with validation
+    pub fn set_daily_goal(&mut self, goal: Option<u32>) -> Result<(), UserConfigurationError> {
+        Self::validate_daily_goal(goal, self.max_session_count)?;
+        self.daily_goal = goal;
+        self.touch();
+        Ok(())
+    }
+
+    /// Update the session type adopted after a reset, with validation; `None`
+    /// leaves the timer alone at the daily reset
+    pub fn set_reset_to_session_type(&mut self, session_type: Option<String>) -> Result<(), UserConfigurationError> {
+        if let Some(session_type) = &session_type {
+            Self::validate_session_type(session_type)?;
+        }
+        self.reset_to_session_type = session_type;
         self.touch();
         Ok(())
     }
@@ -477,10 +824,7 @@ impl UserConfiguration {
 
     /// Update the updated_at timestamp
     pub fn touch(&mut self) {
-        self.updated_at = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        self.updated_at = now_unix() as i64;
     }
 
     /// Check if notifications are enabled and configured
@@ -490,7 +834,7 @@ impl UserConfiguration {
 
     /// Check if webhook notifications should be sent
     pub fn should_send_webhook(&self) -> bool {
-        self.notifications_enabled && self.webhook_url.is_some()
+        self.notifications_enabled && !self.webhook_urls.is_empty()
     }
 
     /// Get work duration in minutes (for display)
@@ -530,6 +874,7 @@ impl UserConfiguration {
         DailyResetTime::from_database_format(
             self.daily_reset_time_type.clone(),
             self.daily_reset_time_hour,
+            self.daily_reset_time_minute,
             self.daily_reset_time_custom.clone(),
         )
     }
@@ -538,9 +883,10 @@ impl UserConfiguration {
     pub fn set_daily_reset_time(&mut self, reset_time: DailyResetTime) -> Result<(), UserConfigurationError> {
         reset_time.validate()?;
 
-        let (time_type, hour, custom_time) = reset_time.to_database_format();
+        let (time_type, hour, minute, custom_time) = reset_time.to_database_format();
         self.daily_reset_time_type = time_type;
         self.daily_reset_time_hour = hour;
+        self.daily_reset_time_minute = minute;
         self.daily_reset_time_custom = custom_time;
 
         self.touch();
@@ -561,12 +907,7 @@ impl UserConfiguration {
             self.daily_reset_enabled = enabled;
             if enabled && self.last_daily_reset_utc.is_none() {
                 // Set initial last reset time to now
-                self.last_daily_reset_utc = Some(
-                    SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap()
-                        .as_secs() as i64,
-                );
+                self.last_daily_reset_utc = Some(now_unix() as i64);
             }
             self.touch();
         }
@@ -591,12 +932,7 @@ impl UserConfiguration {
     pub fn reset_session_count(&mut self) {
         self.today_session_count = 0;
         self.manual_session_override = None;
-        self.last_daily_reset_utc = Some(
-            SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs() as i64,
-        );
+        self.last_daily_reset_utc = Some(now_unix() as i64);
         self.touch();
     }
 
@@ -622,31 +958,17 @@ impl UserConfiguration {
 
     /// Validate session count bounds
     fn validate_session_count(&self, count: u32) -> Result<(), UserConfigurationError> {
-        if count > 1000 {
-            return Err(UserConfigurationError::InvalidSessionCount(count.to_string()));
+        if count > self.max_session_count {
+            return Err(UserConfigurationError::InvalidSessionCount(
+                format!("{} exceeds the maximum of {}", count, self.max_session_count)
+            ));
         }
         Ok(())
     }
 
     /// Get cron expression for daily reset
     pub fn get_daily_reset_cron_expression(&self) -> String {
-        match self.daily_reset_time_type {
-            DailyResetTimeType::Midnight => "0 0 * * *".to_string(),
-            DailyResetTimeType::Hour => {
-                self.daily_reset_time_hour
-                    .map(|h| format!("0 {} * * *", h))
-                    .unwrap_or_else(|| "0 0 * * *".to_string())
-            }
-            DailyResetTimeType::Custom => {
-                if let Some(ref time) = self.daily_reset_time_custom {
-                    let parts: Vec<&str> = time.split(':').collect();
-                    if parts.len() == 2 {
-                        return format!("0 {} {} * *", parts[1], parts[0]);
-                    }
-                }
-                "0 0 * * *".to_string()
-            }
-        }
+        self.get_daily_reset_time().to_cron_expression()
     }
 
     /// Check if daily reset is due based on last reset time and current time
@@ -683,13 +1005,13 @@ impl Default for UserConfiguration {
 /// User configuration validation errors
 #[derive(Debug, thiserror::Error)]
 pub enum UserConfigurationError {
-    #[error("Work duration {0} minutes is invalid (must be 5-60 minutes)")]
+    #[error("Work duration {0}s is invalid (must be 5-60 minutes)")]
     InvalidWorkDuration(u32),
 
-    #[error("Short break duration {0} minutes is invalid (must be 1-15 minutes)")]
+    #[error("Short break duration {0}s is invalid (must be 1-15 minutes)")]
     InvalidShortBreakDuration(u32),
 
-    #[error("Long break duration {0} minutes is invalid (must be 5-30 minutes)")]
+    #[error("Long break duration {0}s is invalid (must be 5-30 minutes)")]
     InvalidLongBreakDuration(u32),
 
     #[error("Long break frequency {0} is invalid (must be 2-10 work sessions)")]
@@ -698,6 +1020,12 @@ pub enum UserConfigurationError {
     #[error("Webhook URL '{0}' is invalid")]
     InvalidWebhookUrl(String),
 
+    #[error("The custom webhook format requires a template")]
+    MissingWebhookTemplate,
+
+    #[error("Invalid webhook template: {0}")]
+    InvalidWebhookTemplate(String),
+
     #[error("Configuration timestamps are inconsistent")]
     InvalidTimestamps,
 
@@ -707,12 +1035,33 @@ pub enum UserConfigurationError {
     #[error("Invalid reset hour {0} (must be 0-23)")]
     InvalidResetHour(u8),
 
+    #[error("Invalid reset minute {0} (must be 0-59)")]
+    InvalidResetMinute(u8),
+
     #[error("Invalid reset time '{0}' (must be HH:MM format)")]
     InvalidResetTime(String),
 
+    #[error("Reset time type 'hour' requires a reset hour")]
+    MissingResetHour,
+
+    #[error("Reset time type 'custom' requires a reset time")]
+    MissingResetTime,
+
+    #[error("Reset time type '{0}' has values set that belong to another type")]
+    UnexpectedResetTimeValue(&'static str),
+
     #[error("Invalid session count: {0}")]
     InvalidSessionCount(String),
 
+    #[error("Maximum session count {0} is invalid (must be 1-100000)")]
+    InvalidMaxSessionCount(u32),
+
+    #[error("Daily goal {0} is invalid (must be 1-{1})")]
+    InvalidDailyGoal(u32, u32),
+
+    #[error("Unknown session type '{0}' (must be work, short_break or long_break)")]
+    InvalidSessionType(String),
+
     #[error("Manual session override is active - automated counting is blocked")]
     ManualOverrideActive,
 
@@ -790,22 +1139,32 @@ mod tests {
         let mut config = UserConfiguration::new();
 
         // Valid HTTPS URL
-        assert!(config.set_webhook_url(Some("https://example.com/webhook".to_string())).is_ok());
+        assert!(config.set_webhook_urls(vec!["https://example.com/webhook".to_string()]).is_ok());
 
         // Valid HTTP URL
-        assert!(config.set_webhook_url(Some("http://localhost:3000/webhook".to_string())).is_ok());
+        assert!(config.set_webhook_urls(vec!["http://localhost:3000/webhook".to_string()]).is_ok());
 
         // Invalid URL
-        assert!(config.set_webhook_url(Some("not-a-url".to_string())).is_err());
+        assert!(config.set_webhook_urls(vec!["not-a-url".to_string()]).is_err());
 
         // Invalid scheme
-        assert!(config.set_webhook_url(Some("ftp://example.com/webhook".to_string())).is_err());
+        assert!(config.set_webhook_urls(vec!["ftp://example.com/webhook".to_string()]).is_err());
+
+        // Every URL is checked, and a rejected list leaves the old one in place
+        let urls = vec!["https://hooks.slack.com/services/T0".to_string(), "https://example.com/webhook".to_string()];
+        config.set_webhook_urls(urls.clone()).unwrap();
+        assert!(config.set_webhook_urls(vec![urls[0].clone(), "not-a-url".to_string()]).is_err());
+        assert_eq!(config.webhook_urls, urls);
+        assert_eq!(UserConfiguration::webhook_urls_from_json(&config.webhook_urls_json()), urls);
     }
 
     #[test]
     fn test_theme_operations() {
         let mut config = UserConfiguration::new();
         assert_eq!(config.theme, Theme::Light);
+        // Timestamps have one-second resolution, so start from an older record
+        config.created_at -= 60;
+        config.updated_at = config.created_at;
 
         config.set_theme(Theme::Dark);
         assert_eq!(config.theme, Theme::Dark);
@@ -852,7 +1211,7 @@ mod tests {
         assert!(!config.should_send_webhook());
 
         // Add webhook URL
-        config.set_webhook_url(Some("https://example.com/webhook".to_string())).unwrap();
+        config.set_webhook_urls(vec!["https://example.com/webhook".to_string()]).unwrap();
         assert!(config.should_send_webhook());
 
         // Disable notifications
@@ -866,4 +1225,217 @@ mod tests {
         assert_eq!(Theme::Light.display_name(), "Light");
         assert_eq!(Theme::Dark.display_name(), "Dark");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_inconsistent_reset_time_rejected() {
+        let cases = [
+            (DailyResetTimeType::Hour, None, None),
+            (DailyResetTimeType::Hour, Some(24), None),
+            (DailyResetTimeType::Hour, Some(8), Some("08:00")),
+            (DailyResetTimeType::Custom, None, None),
+            (DailyResetTimeType::Custom, Some(8), None),
+            (DailyResetTimeType::Custom, None, Some("8am")),
+            (DailyResetTimeType::Custom, Some(8), Some("08:30")),
+            (DailyResetTimeType::Midnight, Some(0), None),
+            (DailyResetTimeType::Midnight, None, Some("00:00")),
+        ];
+
+        for (time_type, hour, time) in cases {
+            let mut config = UserConfiguration::new();
+            config.daily_reset_time_type = time_type.clone();
+            config.daily_reset_time_hour = hour;
+            config.daily_reset_time_custom = time.map(str::to_string);
+            assert!(
+                config.validate().is_err(),
+                "{:?} with hour {:?} and time {:?} should be rejected",
+                time_type, hour, time
+            );
+        }
+
+        let mut config = UserConfiguration::new();
+        config.daily_reset_time_type = DailyResetTimeType::Hour;
+        assert!(matches!(config.validate(), Err(UserConfigurationError::MissingResetHour)));
+        config.daily_reset_time_type = DailyResetTimeType::Custom;
+        assert!(matches!(config.validate(), Err(UserConfigurationError::MissingResetTime)));
+    }
+
+    #[test]
+    fn test_consistent_reset_time_accepted() {
+        let reset_times = [
+            DailyResetTime::midnight(),
+            DailyResetTime::hour(0).unwrap(),
+            DailyResetTime::hour(23).unwrap(),
+            DailyResetTime::custom("14:30".to_string()).unwrap(),
+        ];
+
+        for reset_time in reset_times {
+            let mut config = UserConfiguration::new();
+            config.set_daily_reset_time(reset_time.clone()).unwrap();
+            assert!(config.validate().is_ok(), "{:?} should be accepted", reset_time);
+        }
+    }
+
+    #[test]
+    fn test_hour_reset_with_minutes() {
+        let reset_time = DailyResetTime::hour_minute(8, 30).unwrap();
+        assert_eq!(reset_time.local_time(), (8, 30));
+        assert_eq!(reset_time.to_cron_expression(), "30 8 * * *");
+        assert_eq!(reset_time.display_name(), "8:30");
+
+        // Without minutes the hour type still resets on the hour
+        assert_eq!(DailyResetTime::hour(8).unwrap().to_cron_expression(), "0 8 * * *");
+        // Custom times share the same cron form
+        assert_eq!(DailyResetTime::custom("08:30".to_string()).unwrap().to_cron_expression(), "30 8 * * *");
+
+        assert!(matches!(
+            DailyResetTime::hour_minute(8, 60),
+            Err(UserConfigurationError::InvalidResetMinute(60))
+        ));
+
+        let mut config = UserConfiguration::new();
+        config.daily_reset_time_type = DailyResetTimeType::Custom;
+        config.daily_reset_time_custom = Some("08:30".to_string());
+        config.daily_reset_time_minute = Some(30);
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_max_session_count() {
+        let mut config = UserConfiguration::new();
+        assert_eq!(config.max_session_count, DEFAULT_MAX_SESSION_COUNT);
+        assert!(config.set_manual_session_override(Some(1001)).is_err());
+
+        // A raised cap permits higher counts
+        config.set_max_session_count(2000).unwrap();
+        config.set_manual_session_override(Some(1500)).unwrap();
+        assert_eq!(config.get_current_session_count(), 1500);
+
+        // A lowered cap rejects values above it
+        config.set_manual_session_override(None).unwrap();
+        config.set_max_session_count(2).unwrap();
+        config.increment_session_count().unwrap();
+        config.increment_session_count().unwrap();
+        assert!(config.increment_session_count().is_err());
+        assert!(config.set_manual_session_override(Some(3)).is_err());
+        assert_eq!(config.today_session_count, 2);
+
+        assert!(matches!(
+            config.set_max_session_count(0),
+            Err(UserConfigurationError::InvalidMaxSessionCount(0))
+        ));
+        assert!(config.set_max_session_count(MAX_SESSION_COUNT_LIMIT + 1).is_err());
+    }
+
+    #[test]
+    fn test_webhook_formats_render_completed_work_session() {
+        let event = WebhookEvent {
+            session_type: "work".to_string(),
+            session_count: 3,
+            message: "Work session #3 complete! \"Break\" time.".to_string(),
+            timestamp: 1_700_000_000,
+        };
+
+        let raw = WebhookFormat::Raw.render(None, &event).unwrap();
+        assert_eq!(raw["title"], "Roma Timer");
+        assert_eq!(raw["session_type"], "work");
+        assert_eq!(raw["session_count"], 3);
+        assert_eq!(raw["timestamp"], 1_700_000_000);
+
+        let slack = WebhookFormat::Slack.render(None, &event).unwrap();
+        assert_eq!(slack, serde_json::json!({ "text": event.message }));
+
+        let discord = WebhookFormat::Discord.render(None, &event).unwrap();
+        assert_eq!(discord, serde_json::json!({ "content": event.message }));
+
+        let template = r#"{"msg": "{message}", "kind": "{session_type}", "n": {session_count}, "at": {timestamp}, "meta": {}}"#;
+        let custom = WebhookFormat::Custom.render(Some(template), &event).unwrap();
+        assert_eq!(custom, serde_json::json!({
+            "msg": event.message,
+            "kind": "work",
+            "n": 3,
+            "at": 1_700_000_000,
+            "meta": {}
+        }));
+    }
+
+    #[test]
+    fn test_webhook_template_validated_when_set() {
+        let mut config = UserConfiguration::new();
+
+        assert!(matches!(
+            config.set_webhook_format(WebhookFormat::Custom, None),
+            Err(UserConfigurationError::MissingWebhookTemplate)
+        ));
+        assert!(matches!(
+            config.set_webhook_format(WebhookFormat::Custom, Some(r#"{"text": "{sesion_type}"}"#.to_string())),
+            Err(UserConfigurationError::InvalidWebhookTemplate(_))
+        ));
+        assert!(matches!(
+            config.set_webhook_format(WebhookFormat::Custom, Some(r#"{"text": {message}"#.to_string())),
+            Err(UserConfigurationError::InvalidWebhookTemplate(_))
+        ));
+        assert_eq!(config.webhook_format, WebhookFormat::Raw);
+
+        config
+            .set_webhook_format(WebhookFormat::Custom, Some(r#"{"text": "{message}"}"#.to_string()))
+            .unwrap();
+        assert_eq!(config.webhook_format, WebhookFormat::Custom);
+        assert!(config.validate().is_ok());
+
+        // Built-in formats don't need a template
+        config.set_webhook_format(WebhookFormat::Slack, None).unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_daily_goal_within_max_session_count() {
+        let mut config = UserConfiguration::new();
+        assert_eq!(config.daily_goal, None);
+
+        config.set_daily_goal(Some(8)).unwrap();
+        assert_eq!(config.daily_goal, Some(8));
+        assert!(config.validate().is_ok());
+
+        assert!(matches!(
+            config.set_daily_goal(Some(0)),
+            Err(UserConfigurationError::InvalidDailyGoal(0, DEFAULT_MAX_SESSION_COUNT))
+        ));
+        config.set_max_session_count(10).unwrap();
+        assert!(config.set_daily_goal(Some(11)).is_err());
+        assert_eq!(config.daily_goal, Some(8));
+
+        // Lowering the ceiling below the goal makes the configuration invalid
+        config.set_max_session_count(5).unwrap();
+        assert!(config.validate().is_err());
+
+        config.set_daily_goal(None).unwrap();
+        assert!(config.validate().is_ok());
+    }
+
+    #[test]
+    fn test_custom_reset_time_cron_round_trip() {
+        use crate::models::scheduled_task::ScheduledTask;
+        use chrono::{TimeZone, Utc};
+
+        let mut config = UserConfiguration::new();
+        config.set_timezone("Europe/Berlin".to_string()).unwrap();
+        config.set_daily_reset_time(DailyResetTime::custom("14:30".to_string()).unwrap()).unwrap();
+        assert_eq!(config.get_daily_reset_cron_expression(), "30 14 * * *");
+
+        let mut task = ScheduledTask::daily_reset_task(
+            config.id.clone(),
+            config.get_daily_reset_cron_expression(),
+            config.timezone.clone(),
+        );
+        assert!(task.validate().is_ok());
+
+        // 14:30 in Berlin (UTC+1 in January) is 13:30 UTC
+        let morning = Utc.with_ymd_and_hms(2025, 1, 7, 8, 0, 0).unwrap();
+        task.calculate_next_run(morning).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 7, 13, 30, 0).unwrap());
+
+        let afternoon = Utc.with_ymd_and_hms(2025, 1, 7, 13, 30, 0).unwrap();
+        task.calculate_next_run(afternoon).unwrap();
+        assert_eq!(task.next_run_time(), Utc.with_ymd_and_hms(2025, 1, 8, 13, 30, 0).unwrap());
+    }
+}
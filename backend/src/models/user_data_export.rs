@@ -0,0 +1,174 @@
+//! User Data Export Model
+//!
+//! A user's complete data - configuration, session history, daily statistics
+//! and reset events - as one document, for backups and for moving an account
+//! to another user or server.
+
+use serde::{Deserialize, Serialize};
+use sqlx::FromRow;
+
+use super::daily_session_stats::{DailySessionStats, DailySessionStatsError};
+use super::session_reset_event::{SessionResetEvent, SessionResetEventError};
+use super::user_configuration::{UserConfiguration, UserConfigurationError, SESSION_TYPES};
+
+/// Version of the export document this server reads and writes
+pub const USER_DATA_EXPORT_VERSION: u32 = 1;
+
+/// A finished or in-progress timer session, as stored
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, FromRow)]
+pub struct ExportedSession {
+    pub id: String,
+    pub device_id: String,
+    pub timer_type: String,
+    pub duration: i64,
+    pub elapsed: i64,
+    pub created_at: i64,
+    pub updated_at: i64,
+    pub completed_at: Option<i64>,
+    pub abandoned_at: Option<i64>,
+    pub skipped_at: Option<i64>,
+    pub label: Option<String>,
+    #[serde(default)]
+    pub added_seconds: i64,
+}
+
+impl ExportedSession {
+    /// Validate the session's type and durations
+    pub fn validate(&self) -> Result<(), UserDataExportError> {
+        let invalid = |reason: &str| UserDataExportError::Session(self.id.clone(), reason.to_string());
+
+        if self.id.is_empty() {
+            return Err(invalid("missing id"));
+        }
+        if !SESSION_TYPES.contains(&self.timer_type.as_str()) {
+            return Err(invalid(&format!("unknown session type '{}'", self.timer_type)));
+        }
+        if self.duration < 0 || self.elapsed < 0 || self.added_seconds < 0 {
+            return Err(invalid("negative duration"));
+        }
+        Ok(())
+    }
+}
+
+/// Everything stored for one user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UserDataExport {
+    pub version: u32,
+    /// When the export was taken (Unix timestamp)
+    pub exported_at: i64,
+    pub configuration: UserConfiguration,
+    pub sessions: Vec<ExportedSession>,
+    pub daily_stats: Vec<DailySessionStats>,
+    pub reset_events: Vec<SessionResetEvent>,
+}
+
+impl UserDataExport {
+    /// Move every record over to `user_id`
+    pub fn for_user(mut self, user_id: &str) -> Self {
+        self.configuration.id = user_id.to_string();
+        for stats in &mut self.daily_stats {
+            stats.user_configuration_id = user_id.to_string();
+        }
+        for event in &mut self.reset_events {
+            event.user_configuration_id = user_id.to_string();
+        }
+        self
+    }
+
+    /// Validate the document version and every record in it
+    pub fn validate(&self) -> Result<(), UserDataExportError> {
+        if self.version != USER_DATA_EXPORT_VERSION {
+            return Err(UserDataExportError::UnsupportedVersion(self.version));
+        }
+
+        self.configuration.validate()?;
+        for session in &self.sessions {
+            session.validate()?;
+        }
+        for stats in &self.daily_stats {
+            stats
+                .validate()
+                .map_err(|e| UserDataExportError::DailyStats(stats.date.clone(), e))?;
+        }
+        for event in &self.reset_events {
+            event
+                .validate()
+                .map_err(|e| UserDataExportError::ResetEvent(event.id.clone(), e))?;
+        }
+        Ok(())
+    }
+}
+
+/// Reasons an export document can't be imported
+#[derive(Debug, thiserror::Error)]
+pub enum UserDataExportError {
+    #[error("Unsupported export version {0}")]
+    UnsupportedVersion(u32),
+
+    #[error("Invalid configuration: {0}")]
+    Configuration(#[from] UserConfigurationError),
+
+    #[error("Invalid session {0}: {1}")]
+    Session(String, String),
+
+    #[error("Invalid daily stats for {0}: {1}")]
+    DailyStats(String, DailySessionStatsError),
+
+    #[error("Invalid reset event {0}: {1}")]
+    ResetEvent(String, SessionResetEventError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn export() -> UserDataExport {
+        let configuration = UserConfiguration::with_id("alice".to_string());
+        UserDataExport {
+            version: USER_DATA_EXPORT_VERSION,
+            exported_at: 0,
+            sessions: vec![ExportedSession {
+                id: "session-1".to_string(),
+                device_id: "laptop".to_string(),
+                timer_type: "work".to_string(),
+                duration: 1500,
+                elapsed: 1500,
+                created_at: 1_000,
+                updated_at: 2_500,
+                completed_at: Some(2_500),
+                abandoned_at: None,
+                skipped_at: None,
+                label: None,
+                added_seconds: 0,
+            }],
+            daily_stats: vec![DailySessionStats::new("alice".to_string(), "2024-03-09".to_string(), "UTC".to_string())],
+            reset_events: Vec::new(),
+            configuration,
+        }
+    }
+
+    #[test]
+    fn test_export_validation_checks_every_record() {
+        assert!(export().validate().is_ok());
+
+        let mut bad_version = export();
+        bad_version.version = USER_DATA_EXPORT_VERSION + 1;
+        assert!(matches!(bad_version.validate(), Err(UserDataExportError::UnsupportedVersion(_))));
+
+        let mut bad_config = export();
+        bad_config.configuration.work_duration = 0;
+        assert!(matches!(bad_config.validate(), Err(UserDataExportError::Configuration(_))));
+
+        let mut bad_session = export();
+        bad_session.sessions[0].timer_type = "nap".to_string();
+        assert!(matches!(bad_session.validate(), Err(UserDataExportError::Session(id, _)) if id == "session-1"));
+
+        let mut bad_stats = export();
+        bad_stats.daily_stats[0].work_sessions_completed = -1;
+        assert!(matches!(bad_stats.validate(), Err(UserDataExportError::DailyStats(_, _))));
+
+        let moved = export().for_user("bob");
+        assert_eq!(moved.configuration.id, "bob");
+        assert_eq!(moved.daily_stats[0].user_configuration_id, "bob");
+    }
+}
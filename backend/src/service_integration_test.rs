@@ -4,9 +4,7 @@
 
 #[cfg(test)]
 mod integration_tests {
-    use super::*;
     use crate::services::daily_reset_service::DailyResetService;
-    use crate::services::timezone_service::TimezoneService;
     use crate::services::configuration_service::ConfigurationService;
     use crate::services::timer_service::TimerService;
     use crate::services::time_provider::SystemTimeProvider;
@@ -19,7 +17,7 @@ mod integration_tests {
         // Create temporary database
         let temp_dir = TempDir::new()?;
         let db_path = temp_dir.path().join("test.db");
-        let db_url = format!("sqlite:{}", db_path.display());
+        let db_url = format!("sqlite:{}?mode=rwc", db_path.display());
 
         // Initialize database manager
         let database_manager = Arc::new(DatabaseManager::new(&db_url).await?);
@@ -28,16 +26,12 @@ mod integration_tests {
         // Create services
         let time_provider = Arc::new(SystemTimeProvider);
         let daily_reset_service = Arc::new(DailyResetService::new(
-            database_manager.clone(),
             time_provider,
-        )?);
-
-        let websocket_service = Arc::new(
-            crate::services::websocket_service::WebSocketService::new(database_manager.clone())
-        );
+            database_manager.clone(),
+        ));
 
         let configuration_service = Arc::new(
-            ConfigurationService::new(database_manager.clone(), websocket_service).await?
+            ConfigurationService::without_broadcast(database_manager.clone()).await?
         );
 
         let timer_service = Arc::new(
@@ -53,6 +47,12 @@ mod integration_tests {
 
         // Test that daily reset service works
         let user_id = "test_user_123";
+        sqlx::query(
+            "INSERT INTO user_configurations (id, daily_reset_enabled, created_at, updated_at) VALUES (?, TRUE, 0, 0)"
+        )
+        .bind(user_id)
+        .execute(database_manager.pool.sqlite().unwrap())
+        .await?;
         let daily_status = daily_reset_service.get_daily_reset_status(user_id).await?;
         assert_eq!(daily_status.current_session_count, 0);
 
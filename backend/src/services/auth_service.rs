@@ -0,0 +1,484 @@
+//! Authentication Service
+//!
+//! Password hashing, signed access tokens, token revocation and the `CurrentUser` extractor.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
+
+use crate::database::DatabaseManager;
+use crate::services::time_provider::now_unix;
+
+use axum::http::{header, StatusCode};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::{Algorithm, Argon2, Params, Version};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthClaims {
+    pub sub: String, // Subject (user identifier)
+    pub exp: u64,    // Expiration time
+    pub iat: u64,    // Issued at
+    /// Unique token id, used to revoke it. Absent from tokens issued before logout existed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub jti: Option<String>,
+}
+
+// Authentication functions
+type HmacSha256 = Hmac<Sha256>;
+
+fn get_shared_secret() -> String {
+    env::var("ROMA_TIMER_SHARED_SECRET").unwrap_or_else(|_| "default-secret-change-me".to_string())
+}
+
+pub fn get_pepper() -> String {
+    env::var("ROMA_TIMER_PEPPER")
+        .unwrap_or_else(|_| "default-pepper-change-me-in-production".to_string())
+}
+
+/// Default lifetime of an access token: 15 minutes
+pub const DEFAULT_ACCESS_TOKEN_TTL_SECS: u64 = 15 * 60;
+
+/// Default lifetime of a refresh token: 30 days
+const DEFAULT_REFRESH_TOKEN_TTL_SECS: u64 = 30 * 24 * 60 * 60;
+
+fn get_access_token_ttl() -> u64 {
+    env::var("ROMA_TIMER_ACCESS_TOKEN_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_ACCESS_TOKEN_TTL_SECS)
+}
+
+pub fn get_refresh_token_ttl() -> u64 {
+    env::var("ROMA_TIMER_REFRESH_TOKEN_TTL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(DEFAULT_REFRESH_TOKEN_TTL_SECS)
+}
+
+/// Default allowance (seconds) for client/server clock skew when checking token times
+const DEFAULT_TOKEN_LEEWAY_SECS: u64 = 60;
+
+pub fn get_token_leeway() -> u64 {
+    env::var("ROMA_TIMER_TOKEN_LEEWAY_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_TOKEN_LEEWAY_SECS)
+}
+
+/// How long a device pairing code stays valid
+pub const PAIRING_CODE_TTL_SECS: u64 = 5 * 60;
+
+/// Generate a short, human-typeable pairing code
+pub fn generate_pairing_code() -> String {
+    const ALPHABET: &[u8] = b"ABCDEFGHJKLMNPQRSTUVWXYZ23456789";
+    let mut rng = rand::thread_rng();
+    (0..8)
+        .map(|_| ALPHABET[rng.gen_range(0..ALPHABET.len())] as char)
+        .collect()
+}
+
+pub fn generate_salt() -> String {
+    let mut rng = rand::thread_rng();
+    let salt: [u8; 32] = rng.gen();
+    hex::encode(salt)
+}
+
+/// OWASP-recommended Argon2id minimums: 19 MiB of memory, 2 iterations, 1 lane
+const DEFAULT_ARGON2_MEMORY_KIB: u32 = 19 * 1024;
+
+const DEFAULT_ARGON2_ITERATIONS: u32 = 2;
+
+const DEFAULT_ARGON2_PARALLELISM: u32 = 1;
+
+/// Argon2id cost parameters from `ROMA_TIMER_ARGON2_MEMORY_KIB`,
+/// `ROMA_TIMER_ARGON2_ITERATIONS` and `ROMA_TIMER_ARGON2_PARALLELISM`.
+/// Missing or invalid values fall back to the defaults above.
+fn get_argon2_params() -> Params {
+    let setting = |name: &str, default: u32| {
+        env::var(name)
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .unwrap_or(default)
+    };
+    let memory_kib = setting("ROMA_TIMER_ARGON2_MEMORY_KIB", DEFAULT_ARGON2_MEMORY_KIB);
+    let iterations = setting("ROMA_TIMER_ARGON2_ITERATIONS", DEFAULT_ARGON2_ITERATIONS);
+    let parallelism = setting("ROMA_TIMER_ARGON2_PARALLELISM", DEFAULT_ARGON2_PARALLELISM);
+
+    Params::new(memory_kib, iterations, parallelism, None).unwrap_or_else(|e| {
+        tracing::warn!("Invalid Argon2 parameters ({e}), using defaults");
+        Params::new(
+            DEFAULT_ARGON2_MEMORY_KIB,
+            DEFAULT_ARGON2_ITERATIONS,
+            DEFAULT_ARGON2_PARALLELISM,
+            None,
+        )
+        .expect("default Argon2 parameters are valid")
+    })
+}
+
+/// Argon2id keyed with the pepper, so a leaked database alone can't be cracked
+fn argon2_hasher(pepper: &str, params: Params) -> Result<Argon2<'_>, Box<dyn std::error::Error>> {
+    Argon2::new_with_secret(pepper.as_bytes(), Algorithm::Argon2id, Version::V0x13, params)
+        .map_err(|e| e.to_string().into())
+}
+
+/// Hash a password with Argon2id, returning a PHC string (`$argon2id$v=19$...`)
+/// that records the parameters used
+pub fn hash_password(
+    password: &str,
+    salt: &str,
+    pepper: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let salt = SaltString::encode_b64(&hex::decode(salt)?).map_err(|e| e.to_string())?;
+    let hash = argon2_hasher(pepper, get_argon2_params())?
+        .hash_password(password.as_bytes(), &salt)
+        .map_err(|e| e.to_string())?;
+    Ok(hash.to_string())
+}
+
+/// The HMAC-SHA256 hash used before Argon2id. Only kept so existing users can
+/// still log in and be re-hashed.
+pub fn legacy_hash_password(
+    password: &str,
+    salt: &str,
+    pepper: &str,
+) -> Result<String, Box<dyn std::error::Error>> {
+    let combined = format!("{password}{salt}{pepper}");
+    let mut mac = HmacSha256::new_from_slice(combined.as_bytes())?;
+    mac.update(b"roma-timer-hash");
+    let hash = mac.finalize().into_bytes();
+    Ok(hex::encode(hash))
+}
+
+pub fn verify_password(password: &str, salt: &str, pepper: &str, stored_hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(stored_hash) else {
+        // Not a PHC string: a legacy HMAC hash
+        return match legacy_hash_password(password, salt, pepper) {
+            Ok(computed_hash) => computed_hash == stored_hash,
+            Err(_) => false,
+        };
+    };
+
+    // Verification uses the parameters recorded in the hash, not the current ones
+    match argon2_hasher(pepper, Params::default()) {
+        Ok(hasher) => hasher.verify_password(password.as_bytes(), &parsed).is_ok(),
+        Err(_) => false,
+    }
+}
+
+/// Whether a stored hash should be replaced on the next successful login:
+/// legacy HMAC hashes, and Argon2 hashes made with other parameters
+pub fn needs_rehash(stored_hash: &str) -> bool {
+    let Ok(parsed) = PasswordHash::new(stored_hash) else {
+        return true;
+    };
+    if parsed.algorithm != Algorithm::Argon2id.ident() {
+        return true;
+    }
+
+    let current = get_argon2_params();
+    Params::try_from(&parsed).map_or(true, |params| {
+        params.m_cost() != current.m_cost()
+            || params.t_cost() != current.t_cost()
+            || params.p_cost() != current.p_cost()
+    })
+}
+
+pub fn generate_auth_token(user_id: &str) -> Result<String, Box<dyn std::error::Error>> {
+    let now = now_unix();
+
+    let claims = AuthClaims {
+        sub: user_id.to_string(),
+        iat: now,
+        exp: now + get_access_token_ttl(),
+        jti: Some(Uuid::new_v4().to_string()),
+    };
+
+    sign_claims(&claims)
+}
+
+pub fn sign_claims(claims: &AuthClaims) -> Result<String, Box<dyn std::error::Error>> {
+    let secret = get_shared_secret();
+    let claims_json = serde_json::to_string(claims)?;
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(claims_json.as_bytes());
+    let signature = mac.finalize().into_bytes();
+
+    let token = format!(
+        "{}.{}",
+        general_purpose::STANDARD.encode(claims_json.as_bytes()),
+        general_purpose::STANDARD.encode(signature)
+    );
+
+    Ok(token)
+}
+
+pub fn verify_auth_token(token: &str) -> Result<AuthClaims, Box<dyn std::error::Error>> {
+    verify_auth_token_with_leeway(token, get_token_leeway())
+}
+
+/// Verify a token, allowing `leeway` seconds of clock skew on `exp` and `iat`
+fn verify_auth_token_with_leeway(
+    token: &str,
+    leeway: u64,
+) -> Result<AuthClaims, Box<dyn std::error::Error>> {
+    let secret = get_shared_secret();
+
+    let parts: Vec<&str> = token.split('.').collect();
+    if parts.len() != 2 {
+        return Err("Invalid token format".into());
+    }
+
+    let claims_bytes = general_purpose::STANDARD.decode(parts[0])?;
+    let signature_bytes = general_purpose::STANDARD.decode(parts[1])?;
+
+    let claims_json = String::from_utf8(claims_bytes)?;
+    let claims: AuthClaims = serde_json::from_str(&claims_json)?;
+
+    // Check expiration
+    let now = now_unix();
+
+    if claims.exp.saturating_add(leeway) < now {
+        return Err("Token expired".into());
+    }
+
+    if claims.iat > now.saturating_add(leeway) {
+        return Err("Token issued in the future".into());
+    }
+
+    // Verify signature
+    let mut mac = HmacSha256::new_from_slice(secret.as_bytes())?;
+    mac.update(claims_json.as_bytes());
+    let expected_signature = mac.finalize().into_bytes();
+
+    use hmac::Mac;
+    if signature_bytes.len() != expected_signature.len() {
+        return Err("Invalid signature".into());
+    }
+
+    // Constant-time comparison
+    let mut result = 0u8;
+    for (a, b) in signature_bytes.iter().zip(expected_signature.iter()) {
+        result |= a ^ b;
+    }
+
+    if result != 0 {
+        return Err("Invalid signature".into());
+    }
+
+    if is_token_revoked(&token_revocation_id(&claims, token)) {
+        return Err("Token revoked".into());
+    }
+
+    Ok(claims)
+}
+
+/// Id under which a token is revoked: its `jti`, or for older tokens without
+/// one, its signature
+pub fn token_revocation_id(claims: &AuthClaims, token: &str) -> String {
+    claims
+        .jti
+        .clone()
+        .unwrap_or_else(|| token.rsplit('.').next().unwrap_or_default().to_string())
+}
+
+/// Revoked token ids and their expiry, mirrored from the `revoked_tokens` table
+/// so token verification doesn't need the database
+fn revoked_tokens() -> &'static std::sync::Mutex<HashMap<String, u64>> {
+    static REVOKED: OnceLock<std::sync::Mutex<HashMap<String, u64>>> = OnceLock::new();
+    REVOKED.get_or_init(Default::default)
+}
+
+fn is_token_revoked(token_id: &str) -> bool {
+    revoked_tokens()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .contains_key(token_id)
+}
+
+pub fn mark_token_revoked(token_id: String, expires_at: u64) {
+    revoked_tokens()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .insert(token_id, expires_at);
+}
+
+/// How often expired entries are dropped from the revoked token list
+const REVOKED_TOKEN_CLEANUP_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Revocations of tokens that expired before this are no longer needed.
+/// Tokens are accepted until the leeway past `exp` has run out, so their
+/// revocations are kept until then too.
+pub fn revocation_cutoff(now: u64) -> u64 {
+    now.saturating_sub(get_token_leeway())
+}
+
+/// Drop revoked tokens that verification would reject as expired anyway,
+/// from memory and the database
+pub async fn cleanup_revoked_tokens(database: &DatabaseManager, now: u64) {
+    let cutoff = revocation_cutoff(now);
+    revoked_tokens()
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+        .retain(|_, expires_at| *expires_at >= cutoff);
+    match database.delete_expired_revoked_tokens(cutoff as i64).await {
+        Ok(0) => {}
+        Ok(removed) => tracing::debug!("Dropped {removed} expired revoked token(s)"),
+        Err(e) => tracing::error!("Failed to clean up revoked tokens: {e}"),
+    }
+}
+
+/// Background loop running `cleanup_revoked_tokens`
+pub async fn revoked_token_cleanup(database: Arc<DatabaseManager>) {
+    let mut interval = tokio::time::interval(REVOKED_TOKEN_CLEANUP_INTERVAL);
+    loop {
+        interval.tick().await;
+        cleanup_revoked_tokens(&database, now_unix()).await;
+    }
+}
+
+/// Verify the Bearer token in the request headers and return its claims
+pub fn authenticate(headers: &axum::http::HeaderMap) -> Result<AuthClaims, StatusCode> {
+    let token = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.strip_prefix("Bearer "))
+        .ok_or(StatusCode::UNAUTHORIZED)?;
+
+    verify_auth_token(token).map_err(|_| StatusCode::UNAUTHORIZED)
+}
+
+/// The authenticated user of a request. Uses the claims `auth_middleware`
+/// already verified, falling back to the Bearer token on routes outside it.
+#[derive(Debug)]
+pub struct CurrentUser(pub AuthClaims);
+
+#[axum::async_trait]
+impl<S: Send + Sync> axum::extract::FromRequestParts<S> for CurrentUser {
+    type Rejection = StatusCode;
+
+    async fn from_request_parts(
+        parts: &mut axum::http::request::Parts,
+        _state: &S,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(claims) = parts.extensions.get::<AuthClaims>() {
+            return Ok(CurrentUser(claims.clone()));
+        }
+        authenticate(&parts.headers).map(CurrentUser)
+    }
+}
+
+/// Identify the device a refresh token was issued to, from its User-Agent
+pub fn device_fingerprint(headers: &axum::http::HeaderMap) -> String {
+    use sha2::Digest;
+
+    let user_agent = headers
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or_default();
+    hex::encode(Sha256::digest(user_agent.as_bytes()))
+}
+
+/// Refresh tokens are stored hashed so a leaked database can't be used to sign in
+pub fn hash_refresh_token(token: &str) -> String {
+    use sha2::Digest;
+
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[test]
+    fn test_token_expired_within_leeway_is_accepted() {
+        let now = now_unix();
+        let token = token_with_times(now - 3600, now - 30);
+        assert!(verify_auth_token_with_leeway(&token, 60).is_ok());
+    }
+
+    #[test]
+    fn test_token_expired_beyond_leeway_is_rejected() {
+        let now = now_unix();
+        let token = token_with_times(now - 3600, now - 120);
+        assert!(verify_auth_token_with_leeway(&token, 60).is_err());
+
+        // Issued too far in the future
+        let token = token_with_times(now + 120, now + 3600);
+        assert!(verify_auth_token_with_leeway(&token, 60).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_current_user_extractor() {
+        use axum::extract::FromRequestParts;
+
+        async fn extract(authorization: Option<String>) -> Result<CurrentUser, StatusCode> {
+            let mut request = axum::http::Request::builder().uri("/api/timer");
+            if let Some(value) = authorization {
+                request = request.header(header::AUTHORIZATION, value);
+            }
+            let (mut parts, _) = request.body(()).unwrap().into_parts();
+            CurrentUser::from_request_parts(&mut parts, &()).await
+        }
+
+        let token = generate_auth_token("alice").unwrap();
+        let CurrentUser(claims) = extract(Some(format!("Bearer {token}"))).await.unwrap();
+        assert_eq!(claims.sub, "alice");
+
+        assert_eq!(extract(None).await.unwrap_err(), StatusCode::UNAUTHORIZED);
+
+        let now = now_unix();
+        let expired = token_with_times(now - 7200, now - 3600);
+        assert_eq!(
+            extract(Some(format!("Bearer {expired}"))).await.unwrap_err(),
+            StatusCode::UNAUTHORIZED
+        );
+    }
+
+    #[test]
+    fn test_argon2_password_round_trip() {
+        let salt = generate_salt();
+        let hash = hash_password("correct horse", &salt, "pepper").unwrap();
+
+        assert!(hash.starts_with("$argon2id$"));
+        assert!(verify_password("correct horse", &salt, "pepper", &hash));
+        assert!(!verify_password("wrong horse", &salt, "pepper", &hash));
+        assert!(!verify_password("correct horse", &salt, "other-pepper", &hash));
+        assert!(!needs_rehash(&hash));
+    }
+
+    #[tokio::test]
+    async fn test_revocation_outlives_token_expiry_by_the_leeway() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_, ws_manager) = test_app_state(&temp_dir).await;
+
+        let now = now_unix();
+        let token = token_with_times(now - 3600, now - 1);
+        let claims = verify_auth_token(&token).unwrap();
+        let token_id = token_revocation_id(&claims, &token);
+        ws_manager.database.revoke_token(&token_id, claims.exp as i64).await.unwrap();
+        mark_token_revoked(token_id.clone(), claims.exp);
+
+        // Past exp but still inside the leeway: the token would verify again
+        // if its revocation were dropped
+        cleanup_revoked_tokens(&ws_manager.database, now).await;
+        assert!(is_token_revoked(&token_id));
+        assert!(verify_auth_token(&token).is_err());
+        let stored = ws_manager.database.get_revoked_tokens(0).await.unwrap();
+        assert_eq!(stored.len(), 1);
+
+        cleanup_revoked_tokens(&ws_manager.database, claims.exp + get_token_leeway() + 1).await;
+        assert!(!is_token_revoked(&token_id));
+        assert!(ws_manager.database.get_revoked_tokens(0).await.unwrap().is_empty());
+    }
+}
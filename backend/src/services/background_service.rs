@@ -0,0 +1,483 @@
+//! Background Service
+//!
+//! Periodic sweepers for WebSocket connections and paused sessions, and the scheduled task runner.
+
+use std::env;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use crate::api::daily_reset::daily_reset_status_message;
+use crate::services::time_provider::now_unix;
+use crate::websocket::manager::{SharedWsManager, WebSocketManager};
+use crate::websocket::server::WS_CLOSE_RECONNECT;
+
+use axum::extract::ws::Message;
+
+/// How often to sweep dead WebSocket connections, from
+/// `ROMA_TIMER_WS_SWEEP_INTERVAL_SECS`. Defaults to 30s; zero disables the sweep.
+pub fn get_ws_sweep_interval() -> Option<Duration> {
+    let secs = env::var("ROMA_TIMER_WS_SWEEP_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(30);
+    (secs > 0).then(|| Duration::from_secs(secs))
+}
+
+/// Seconds a client may stay silent before the sweep drops its connection,
+/// from `ROMA_TIMER_WS_HEARTBEAT_TIMEOUT_SECS`. Defaults to 90s; zero only
+/// drops connections whose channel has closed.
+pub fn get_ws_heartbeat_timeout() -> Option<u64> {
+    let secs = env::var("ROMA_TIMER_WS_HEARTBEAT_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(90);
+    (secs > 0).then_some(secs)
+}
+
+/// Every sweep interval, drop dead connections and, with a heartbeat timeout
+/// set, ping the rest so live clients answer before the next sweep
+pub async fn dead_connection_sweeper(ws_manager: SharedWsManager, sweep_interval: Duration) {
+    let mut interval = tokio::time::interval(sweep_interval);
+
+    loop {
+        interval.tick().await;
+        let now = now_unix();
+        ws_manager.sweep_dead_connections(now).await;
+        if ws_manager.heartbeat_timeout_secs.is_some() {
+            ws_manager.ping_connections().await;
+        }
+    }
+}
+
+/// How often to run due scheduled tasks such as daily resets, from
+/// `ROMA_TIMER_SCHEDULER_INTERVAL_SECS`. Defaults to 60s.
+pub fn get_scheduler_interval() -> Duration {
+    let secs = env::var("ROMA_TIMER_SCHEDULER_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(60);
+    Duration::from_secs(secs)
+}
+
+/// How often to deactivate scheduled tasks left behind by deleted or disabled
+/// configurations, from `ROMA_TIMER_TASK_RECONCILIATION_INTERVAL_SECS`. Defaults to an hour.
+pub fn get_task_reconciliation_interval() -> Duration {
+    let secs = env::var("ROMA_TIMER_TASK_RECONCILIATION_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|secs| *secs > 0)
+        .unwrap_or(60 * 60);
+    Duration::from_secs(secs)
+}
+
+/// Every `period`, run the scheduled tasks that are due by `time_provider`'s
+/// clock, until `shutdown` turns true
+pub async fn scheduled_task_runner(
+    ws_manager: SharedWsManager,
+    time_provider: Arc<dyn crate::services::time_provider::TimeProvider>,
+    period: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(period);
+    ws_manager.scheduler_running.store(true, Ordering::Release);
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            _ = shutdown.wait_for(|stop| *stop) => {
+                ws_manager.scheduler_running.store(false, Ordering::Release);
+                tracing::info!(target: "roma::scheduler", "Scheduled task runner stopped");
+                return;
+            }
+        }
+        run_due_scheduled_tasks(&ws_manager, time_provider.clone()).await;
+    }
+}
+
+/// Run the due scheduled tasks once and broadcast the new daily reset status
+/// to every user whose count was reset
+async fn run_due_scheduled_tasks(
+    ws_manager: &SharedWsManager,
+    time_provider: Arc<dyn crate::services::time_provider::TimeProvider>,
+) {
+    let service = crate::services::daily_reset_service::DailyResetService::new(time_provider, ws_manager.database.clone())
+        .with_websocket_manager(ws_manager.clone());
+
+    let summary = match service.process_pending_tasks().await {
+        Ok(summary) => summary,
+        Err(e) => {
+            tracing::error!(target: "roma::scheduler", "Failed to process scheduled tasks: {e}");
+            return;
+        }
+    };
+
+    if summary.processed > 0 {
+        tracing::info!(
+            target: "roma::scheduler",
+            processed = summary.processed,
+            failed = summary.failed,
+            resets = summary.reset_events.len(),
+            "Processed due scheduled tasks"
+        );
+    }
+
+    for event in &summary.reset_events {
+        let user_id = &event.user_configuration_id;
+        let status = daily_reset_status_message(ws_manager.database.clone(), user_id).await;
+        ws_manager.broadcast_message(user_id, status).await;
+    }
+}
+
+/// Maximum seconds a WebSocket connection may stay open before the server
+/// asks the client to reconnect. Unset (the default) means no limit.
+pub fn get_ws_max_lifetime() -> Option<u64> {
+    env::var("ROMA_TIMER_WS_MAX_LIFETIME_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|secs| *secs > 0)
+}
+
+pub async fn connection_lifetime_sweeper(ws_manager: SharedWsManager, max_lifetime_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(max_lifetime_secs.clamp(1, 30)));
+
+    loop {
+        interval.tick().await;
+        let now = now_unix();
+        close_expired_connections(&ws_manager, max_lifetime_secs, now).await;
+    }
+}
+
+/// Close every connection open for at least `max_lifetime_secs` with
+/// `WS_CLOSE_RECONNECT`, so its client reconnects and re-validates its token.
+/// Returns the ids of the closed connections.
+async fn close_expired_connections(
+    ws_manager: &WebSocketManager,
+    max_lifetime_secs: u64,
+    now: u64,
+) -> Vec<String> {
+    let expired: Vec<String> = ws_manager
+        .connections
+        .lock()
+        .await
+        .values()
+        .filter(|connection| now.saturating_sub(connection.connected_at) >= max_lifetime_secs)
+        .map(|connection| connection.id.clone())
+        .collect();
+
+    let senders = ws_manager.senders.lock().await;
+    for connection_id in &expired {
+        tracing::info!(target: "roma::ws", "WebSocket {connection_id} reached its maximum lifetime, asking client to reconnect");
+        if let Some(sender) = senders.get(connection_id) {
+            let _ = sender.send(Message::Close(Some(axum::extract::ws::CloseFrame {
+                code: WS_CLOSE_RECONNECT,
+                reason: "reconnect".into(),
+            })));
+        }
+    }
+
+    expired
+}
+
+/// Seconds a partially-used session may stay paused before it's abandoned.
+/// Unset (the default) keeps paused sessions indefinitely.
+pub fn get_paused_abandon_timeout() -> Option<u64> {
+    env::var("ROMA_TIMER_PAUSED_ABANDON_TIMEOUT_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|secs| *secs > 0)
+}
+
+/// Background loop that abandons sessions left paused longer than `timeout_secs`
+pub async fn paused_session_sweeper(ws_manager: SharedWsManager, timeout_secs: u64) {
+    let mut interval = tokio::time::interval(Duration::from_secs(timeout_secs.clamp(1, 60)));
+
+    loop {
+        interval.tick().await;
+        let now = now_unix();
+        abandon_stale_paused_sessions(&ws_manager, timeout_secs, now).await;
+    }
+}
+
+/// For every user whose timer has been paused mid-session for longer than
+/// `timeout_secs`, record the partial session as abandoned and reset the timer
+/// to the full session duration. Returns the abandoned session ids.
+async fn abandon_stale_paused_sessions(
+    ws_manager: &WebSocketManager,
+    timeout_secs: u64,
+    now: u64,
+) -> Vec<String> {
+    let mut states = ws_manager.timer_states.lock().await;
+    let mut stale_timers = Vec::new();
+    for (user_id, timer_state) in states.iter() {
+        let partially_used = timer_state.remaining_seconds < timer_state.extended_duration();
+        let stale = now.saturating_sub(timer_state.last_updated) > timeout_secs;
+        if !timer_state.is_running && partially_used && stale {
+            stale_timers.push(user_id.clone());
+        }
+    }
+
+    let mut abandoned_timers = Vec::new();
+    for user_id in stale_timers {
+        let timer_state = states.user(&user_id);
+        let abandoned = timer_state.clone();
+        timer_state.rewind();
+        timer_state.last_updated = now;
+        abandoned_timers.push((user_id, abandoned, timer_state.clone()));
+    }
+    drop(states);
+
+    let mut session_ids = Vec::new();
+    for (user_id, abandoned, updated_state) in abandoned_timers {
+        match ws_manager
+            .database
+            .record_abandoned_session(&abandoned, &user_id, "server", now as i64)
+            .await
+        {
+            Ok(session_id) => session_ids.push(session_id),
+            Err(e) => tracing::error!("Failed to record abandoned session: {e}"),
+        }
+
+        tracing::info!(
+            "Abandoned {} session for {user_id} paused for more than {timeout_secs}s",
+            abandoned.session_type
+        );
+        ws_manager.update_timer_state(&user_id, updated_state).await;
+    }
+
+    session_ids
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use tokio::sync::mpsc;
+    use crate::api::daily_reset::update_daily_reset_config;
+    use crate::api::json::ApiJson;
+    use crate::websocket::manager::WsMessage;
+    use axum::extract::State;
+
+    #[tokio::test]
+    async fn test_scheduler_loop_runs_due_daily_reset_task() {
+        use crate::models::scheduled_task::ScheduledTask;
+        use chrono::TimeZone;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let _ = update_daily_reset_config(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(serde_json::from_value(serde_json::json!({
+                "enabled": true,
+                "timezone": "UTC",
+                "reset_time": {"type": "midnight"},
+            })).unwrap()),
+        )
+        .await
+        .unwrap();
+        sqlx::query("UPDATE user_configurations SET today_session_count = 3 WHERE id = 'alice'")
+            .execute(ws_manager.database.pool.sqlite().unwrap())
+            .await
+            .unwrap();
+
+        // Thirty seconds past midnight on the mock clock: the midnight run is due
+        let now = chrono::Utc.with_ymd_and_hms(2025, 1, 7, 0, 0, 30).unwrap();
+        let clock = Arc::new(crate::services::time_provider::MockTimeProvider::new(now));
+        let mut task = ScheduledTask::daily_reset_task("alice".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        task.next_run_utc = now.timestamp() - 30;
+        ws_manager.database.save_scheduled_task(&task).await.unwrap();
+
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let runner = tokio::spawn(scheduled_task_runner(
+            ws_manager.clone(),
+            clock,
+            Duration::from_millis(20),
+            shutdown_rx,
+        ));
+
+        let mut ran = None;
+        for _ in 0..50 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+            let stored = ws_manager.database.get_scheduled_task(&task.id).await.unwrap().unwrap();
+            if stored.run_count > 0 {
+                ran = Some(stored);
+                break;
+            }
+        }
+        let ran = ran.expect("due task was never run");
+        assert_eq!(ran.run_count, 1);
+        assert_eq!(ran.failure_count, 0);
+        assert_eq!(ran.last_run_utc, Some(now.timestamp()));
+        assert_eq!(ran.next_run_utc, chrono::Utc.with_ymd_and_hms(2025, 1, 8, 0, 0, 0).unwrap().timestamp());
+        assert!(ran.is_active);
+
+        let (count,): (i64,) = sqlx::query_as("SELECT today_session_count FROM user_configurations WHERE id = 'alice'")
+            .fetch_one(ws_manager.database.pool.sqlite().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(count, 0);
+
+        // Later ticks find nothing due until the mock clock reaches the next run
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        let stored = ws_manager.database.get_scheduled_task(&task.id).await.unwrap().unwrap();
+        assert_eq!(stored.run_count, 1);
+
+        shutdown_tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), runner)
+            .await
+            .expect("runner did not stop on shutdown")
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_stale_paused_session_is_abandoned() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let paused_at = 1_700_000_000;
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.is_running = false;
+            timer_state.remaining_seconds = 600;
+            timer_state.last_updated = paused_at;
+        }
+
+        // Within the timeout nothing happens
+        assert!(abandon_stale_paused_sessions(&ws_manager, 300, paused_at + 200).await.is_empty());
+        assert_eq!(state.lock().await.user("alice").remaining_seconds, 600);
+
+        let session_id = abandon_stale_paused_sessions(&ws_manager, 300, paused_at + 301)
+            .await
+            .pop()
+            .expect("paused session should be abandoned");
+        assert_eq!(state.lock().await.user("alice").remaining_seconds, 25 * 60);
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        let (elapsed, abandoned_at): (i64, Option<i64>) =
+            sqlx::query_as("SELECT elapsed, abandoned_at FROM timer_sessions WHERE id = ?")
+                .bind(session_id)
+                .fetch_one(pool)
+                .await
+                .unwrap();
+        assert_eq!(elapsed, 25 * 60 - 600);
+        assert_eq!(abandoned_at, Some((paused_at + 301) as i64));
+    }
+
+    #[tokio::test]
+    async fn test_connections_closed_for_reconnect_after_max_lifetime() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_, ws_manager) = test_app_state(&temp_dir).await;
+
+        let (old_sender, mut old_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("old".to_string(), "alice", None, old_sender).await;
+        let connected_at = ws_manager.connections.lock().await["old"].connected_at;
+
+        let (new_sender, mut new_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("new".to_string(), "alice", None, new_sender).await;
+        ws_manager.connections.lock().await.get_mut("new").unwrap().connected_at = connected_at + 30;
+        while old_receiver.try_recv().is_ok() {}
+        while new_receiver.try_recv().is_ok() {}
+
+        // Not yet expired
+        assert!(close_expired_connections(&ws_manager, 60, connected_at + 59).await.is_empty());
+        assert!(old_receiver.try_recv().is_err());
+
+        let closed = close_expired_connections(&ws_manager, 60, connected_at + 60).await;
+        assert_eq!(closed, ["old"]);
+        match old_receiver.try_recv() {
+            Ok(Message::Close(Some(frame))) => {
+                assert_eq!(frame.code, WS_CLOSE_RECONNECT);
+                assert_eq!(frame.reason, "reconnect");
+            }
+            other => panic!("expected a reconnect close frame, got {other:?}"),
+        }
+        assert!(new_receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_sweep_prunes_dead_connections() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_heartbeat_timeout(Some(60)),
+        );
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("alive".to_string(), "alice", None, sender).await;
+        let (closed_sender, closed_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("closed".to_string(), "alice", None, closed_sender).await;
+        let (silent_sender, _silent_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("silent".to_string(), "alice", None, silent_sender).await;
+        while receiver.try_recv().is_ok() {}
+        // Closed only now: a broadcast to it would have removed it before the sweep
+        drop(closed_receiver);
+
+        let now = ws_manager.connections.lock().await["alive"].last_seen;
+        ws_manager.connections.lock().await.get_mut("silent").unwrap().last_seen = now - 61;
+
+        assert_eq!(ws_manager.sweep_dead_connections(now).await, 2);
+        assert_eq!(ws_manager.connections_swept.load(Ordering::Relaxed), 2);
+        assert_eq!(
+            ws_manager.connections.lock().await.keys().collect::<Vec<_>>(),
+            ["alive"]
+        );
+
+        // The surviving client hears the updated device count
+        let mut device_counts = Vec::new();
+        while let Ok(Message::Text(text)) = receiver.try_recv() {
+            if let Ok(WsMessage::ConnectionStatus { device_count, .. }) = serde_json::from_str(&text) {
+                device_counts.push(device_count);
+            }
+        }
+        assert_eq!(device_counts.last(), Some(&1));
+
+        assert_eq!(ws_manager.sweep_dead_connections(now).await, 0);
+    }
+
+    #[tokio::test]
+    async fn test_heartbeat_evicts_clients_that_never_answer_pings() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_heartbeat_timeout(Some(1)),
+        );
+
+        // One client answers every ping, as a browser does; the other never replies
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("responsive".to_string(), "alice", None, sender).await;
+        let (silent_sender, mut silent_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("silent".to_string(), "alice", None, silent_sender).await;
+        let responder = ws_manager.clone();
+        tokio::spawn(async move {
+            while let Some(message) = receiver.recv().await {
+                if let Message::Ping(_) = message {
+                    responder.touch_connection("responsive").await;
+                }
+            }
+        });
+
+        let sweeper = tokio::spawn(dead_connection_sweeper(ws_manager.clone(), Duration::from_millis(100)));
+        for _ in 0..50 {
+            if !ws_manager.connections.lock().await.contains_key("silent") {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(100)).await;
+        }
+        sweeper.abort();
+
+        assert_eq!(
+            ws_manager.connections.lock().await.keys().collect::<Vec<_>>(),
+            ["responsive"]
+        );
+        let pinged = std::iter::from_fn(|| silent_receiver.try_recv().ok())
+            .any(|message| matches!(message, Message::Ping(_)));
+        assert!(pinged, "the silent client was pinged before being dropped");
+    }
+}
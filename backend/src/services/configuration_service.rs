@@ -2,9 +2,10 @@
 //!
 //! Handles user configuration management, persistence, and real-time synchronization.
 
-use crate::models::user_configuration::{UserConfiguration, UserConfigurationError};
+use crate::models::user_configuration::{UserConfiguration, UserConfigurationError, WebhookFormat};
 use crate::services::websocket_service::{WebSocketService, WebSocketMessage};
 use crate::database::{DatabaseManager, connection::DatabasePool};
+use crate::services::time_provider::now_unix;
 use anyhow::Result;
 use std::sync::Arc;
 use tokio::sync::RwLock;
@@ -19,22 +20,61 @@ struct UserConfigurationRow {
     long_break_duration: i64,
     long_break_frequency: i64,
     notifications_enabled: bool,
-    webhook_url: Option<String>,
+    webhook_urls: String,
+    webhook_format: String,
+    webhook_template: Option<String>,
     wait_for_interaction: bool,
     theme: String,
     // Daily session reset fields
     timezone: String,
     daily_reset_time_type: String,
     daily_reset_time_hour: Option<i64>,
+    daily_reset_time_minute: Option<i64>,
     daily_reset_time_custom: Option<String>,
     daily_reset_enabled: bool,
     last_daily_reset_utc: Option<i64>,
     today_session_count: i64,
     manual_session_override: Option<i64>,
+    max_session_count: i64,
+    daily_goal: Option<i64>,
+    stop_session_on_daily_reset: bool,
+    reset_to_session_type: Option<String>,
+    auto_start_on_first_connect: bool,
+    resume_work_duration: bool,
+    min_long_break_before_skip: Option<i64>,
+    pause_on_webhook_failure: bool,
     created_at: i64,
     updated_at: i64,
 }
 
+/// Bind a configuration's columns, in the order the INSERT statements list
+/// them. A macro so the same binds serve both SQLite and PostgreSQL queries.
+macro_rules! bind_configuration {
+    ($query:expr, $config:expr, $theme:expr, $updated_at:expr) => {
+        $query
+            .bind(&$config.id)
+            .bind($config.work_duration as i64)
+            .bind($config.short_break_duration as i64)
+            .bind($config.long_break_duration as i64)
+            .bind($config.long_break_frequency as i64)
+            .bind($config.notifications_enabled)
+            .bind($config.webhook_urls_json())
+            .bind($config.webhook_format.as_str())
+            .bind(&$config.webhook_template)
+            .bind($config.wait_for_interaction)
+            .bind($theme)
+            .bind(&$config.reset_to_session_type)
+            .bind($config.auto_start_on_first_connect)
+            .bind($config.resume_work_duration)
+            .bind($config.min_long_break_before_skip.map(|secs| secs as i64))
+            .bind($config.pause_on_webhook_failure)
+            .bind($config.max_session_count as i64)
+            .bind($config.daily_goal.map(|goal| goal as i64))
+            .bind($config.created_at)
+            .bind($updated_at)
+    };
+}
+
 /// Configuration service responsible for managing user settings
 #[derive(Debug, Clone)]
 pub struct ConfigurationService {
@@ -45,11 +85,12 @@ pub struct ConfigurationService {
     config_cache: Arc<RwLock<UserConfiguration>>,
 
     /// WebSocket service for real-time updates
-    websocket_service: WebSocketService,
+    websocket_service: Option<WebSocketService>,
 }
 
-/// Configuration update request
+/// Configuration update request, with field names as the frontend sends them
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
 pub struct ConfigurationUpdate {
     /// Work session duration in seconds
     pub work_duration: Option<u32>,
@@ -66,14 +107,41 @@ pub struct ConfigurationUpdate {
     /// Whether browser notifications are enabled
     pub notifications_enabled: Option<bool>,
 
-    /// Optional webhook URL for notifications
-    pub webhook_url: Option<Option<String>>,
+    /// Webhook URLs for notifications, replacing the current list
+    pub webhook_urls: Option<Vec<String>>,
+
+    /// Shape of webhook bodies
+    pub webhook_format: Option<WebhookFormat>,
+
+    /// JSON template used by the Custom webhook format
+    pub webhook_template: Option<Option<String>>,
 
     /// Whether to wait for user interaction before starting next session
     pub wait_for_interaction: Option<bool>,
 
     /// UI theme preference
     pub theme: Option<String>,
+
+    /// Session type the timer is put back to at the daily reset
+    pub reset_to_session_type: Option<Option<String>>,
+
+    /// Whether the first connection after the daily reset starts a work session
+    pub auto_start_on_first_connect: Option<bool>,
+
+    /// Whether work after a break resumes the pre-break work duration
+    pub resume_work_duration: Option<bool>,
+
+    /// Seconds of a long break that must pass before it can be skipped
+    pub min_long_break_before_skip: Option<Option<u32>>,
+
+    /// Whether the timer pauses when a completion webhook can't be delivered
+    pub pause_on_webhook_failure: Option<bool>,
+
+    /// Highest session count allowed in a day
+    pub max_session_count: Option<u32>,
+
+    /// Target work sessions per day; `Some(None)` clears the goal
+    pub daily_goal: Option<Option<u32>>,
 }
 
 /// Configuration service errors
@@ -100,6 +168,23 @@ impl ConfigurationService {
     pub async fn new(
         database_manager: Arc<DatabaseManager>,
         websocket_service: WebSocketService,
+    ) -> Result<Self, ConfigurationServiceError> {
+        Self::with_websocket_service(database_manager, Some(websocket_service)).await
+    }
+
+    /// Create a configuration service that doesn't broadcast updates. The timer
+    /// service needs a configuration service, so tests building one can't also
+    /// hand it a WebSocket service wrapping that timer.
+    #[cfg(test)]
+    pub async fn without_broadcast(
+        database_manager: Arc<DatabaseManager>,
+    ) -> Result<Self, ConfigurationServiceError> {
+        Self::with_websocket_service(database_manager, None).await
+    }
+
+    async fn with_websocket_service(
+        database_manager: Arc<DatabaseManager>,
+        websocket_service: Option<WebSocketService>,
     ) -> Result<Self, ConfigurationServiceError> {
         let service = Self {
             database_manager: database_manager.clone(),
@@ -114,76 +199,160 @@ impl ConfigurationService {
         Ok(service)
     }
 
-    
-    /// Load configuration from database or create default
+
+    /// Load configuration from database, creating the default on first run.
+    /// The default is inserted with `ON CONFLICT DO NOTHING` and then re-read,
+    /// so concurrent first-run initializations all settle on the same row.
     async fn load_configuration(&self) -> Result<(), ConfigurationServiceError> {
         debug!("Loading configuration from {} database", self.database_manager.database_type);
 
-        // Try to load configuration from database
-        let query = sqlx::query_as::<_, UserConfigurationRow>(
-            r#"
+        let config = match self.fetch_configuration().await? {
+            Some(config) => {
+                debug!("Configuration loaded from database");
+                config
+            }
+            None => {
+                debug!("No configuration found in database, creating default");
+                self.insert_default_configuration(&UserConfiguration::new()).await?;
+                self.fetch_configuration()
+                    .await?
+                    .ok_or(ConfigurationServiceError::NotFound)?
+            }
+        };
+
+        *self.config_cache.write().await = config;
+        Ok(())
+    }
+
+    /// Read the stored configuration, if there is one
+    async fn fetch_configuration(&self) -> Result<Option<UserConfiguration>, ConfigurationServiceError> {
+        const SELECT_CONFIGURATION: &str = r#"
             SELECT id, work_duration, short_break_duration, long_break_duration,
-                   long_break_frequency, notifications_enabled, webhook_url,
+                   long_break_frequency, notifications_enabled, webhook_urls, webhook_format, webhook_template,
                    wait_for_interaction, theme, timezone, daily_reset_time_type,
-                   daily_reset_time_hour, daily_reset_time_custom, daily_reset_enabled,
-                   last_daily_reset_utc, today_session_count, manual_session_override,
+                   daily_reset_time_hour, daily_reset_time_minute, daily_reset_time_custom, daily_reset_enabled,
+                   last_daily_reset_utc, today_session_count, manual_session_override, max_session_count, daily_goal,
+                   stop_session_on_daily_reset, reset_to_session_type, auto_start_on_first_connect,
+                   resume_work_duration, min_long_break_before_skip, pause_on_webhook_failure,
                    created_at, updated_at
             FROM user_configurations
             ORDER BY updated_at DESC
             LIMIT 1
-            "#
-        );
+            "#;
 
-        match query.fetch_one(match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        }).await {
-            Ok(row) => {
-                let config = UserConfiguration {
-                    id: row.id.expect("Database row missing id"),
-                    work_duration: row.work_duration as u32,
-                    short_break_duration: row.short_break_duration as u32,
-                    long_break_duration: row.long_break_duration as u32,
-                    long_break_frequency: row.long_break_frequency as u32,
-                    notifications_enabled: row.notifications_enabled,
-                    webhook_url: row.webhook_url,
-                    wait_for_interaction: row.wait_for_interaction,
-                    theme: match row.theme.as_str() {
-                        "Dark" => crate::models::user_configuration::Theme::Dark,
-                        _ => crate::models::user_configuration::Theme::Light,
-                    },
-                    // Daily session reset fields
-                    timezone: row.timezone,
-                    daily_reset_time_type: match row.daily_reset_time_type.as_str() {
-                        "hour" => crate::models::user_configuration::DailyResetTimeType::Hour,
-                        "custom" => crate::models::user_configuration::DailyResetTimeType::Custom,
-                        _ => crate::models::user_configuration::DailyResetTimeType::Midnight,
-                    },
-                    daily_reset_time_hour: row.daily_reset_time_hour.map(|x| x as u8),
-                    daily_reset_time_custom: row.daily_reset_time_custom,
-                    daily_reset_enabled: row.daily_reset_enabled,
-                    last_daily_reset_utc: row.last_daily_reset_utc,
-                    today_session_count: row.today_session_count as u32,
-                    manual_session_override: row.manual_session_override.map(|x| x as u32),
-                    created_at: row.created_at,
-                    updated_at: row.updated_at,
-                };
+        let row = match &self.database_manager.pool {
+            DatabasePool::Sqlite(pool) => {
+                sqlx::query_as::<_, UserConfigurationRow>(SELECT_CONFIGURATION)
+                    .fetch_optional(pool)
+                    .await
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::Postgres(pool) => {
+                sqlx::query_as::<_, UserConfigurationRow>(SELECT_CONFIGURATION)
+                    .fetch_optional(pool)
+                    .await
+            }
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to load configuration: {}", e))?;
+
+        Ok(row.map(|row| {
+            UserConfiguration {
+                id: row.id.expect("Database row missing id"),
+                work_duration: row.work_duration as u32,
+                short_break_duration: row.short_break_duration as u32,
+                long_break_duration: row.long_break_duration as u32,
+                long_break_frequency: row.long_break_frequency as u32,
+                notifications_enabled: row.notifications_enabled,
+                webhook_urls: UserConfiguration::webhook_urls_from_json(&row.webhook_urls),
+                webhook_format: WebhookFormat::parse(&row.webhook_format).unwrap_or_default(),
+                webhook_template: row.webhook_template,
+                wait_for_interaction: row.wait_for_interaction,
+                theme: match row.theme.as_str() {
+                    "Dark" => crate::models::user_configuration::Theme::Dark,
+                    _ => crate::models::user_configuration::Theme::Light,
+                },
+                // Daily session reset fields
+                timezone: row.timezone,
+                daily_reset_time_type: match row.daily_reset_time_type.as_str() {
+                    "hour" => crate::models::user_configuration::DailyResetTimeType::Hour,
+                    "custom" => crate::models::user_configuration::DailyResetTimeType::Custom,
+                    _ => crate::models::user_configuration::DailyResetTimeType::Midnight,
+                },
+                daily_reset_time_hour: row.daily_reset_time_hour.map(|x| x as u8),
+                daily_reset_time_minute: row.daily_reset_time_minute.map(|x| x as u8),
+                daily_reset_time_custom: row.daily_reset_time_custom,
+                daily_reset_enabled: row.daily_reset_enabled,
+                last_daily_reset_utc: row.last_daily_reset_utc,
+                today_session_count: row.today_session_count as u32,
+                manual_session_override: row.manual_session_override.map(|x| x as u32),
+                max_session_count: row.max_session_count as u32,
+                daily_goal: row.daily_goal.map(|goal| goal as u32),
+                stop_session_on_daily_reset: row.stop_session_on_daily_reset,
+                reset_to_session_type: row.reset_to_session_type,
+                auto_start_on_first_connect: row.auto_start_on_first_connect,
+                resume_work_duration: row.resume_work_duration,
+                min_long_break_before_skip: row.min_long_break_before_skip.map(|secs| secs as u32),
+                pause_on_webhook_failure: row.pause_on_webhook_failure,
+                created_at: row.created_at,
+                updated_at: row.updated_at,
+            }
+        }))
+    }
 
-                debug!("Configuration loaded from database");
-                *self.config_cache.write().await = config;
+    /// Insert `config` unless a configuration with the same id already exists
+    async fn insert_default_configuration(&self, config: &UserConfiguration) -> Result<(), ConfigurationServiceError> {
+        let theme_str = match config.theme {
+            crate::models::user_configuration::Theme::Light => "Light",
+            crate::models::user_configuration::Theme::Dark => "Dark",
+        };
+
+        let sql = match self.database_manager.database_type {
+            crate::database::DatabaseType::Sqlite => {
+                r#"
+                INSERT INTO user_configurations
+                (id, work_duration, short_break_duration, long_break_duration,
+                 long_break_frequency, notifications_enabled, webhook_urls, webhook_format, webhook_template,
+                 wait_for_interaction, theme, reset_to_session_type, auto_start_on_first_connect,
+                 resume_work_duration, min_long_break_before_skip, pause_on_webhook_failure,
+                 max_session_count, daily_goal, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT (id) DO NOTHING
+                "#
             }
-            Err(_) => {
-                // No configuration found, use default
-                let default_config = UserConfiguration::new();
-                debug!("No configuration found in database, using default");
-                *self.config_cache.write().await = default_config.clone();
-
-                // Save default configuration to database
-                if let Err(e) = self.save_configuration(&default_config).await {
-                    warn!("Failed to save default configuration: {}", e);
-                }
+            crate::database::DatabaseType::Postgres => {
+                r#"
+                INSERT INTO user_configurations
+                (id, work_duration, short_break_duration, long_break_duration,
+                 long_break_frequency, notifications_enabled, webhook_urls, webhook_format, webhook_template,
+                 wait_for_interaction, theme, reset_to_session_type, auto_start_on_first_connect,
+                 resume_work_duration, min_long_break_before_skip, pause_on_webhook_failure,
+                 max_session_count, daily_goal, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+                ON CONFLICT (id) DO NOTHING
+                "#
+            }
+        };
+
+        let rows_affected = match &self.database_manager.pool {
+            DatabasePool::Sqlite(pool) => {
+                bind_configuration!(sqlx::query(sql), config, theme_str, config.updated_at)
+                    .execute(pool)
+                    .await
+                    .map(|result| result.rows_affected())
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::Postgres(pool) => {
+                bind_configuration!(sqlx::query(sql), config, theme_str, config.updated_at)
+                    .execute(pool)
+                    .await
+                    .map(|result| result.rows_affected())
             }
         }
+        .map_err(|e| anyhow::anyhow!("Failed to save default configuration: {}", e))?;
 
+        if rows_affected == 0 {
+            debug!("Default configuration already created by another initializer");
+        }
         Ok(())
     }
 
@@ -224,8 +393,14 @@ impl ConfigurationService {
             config.touch();
         }
 
-        if let Some(webhook_url) = update.webhook_url {
-            config.set_webhook_url(webhook_url)?;
+        if let Some(webhook_urls) = update.webhook_urls {
+            config.set_webhook_urls(webhook_urls)?;
+        }
+
+        if update.webhook_format.is_some() || update.webhook_template.is_some() {
+            let webhook_format = update.webhook_format.unwrap_or_else(|| config.webhook_format.clone());
+            let webhook_template = update.webhook_template.unwrap_or_else(|| config.webhook_template.clone());
+            config.set_webhook_format(webhook_format, webhook_template)?;
         }
 
         if let Some(wait_for_interaction) = update.wait_for_interaction {
@@ -242,6 +417,38 @@ impl ConfigurationService {
             config.set_theme(theme);
         }
 
+        if let Some(reset_to_session_type) = update.reset_to_session_type {
+            config.set_reset_to_session_type(reset_to_session_type)?;
+        }
+
+        if let Some(auto_start_on_first_connect) = update.auto_start_on_first_connect {
+            config.auto_start_on_first_connect = auto_start_on_first_connect;
+            config.touch();
+        }
+
+        if let Some(resume_work_duration) = update.resume_work_duration {
+            config.resume_work_duration = resume_work_duration;
+            config.touch();
+        }
+
+        if let Some(min_long_break_before_skip) = update.min_long_break_before_skip {
+            config.min_long_break_before_skip = min_long_break_before_skip;
+            config.touch();
+        }
+
+        if let Some(pause_on_webhook_failure) = update.pause_on_webhook_failure {
+            config.pause_on_webhook_failure = pause_on_webhook_failure;
+            config.touch();
+        }
+
+        if let Some(max_session_count) = update.max_session_count {
+            config.set_max_session_count(max_session_count)?;
+        }
+
+        if let Some(daily_goal) = update.daily_goal {
+            config.set_daily_goal(daily_goal)?;
+        }
+
         // Validate complete configuration
         config.validate()?;
 
@@ -269,73 +476,69 @@ impl ConfigurationService {
             crate::models::user_configuration::Theme::Dark => "Dark",
         };
 
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs() as i64;
+        let now = now_unix() as i64;
 
         // Use UPSERT (INSERT OR REPLACE for SQLite, ON CONFLICT for PostgreSQL)
-        let query = match self.database_manager.database_type {
+        let sql = match self.database_manager.database_type {
             crate::database::DatabaseType::Sqlite => {
-                sqlx::query(
-                    r#"
-                    INSERT OR REPLACE INTO user_configurations
-                    (id, work_duration, short_break_duration, long_break_duration,
-                     long_break_frequency, notifications_enabled, webhook_url,
-                     wait_for_interaction, theme, created_at, updated_at)
-                    VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
-                    "#
-                )
-                .bind(&config.id)
-                .bind(config.work_duration as i64)
-                .bind(config.short_break_duration as i64)
-                .bind(config.long_break_duration as i64)
-                .bind(config.long_break_frequency as i64)
-                .bind(config.notifications_enabled)
-                .bind(&config.webhook_url)
-                .bind(config.wait_for_interaction)
-                .bind(theme_str)
-                .bind(config.created_at as i64)
-                .bind(now)
+                r#"
+                INSERT OR REPLACE INTO user_configurations
+                (id, work_duration, short_break_duration, long_break_duration,
+                 long_break_frequency, notifications_enabled, webhook_urls, webhook_format, webhook_template,
+                 wait_for_interaction, theme, reset_to_session_type, auto_start_on_first_connect,
+                 resume_work_duration, min_long_break_before_skip, pause_on_webhook_failure,
+                 max_session_count, daily_goal, created_at, updated_at)
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                "#
             }
             crate::database::DatabaseType::Postgres => {
-                sqlx::query(
-                    r#"
-                    INSERT INTO user_configurations
-                    (id, work_duration, short_break_duration, long_break_duration,
-                     long_break_frequency, notifications_enabled, webhook_url,
-                     wait_for_interaction, theme, created_at, updated_at)
-                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11)
-                    ON CONFLICT (id) DO UPDATE SET
-                        work_duration = EXCLUDED.work_duration,
-                        short_break_duration = EXCLUDED.short_break_duration,
-                        long_break_duration = EXCLUDED.long_break_duration,
-                        long_break_frequency = EXCLUDED.long_break_frequency,
-                        notifications_enabled = EXCLUDED.notifications_enabled,
-                        webhook_url = EXCLUDED.webhook_url,
-                        wait_for_interaction = EXCLUDED.wait_for_interaction,
-                        theme = EXCLUDED.theme,
-                        updated_at = EXCLUDED.updated_at
-                    "#
-                )
-                .bind(&config.id)
-                .bind(config.work_duration as i64)
-                .bind(config.short_break_duration as i64)
-                .bind(config.long_break_duration as i64)
-                .bind(config.long_break_frequency as i64)
-                .bind(config.notifications_enabled)
-                .bind(&config.webhook_url)
-                .bind(config.wait_for_interaction)
-                .bind(theme_str)
-                .bind(config.created_at as i64)
-                .bind(now)
+                r#"
+                INSERT INTO user_configurations
+                (id, work_duration, short_break_duration, long_break_duration,
+                 long_break_frequency, notifications_enabled, webhook_urls, webhook_format, webhook_template,
+                 wait_for_interaction, theme, reset_to_session_type, auto_start_on_first_connect,
+                 resume_work_duration, min_long_break_before_skip, pause_on_webhook_failure,
+                 max_session_count, daily_goal, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20)
+                ON CONFLICT (id) DO UPDATE SET
+                    work_duration = EXCLUDED.work_duration,
+                    short_break_duration = EXCLUDED.short_break_duration,
+                    long_break_duration = EXCLUDED.long_break_duration,
+                    long_break_frequency = EXCLUDED.long_break_frequency,
+                    notifications_enabled = EXCLUDED.notifications_enabled,
+                    webhook_urls = EXCLUDED.webhook_urls,
+                    webhook_format = EXCLUDED.webhook_format,
+                    webhook_template = EXCLUDED.webhook_template,
+                    wait_for_interaction = EXCLUDED.wait_for_interaction,
+                    theme = EXCLUDED.theme,
+                    reset_to_session_type = EXCLUDED.reset_to_session_type,
+                    auto_start_on_first_connect = EXCLUDED.auto_start_on_first_connect,
+                    resume_work_duration = EXCLUDED.resume_work_duration,
+                    min_long_break_before_skip = EXCLUDED.min_long_break_before_skip,
+                    pause_on_webhook_failure = EXCLUDED.pause_on_webhook_failure,
+                    max_session_count = EXCLUDED.max_session_count,
+                    daily_goal = EXCLUDED.daily_goal,
+                    updated_at = EXCLUDED.updated_at
+                "#
             }
         };
 
-        query.execute(match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        }).await
-            .map_err(|e| anyhow::anyhow!("Failed to save configuration: {}", e))?;
+        match &self.database_manager.pool {
+            DatabasePool::Sqlite(pool) => {
+                bind_configuration!(sqlx::query(sql), config, theme_str, now)
+                    .execute(pool)
+                    .await
+                    .map(|_| ())
+            }
+            #[cfg(feature = "postgres")]
+            DatabasePool::Postgres(pool) => {
+                bind_configuration!(sqlx::query(sql), config, theme_str, now)
+                    .execute(pool)
+                    .await
+                    .map(|_| ())
+            }
+        }
+        .map_err(|e| anyhow::anyhow!("Failed to save configuration: {}", e))?;
 
         debug!("Configuration saved successfully to database");
         Ok(())
@@ -346,6 +549,10 @@ impl ConfigurationService {
         &self,
         config: &UserConfiguration,
     ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+        let Some(websocket_service) = &self.websocket_service else {
+            return Ok(());
+        };
+
         let message = WebSocketMessage::ConfigurationUpdate {
             payload: serde_json::json!({
                 "id": config.id,
@@ -354,19 +561,28 @@ impl ConfigurationService {
                 "longBreakDuration": config.long_break_duration,
                 "longBreakFrequency": config.long_break_frequency,
                 "notificationsEnabled": config.notifications_enabled,
-                "webhookUrl": config.webhook_url,
+                "webhookUrls": config.webhook_urls,
+                "webhookFormat": config.webhook_format.as_str(),
+                "webhookTemplate": config.webhook_template,
                 "waitForInteraction": config.wait_for_interaction,
                 "theme": match config.theme {
                     crate::models::user_configuration::Theme::Light => "Light",
                     crate::models::user_configuration::Theme::Dark => "Dark",
                 },
+                "resetToSessionType": config.reset_to_session_type,
+                "autoStartOnFirstConnect": config.auto_start_on_first_connect,
+                "resumeWorkDuration": config.resume_work_duration,
+                "minLongBreakBeforeSkip": config.min_long_break_before_skip,
+                "pauseOnWebhookFailure": config.pause_on_webhook_failure,
+                "maxSessionCount": config.max_session_count,
+                "dailyGoal": config.daily_goal,
                 "createdAt": config.created_at,
                 "updatedAt": config.updated_at,
             }),
         };
 
         // Send broadcast message
-        if let Err(e) = self.websocket_service.broadcast_message(message).await {
+        if let Err(e) = websocket_service.broadcast_message(message).await {
             warn!("Failed to broadcast configuration update: {}", e);
         }
 
@@ -385,12 +601,21 @@ impl ConfigurationService {
             long_break_duration: Some(default_config.long_break_duration),
             long_break_frequency: Some(default_config.long_break_frequency),
             notifications_enabled: Some(default_config.notifications_enabled),
-            webhook_url: Some(None),
+            webhook_urls: Some(Vec::new()),
+            webhook_format: Some(default_config.webhook_format),
+            webhook_template: Some(None),
             wait_for_interaction: Some(default_config.wait_for_interaction),
             theme: Some(match default_config.theme {
                 crate::models::user_configuration::Theme::Light => "Light".to_string(),
                 crate::models::user_configuration::Theme::Dark => "Dark".to_string(),
             }),
+            reset_to_session_type: Some(default_config.reset_to_session_type),
+            auto_start_on_first_connect: Some(default_config.auto_start_on_first_connect),
+            resume_work_duration: Some(default_config.resume_work_duration),
+            min_long_break_before_skip: Some(default_config.min_long_break_before_skip),
+            pause_on_webhook_failure: Some(default_config.pause_on_webhook_failure),
+            max_session_count: Some(default_config.max_session_count),
+            daily_goal: Some(default_config.daily_goal),
         })
         .await
     }
@@ -425,10 +650,10 @@ impl ConfigurationService {
         Ok(config.should_send_notifications())
     }
 
-    /// Get webhook URL if configured
-    pub async fn get_webhook_url(&self) -> Result<Option<String>, ConfigurationServiceError> {
+    /// Get the configured webhook URLs
+    pub async fn get_webhook_urls(&self) -> Result<Vec<String>, ConfigurationServiceError> {
         let config = self.get_configuration().await?;
-        Ok(config.webhook_url.clone())
+        Ok(config.webhook_urls)
     }
 
     /// Check if should wait for interaction
@@ -442,19 +667,16 @@ impl ConfigurationService {
 mod tests {
     use super::*;
     use crate::models::user_configuration::Theme;
-    use sqlx::SqlitePool;
 
-    async fn create_test_service() -> (ConfigurationService, SqlitePool) {
-        let pool = SqlitePool::connect(":memory:").await.unwrap();
+    async fn create_test_service() -> (ConfigurationService, Arc<DatabaseManager>) {
+        let database = Arc::new(DatabaseManager::new("sqlite::memory:").await.unwrap());
+        database.migrate().await.unwrap();
 
-        // Create mock websocket service (for testing we can create a simple one)
-        let websocket_service = WebSocketService::new(pool.clone());
-
-        let service = ConfigurationService::new(pool.clone(), websocket_service)
+        let service = ConfigurationService::without_broadcast(database.clone())
             .await
             .unwrap();
 
-        (service, pool)
+        (service, database)
     }
 
     #[tokio::test]
@@ -536,6 +758,94 @@ mod tests {
         assert_eq!(reset_config.work_duration, 1500);
         assert_eq!(reset_config.theme, Theme::Light);
     }
+
+    #[tokio::test]
+    async fn test_concurrent_first_run_creates_one_configuration() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("config_race.db");
+        let database = Arc::new(
+            DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy()))
+                .await
+                .unwrap(),
+        );
+        database.migrate().await.unwrap();
+
+        let timer_service = Arc::new(
+            crate::services::timer_service::TimerService::new_with_config(UserConfiguration::new()).await,
+        );
+        let initializations = (0..8).map(|_| {
+            let database = database.clone();
+            let websocket_service = WebSocketService::new(timer_service.clone());
+            tokio::spawn(async move { ConfigurationService::new(database, websocket_service).await })
+        });
+
+        let mut configs = Vec::new();
+        for initialization in initializations.collect::<Vec<_>>() {
+            let service = initialization.await.unwrap().unwrap();
+            configs.push(service.get_configuration().await.unwrap());
+        }
+
+        let (rows,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM user_configurations")
+            .fetch_one(database.pool.sqlite().unwrap())
+            .await
+            .unwrap();
+        assert_eq!(rows, 1);
+
+        let first = &configs[0];
+        for config in &configs {
+            assert_eq!(config.id, first.id);
+            assert_eq!(config.created_at, first.created_at);
+            assert_eq!(config.updated_at, first.updated_at);
+            assert_eq!(config.work_duration, first.work_duration);
+            assert_eq!(config.theme, first.theme);
+        }
+    }
+
+    /// Runs against the database in `ROMA_TIMER_TEST_POSTGRES_URL` when the
+    /// `postgres` feature is enabled; skipped when the variable is unset
+    #[cfg(feature = "postgres")]
+    #[tokio::test]
+    async fn test_configuration_round_trip_on_postgres() {
+        let Ok(database_url) = std::env::var("ROMA_TIMER_TEST_POSTGRES_URL") else {
+            eprintln!("ROMA_TIMER_TEST_POSTGRES_URL not set; skipping");
+            return;
+        };
+        let database = Arc::new(DatabaseManager::new(&database_url).await.unwrap());
+        database.migrate().await.unwrap();
+        sqlx::query("DELETE FROM user_configurations")
+            .execute(database.pool.postgres().unwrap())
+            .await
+            .unwrap();
+
+        let timer_service = Arc::new(
+            crate::services::timer_service::TimerService::new_with_config(UserConfiguration::new()).await,
+        );
+        let service = ConfigurationService::new(database.clone(), WebSocketService::new(timer_service.clone()))
+            .await
+            .unwrap();
+        let created = service.get_configuration().await.unwrap();
+        assert_eq!(created.work_duration, 1500);
+
+        service
+            .update_configuration(ConfigurationUpdate {
+                work_duration: Some(1800),
+                theme: Some("Dark".to_string()),
+                ..Default::default()
+            })
+            .await
+            .unwrap();
+
+        // A fresh service reads back what the first one saved
+        let reloaded = ConfigurationService::new(database, WebSocketService::new(timer_service))
+            .await
+            .unwrap()
+            .get_configuration()
+            .await
+            .unwrap();
+        assert_eq!(reloaded.id, created.id);
+        assert_eq!(reloaded.work_duration, 1800);
+        assert_eq!(reloaded.theme, Theme::Dark);
+    }
 }
 
 impl Default for ConfigurationUpdate {
@@ -546,9 +856,18 @@ impl Default for ConfigurationUpdate {
             long_break_duration: None,
             long_break_frequency: None,
             notifications_enabled: None,
-            webhook_url: None,
+            webhook_urls: None,
+            webhook_format: None,
+            webhook_template: None,
             wait_for_interaction: None,
             theme: None,
+            reset_to_session_type: None,
+            auto_start_on_first_connect: None,
+            resume_work_duration: None,
+            min_long_break_before_skip: None,
+            pause_on_webhook_failure: None,
+            max_session_count: None,
+            daily_goal: None,
         }
     }
 }
\ No newline at end of file
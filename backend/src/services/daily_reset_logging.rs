@@ -477,9 +477,7 @@ mod tests {
 
     #[test]
     fn test_logging_context_creation() {
-        let logger = DailyResetLogger;
-        let context = logger
-            .new_context("test-user".to_string(), DailyResetOperation::SessionReset)
+        let context = DailyResetLogger::new_context("test-user".to_string(), DailyResetOperation::SessionReset)
             .with_device_id("test-device".to_string())
             .with_request_id("test-request".to_string());
 
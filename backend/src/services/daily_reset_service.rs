@@ -4,15 +4,18 @@
 //! This version provides the essential business logic for timezone-aware reset scheduling.
 
 use std::sync::Arc;
-use chrono::{DateTime, Utc, TimeZone};
+use chrono::{DateTime, LocalResult, NaiveDateTime, Utc, TimeZone};
 use chrono_tz::Tz;
 
 use crate::models::{
-    user_configuration::{UserConfiguration, DailyResetTimeType},
-    session_reset_event::SessionResetEvent,
+    user_configuration::{DailyResetTime, UserConfiguration, WebhookFormat},
+    session_reset_event::{SessionResetEvent, SessionResetTriggerSource},
+    scheduled_task::{ScheduledTask, ScheduledTaskType},
+    daily_session_stats::DailySessionStats as DailyStatsArchive,
 };
+use crate::services::stats_service::{aggregate_daily_stats, streaks, AggregatedStats, GoalProgress, Granularity, Streaks, WeekStart};
 use crate::services::time_provider::TimeProvider;
-use crate::database::{DatabaseManager, connection::DatabasePool};
+use crate::database::{DatabaseManager, connection::CompletedSessionTotals};
 use crate::error::AppError;
 use sqlx::Row;
 use thiserror::Error;
@@ -36,6 +39,21 @@ pub struct DailyResetStatus {
     pub last_reset_utc: Option<i64>,
 }
 
+/// Full daily reset status sent to clients hydrating the daily reset UI
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+pub struct DailyResetStatusSnapshot {
+    pub user_id: String,
+    pub enabled: bool,
+    pub timezone: String,
+    /// Effective count (manual override if set, otherwise today's count)
+    pub current_session_count: u32,
+    pub manual_session_override: Option<u32>,
+    pub next_reset_time_utc: Option<i64>,
+    /// Next reset in the user's timezone (RFC 3339)
+    pub next_reset_time_local: Option<String>,
+    pub is_due: bool,
+}
+
 /// Daily session stats for the daily reset service
 #[derive(Debug, Clone)]
 pub struct DailySessionStats {
@@ -50,23 +68,6 @@ pub struct DailySessionStats {
 }
 
 
-/// Parse time string (HH:MM) to naive time components
-fn parse_time_to_naive_time(time_str: &str) -> Option<(u32, u32)> {
-    let parts: Vec<&str> = time_str.split(':').collect();
-    if parts.len() != 2 {
-        return None;
-    }
-
-    let hour = parts[0].parse::<u32>().ok()?;
-    let minute = parts[1].parse::<u32>().ok()?;
-
-    if hour > 23 || minute > 59 {
-        return None;
-    }
-
-    Some((hour, minute))
-}
-
 /// Daily Reset Service
 ///
 /// Provides timezone-aware daily session reset functionality with database persistence.
@@ -76,8 +77,52 @@ pub struct DailyResetService {
     time_provider: Arc<dyn TimeProvider>,
     /// Database manager for persistence
     database_manager: Arc<DatabaseManager>,
+    /// WebSocket manager holding the live timer, used to stop sessions on reset
+    ws_manager: Option<Arc<crate::websocket::manager::WebSocketManager>>,
+    /// Minimum time between automatic resets for the same user
+    min_reset_interval: chrono::Duration,
+    /// Reset the session count when a timezone change moves the user into a
+    /// different local day
+    reset_on_timezone_change: bool,
+    /// Directory scheduled backup tasks write to
+    backup_dir: std::path::PathBuf,
 }
 
+/// The local day whose session count is in progress at `now`; each day runs
+/// from one reset time to the next
+fn local_reset_day(now: DateTime<Utc>, timezone: Tz, (hour, minute): (u32, u32)) -> chrono::NaiveDate {
+    let since_reset = chrono::Duration::hours(hour as i64) + chrono::Duration::minutes(minute as i64);
+    (now.with_timezone(&timezone).naive_local() - since_reset).date()
+}
+
+/// Wall-clock time the reset happens at on `date`, for any reset time type
+fn reset_naive_time_for(date: chrono::NaiveDate, user_config: &UserConfiguration) -> Result<NaiveDateTime, AppError> {
+    let (hour, minute) = user_config.get_daily_reset_time().local_time();
+    date.and_hms_opt(hour, minute, 0).ok_or_else(|| {
+        AppError::UserConfiguration(
+            crate::models::user_configuration::UserConfigurationError::InvalidResetTime(format!("{:02}:{:02}", hour, minute))
+        )
+    })
+}
+
+/// Resolve a wall-clock reset time in `timezone`. A time skipped by a DST jump
+/// forward rolls on to the first valid minute after the gap; a time repeated
+/// by a DST fall back uses the earlier of the two instants.
+fn resolve_local_time(timezone: &Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+    let mut candidate = local;
+    for _ in 0..=24 * 60 {
+        match timezone.from_local_datetime(&candidate) {
+            LocalResult::Single(time) => return Some(time),
+            LocalResult::Ambiguous(earliest, _) => return Some(earliest),
+            LocalResult::None => candidate += chrono::Duration::minutes(1),
+        }
+    }
+    None
+}
+
+/// Default minimum time between automatic resets; under a day to allow for DST shifts
+pub const DEFAULT_MIN_RESET_INTERVAL_SECS: i64 = 23 * 3600;
+
 impl DailyResetService {
     /// Create a new daily reset service
     pub fn new(
@@ -87,9 +132,38 @@ impl DailyResetService {
         Self {
             time_provider,
             database_manager,
+            ws_manager: None,
+            min_reset_interval: chrono::Duration::seconds(DEFAULT_MIN_RESET_INTERVAL_SECS),
+            reset_on_timezone_change: false,
+            backup_dir: crate::api::admin::get_backup_dir(),
         }
     }
 
+    /// Reset the count with a `TimezoneChange` event when a timezone change
+    /// crosses a local day boundary
+    pub fn with_reset_on_timezone_change(mut self, enabled: bool) -> Self {
+        self.reset_on_timezone_change = enabled;
+        self
+    }
+
+    /// Override the minimum time between automatic resets
+    pub fn with_min_reset_interval(mut self, interval: chrono::Duration) -> Self {
+        self.min_reset_interval = interval;
+        self
+    }
+
+    /// Override where scheduled backup tasks write, `ROMA_TIMER_BACKUP_DIR` by default
+    pub fn with_backup_dir(mut self, backup_dir: std::path::PathBuf) -> Self {
+        self.backup_dir = backup_dir;
+        self
+    }
+
+    /// Attach the WebSocket manager so resets can pause the running timer
+    pub fn with_websocket_manager(mut self, ws_manager: Arc<crate::websocket::manager::WebSocketManager>) -> Self {
+        self.ws_manager = Some(ws_manager);
+        self
+    }
+
     /// Calculate the next daily reset time for a user configuration
     #[instrument(skip(self, user_config))]
     pub fn calculate_next_reset_time(
@@ -111,30 +185,9 @@ impl DailyResetService {
         let current_date = current_local.date_naive();
 
         // Calculate reset time for today
-        let reset_time = match user_config.daily_reset_time_type {
-            DailyResetTimeType::Midnight => {
-                current_date.and_hms_opt(0, 0, 0)
-            }
-            DailyResetTimeType::Hour => {
-                if let Some(hour) = user_config.daily_reset_time_hour {
-                    current_date.and_hms_opt(hour as u32, 0, 0)
-                } else {
-                    current_date.and_hms_opt(0, 0, 0) // Default to midnight
-                }
-            }
-            DailyResetTimeType::Custom => {
-                if let Some(ref time_str) = user_config.daily_reset_time_custom {
-                    parse_time_to_naive_time(time_str)
-                        .map(|(hour, minute)| current_date.and_hms_opt(hour, minute, 0))
-                        .unwrap_or_else(|| current_date.and_hms_opt(0, 0, 0))
-                } else {
-                    current_date.and_hms_opt(0, 0, 0)
-                }
-            }
-        };
+        let reset_time = reset_naive_time_for(current_date, user_config)?;
 
-        let reset_local = user_timezone.from_local_datetime(&reset_time.unwrap())
-            .single()
+        let reset_local = resolve_local_time(&user_timezone, reset_time)
             .ok_or_else(|| {
                 warn!("Failed to create local datetime for reset time");
                 AppError::UserConfiguration(
@@ -152,30 +205,9 @@ impl DailyResetService {
                     )
                 })?;
 
-            let tomorrow_reset_time = match user_config.daily_reset_time_type {
-                DailyResetTimeType::Midnight => {
-                    tomorrow_date.and_hms_opt(0, 0, 0)
-                }
-                DailyResetTimeType::Hour => {
-                    if let Some(hour) = user_config.daily_reset_time_hour {
-                        tomorrow_date.and_hms_opt(hour as u32, 0, 0)
-                    } else {
-                        tomorrow_date.and_hms_opt(0, 0, 0)
-                    }
-                }
-                DailyResetTimeType::Custom => {
-                    if let Some(ref time_str) = user_config.daily_reset_time_custom {
-                        parse_time_to_naive_time(time_str)
-                            .map(|(hour, minute)| tomorrow_date.and_hms_opt(hour, minute, 0))
-                            .unwrap_or_else(|| tomorrow_date.and_hms_opt(0, 0, 0))
-                    } else {
-                        tomorrow_date.and_hms_opt(0, 0, 0)
-                    }
-                }
-            };
+            let tomorrow_reset_time = reset_naive_time_for(tomorrow_date, user_config)?;
 
-            let tomorrow_local = user_timezone.from_local_datetime(&tomorrow_reset_time.unwrap())
-                .single()
+            let tomorrow_local = resolve_local_time(&user_timezone, tomorrow_reset_time)
                 .ok_or_else(|| {
                     warn!("Failed to create local datetime for tomorrow");
                     AppError::UserConfiguration(
@@ -231,6 +263,34 @@ impl DailyResetService {
             .unwrap_or(0)
     }
 
+    /// Build the full daily reset status for a user configuration
+    pub fn status_snapshot(&self, user_config: &UserConfiguration) -> Result<DailyResetStatusSnapshot, AppError> {
+        let (next_reset_time_utc, next_reset_time_local) = if user_config.daily_reset_enabled {
+            let next_reset = self.calculate_next_reset_time(user_config)?;
+            let user_timezone: Tz = user_config.timezone.parse()
+                .map_err(|_| AppError::UserConfiguration(
+                    crate::models::user_configuration::UserConfigurationError::InvalidTimezone(user_config.timezone.clone())
+                ))?;
+            (
+                Some(next_reset.timestamp()),
+                Some(next_reset.with_timezone(&user_timezone).to_rfc3339()),
+            )
+        } else {
+            (None, None)
+        };
+
+        Ok(DailyResetStatusSnapshot {
+            user_id: user_config.id.clone(),
+            enabled: user_config.daily_reset_enabled,
+            timezone: user_config.timezone.clone(),
+            current_session_count: self.get_current_session_count(user_config),
+            manual_session_override: user_config.manual_session_override,
+            next_reset_time_utc,
+            next_reset_time_local,
+            is_due: self.should_reset_today(user_config)?,
+        })
+    }
+
     /// Validate timezone string
     pub fn validate_timezone(&self, timezone: &str) -> Result<(), AppError> {
         timezone.parse::<Tz>()
@@ -245,13 +305,39 @@ impl DailyResetService {
     // ===== Database Operations =====
 
     /// Perform a complete daily session reset for a user configuration
-    /// This is the main method that orchestrates the entire reset process
+    /// This is the main method that orchestrates the entire reset process.
+    /// Automatic triggers are refused with `DailyResetTooSoon` if the last reset
+    /// was within the minimum reset interval; manual triggers always proceed.
     #[instrument(skip(self, user_config))]
-    pub async fn perform_daily_reset(&self, user_config: &UserConfiguration) -> Result<SessionResetEvent, AppError> {
+    pub async fn perform_daily_reset(
+        &self,
+        user_config: &UserConfiguration,
+        trigger: SessionResetTriggerSource,
+    ) -> Result<SessionResetEvent, AppError> {
         let current_time = self.time_provider.now_utc();
 
+        if trigger.is_automatic() {
+            if let Some(last_reset) = user_config.last_daily_reset_utc {
+                let since_last_reset = current_time.timestamp() - last_reset;
+                if since_last_reset < self.min_reset_interval.num_seconds() {
+                    return Err(AppError::DailyResetTooSoon(since_last_reset));
+                }
+            }
+        }
+
         info!("Starting daily session reset for user {}", user_config.id);
 
+        // 0. Stop any in-progress session if the user opted in, leaving it paused
+        // where it was; otherwise put a stopped timer back to its reset session
+        let stopped_session = if user_config.stop_session_on_daily_reset {
+            self.stop_running_session(user_config, current_time).await?
+        } else {
+            None
+        };
+        if stopped_session.is_none() {
+            self.reset_timer_session(user_config, current_time).await;
+        }
+
         // 1. Get current session count before reset
         let previous_session_count = self.get_current_session_count(user_config);
 
@@ -262,30 +348,96 @@ impl DailyResetService {
         self.reset_user_configuration(user_config, current_time).await?;
 
         // 4. Create reset event for audit trail
-        let reset_event = self.create_reset_event(user_config, previous_session_count, session_stats, current_time).await?;
+        let reset_event = self.create_reset_event(user_config, previous_session_count, session_stats, current_time, trigger).await?;
 
         info!("Daily session reset completed successfully for user {}", user_config.id);
 
         Ok(reset_event)
     }
 
+    /// Pause the running timer, record the in-progress session as abandoned and
+    /// broadcast the paused state. Returns the abandoned session id, if any.
+    async fn stop_running_session(&self, user_config: &UserConfiguration, reset_time: DateTime<Utc>) -> Result<Option<String>, AppError> {
+        let Some(ws_manager) = &self.ws_manager else {
+            return Ok(None);
+        };
+
+        let mut states = ws_manager.timer_states.lock().await;
+        let timer_state = states.user(&user_config.id);
+        if !timer_state.is_running {
+            return Ok(None);
+        }
+
+        timer_state.stop();
+        timer_state.last_updated = reset_time.timestamp() as u64;
+        let paused_state = timer_state.clone();
+        drop(states);
+
+        let session_id = self.database_manager
+            .record_abandoned_session(&paused_state, &user_config.id, "server", reset_time.timestamp())
+            .await
+            .map_err(|e| AppError::SessionResetFailed(e.to_string()))?;
+
+        info!("Stopped running {} session {} for user {} at daily reset", paused_state.session_type, session_id, user_config.id);
+
+        ws_manager.update_timer_state(&user_config.id, paused_state).await;
+        Ok(Some(session_id))
+    }
+
+    /// Put a stopped timer back to the start of the user's `reset_to_session_type`
+    /// session and broadcast it. A running session, or a user without a reset
+    /// session type, is left alone.
+    async fn reset_timer_session(&self, user_config: &UserConfiguration, reset_time: DateTime<Utc>) {
+        let Some(ws_manager) = &self.ws_manager else {
+            return;
+        };
+        let Some(session_type) = &user_config.reset_to_session_type else {
+            return;
+        };
+
+        let mut states = ws_manager.timer_states.lock().await;
+        let timer_state = states.user(&user_config.id);
+        if timer_state.is_running {
+            return;
+        }
+
+        timer_state.reset_to(session_type);
+        timer_state.last_updated = reset_time.timestamp() as u64;
+        let reset_state = timer_state.clone();
+        drop(states);
+
+        debug!("Timer reset to {} session for user {}", reset_state.session_type, user_config.id);
+        ws_manager.update_timer_state(&user_config.id, reset_state).await;
+    }
+
     /// Save today's session statistics to the database
     #[instrument(skip(self, user_config))]
-    async fn save_daily_session_stats(&self, user_config: &UserConfiguration, reset_time: DateTime<Utc>) -> Result<DailySessionStats, AppError> {
+    async fn save_daily_session_stats(&self, user_config: &UserConfiguration, reset_time: DateTime<Utc>) -> Result<DailyStatsArchive, AppError> {
         let today_date = reset_time.date_naive().to_string();
         let user_timezone: Tz = user_config.timezone.parse()
             .map_err(|_e| AppError::UserConfiguration(
                 crate::models::user_configuration::UserConfigurationError::InvalidTimezone(user_config.timezone.clone())
             ))?;
 
+        // Archive the sessions actually completed since the last reset (or the last day)
+        let since = user_config.last_daily_reset_utc
+            .unwrap_or_else(|| reset_time.timestamp() - 24 * 60 * 60);
+        let totals = self.database_manager
+            .completed_session_totals(&user_config.id, since, reset_time.timestamp() + 1)
+            .await
+            .map_err(|e| AppError::SessionResetFailed(e.to_string()))?;
+        let (work_sessions, work_seconds) = work_totals_or_estimate(&totals, user_config);
+
         // Check if stats already exist for today
         let existing_stats = self.get_daily_session_stats(&user_config.id, &today_date).await?;
 
         if let Some(mut stats) = existing_stats {
             // Update existing stats
-            stats.work_sessions_completed = user_config.today_session_count as i64;
-            stats.total_work_seconds = (user_config.today_session_count * user_config.work_duration) as i64; // Estimate
+            stats.work_sessions_completed = work_sessions;
+            stats.total_work_seconds = work_seconds;
+            stats.total_break_seconds = totals.break_seconds;
             stats.manual_overrides = user_config.manual_session_override.unwrap_or(0) as i64;
+            stats.final_session_count = self.get_current_session_count(user_config) as i64;
             stats.updated_at = reset_time.timestamp();
 
             // Update in database
@@ -295,11 +447,16 @@ impl DailyResetService {
             Ok(stats)
         } else {
             // Create new stats
-            let stats = DailySessionStats::new(
+            let mut stats = DailyStatsArchive::new(
                 user_config.id.clone(),
                 today_date.clone(),
                 user_timezone.to_string(),
             );
+            stats.work_sessions_completed = work_sessions;
+            stats.total_work_seconds = work_seconds;
+            stats.total_break_seconds = totals.break_seconds;
+            stats.manual_overrides = user_config.manual_session_override.unwrap_or(0) as i64;
+            stats.final_session_count = self.get_current_session_count(user_config) as i64;
 
             // Save to database
             let saved_stats = self.insert_daily_session_stats(&stats).await?;
@@ -310,18 +467,16 @@ impl DailyResetService {
     }
 
     /// Get daily session stats for a specific date
-    async fn get_daily_session_stats(&self, user_id: &str, date: &str) -> Result<Option<DailySessionStats>, AppError> {
-        let pool = match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        };
+    async fn get_daily_session_stats(&self, user_id: &str, date: &str) -> Result<Option<DailyStatsArchive>, AppError> {
+        let pool = self.database_manager.pool.sqlite()?;
 
         let row = sqlx::query(
             r#"
-            SELECT id, user_id, date, timezone, work_sessions_completed,
+            SELECT id, user_configuration_id, date, timezone, work_sessions_completed,
                    total_work_seconds, total_break_seconds, manual_overrides,
-                   created_at, updated_at
+                   final_session_count, created_at, updated_at
             FROM daily_session_stats
-            WHERE user_id = ? AND date = ?
+            WHERE user_configuration_id = ? AND date = ?
             "#
         )
         .bind(user_id)
@@ -332,7 +487,7 @@ impl DailyResetService {
 
         match row {
             Some(row) => {
-                let stats = DailySessionStats {
+                let stats = DailyStatsArchive {
                     id: row.get("id"),
                     user_configuration_id: row.get("user_configuration_id"),
                     date: row.get("date"),
@@ -352,10 +507,8 @@ impl DailyResetService {
     }
 
     /// Insert new daily session stats
-    async fn insert_daily_session_stats(&self, stats: &DailySessionStats) -> Result<DailySessionStats, AppError> {
-        let pool = match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        };
+    async fn insert_daily_session_stats(&self, stats: &DailyStatsArchive) -> Result<DailyStatsArchive, AppError> {
+        let pool = self.database_manager.pool.sqlite()?;
 
         let _ = sqlx::query(
             r#"
@@ -386,16 +539,15 @@ impl DailyResetService {
     }
 
     /// Update existing daily session stats
-    async fn update_daily_session_stats(&self, stats: &DailySessionStats) -> Result<(), AppError> {
-        let pool = match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        };
+    async fn update_daily_session_stats(&self, stats: &DailyStatsArchive) -> Result<(), AppError> {
+        let pool = self.database_manager.pool.sqlite()?;
 
         sqlx::query(
             r#"
             UPDATE daily_session_stats
             SET work_sessions_completed = ?, total_work_seconds = ?,
-                total_break_seconds = ?, manual_overrides = ?, updated_at = ?
+                total_break_seconds = ?, manual_overrides = ?,
+                final_session_count = ?, updated_at = ?
             WHERE id = ?
             "#
         )
@@ -403,6 +555,7 @@ impl DailyResetService {
         .bind(stats.total_work_seconds)
         .bind(stats.total_break_seconds)
         .bind(stats.manual_overrides)
+        .bind(stats.final_session_count)
         .bind(stats.updated_at)
         .bind(&stats.id)
         .execute(pool)
@@ -415,9 +568,7 @@ impl DailyResetService {
 
     /// Reset user configuration session counts
     async fn reset_user_configuration(&self, user_config: &UserConfiguration, reset_time: DateTime<Utc>) -> Result<(), AppError> {
-        let pool = match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        };
+        let pool = self.database_manager.pool.sqlite()?;
 
         let timestamp = reset_time.timestamp();
 
@@ -445,45 +596,178 @@ impl DailyResetService {
         &self,
         user_config: &UserConfiguration,
         previous_session_count: u32,
-        session_stats: DailySessionStats,
+        session_stats: DailyStatsArchive,
         reset_time: DateTime<Utc>,
+        trigger: SessionResetTriggerSource,
     ) -> Result<SessionResetEvent, AppError> {
-        let pool = match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        };
-
-        let event = SessionResetEvent::scheduled_daily_reset(
+        let mut event = SessionResetEvent::scheduled_daily_reset(
             user_config.id.clone(),
             previous_session_count,
             reset_time,
             user_config.timezone.clone(),
         );
+        event.trigger_source = trigger;
+
+        self.database_manager
+            .insert_session_reset_event(&event)
+            .await
+            .map_err(|e| AppError::SessionResetFailed(e.to_string()))?;
+
+        info!("Created reset event with ID: {} for user: {}", event.id, user_config.id);
+        Ok(event)
+    }
+
+    /// Save a user's timezone and daily reset time. A ConfigurationChange reset
+    /// event is recorded only if the timezone or the local reset time actually
+    /// changed and there was a session count to reset; saving identical
+    /// settings records nothing. With `reset_on_timezone_change`, a timezone
+    /// change that puts the user in a different local day resets the count and
+    /// records a TimezoneChange event instead. Returns the recorded event, if any.
+    #[instrument(skip(self, reset_time))]
+    pub async fn update_daily_reset_configuration(
+        &self,
+        user_id: &str,
+        reset_time: DailyResetTime,
+        timezone: &str,
+    ) -> Result<Option<SessionResetEvent>, AppError> {
+        self.validate_timezone(timezone)?;
+        reset_time.validate()?;
+
+        let user_config = self.load_user_configuration(user_id).await?;
+        let old_reset_time = user_config.get_daily_reset_time();
+        let changed = user_config.timezone != timezone
+            || old_reset_time.local_time() != reset_time.local_time();
+
+        let pool = self.database_manager.pool.sqlite()?;
 
         sqlx::query(
             r#"
-            INSERT INTO session_reset_events (
-                id, user_configuration_id, reset_type, previous_count, new_count,
-                reset_timestamp_utc, user_timezone, local_reset_time, trigger_source,
-                context, created_at
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            UPDATE user_configurations
+            SET timezone = ?, daily_reset_time_type = ?, daily_reset_time_hour = ?,
+                daily_reset_time_minute = ?, daily_reset_time_custom = ?, updated_at = ?
+            WHERE id = ?
             "#
         )
-        .bind(&event.id)
-        .bind(&event.user_configuration_id)
-        .bind(&event.reset_type)
-        .bind(event.previous_count)
-        .bind(event.new_count)
-        .bind(event.reset_timestamp_utc)
-        .bind(&event.user_timezone)
-        .bind(&event.local_reset_time)
-        .bind(&event.trigger_source)
-        .bind(&event.context)
-        .bind(event.created_at)
+        .bind(timezone)
+        .bind(reset_time.time_type.as_str())
+        .bind(reset_time.hour.map(|hour| hour as i64))
+        .bind(reset_time.minute.map(|minute| minute as i64))
+        .bind(&reset_time.time)
+        .bind(self.time_provider.now_utc().timestamp())
+        .bind(user_id)
         .execute(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
 
-        info!("Created reset event with ID: {} for user: {}", event.id, user_config.id);
+        let current_count = user_config.get_current_session_count();
+        if !changed || current_count == 0 {
+            debug!("Daily reset configuration for user {} saved without a meaningful change", user_id);
+            return Ok(None);
+        }
+
+        let now = self.time_provider.now_utc();
+        if self.reset_on_timezone_change && user_config.timezone != timezone {
+            let old_tz = user_config.timezone.parse::<Tz>().unwrap_or(chrono_tz::UTC);
+            let new_tz = timezone.parse::<Tz>().unwrap_or(chrono_tz::UTC);
+            let old_day = local_reset_day(now, old_tz, old_reset_time.local_time());
+            let new_day = local_reset_day(now, new_tz, reset_time.local_time());
+            if old_day != new_day {
+                let event = SessionResetEvent::timezone_change_reset(
+                    user_config.id.clone(),
+                    current_count,
+                    now,
+                    &user_config.timezone,
+                    timezone,
+                );
+                self.reset_user_configuration(&user_config, now).await?;
+                self.database_manager
+                    .insert_session_reset_event(&event)
+                    .await
+                    .map_err(|e| AppError::SessionResetFailed(e.to_string()))?;
+
+                info!(
+                    "Timezone change {} -> {} moved user {} from {} to {}, reset count of {}",
+                    user_config.timezone, timezone, user_id, old_day, new_day, current_count
+                );
+                return Ok(Some(event));
+            }
+        }
+
+        let event = SessionResetEvent::configuration_change_reset(
+            user_config.id.clone(),
+            current_count,
+            now,
+            user_config.timezone.clone(),
+            &serde_json::json!({
+                "old_timezone": user_config.timezone,
+                "new_timezone": timezone,
+                "old_reset_time": old_reset_time.display_name(),
+                "new_reset_time": reset_time.display_name(),
+            }),
+        );
+
+        self.database_manager
+            .insert_session_reset_event(&event)
+            .await
+            .map_err(|e| AppError::SessionResetFailed(e.to_string()))?;
+
+        info!("Recorded configuration change reset event for user {}", user_id);
+        Ok(Some(event))
+    }
+
+    /// Save a user's whole daily reset configuration, creating their
+    /// configuration with the defaults if they have none yet. Timezone and
+    /// reset time are saved as by `update_daily_reset_configuration`, whose
+    /// event is returned. Disabling daily reset also deactivates the user's
+    /// scheduled daily reset tasks.
+    #[instrument(skip(self, reset_time))]
+    pub async fn configure_daily_reset(
+        &self,
+        user_id: &str,
+        enabled: bool,
+        reset_time: DailyResetTime,
+        timezone: &str,
+    ) -> Result<Option<SessionResetEvent>, AppError> {
+        // Reject bad input before creating anything
+        self.validate_timezone(timezone)?;
+        reset_time.validate()?;
+
+        let pool = self.database_manager.pool.sqlite()?;
+        let now = self.time_provider.now_utc().timestamp();
+        sqlx::query(
+            "INSERT OR IGNORE INTO user_configurations (id, created_at, updated_at) VALUES (?, ?, ?)"
+        )
+        .bind(user_id)
+        .bind(now)
+        .bind(now)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e))?;
+
+        let event = self.update_daily_reset_configuration(user_id, reset_time, timezone).await?;
+
+        sqlx::query("UPDATE user_configurations SET daily_reset_enabled = ?, updated_at = ? WHERE id = ?")
+            .bind(enabled)
+            .bind(now)
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::Database(e))?;
+
+        if !enabled {
+            let tasks = self.database_manager
+                .get_scheduled_tasks_for_user(user_id)
+                .await
+                .map_err(|e| AppError::DailyResetScheduling(e.to_string()))?;
+            for task in tasks.iter().filter(|task| task.is_active && task.task_type == ScheduledTaskType::DailyReset) {
+                self.database_manager
+                    .deactivate_scheduled_task(&task.id)
+                    .await
+                    .map_err(|e| AppError::DailyResetScheduling(e.to_string()))?;
+                info!("Deactivated daily reset task {} for user {} with daily reset disabled", task.id, user_id);
+            }
+        }
+
         Ok(event)
     }
 
@@ -493,9 +777,7 @@ impl DailyResetService {
     pub async fn process_pending_daily_resets(&self) -> Result<Vec<SessionResetEvent>, AppError> {
         info!("Processing pending daily resets");
 
-        let pool = match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        };
+        let pool = self.database_manager.pool.sqlite()?;
 
         // Find all users with daily reset enabled who need reset
         let rows = sqlx::query(
@@ -542,10 +824,13 @@ impl DailyResetService {
                 let user_config = self.load_user_configuration(&user_id).await?;
 
                 // Perform reset
-                match self.perform_daily_reset(&user_config).await {
+                match self.perform_daily_reset(&user_config, SessionResetTriggerSource::BackgroundService).await {
                     Ok(reset_event) => {
                         reset_events.push(reset_event);
                     }
+                    Err(AppError::DailyResetTooSoon(since_last_reset)) => {
+                        info!("Skipping daily reset for user {}: last reset {}s ago", user_id, since_last_reset);
+                    }
                     Err(e) => {
                         error!("Failed to perform daily reset for user {}: {}", user_id, e);
                         // Continue with other users
@@ -558,29 +843,122 @@ impl DailyResetService {
         Ok(reset_events)
     }
 
+    /// Run every active scheduled task that is due: reset the daily session
+    /// count of each due daily reset task's user or back up the database for a
+    /// backup task, record the run on the task
+    /// and schedule its next run from its cron expression. A failing task is
+    /// recorded as a failure without stopping the others.
+    #[instrument(skip(self))]
+    pub async fn process_pending_tasks(&self) -> Result<PendingTasksSummary, AppError> {
+        let now = self.time_provider.now_utc();
+        let tasks = self.database_manager
+            .get_due_scheduled_tasks(now.timestamp())
+            .await
+            .map_err(|e| AppError::DailyResetScheduling(e.to_string()))?;
+
+        let mut summary = PendingTasksSummary::default();
+        for mut task in tasks {
+            match self.run_scheduled_task(&task).await {
+                Ok(event) => {
+                    task.mark_success();
+                    summary.reset_events.extend(event);
+                }
+                Err(e) => {
+                    error!("Scheduled task {} failed: {}", task.id, e);
+                    task.mark_failure();
+                    summary.failed += 1;
+                }
+            }
+            summary.processed += 1;
+
+            task.last_run_utc = Some(now.timestamp());
+            if let Err(e) = task.calculate_next_run(now) {
+                warn!("Deactivating scheduled task {} with unusable schedule '{}': {}", task.id, task.cron_expression, e);
+                task.deactivate();
+            }
+            self.database_manager
+                .save_scheduled_task(&task)
+                .await
+                .map_err(|e| AppError::DailyResetScheduling(e.to_string()))?;
+        }
+
+        Ok(summary)
+    }
+
+    /// Run one scheduled task, returning the reset event it recorded, if any
+    async fn run_scheduled_task(&self, task: &ScheduledTask) -> Result<Option<SessionResetEvent>, AppError> {
+        match (&task.task_type, &task.user_configuration_id) {
+            (ScheduledTaskType::DailyReset, Some(user_id)) => {
+                let user_config = self.load_user_configuration(user_id).await?;
+                if !user_config.daily_reset_enabled {
+                    debug!("Skipping daily reset task {}: daily reset disabled for user {}", task.id, user_id);
+                    return Ok(None);
+                }
+
+                match self.perform_daily_reset(&user_config, SessionResetTriggerSource::BackgroundService).await {
+                    Ok(event) => Ok(Some(event)),
+                    Err(AppError::DailyResetTooSoon(since_last_reset)) => {
+                        info!("Skipping daily reset for user {}: last reset {}s ago", user_id, since_last_reset);
+                        Ok(None)
+                    }
+                    Err(e) => Err(e),
+                }
+            }
+            (ScheduledTaskType::DailyReset, None) => {
+                self.process_pending_daily_resets().await.map(|_| None)
+            }
+            (ScheduledTaskType::Backup, _) => {
+                let backup = crate::api::admin::create_backup(
+                    &self.database_manager,
+                    &self.backup_dir,
+                    crate::api::admin::get_backup_retention(),
+                )
+                .await
+                .map_err(|e| AppError::Internal(format!("Database backup failed: {e}")))?;
+                info!("Scheduled backup written to {} ({} bytes)", backup.path, backup.size_bytes);
+                Ok(None)
+            }
+            // Recorded as a failure rather than a run that never happened
+            (task_type, _) => Err(AppError::Internal(format!(
+                "No runner for {} tasks",
+                task_type.display_name()
+            ))),
+        }
+    }
+
     /// Load user configuration from database
     async fn load_user_configuration(&self, user_id: &str) -> Result<UserConfiguration, AppError> {
-        let pool = match &self.database_manager.pool {
-            DatabasePool::Sqlite(pool) => pool,
-        };
+        self.find_user_configuration(user_id).await?
+            .ok_or(AppError::ConfigurationNotFound)
+    }
+
+    /// Load user configuration from database, if one exists
+    pub async fn find_user_configuration(&self, user_id: &str) -> Result<Option<UserConfiguration>, AppError> {
+        let pool = self.database_manager.pool.sqlite()?;
 
         let row = sqlx::query(
             r#"
             SELECT id, work_duration, short_break_duration, long_break_duration,
-                   long_break_frequency, notifications_enabled, webhook_url,
+                   long_break_frequency, notifications_enabled, webhook_urls, webhook_format, webhook_template,
                    wait_for_interaction, theme, timezone, daily_reset_time_type,
-                   daily_reset_time_hour, daily_reset_time_custom, daily_reset_enabled,
-                   last_daily_reset_utc, today_session_count, manual_session_override,
+                   daily_reset_time_hour, daily_reset_time_minute, daily_reset_time_custom, daily_reset_enabled,
+                   last_daily_reset_utc, today_session_count, manual_session_override, max_session_count, daily_goal,
+                   stop_session_on_daily_reset, reset_to_session_type, auto_start_on_first_connect,
+                   resume_work_duration, min_long_break_before_skip, pause_on_webhook_failure,
                    created_at, updated_at
             FROM user_configurations
             WHERE id = ?
             "#
         )
         .bind(user_id)
-        .fetch_one(pool)
+        .fetch_optional(pool)
         .await
         .map_err(|e| AppError::Database(e))?;
 
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
         let user_config = UserConfiguration {
             id: row.get("id"),
             work_duration: row.get("work_duration"),
@@ -588,7 +966,9 @@ impl DailyResetService {
             long_break_duration: row.get("long_break_duration"),
             long_break_frequency: row.get("long_break_frequency"),
             notifications_enabled: row.get("notifications_enabled"),
-            webhook_url: row.get("webhook_url"),
+            webhook_urls: UserConfiguration::webhook_urls_from_json(&row.get::<String, _>("webhook_urls")),
+            webhook_format: WebhookFormat::parse(&row.get::<String, _>("webhook_format")).unwrap_or_default(),
+            webhook_template: row.get("webhook_template"),
             wait_for_interaction: row.get("wait_for_interaction"),
             theme: match row.get::<String, _>("theme").as_str() {
                 "Dark" => crate::models::user_configuration::Theme::Dark,
@@ -601,28 +981,36 @@ impl DailyResetService {
                 _ => crate::models::user_configuration::DailyResetTimeType::Midnight,
             },
             daily_reset_time_hour: row.get("daily_reset_time_hour"),
+            daily_reset_time_minute: row.get("daily_reset_time_minute"),
             daily_reset_time_custom: row.get("daily_reset_time_custom"),
             daily_reset_enabled: row.get("daily_reset_enabled"),
             last_daily_reset_utc: row.get("last_daily_reset_utc"),
             today_session_count: row.get("today_session_count"),
             manual_session_override: row.get("manual_session_override"),
+            max_session_count: row.get("max_session_count"),
+            daily_goal: row.get("daily_goal"),
+            stop_session_on_daily_reset: row.get("stop_session_on_daily_reset"),
+            reset_to_session_type: row.get("reset_to_session_type"),
+            auto_start_on_first_connect: row.get("auto_start_on_first_connect"),
+            resume_work_duration: row.get("resume_work_duration"),
+            min_long_break_before_skip: row.get("min_long_break_before_skip"),
+            pause_on_webhook_failure: row.get("pause_on_webhook_failure"),
             created_at: row.get("created_at"),
             updated_at: row.get("updated_at"),
         };
 
-        Ok(user_config)
+        Ok(Some(user_config))
     }
 
-    /// Validate session count within acceptable bounds
+    /// Validate session count against the user's configured maximum
     #[instrument(skip(self))]
-    pub async fn validate_session_count(&self, count: i64) -> Result<(), SessionCountValidationError> {
+    pub async fn validate_session_count(&self, count: i64, max: u32) -> Result<(), SessionCountValidationError> {
         const MIN_SESSION_COUNT: u32 = 0;
-        const MAX_SESSION_COUNT: u32 = 100;
 
-        if count < MIN_SESSION_COUNT as i64 || count > MAX_SESSION_COUNT as i64 {
+        if count < MIN_SESSION_COUNT as i64 || count > max as i64 {
             return Err(SessionCountValidationError::OutOfRange {
                 min: MIN_SESSION_COUNT,
-                max: MAX_SESSION_COUNT,
+                max,
                 value: count,
             });
         }
@@ -639,7 +1027,8 @@ impl DailyResetService {
         manual_override: bool,
     ) -> Result<(), AppError> {
         // Validate session count
-        self.validate_session_count(session_count as i64).await
+        let user_config = self.load_user_configuration(user_id).await?;
+        self.validate_session_count(session_count as i64, user_config.max_session_count).await
             .map_err(|e| AppError::UserConfiguration(
                 crate::models::user_configuration::UserConfigurationError::InvalidSessionCount(format!("{}", e))
             ))?;
@@ -669,6 +1058,69 @@ impl DailyResetService {
         Ok(())
     }
 
+    /// Set, or clear with `None`, the manual override that masks today's
+    /// session count. Returns the updated configuration.
+    #[instrument(skip(self))]
+    pub async fn set_manual_session_override(
+        &self,
+        user_id: &str,
+        count: Option<u32>,
+    ) -> Result<UserConfiguration, AppError> {
+        let user_config = self.load_user_configuration(user_id).await?;
+        if let Some(count) = count {
+            self.validate_session_count(count as i64, user_config.max_session_count).await
+                .map_err(|e| AppError::UserConfiguration(
+                    crate::models::user_configuration::UserConfigurationError::InvalidSessionCount(format!("{}", e))
+                ))?;
+        }
+
+        let pool = self.database_manager.pool.sqlite()?;
+
+        sqlx::query("UPDATE user_configurations SET manual_session_override = ?, updated_at = ? WHERE id = ?")
+            .bind(count.map(|count| count as i64))
+            .bind(self.time_provider.now_utc().timestamp())
+            .bind(user_id)
+            .execute(pool)
+            .await
+            .map_err(|e| AppError::Database(e))?;
+
+        info!("Set manual session override for user {} to {:?}", user_id, count);
+
+        self.load_user_configuration(user_id).await
+    }
+
+    /// Make an active manual override permanent: today's session count takes
+    /// the override's value and the override is cleared, so automated counting
+    /// continues from it. Without an override nothing changes. Returns the
+    /// updated configuration.
+    #[instrument(skip(self))]
+    pub async fn merge_manual_session_override(&self, user_id: &str) -> Result<UserConfiguration, AppError> {
+        let user_config = self.load_user_configuration(user_id).await?;
+        let Some(override_count) = user_config.manual_session_override else {
+            return Ok(user_config);
+        };
+
+        let pool = self.database_manager.pool.sqlite()?;
+
+        sqlx::query(
+            r#"
+            UPDATE user_configurations
+            SET today_session_count = ?, manual_session_override = NULL, updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(override_count as i64)
+        .bind(self.time_provider.now_utc().timestamp())
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e))?;
+
+        info!("Merged manual session override of {} into today's count for user {}", override_count, user_id);
+
+        self.load_user_configuration(user_id).await
+    }
+
     /// Get current daily reset status for a user
     #[instrument(skip(self))]
     pub async fn get_daily_reset_status(&self, user_id: &str) -> Result<DailyResetStatus, AppError> {
@@ -687,7 +1139,7 @@ impl DailyResetService {
         };
 
         // Determine if reset is due today
-        let reset_due_today = self.should_reset_today(&user_config);
+        let reset_due_today = self.should_reset_today(&user_config)?;
 
         // Get current session count (prefer manual override)
         let current_session_count = daily_stats.manual_session_override
@@ -702,39 +1154,126 @@ impl DailyResetService {
         })
     }
 
-    /// Increment session count (automated counting)
+    /// Work sessions and seconds archived for the user's local days `start`
+    /// through `end`, summed per day, week or month. Weeks start on the
+    /// default day for the user's timezone.
     #[instrument(skip(self))]
+    pub async fn get_aggregated_statistics(
+        &self,
+        user_id: &str,
+        start: chrono::NaiveDate,
+        end: chrono::NaiveDate,
+        granularity: Granularity,
+    ) -> Result<Vec<AggregatedStats>, AppError> {
+        if start > end {
+            return Err(AppError::BadRequest(format!("start {} is after end {}", start, end)));
+        }
+
+        let timezone = self.find_user_configuration(user_id).await?
+            .map(|config| config.timezone)
+            .unwrap_or_else(|| "UTC".to_string());
+        let week_start = WeekStart::default_for_timezone(&timezone);
+
+        let daily_stats = self.database_manager
+            .get_daily_session_stats_range(user_id, start, end)
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        Ok(aggregate_daily_stats(&daily_stats, granularity, week_start))
+    }
+
+    /// The user's current and longest streaks of days with a completed work
+    /// session. Days run from one reset time to the next in the user's
+    /// timezone; the day in progress counts once it has a session.
+    #[instrument(skip(self))]
+    pub async fn get_streaks(&self, user_id: &str) -> Result<Streaks, AppError> {
+        let user_config = self.find_user_configuration(user_id).await?
+            .unwrap_or_else(|| UserConfiguration::with_id(user_id.to_string()));
+        let timezone: Tz = user_config.timezone.parse().unwrap_or(chrono_tz::UTC);
+        let today = local_reset_day(
+            self.time_provider.now_utc(),
+            timezone,
+            user_config.get_daily_reset_time().local_time(),
+        );
+
+        let history = self.database_manager
+            .get_daily_session_stats_range(user_id, DateTime::UNIX_EPOCH.date_naive(), today)
+            .await
+            .map_err(|e| AppError::Internal(e.to_string()))?;
+
+        Ok(streaks(&history, today, user_config.today_session_count as i64))
+    }
+
+    /// Today's session count (honoring a manual override) against the user's daily goal
+    #[instrument(skip(self))]
+    pub async fn get_goal_progress(&self, user_id: &str) -> Result<GoalProgress, AppError> {
+        let user_config = self.find_user_configuration(user_id).await?
+            .unwrap_or_else(|| UserConfiguration::with_id(user_id.to_string()));
+        Ok(GoalProgress::new(user_config.get_current_session_count(), user_config.daily_goal))
+    }
+
+    /// Increment today's session count (automated counting on work-session completion)
     pub async fn increment_session_count(&self, user_id: &str) -> Result<u32, AppError> {
+        self.increment_session_count_notifying(user_id, self.ws_manager.as_deref()).await
+    }
+
+    /// Increment today's session count, announcing a reached daily goal to
+    /// `ws_manager`'s clients rather than the attached manager's
+    #[instrument(skip(self, ws_manager))]
+    pub async fn increment_session_count_notifying(
+        &self,
+        user_id: &str,
+        ws_manager: Option<&crate::websocket::manager::WebSocketManager>,
+    ) -> Result<u32, AppError> {
         // Load user configuration
         let user_config = self.load_user_configuration(user_id).await?;
 
-        // Get current daily stats
-        let today = self.time_provider.now_utc().date_naive();
-        let daily_stats = self.get_or_create_daily_stats(user_id, &today).await?;
-
         // Check if manual override is active (should block automated increments)
-        if daily_stats.manual_session_override.is_some() {
+        if user_config.manual_session_override.is_some() {
             return Err(AppError::UserConfiguration(
                 crate::models::user_configuration::UserConfigurationError::ManualOverrideActive
             ));
         }
 
         // Increment session count
-        let new_count = daily_stats.session_count + 1;
+        let new_count = user_config.today_session_count + 1;
 
         // Validate new count
-        self.validate_session_count(new_count as i64).await
+        self.validate_session_count(new_count as i64, user_config.max_session_count).await
             .map_err(|e| AppError::UserConfiguration(
                 crate::models::user_configuration::UserConfigurationError::InvalidSessionCount(format!("{}", e))
             ))?;
 
-        // Update daily stats
-        let mut updated_stats = daily_stats;
-        updated_stats.session_count = new_count;
-        self.persist_daily_session_stats(&updated_stats).await?;
+        let pool = self.database_manager.pool.sqlite()?;
+
+        sqlx::query(
+            r#"
+            UPDATE user_configurations
+            SET today_session_count = ?, updated_at = ?
+            WHERE id = ?
+            "#
+        )
+        .bind(new_count as i64)
+        .bind(self.time_provider.now_utc().timestamp())
+        .bind(user_id)
+        .execute(pool)
+        .await
+        .map_err(|e| AppError::Database(e))?;
 
         info!("Incremented session count for user {} to {}", user_id, new_count);
 
+        // Celebrate only the session that crosses the goal
+        if let Some(goal) = user_config.daily_goal {
+            if user_config.today_session_count < goal && new_count >= goal {
+                info!("User {} reached their daily goal of {} sessions", user_id, goal);
+                if let Some(ws_manager) = ws_manager {
+                    ws_manager
+                        .broadcast_message(user_id, crate::websocket::manager::WsMessage::GoalReached { goal, session_count: new_count })
+                        .await;
+                }
+            }
+        }
+
         Ok(new_count)
     }
 
@@ -793,14 +1332,41 @@ impl DailyResetService {
     }
 }
 
+/// What one pass over the due scheduled tasks did
+#[derive(Debug, Default)]
+pub struct PendingTasksSummary {
+    /// Due tasks that were run, including failed ones
+    pub processed: u32,
+    /// Tasks whose run failed
+    pub failed: u32,
+    /// Daily resets the tasks performed
+    pub reset_events: Vec<SessionResetEvent>,
+}
+
+/// Work sessions and seconds to archive for the day, from `totals` of the
+/// user's own completed sessions. Days without any (from before sessions were
+/// recorded) fall back to estimating from the session count and the
+/// configured work duration.
+fn work_totals_or_estimate(totals: &CompletedSessionTotals, user_config: &UserConfiguration) -> (i64, i64) {
+    if totals.work_sessions > 0 || totals.break_seconds > 0 {
+        return (totals.work_sessions, totals.work_seconds);
+    }
+
+    let estimated_sessions = i64::from(user_config.today_session_count);
+    (estimated_sessions, estimated_sessions * i64::from(user_config.work_duration))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::user_configuration::DailyResetTime;
     use crate::services::time_provider::MockTimeProvider;
 
     async fn create_test_service() -> Result<(DailyResetService, ()), Box<dyn std::error::Error>> {
         let time_provider = Arc::new(MockTimeProvider::new_from_now());
-        let service = DailyResetService::new(time_provider);
+        let database_manager = Arc::new(DatabaseManager::new("sqlite::memory:").await?);
+        database_manager.migrate().await?;
+        let service = DailyResetService::new(time_provider, database_manager);
 
         Ok((service, ()))
     }
@@ -825,12 +1391,12 @@ mod tests {
 
         let mut config = UserConfiguration::new();
         config.set_timezone("UTC".to_string())?;
-        config.set_daily_reset_time(DailyResetTimeType::Midnight);
+        config.set_daily_reset_time(DailyResetTime::midnight())?;
         config.set_daily_reset_enabled(true);
 
         let next_reset = service.calculate_next_reset_time(&config)?;
         // Should be midnight UTC tomorrow
-        let expected = service.time_provider.now().date_naive().succ_opt()
+        let expected = service.time_provider.now_utc().date_naive().succ_opt()
             .unwrap()
             .and_hms_opt(0, 0, 0)
             .unwrap()
@@ -843,6 +1409,83 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_daily_reset_stops_running_session() -> Result<(), Box<dyn std::error::Error>> {
+        for stop_session in [true, false] {
+            let temp_dir = tempfile::TempDir::new()?;
+            let db_path = temp_dir.path().join("test_stop_session.db");
+            let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+            database_manager.migrate().await?;
+
+            let timer_state = Arc::new(tokio::sync::Mutex::new(crate::models::timer_state::TimerStates::new(crate::models::timer_state::TimerState {
+                is_running: true,
+                remaining_seconds: 600,
+                session_type: "work".to_string(),
+                session_count: 2,
+                work_duration: 1500,
+                short_break_duration: 300,
+                long_break_duration: 900,
+                long_break_frequency: crate::models::timer_state::DEFAULT_LONG_BREAK_FREQUENCY,
+                last_updated: 0,
+                work_sessions_since_long_break: 0,
+                label: None,
+                session_type_labels: Default::default(),
+                plan: None,
+                pre_break_work_duration: None,
+                added_seconds: 0,
+                session_ends_at: None,
+            })));
+            let ws_manager = Arc::new(crate::websocket::manager::WebSocketManager::new(timer_state.clone(), database_manager.clone()));
+            let (sender, mut receiver) = tokio::sync::mpsc::unbounded_channel();
+            ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+            while receiver.try_recv().is_ok() {}
+
+            let time_provider = Arc::new(MockTimeProvider::new_from_now());
+            let service = DailyResetService::new(time_provider.clone(), database_manager.clone())
+                .with_websocket_manager(ws_manager);
+
+            let pool = database_manager.pool.sqlite().unwrap();
+            sqlx::query(
+                "INSERT INTO user_configurations (id, daily_reset_enabled, today_session_count, stop_session_on_daily_reset, created_at, updated_at) VALUES ('alice', TRUE, 2, ?, 0, 0)"
+            )
+            .bind(stop_session)
+            .execute(pool)
+            .await?;
+            let config = service.load_user_configuration("alice").await?;
+
+            service.perform_daily_reset(&config, SessionResetTriggerSource::UserAction).await?;
+
+            let state = timer_state.lock().await.get("alice");
+            let sessions: Vec<(i64, Option<i64>)> =
+                sqlx::query_as("SELECT elapsed, abandoned_at FROM timer_sessions WHERE user_id = 'alice'")
+                    .fetch_all(pool)
+                    .await?;
+            let mut updates = 0;
+            while let Ok(message) = receiver.try_recv() {
+                if message.to_text()?.contains("\"TimerStateUpdate\"") {
+                    updates += 1;
+                }
+            }
+            if stop_session {
+                // Stopped and recorded as abandoned, left paused where it was
+                // with a single update
+                assert!(!state.is_running);
+                assert_eq!(state.session_type, "work");
+                assert_eq!(state.remaining_seconds, 600);
+                assert_eq!(sessions, [(900, Some(time_provider.now_utc().timestamp()))]);
+                assert_eq!(updates, 1);
+            } else {
+                // The running session carries on untouched
+                assert!(state.is_running);
+                assert_eq!(state.remaining_seconds, 600);
+                assert!(sessions.is_empty());
+                assert_eq!(updates, 0);
+            }
+        }
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn test_session_count_override() -> Result<(), Box<dyn std::error::Error>> {
         let (service, _) = create_test_service().await?;
@@ -854,4 +1497,526 @@ mod tests {
 
         Ok(())
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_daily_stats_use_actual_session_durations() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_daily_stats.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let time_provider = Arc::new(MockTimeProvider::new_from_now());
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone());
+        let reset_time = time_provider.now_utc();
+        let now = reset_time.timestamp();
+
+        // Two 50-minute work sessions and a 10-minute break completed today
+        database_manager.record_completed_session("default", "work", 3000, 0, "server", now - 7200, None).await?;
+        database_manager.record_completed_session("default", "short_break", 600, 0, "server", now - 4000, None).await?;
+        database_manager.record_completed_session("default", "work", 3000, 0, "server", now - 600, None).await?;
+        // Completed before the last reset, so not part of today's archive
+        database_manager.record_completed_session("default", "work", 3000, 0, "server", now - 90_000, None).await?;
+
+        let mut config = UserConfiguration::with_id("default".to_string());
+        config.work_duration = 3000;
+        config.today_session_count = 2;
+        config.last_daily_reset_utc = Some(now - 86_400);
+
+        let stats = service.save_daily_session_stats(&config, reset_time).await?;
+
+        assert_eq!(stats.work_sessions_completed, 2);
+        assert_eq!(stats.total_work_seconds, 6000);
+        assert_ne!(stats.total_work_seconds, 2 * 25 * 60);
+        assert_eq!(stats.total_break_seconds, 600);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_daily_stats_only_archive_own_sessions() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_own_daily_stats.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let time_provider = Arc::new(MockTimeProvider::new_from_now());
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone());
+        let reset_time = time_provider.now_utc();
+        let now = reset_time.timestamp();
+
+        // Alice and Bob both completed sessions since Alice's last reset
+        database_manager.record_completed_session("alice", "work", 3000, 0, "server", now - 7200, None).await?;
+        database_manager.record_completed_session("bob", "work", 1200, 0, "server", now - 5000, None).await?;
+        database_manager.record_completed_session("bob", "short_break", 300, 0, "server", now - 4000, None).await?;
+        database_manager.record_completed_session("bob", "work", 1200, 0, "server", now - 600, None).await?;
+
+        let mut config = UserConfiguration::with_id("alice".to_string());
+        config.today_session_count = 1;
+        config.last_daily_reset_utc = Some(now - 86_400);
+        let stats = service.save_daily_session_stats(&config, reset_time).await?;
+
+        assert_eq!(stats.work_sessions_completed, 1);
+        assert_eq!(stats.total_work_seconds, 3000);
+        assert_eq!(stats.total_break_seconds, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_daily_stats_sum_mixed_durations_and_estimate_legacy_days() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_mixed_daily_stats.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let time_provider = Arc::new(MockTimeProvider::new_from_now());
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone());
+        let reset_time = time_provider.now_utc();
+        let now = reset_time.timestamp();
+
+        // A legacy day: sessions were counted but none were recorded
+        let mut legacy_config = UserConfiguration::with_id("default".to_string());
+        legacy_config.work_duration = 40 * 60;
+        legacy_config.today_session_count = 3;
+        legacy_config.last_daily_reset_utc = Some(now - 86_400);
+        let legacy = service.save_daily_session_stats(&legacy_config, reset_time).await?;
+        assert_eq!(legacy.work_sessions_completed, 3);
+        assert_eq!(legacy.total_work_seconds, 3 * 40 * 60);
+
+        // Another user's recorded sessions don't stop the estimate
+        database_manager.record_completed_session("bob", "work", 600, 0, "server", now - 3000, None).await?;
+        database_manager.record_completed_session("bob", "short_break", 300, 0, "server", now - 2000, None).await?;
+        let legacy = service.save_daily_session_stats(&legacy_config, reset_time).await?;
+        assert_eq!(legacy.work_sessions_completed, 3);
+        assert_eq!(legacy.total_work_seconds, 3 * 40 * 60);
+        assert_eq!(legacy.total_break_seconds, 0);
+
+        // Work sessions of 50, 20 and 45 minutes
+        for (duration, ago) in [(3000, 9000), (1200, 5000), (2700, 1000)] {
+            database_manager.record_completed_session("default", "work", duration, 0, "server", now - ago, None).await?;
+        }
+
+        let mut config = UserConfiguration::with_id("default".to_string());
+        config.work_duration = 40 * 60;
+        config.today_session_count = 3;
+        config.last_daily_reset_utc = Some(now - 86_400);
+        let stats = service.save_daily_session_stats(&config, reset_time).await?;
+
+        assert_eq!(stats.work_sessions_completed, 3);
+        assert_eq!(stats.total_work_seconds, 3000 + 1200 + 2700);
+        assert_ne!(stats.total_work_seconds, 3 * 25 * 60);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_daily_reset_uses_configured_session_type() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_reset_session_type.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let timer_state = Arc::new(tokio::sync::Mutex::new(crate::models::timer_state::TimerStates::new(crate::models::timer_state::TimerState {
+            is_running: false,
+            remaining_seconds: 120,
+            session_type: "work".to_string(),
+            session_count: 3,
+            work_duration: 1500,
+            short_break_duration: 300,
+            long_break_duration: 900,
+            long_break_frequency: crate::models::timer_state::DEFAULT_LONG_BREAK_FREQUENCY,
+            last_updated: 0,
+            work_sessions_since_long_break: 0,
+            label: None,
+            session_type_labels: Default::default(),
+            plan: None,
+            pre_break_work_duration: None,
+            added_seconds: 0,
+            session_ends_at: None,
+        })));
+        let ws_manager = Arc::new(crate::websocket::manager::WebSocketManager::new(timer_state.clone(), database_manager.clone()));
+
+        let time_provider = Arc::new(MockTimeProvider::new_from_now());
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone())
+            .with_websocket_manager(ws_manager);
+
+        // Without a reset session type the timer is left as it was
+        let mut config = UserConfiguration::new();
+        service.reset_timer_session(&config, time_provider.now_utc()).await;
+        let state = timer_state.lock().await.get(&config.id);
+        assert_eq!(state.session_type, "work");
+        assert_eq!(state.remaining_seconds, 120);
+
+        assert!(config.set_reset_to_session_type(Some("planning".to_string())).is_err());
+        config.set_reset_to_session_type(Some("long_break".to_string()))?;
+
+        service.reset_timer_session(&config, time_provider.now_utc()).await;
+
+        let state = timer_state.lock().await.get(&config.id);
+        assert_eq!(state.session_type, "long_break");
+        assert_eq!(state.remaining_seconds, 900);
+        assert!(!state.is_running);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_automatic_reset_too_soon_is_skipped() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_min_reset_interval.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let time_provider = Arc::new(MockTimeProvider::new_from_now());
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone());
+        let now = time_provider.now_utc().timestamp();
+
+        let pool = database_manager.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, daily_reset_enabled, today_session_count, last_daily_reset_utc, created_at, updated_at) VALUES ('alice', TRUE, 3, ?, 0, 0)"
+        )
+        .bind(now - 3600)
+        .execute(pool)
+        .await?;
+        let config = service.load_user_configuration("alice").await?;
+
+        let result = service.perform_daily_reset(&config, SessionResetTriggerSource::BackgroundService).await;
+        assert!(matches!(result, Err(AppError::DailyResetTooSoon(3600))));
+        let (count,): (i64,) = sqlx::query_as("SELECT today_session_count FROM user_configurations WHERE id = 'alice'")
+            .fetch_one(pool)
+            .await?;
+        assert_eq!(count, 3);
+
+        let event = service.perform_daily_reset(&config, SessionResetTriggerSource::UserAction).await?;
+        assert_eq!(event.trigger_source, SessionResetTriggerSource::UserAction);
+        let (count,): (i64,) = sqlx::query_as("SELECT today_session_count FROM user_configurations WHERE id = 'alice'")
+            .fetch_one(pool)
+            .await?;
+        assert_eq!(count, 0);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hour_with_minutes_reset_time() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_reset_minutes.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+
+        let mut config = UserConfiguration::new();
+        config.set_timezone("America/New_York".to_string())?;
+        config.set_daily_reset_time(DailyResetTime::hour_minute(8, 30)?)?;
+        config.set_daily_reset_enabled(true);
+        assert_eq!(config.get_daily_reset_cron_expression(), "30 8 * * *");
+
+        // 07:00 EST: today's 08:30 EST is still ahead
+        let time_provider = Arc::new(MockTimeProvider::new(Utc.with_ymd_and_hms(2024, 1, 15, 12, 0, 0).unwrap()));
+        let service = DailyResetService::new(time_provider, database_manager.clone());
+        assert_eq!(
+            service.calculate_next_reset_time(&config)?,
+            Utc.with_ymd_and_hms(2024, 1, 15, 13, 30, 0).unwrap()
+        );
+
+        // 09:00 EST: already passed, so tomorrow's
+        let time_provider = Arc::new(MockTimeProvider::new(Utc.with_ymd_and_hms(2024, 1, 15, 14, 0, 0).unwrap()));
+        let service = DailyResetService::new(time_provider, database_manager);
+        assert_eq!(
+            service.calculate_next_reset_time(&config)?,
+            Utc.with_ymd_and_hms(2024, 1, 16, 13, 30, 0).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_hourly_reset_rolls_over_to_tomorrow() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_reset_hourly_tomorrow.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+
+        let mut config = UserConfiguration::new();
+        config.set_timezone("Europe/Berlin".to_string())?;
+        config.set_daily_reset_time(DailyResetTime::hour(6)?)?;
+        config.set_daily_reset_enabled(true);
+
+        // 07:00 CET on the 31st: today's 06:00 has passed, next is 06:00 on the 1st
+        let time_provider = Arc::new(MockTimeProvider::new(Utc.with_ymd_and_hms(2024, 1, 31, 6, 0, 0).unwrap()));
+        let service = DailyResetService::new(time_provider, database_manager);
+        assert_eq!(
+            service.calculate_next_reset_time(&config)?,
+            Utc.with_ymd_and_hms(2024, 2, 1, 5, 0, 0).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_reset_time_across_dst_transitions() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_reset_dst.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+
+        let mut config = UserConfiguration::new();
+        config.set_timezone("America/New_York".to_string())?;
+        config.set_daily_reset_time(DailyResetTime::hour_minute(2, 30)?)?;
+        config.set_daily_reset_enabled(true);
+
+        // 2025-03-09: 02:30 doesn't exist, so the reset moves to 03:00 EDT
+        let time_provider = Arc::new(MockTimeProvider::new(Utc.with_ymd_and_hms(2025, 3, 9, 5, 0, 0).unwrap()));
+        let service = DailyResetService::new(time_provider, database_manager.clone());
+        assert_eq!(
+            service.calculate_next_reset_time(&config)?,
+            Utc.with_ymd_and_hms(2025, 3, 9, 7, 0, 0).unwrap()
+        );
+
+        // Same gap reached through the tomorrow branch
+        let time_provider = Arc::new(MockTimeProvider::new(Utc.with_ymd_and_hms(2025, 3, 8, 12, 0, 0).unwrap()));
+        let service = DailyResetService::new(time_provider, database_manager.clone());
+        assert_eq!(
+            service.calculate_next_reset_time(&config)?,
+            Utc.with_ymd_and_hms(2025, 3, 9, 7, 0, 0).unwrap()
+        );
+
+        // 2025-11-02: 01:30 happens twice, the earlier (EDT) instant wins
+        config.set_daily_reset_time(DailyResetTime::hour_minute(1, 30)?)?;
+        let time_provider = Arc::new(MockTimeProvider::new(Utc.with_ymd_and_hms(2025, 11, 2, 4, 0, 0).unwrap()));
+        let service = DailyResetService::new(time_provider, database_manager);
+        assert_eq!(
+            service.calculate_next_reset_time(&config)?,
+            Utc.with_ymd_and_hms(2025, 11, 2, 5, 30, 0).unwrap()
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_session_count_respects_configured_maximum() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_max_session_count.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let pool = database_manager.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, today_session_count, max_session_count, created_at, updated_at) VALUES ('alice', 4, 5000, 0, 0), ('bob', 4, 5, 0, 0)"
+        )
+        .execute(pool)
+        .await?;
+
+        let service = DailyResetService::new(Arc::new(MockTimeProvider::new_from_now()), database_manager.clone());
+
+        // A raised cap permits counts past the default of 1000
+        let config = service.set_manual_session_override("alice", Some(1500)).await?;
+        assert_eq!(config.manual_session_override, Some(1500));
+        assert!(service.set_manual_session_override("alice", Some(5001)).await.is_err());
+
+        // A lowered cap rejects anything above it
+        assert!(service.set_manual_session_override("bob", Some(6)).await.is_err());
+        assert_eq!(service.increment_session_count("bob").await?, 5);
+        assert!(service.increment_session_count("bob").await.is_err());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_configuration_change_event_only_when_changed() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_config_change_event.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let pool = database_manager.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, timezone, daily_reset_time_type, daily_reset_time_hour, today_session_count, created_at, updated_at) VALUES ('alice', 'Europe/London', 'hour', 6, 3, 0, 0)"
+        )
+        .execute(pool)
+        .await?;
+
+        let service = DailyResetService::new(Arc::new(MockTimeProvider::new_from_now()), database_manager.clone());
+        let event_count = || async {
+            let (count,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM session_reset_events WHERE user_configuration_id = 'alice'")
+                .fetch_one(pool)
+                .await
+                .unwrap();
+            count
+        };
+
+        // Identical settings: nothing recorded
+        let event = service.update_daily_reset_configuration("alice", DailyResetTime::hour(6)?, "Europe/London").await?;
+        assert!(event.is_none());
+        assert_eq!(event_count().await, 0);
+
+        // A new reset time is a real change
+        let event = service.update_daily_reset_configuration("alice", DailyResetTime::hour_minute(6, 30)?, "Europe/London").await?;
+        let event = event.expect("a changed reset time should record an event");
+        assert_eq!(event.previous_count, 3);
+        assert_eq!(event_count().await, 1);
+
+        let saved = service.find_user_configuration("alice").await?.unwrap();
+        assert_eq!(saved.get_daily_reset_time().local_time(), (6, 30));
+
+        // Saving it again is a no-op
+        assert!(service.update_daily_reset_configuration("alice", DailyResetTime::hour_minute(6, 30)?, "Europe/London").await?.is_none());
+        assert_eq!(event_count().await, 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_timezone_change_across_day_boundary() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::models::session_reset_event::SessionResetEventType;
+
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_timezone_change_reset.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let pool = database_manager.pool.sqlite().unwrap();
+        for user in ["alice", "bob"] {
+            sqlx::query(
+                "INSERT INTO user_configurations (id, timezone, daily_reset_time_type, today_session_count, created_at, updated_at) VALUES (?, 'Europe/London', 'midnight', 3, 0, 0)"
+            )
+            .bind(user)
+            .execute(pool)
+            .await?;
+        }
+
+        // 23:30 on the 10th in London is already 08:30 on the 11th in Tokyo
+        let time_provider = Arc::new(MockTimeProvider::new_from_ymd_hms(2025, 1, 10, 23, 30, 0)?);
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone())
+            .with_reset_on_timezone_change(true);
+
+        // Lisbon shares London's date: no day boundary crossed
+        let event = service.update_daily_reset_configuration("alice", DailyResetTime::midnight(), "Europe/Lisbon").await?.unwrap();
+        assert_eq!(event.reset_type, SessionResetEventType::ConfigurationChange);
+        assert_eq!(service.find_user_configuration("alice").await?.unwrap().get_current_session_count(), 3);
+
+        let event = service.update_daily_reset_configuration("alice", DailyResetTime::midnight(), "Asia/Tokyo").await?.unwrap();
+        assert_eq!(event.reset_type, SessionResetEventType::TimezoneChange);
+        assert_eq!(event.previous_count, 3);
+        assert!(event.context.as_deref().unwrap().contains("Europe/Lisbon"));
+        assert_eq!(service.find_user_configuration("alice").await?.unwrap().get_current_session_count(), 0);
+
+        // With the flag off the count is kept and the change is a plain configuration change
+        let service = DailyResetService::new(time_provider, database_manager.clone());
+        let event = service.update_daily_reset_configuration("bob", DailyResetTime::midnight(), "Asia/Tokyo").await?.unwrap();
+        assert_eq!(event.reset_type, SessionResetEventType::ConfigurationChange);
+        assert_eq!(service.find_user_configuration("bob").await?.unwrap().get_current_session_count(), 3);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_reset_records_background_service() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_scheduled_trigger.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let time_provider = Arc::new(MockTimeProvider::new_from_now());
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone());
+        let now = time_provider.now_utc().timestamp();
+
+        let pool = database_manager.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, daily_reset_enabled, today_session_count, last_daily_reset_utc, created_at, updated_at) VALUES ('alice', TRUE, 3, ?, 0, 0)"
+        )
+        .bind(now - 25 * 3600)
+        .execute(pool)
+        .await?;
+
+        let events = service.process_pending_daily_resets().await?;
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0].trigger_source, SessionResetTriggerSource::BackgroundService);
+        let (source,): (String,) = sqlx::query_as("SELECT trigger_source FROM session_reset_events WHERE user_configuration_id = 'alice'")
+            .fetch_one(pool)
+            .await?;
+        assert_eq!(source, "background_service");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_backup_task_writes_backup() -> Result<(), Box<dyn std::error::Error>> {
+        use crate::models::scheduled_task::ScheduledTask;
+
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_scheduled_backup.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let time_provider = Arc::new(MockTimeProvider::new_from_now());
+        let backup_dir = temp_dir.path().join("backups");
+        let service = DailyResetService::new(time_provider.clone(), database_manager.clone())
+            .with_backup_dir(backup_dir.clone());
+        let now = time_provider.now_utc().timestamp();
+
+        let mut backup = ScheduledTask::new(ScheduledTaskType::Backup, "0 3 * * 0".to_string(), "UTC".to_string());
+        backup.next_run_utc = now - 60;
+        database_manager.save_scheduled_task(&backup).await?;
+        // Task types without a runner fail instead of counting as run
+        let mut cleanup = ScheduledTask::new(ScheduledTaskType::Cleanup, "0 2 * * 0".to_string(), "UTC".to_string());
+        cleanup.next_run_utc = now - 60;
+        database_manager.save_scheduled_task(&cleanup).await?;
+
+        let summary = service.process_pending_tasks().await?;
+        assert_eq!(summary.processed, 2);
+        assert_eq!(summary.failed, 1);
+
+        let backups: Vec<_> = std::fs::read_dir(&backup_dir)?.collect();
+        assert_eq!(backups.len(), 1);
+        let stored = database_manager.get_scheduled_task(&backup.id).await?.unwrap();
+        assert_eq!((stored.run_count, stored.failure_count), (1, 0));
+        let stored = database_manager.get_scheduled_task(&cleanup.id).await?.unwrap();
+        assert_eq!((stored.run_count, stored.failure_count), (0, 1));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_aggregated_statistics_use_timezone_weeks() -> Result<(), Box<dyn std::error::Error>> {
+        let temp_dir = tempfile::TempDir::new()?;
+        let db_path = temp_dir.path().join("test_aggregated_stats.db");
+        let database_manager = Arc::new(DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy())).await?);
+        database_manager.migrate().await?;
+
+        let pool = database_manager.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, timezone, created_at, updated_at) VALUES ('alice', 'America/New_York', 0, 0)"
+        )
+        .execute(pool)
+        .await?;
+
+        let service = DailyResetService::new(Arc::new(MockTimeProvider::new_from_now()), database_manager.clone());
+
+        // Sunday 2024-03-03 through Tuesday 2024-03-12, two sessions a day
+        let start = chrono::NaiveDate::from_ymd_opt(2024, 3, 3).unwrap();
+        let end = start + chrono::Duration::days(9);
+        for offset in 0..10 {
+            let date = start + chrono::Duration::days(offset);
+            let mut stats = DailyStatsArchive::new(
+                "alice".to_string(),
+                date.format("%Y-%m-%d").to_string(),
+                "America/New_York".to_string(),
+            );
+            stats.work_sessions_completed = 2;
+            stats.total_work_seconds = 3000;
+            service.insert_daily_session_stats(&stats).await?;
+        }
+
+        // New York weeks start on Sunday
+        let weeks = service.get_aggregated_statistics("alice", start, end, Granularity::Week).await?;
+        let buckets: Vec<_> = weeks.iter().map(|week| (week.period_start, week.work_sessions, week.work_seconds)).collect();
+        assert_eq!(buckets, vec![
+            (start, 14, 21_000),
+            (chrono::NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(), 6, 9_000),
+        ]);
+
+        let days = service.get_aggregated_statistics("alice", start, end, Granularity::Day).await?;
+        assert_eq!(days.len(), 10);
+
+        assert!(service.get_aggregated_statistics("alice", end, start, Granularity::Week).await.is_err());
+
+        Ok(())
+    }
+}
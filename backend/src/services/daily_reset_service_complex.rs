@@ -222,7 +222,7 @@ impl DailyResetService {
         // Record daily statistics
         let today = current_time.format("%Y-%m-%d").to_string();
         let work_sessions_completed = session_count_before as u32;
-        let total_work_seconds = work_sessions_completed * 25 * 60; // Assume 25 minutes per session
+        let total_work_seconds = work_sessions_completed * user_config.work_duration; // Estimate from the configured duration
 
         self.db_manager.record_daily_session_stat(
             &user_config.id,
@@ -393,9 +393,11 @@ impl DailyResetService {
 
         self.db_manager.save_user_configuration(&updated_config).await?;
 
-        // If configuration changed significantly, record a reset event
+        // Only record a reset event if the timezone or reset time actually changed
+        let changed = user_config.timezone != timezone
+            || user_config.get_daily_reset_time().local_time() != reset_time.local_time();
         let current_count = updated_config.get_current_session_count();
-        if current_count > 0 {
+        if changed && current_count > 0 {
             let reset_event = SessionResetEvent::configuration_change_reset(
                 user_config.id.clone(),
                 current_count,
@@ -11,5 +11,11 @@ pub mod daily_reset_service;
 pub mod daily_reset_task_handler;
 pub mod timezone_service;
 pub mod scheduling_service;
+pub mod stats_service;
+pub mod auth_service;
+pub mod background_service;
+pub mod settings_service;
+pub mod timer_control_service;
+pub mod webhook_service;
 
 // Re-export commonly used services
@@ -2,7 +2,8 @@
 //!
 //! Provides background task scheduling functionality for the daily reset feature.
 
-use crate::models::scheduled_task::{ScheduledTask, ScheduledTaskType};
+use crate::database::DatabaseManager;
+use crate::models::scheduled_task::{CronSchedule, ScheduledTask, ScheduledTaskType};
 use crate::services::time_provider::TimeProvider;
 use anyhow::Result;
 use async_trait::async_trait;
@@ -146,7 +147,8 @@ impl SchedulingService {
 
         // Create the job
         let job_id_for_job = job_id.clone();
-        let job = Job::new_async(&task.cron_expression, move |_uuid, _l| {
+        // Task expressions have five fields; the scheduler also wants seconds
+        let job = Job::new_async(&format!("0 {}", task.cron_expression), move |_uuid, _l| {
             let job_id = job_id_for_job.clone();
             let task_type = task_type.clone();
             let handlers = Arc::clone(&handlers);
@@ -266,13 +268,14 @@ impl SchedulingService {
     /// # Returns
     /// `Ok(())` if valid, `Err(SchedulingError)` otherwise
     fn validate_cron_expression(&self, cron_expression: &str) -> SchedulingResult<()> {
-        // Try to parse the cron expression
-        Job::new_async(cron_expression, |_uuid, _l| Box::pin(async {}))
-            .map_err(|_| SchedulingError::InvalidCronExpression {
-                cron_expression: cron_expression.to_string(),
-            })?;
+        self.parse_cron_expression(cron_expression).map(|_| ())
+    }
 
-        Ok(())
+    /// Parses a five-field cron expression the same way scheduled tasks do
+    fn parse_cron_expression(&self, cron_expression: &str) -> SchedulingResult<CronSchedule> {
+        CronSchedule::parse(cron_expression).map_err(|_| SchedulingError::InvalidCronExpression {
+            cron_expression: cron_expression.to_string(),
+        })
     }
 
     /// Generates a cron expression for daily execution at a specific time
@@ -306,18 +309,43 @@ impl SchedulingService {
     /// # Returns
     /// `Ok(Some(DateTime<Utc>))` with next run time, `Ok(None)` if no future runs, `Err(SchedulingError)` if invalid
     pub async fn get_next_run_time(&self, cron_expression: &str) -> SchedulingResult<Option<DateTime<Utc>>> {
-        // Validate the cron expression first
-        self.validate_cron_expression(cron_expression)?;
+        let schedule = self.parse_cron_expression(cron_expression)?;
+        Ok(schedule.next_after(self.time_provider.now_utc(), &Utc))
+    }
 
-        // For now, we'll use a simple implementation
-        // In a production system, you might want to use a more sophisticated cron parser
-        let current_time = self.time_provider.now_utc();
+    /// Deactivates persisted daily reset tasks whose user configuration was deleted
+    /// or no longer has daily reset enabled
+    ///
+    /// # Arguments
+    /// * `database` - Database holding the scheduled tasks
+    ///
+    /// # Returns
+    /// `Ok(Vec<String>)` of deactivated task IDs
+    pub async fn reconcile_orphaned_tasks(database: &DatabaseManager) -> Result<Vec<String>> {
+        let task_ids = database.deactivate_orphaned_scheduled_tasks().await?;
+
+        if !task_ids.is_empty() {
+            info!("Deactivated {} orphaned scheduled task(s): {}", task_ids.len(), task_ids.join(", "));
+        }
 
-        // This is a simplified implementation - you'd typically use a cron library
-        // that can compute next execution times accurately
-        let next_run = current_time + chrono::Duration::hours(1); // Placeholder
+        Ok(task_ids)
+    }
 
-        Ok(Some(next_run))
+    /// Spawns a background loop that periodically reconciles orphaned tasks
+    ///
+    /// # Arguments
+    /// * `database` - Database holding the scheduled tasks
+    /// * `period` - Time between reconciliation runs
+    pub fn spawn_reconciliation_loop(database: Arc<DatabaseManager>, period: std::time::Duration) -> tokio::task::JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(period);
+            loop {
+                interval.tick().await;
+                if let Err(e) = Self::reconcile_orphaned_tasks(&database).await {
+                    error!("Scheduled task reconciliation failed: {}", e);
+                }
+            }
+        })
     }
 
     /// Checks if the scheduler is currently running
@@ -415,14 +443,11 @@ mod tests {
 
     #[tokio::test]
     async fn test_generate_cron_expressions() {
-        let time_provider = Arc::new(SystemTimeProvider);
-        let service = SchedulingService::new(time_provider);
+        assert_eq!(SchedulingService::generate_daily_cron_expression(0, 0), "0 0 * * *");
+        assert_eq!(SchedulingService::generate_daily_cron_expression(14, 30), "30 14 * * *");
 
-        assert_eq!(service.generate_daily_cron_expression(0, 0), "0 0 * * *");
-        assert_eq!(service.generate_daily_cron_expression(14, 30), "30 14 * * *");
-
-        assert_eq!(service.generate_hourly_cron_expression(0), "0 * * * *");
-        assert_eq!(service.generate_hourly_cron_expression(15), "15 * * * *");
+        assert_eq!(SchedulingService::generate_hourly_cron_expression(0), "0 * * * *");
+        assert_eq!(SchedulingService::generate_hourly_cron_expression(15), "15 * * * *");
     }
 
     #[tokio::test]
@@ -443,10 +468,39 @@ mod tests {
         let time_provider = Arc::new(SystemTimeProvider);
         let service = SchedulingService::new(time_provider);
 
-        let next_run = service.get_next_run_time("0 0 * * *").unwrap();
+        let next_run = service.get_next_run_time("0 0 * * *").await.unwrap();
         assert!(next_run.is_some());
 
         // Should fail for invalid cron expressions
-        assert!(service.get_next_run_time("invalid").is_err());
+        assert!(service.get_next_run_time("invalid").await.is_err());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_reconcile_orphaned_tasks() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test_scheduling.db");
+        let database = DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy()))
+            .await
+            .unwrap();
+        database.migrate().await.unwrap();
+
+        let pool = database.pool.sqlite().unwrap();
+        sqlx::query(
+            "INSERT INTO user_configurations (id, daily_reset_enabled, created_at, updated_at) VALUES ('live-config', TRUE, 0, 0)"
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let live_task = ScheduledTask::daily_reset_task("live-config".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        let orphaned_task = ScheduledTask::daily_reset_task("deleted-config".to_string(), "0 0 * * *".to_string(), "UTC".to_string());
+        database.save_scheduled_task(&live_task).await.unwrap();
+        database.save_scheduled_task(&orphaned_task).await.unwrap();
+
+        let cleaned = SchedulingService::reconcile_orphaned_tasks(&database).await.unwrap();
+        assert_eq!(cleaned, vec![orphaned_task.id.clone()]);
+
+        assert!(!database.get_scheduled_task(&orphaned_task.id).await.unwrap().unwrap().is_active);
+        assert!(database.get_scheduled_task(&live_task.id).await.unwrap().unwrap().is_active);
+    }
+}
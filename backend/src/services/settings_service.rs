@@ -0,0 +1,442 @@
+//! Settings Service
+//!
+//! Validates settings changes and throttles how often they are applied and broadcast.
+
+use std::collections::BTreeMap;
+use std::env;
+use std::time::Duration;
+
+use crate::models::timer_state::{SharedState, TimerState};
+use crate::services::time_provider::now_unix;
+use crate::websocket::manager::{SharedWsManager, WsMessage};
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsRequest {
+    pub work_duration: Option<u32>,
+    pub short_break_duration: Option<u32>,
+    pub long_break_duration: Option<u32>,
+    pub long_break_frequency: Option<u32>,
+    /// Display names keyed by session type id; replaces the current labels.
+    /// Session types left out are shown under their built-in names.
+    #[serde(default)]
+    pub session_type_labels: Option<BTreeMap<String, String>>,
+}
+
+/// Longest display name accepted for a session type, in characters
+const MAX_SESSION_TYPE_LABEL_CHARS: usize = 32;
+
+impl SettingsRequest {
+    /// Fold a newer request into this one; fields set in `newer` win
+    pub fn merge(&mut self, newer: SettingsRequest) {
+        self.work_duration = newer.work_duration.or(self.work_duration);
+        self.short_break_duration = newer.short_break_duration.or(self.short_break_duration);
+        self.long_break_duration = newer.long_break_duration.or(self.long_break_duration);
+        self.long_break_frequency = newer.long_break_frequency.or(self.long_break_frequency);
+        self.session_type_labels = newer.session_type_labels.or(self.session_type_labels.take());
+    }
+
+    /// Check durations and long break frequency are within the bounds
+    /// `UserConfiguration` enforces, and that the session type labels, if any,
+    /// name known session types and are non-empty and at most
+    /// `MAX_SESSION_TYPE_LABEL_CHARS` long
+    pub fn validate(&self) -> Result<(), String> {
+        use crate::models::user_configuration::UserConfiguration;
+
+        let checks = [
+            self.work_duration.map(UserConfiguration::validate_work_duration),
+            self.short_break_duration.map(UserConfiguration::validate_short_break_duration),
+            self.long_break_duration.map(UserConfiguration::validate_long_break_duration),
+            self.long_break_frequency.map(UserConfiguration::validate_long_break_frequency),
+        ];
+        for check in checks.into_iter().flatten() {
+            check.map_err(|e| e.to_string())?;
+        }
+
+        let Some(labels) = &self.session_type_labels else {
+            return Ok(());
+        };
+        for (session_type, label) in labels {
+            if !crate::models::user_configuration::SESSION_TYPES.contains(&session_type.as_str()) {
+                return Err(format!("Unknown session type '{session_type}'"));
+            }
+            let length = label.trim().chars().count();
+            if length == 0 || length > MAX_SESSION_TYPE_LABEL_CHARS {
+                return Err(format!(
+                    "Label for '{session_type}' must be 1 to {MAX_SESSION_TYPE_LABEL_CHARS} characters"
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Minimum interval between applied settings updates per user. Faster updates
+/// are coalesced. Defaults to 500ms; zero disables the limit.
+pub fn get_settings_update_interval() -> Duration {
+    let millis = env::var("ROMA_TIMER_SETTINGS_UPDATE_INTERVAL_MS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(500);
+    Duration::from_millis(millis)
+}
+
+/// Apply new session durations, restarting the current session at its new
+/// length if it isn't running, and the long break frequency
+fn apply_settings(timer_state: &mut TimerState, request: &SettingsRequest) {
+    if let Some(work_duration) = request.work_duration {
+        timer_state.work_duration = work_duration;
+        if timer_state.session_type == "work" && !timer_state.is_running {
+            timer_state.rewind();
+        }
+    }
+
+    if let Some(short_break_duration) = request.short_break_duration {
+        timer_state.short_break_duration = short_break_duration;
+        if timer_state.session_type == "short_break" && !timer_state.is_running {
+            timer_state.rewind();
+        }
+    }
+
+    if let Some(long_break_duration) = request.long_break_duration {
+        timer_state.long_break_duration = long_break_duration;
+        if timer_state.session_type == "long_break" && !timer_state.is_running {
+            timer_state.rewind();
+        }
+    }
+
+    if let Some(long_break_frequency) = request.long_break_frequency {
+        timer_state.long_break_frequency = long_break_frequency;
+    }
+
+    if let Some(labels) = &request.session_type_labels {
+        timer_state.session_type_labels = labels
+            .iter()
+            .map(|(session_type, label)| (session_type.clone(), label.trim().to_string()))
+            .collect();
+    }
+
+    timer_state.normalize();
+    timer_state.last_updated = now_unix();
+}
+
+/// Per-user bookkeeping for settings-update rate limiting
+#[derive(Debug, Default)]
+pub struct SettingsThrottle {
+    last_applied: Option<std::time::Instant>,
+    pending: Option<SettingsRequest>,
+}
+
+#[derive(Debug)]
+pub enum SettingsOutcome {
+    Applied(TimerState),
+    /// Not applied because the request failed validation
+    Rejected(String),
+    /// Coalesced with other updates and applied once `retry_after` has passed
+    Deferred { retry_after: Duration },
+}
+
+/// Apply a settings update, or coalesce it if the user applied one within the
+/// configured interval. Coalesced updates are merged (latest value wins) and
+/// applied together at the end of the window.
+pub async fn submit_settings_update(
+    state: &SharedState,
+    ws_manager: &SharedWsManager,
+    user_id: &str,
+    request: SettingsRequest,
+) -> SettingsOutcome {
+    if let Err(reason) = request.validate() {
+        return SettingsOutcome::Rejected(reason);
+    }
+
+    let interval = ws_manager.settings_update_interval;
+    let mut throttles = ws_manager.settings_throttles.lock().await;
+    let throttle = throttles.entry(user_id.to_string()).or_default();
+    let now = std::time::Instant::now();
+    let wait = throttle
+        .last_applied
+        .map(|last| interval.saturating_sub(now.duration_since(last)))
+        .unwrap_or(Duration::ZERO);
+
+    if let Some(pending) = throttle.pending.as_mut() {
+        pending.merge(request);
+        return SettingsOutcome::Deferred { retry_after: wait };
+    }
+
+    if wait.is_zero() {
+        throttle.last_applied = Some(now);
+        drop(throttles);
+        return SettingsOutcome::Applied(apply_and_broadcast_settings(state, ws_manager, user_id, request).await);
+    }
+
+    tracing::debug!("Deferring settings update for {user_id} by {}ms", wait.as_millis());
+    throttle.pending = Some(request);
+    drop(throttles);
+
+    let state = state.clone();
+    let ws_manager = ws_manager.clone();
+    let user_id = user_id.to_string();
+    tokio::spawn(async move {
+        tokio::time::sleep(wait).await;
+        flush_pending_settings(&state, &ws_manager, &user_id).await;
+    });
+
+    SettingsOutcome::Deferred { retry_after: wait }
+}
+
+/// Apply a user's coalesced settings update, if one is waiting
+async fn flush_pending_settings(state: &SharedState, ws_manager: &SharedWsManager, user_id: &str) {
+    let mut throttles = ws_manager.settings_throttles.lock().await;
+    let Some(throttle) = throttles.get_mut(user_id) else {
+        return;
+    };
+    let Some(request) = throttle.pending.take() else {
+        return;
+    };
+    throttle.last_applied = Some(std::time::Instant::now());
+    drop(throttles);
+
+    apply_and_broadcast_settings(state, ws_manager, user_id, request).await;
+}
+
+async fn apply_and_broadcast_settings(
+    state: &SharedState,
+    ws_manager: &SharedWsManager,
+    user_id: &str,
+    request: SettingsRequest,
+) -> TimerState {
+    let mut states = state.lock().await;
+    let timer_state = states.user(user_id);
+    apply_settings(timer_state, &request);
+    let updated_state = timer_state.clone();
+    drop(states);
+
+    // Broadcast settings change via WebSocket
+    ws_manager
+        .broadcast_message(user_id, WsMessage::SettingsUpdate(request))
+        .await;
+
+    // Durations, frequency and labels all live in the timer state, so persist
+    // and push it too
+    ws_manager.update_timer_state(user_id, updated_state.clone()).await;
+
+    updated_state
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use tokio::sync::mpsc;
+    use crate::api::json::ApiJson;
+    use crate::api::settings::{get_settings, update_settings};
+    use crate::websocket::manager::WebSocketManager;
+    use axum::extract::State;
+    use axum::extract::ws::Message;
+    use axum::http::StatusCode;
+    use axum::response::Json;
+
+    #[tokio::test]
+    async fn test_rapid_settings_updates_are_coalesced() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_settings_update_interval(Duration::from_millis(200)),
+        );
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        let mut statuses = Vec::new();
+        for minutes in 20..30 {
+            let response = update_settings(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(SettingsRequest {
+                    work_duration: Some(minutes * 60),
+                    short_break_duration: (minutes == 21).then_some(7 * 60),
+                    long_break_duration: None,
+                    long_break_frequency: None,
+                    session_type_labels: None,
+                }),
+            )
+            .await
+            .unwrap();
+            statuses.push(response.status());
+        }
+
+        assert_eq!(statuses[0], StatusCode::OK);
+        assert!(statuses[1..].iter().all(|status| *status == StatusCode::ACCEPTED));
+        assert_eq!(state.lock().await.user("alice").work_duration, 20 * 60);
+
+        tokio::time::sleep(Duration::from_millis(400)).await;
+
+        // The final values win, and earlier fields not overwritten survive the merge
+        let timer_state = state.lock().await.get("alice");
+        assert_eq!(timer_state.work_duration, 29 * 60);
+        assert_eq!(timer_state.short_break_duration, 7 * 60);
+
+        let mut settings_broadcasts = 0;
+        while let Ok(Message::Text(text)) = receiver.try_recv() {
+            if text.contains("\"SettingsUpdate\"") {
+                settings_broadcasts += 1;
+            }
+        }
+        assert_eq!(settings_broadcasts, 2);
+    }
+
+    #[tokio::test]
+    async fn test_session_type_labels_in_state_update() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        let settings = |labels: &[(&str, &str)]| SettingsRequest {
+            work_duration: None,
+            short_break_duration: None,
+            long_break_duration: None,
+            long_break_frequency: None,
+            session_type_labels: Some(
+                labels.iter().map(|(id, label)| (id.to_string(), label.to_string())).collect(),
+            ),
+        };
+
+        let response = update_settings(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(settings(&[("work", " Deep Focus "), ("short_break", "Stretch")])),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let update = std::iter::from_fn(|| receiver.try_recv().ok())
+            .find_map(|message| match message {
+                Message::Text(text) => match serde_json::from_str::<WsMessage>(&text) {
+                    Ok(WsMessage::TimerStateUpdate(update)) => Some(update),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .expect("no TimerStateUpdate broadcast");
+        assert_eq!(update.state.session_type, "work");
+        assert_eq!(update.session_type_label, "Deep Focus");
+        assert_eq!(update.state.session_type_labels["short_break"], "Stretch");
+
+        // Session types without a custom label keep their built-in name
+        let long_break = TimerState {
+            session_type: "long_break".to_string(),
+            ..update.state.clone()
+        };
+        assert_eq!(long_break.session_type_label(), "Long Break");
+
+        let Json(current) = get_settings(State((state.clone(), ws_manager.clone())), current_user("alice"))
+            .await
+            .unwrap();
+        assert_eq!(current["session_type_labels"]["work"], "Deep Focus");
+
+        // Unknown ids and blank or over-long labels are rejected without changing anything
+        for invalid in [
+            settings(&[("lunch", "Lunch")]),
+            settings(&[("work", "   ")]),
+            settings(&[("work", &"x".repeat(MAX_SESSION_TYPE_LABEL_CHARS + 1))]),
+        ] {
+            let response = update_settings(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(invalid),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+        assert_eq!(state.lock().await.user("alice").session_type_label(), "Deep Focus");
+    }
+
+    #[tokio::test]
+    async fn test_settings_request_bounds() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let request = |field: &str, value: u32| {
+            let mut request = SettingsRequest {
+                work_duration: None,
+                short_break_duration: None,
+                long_break_duration: None,
+                long_break_frequency: None,
+                session_type_labels: None,
+            };
+            match field {
+                "work" => request.work_duration = Some(value),
+                "short_break" => request.short_break_duration = Some(value),
+                "long_break" => request.long_break_duration = Some(value),
+                _ => request.long_break_frequency = Some(value),
+            }
+            request
+        };
+
+        // (field, lowest accepted, highest accepted)
+        for (field, min, max) in [
+            ("work", 5 * 60, 60 * 60),
+            ("short_break", 60, 15 * 60),
+            ("long_break", 5 * 60, 30 * 60),
+            ("frequency", 2, 10),
+        ] {
+            assert!(request(field, min - 1).validate().is_err(), "{field} {}", min - 1);
+            assert!(request(field, max + 1).validate().is_err(), "{field} {}", max + 1);
+
+            // Accepted values are saved, not just applied in memory
+            for value in [min, max] {
+                assert!(request(field, value).validate().is_ok(), "{field} {value}");
+                let response = update_settings(
+                    State((state.clone(), ws_manager.clone())),
+                    current_user("alice"),
+                    ApiJson(request(field, value)),
+                )
+                .await
+                .unwrap();
+                assert_eq!(response.status(), StatusCode::OK, "{field} {value}");
+
+                let persisted = ws_manager.database.get_timer_state("alice").await.unwrap().unwrap();
+                let saved = match field {
+                    "work" => persisted.work_duration,
+                    "short_break" => persisted.short_break_duration,
+                    "long_break" => persisted.long_break_duration,
+                    _ => persisted.long_break_frequency,
+                };
+                assert_eq!(saved, value, "{field} {value}");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_out_of_range_settings_are_rejected() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let response = update_settings(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(SettingsRequest {
+                work_duration: Some(25 * 60),
+                short_break_duration: Some(20 * 60),
+                long_break_duration: None,
+                long_break_frequency: None,
+                session_type_labels: None,
+            }),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body: serde_json::Value = response_json(response).await;
+        assert_eq!(body["error"], "invalid_settings");
+        assert_eq!(body["message"], "Short break duration 1200s is invalid (must be 1-15 minutes)");
+        assert_eq!(state.lock().await.get("alice").short_break_duration, 5 * 60);
+    }
+}
@@ -0,0 +1,593 @@
+//! Stats Service for Roma Timer
+//!
+//! Rolls archived daily session statistics up into weekly and monthly aggregates and scores
+//! how closely sessions were followed.
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Weekday};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::database::connection::SessionRecordRow;
+use crate::models::daily_session_stats::DailySessionStats;
+
+/// Weights applied when computing a focus score
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct FocusScoreWeights {
+    /// Score deducted per skipped work session
+    pub skip_penalty: f64,
+    /// Score deducted per abandoned work session
+    pub abandon_penalty: f64,
+}
+
+impl Default for FocusScoreWeights {
+    fn default() -> Self {
+        Self {
+            skip_penalty: 0.05,
+            abandon_penalty: 0.10,
+        }
+    }
+}
+
+/// Work-session adherence over some period
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SessionMetrics {
+    pub completed_sessions: i64,
+    /// Seconds worked in sessions that ran to completion
+    pub completed_work_seconds: i64,
+    /// Full configured duration of every work session started, however it ended
+    pub planned_work_seconds: i64,
+    pub skipped_sessions: i64,
+    pub abandoned_sessions: i64,
+}
+
+impl SessionMetrics {
+    pub fn merge(&mut self, other: &SessionMetrics) {
+        self.completed_sessions += other.completed_sessions;
+        self.completed_work_seconds += other.completed_work_seconds;
+        self.planned_work_seconds += other.planned_work_seconds;
+        self.skipped_sessions += other.skipped_sessions;
+        self.abandoned_sessions += other.abandoned_sessions;
+    }
+
+    /// Focus score in `[0, 1]`:
+    /// `completed_work_seconds / planned_work_seconds
+    ///   - skip_penalty * skipped_sessions - abandon_penalty * abandoned_sessions`.
+    /// None when no work session was planned.
+    pub fn focus_score(&self, weights: &FocusScoreWeights) -> Option<f64> {
+        if self.planned_work_seconds <= 0 {
+            return None;
+        }
+
+        let adherence = self.completed_work_seconds as f64 / self.planned_work_seconds as f64;
+        let score = adherence
+            - weights.skip_penalty * self.skipped_sessions as f64
+            - weights.abandon_penalty * self.abandoned_sessions as f64;
+        Some(score.clamp(0.0, 1.0))
+    }
+}
+
+/// Statistics for one day
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyStats {
+    pub date: NaiveDate,
+    /// Work sessions completed during the day
+    pub sessions: i64,
+    /// Seconds spent in completed work sessions
+    pub focus_seconds: i64,
+    pub focus_score: Option<f64>,
+}
+
+/// UTC timestamps bounding the local days `from` through `to` (end exclusive)
+pub fn local_day_bounds(tz: Tz, from: NaiveDate, to: NaiveDate) -> (i64, i64) {
+    let start_of = |date: NaiveDate| {
+        let midnight = date.and_hms_opt(0, 0, 0).unwrap();
+        tz.from_local_datetime(&midnight)
+            .earliest()
+            .map(|dt| dt.timestamp())
+            .unwrap_or_else(|| midnight.and_utc().timestamp())
+    };
+    (start_of(from), start_of(to + Duration::days(1)))
+}
+
+/// Bucket finished work sessions by local day in `tz`. Break sessions are ignored.
+pub fn session_metrics_by_day(sessions: &[SessionRecordRow], tz: Tz) -> BTreeMap<NaiveDate, SessionMetrics> {
+    let mut days: BTreeMap<NaiveDate, SessionMetrics> = BTreeMap::new();
+
+    for session in sessions.iter().filter(|session| session.timer_type == "work") {
+        let Some(ended_at) = session.completed_at.or(session.skipped_at).or(session.abandoned_at) else {
+            continue;
+        };
+        let Some(ended_at) = DateTime::from_timestamp(ended_at, 0) else {
+            continue;
+        };
+
+        let day = days.entry(ended_at.with_timezone(&tz).date_naive()).or_default();
+        day.planned_work_seconds += session.duration;
+        if session.completed_at.is_some() {
+            day.completed_sessions += 1;
+            day.completed_work_seconds += session.elapsed;
+        } else if session.skipped_at.is_some() {
+            day.skipped_sessions += 1;
+        } else {
+            day.abandoned_sessions += 1;
+        }
+    }
+
+    days
+}
+
+/// Per-day statistics with focus scores, ordered by date
+pub fn daily_stats(metrics: &BTreeMap<NaiveDate, SessionMetrics>, weights: &FocusScoreWeights) -> Vec<DailyStats> {
+    metrics
+        .iter()
+        .map(|(date, day)| DailyStats {
+            date: *date,
+            sessions: day.completed_sessions,
+            focus_seconds: day.completed_work_seconds,
+            focus_score: day.focus_score(weights),
+        })
+        .collect()
+}
+
+/// Day a week is considered to start on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WeekStart {
+    Mon,
+    Sun,
+}
+
+impl WeekStart {
+    /// Locale-appropriate default for a timezone: Sunday in the Americas, Monday elsewhere
+    pub fn default_for_timezone(timezone: &str) -> Self {
+        match timezone.parse::<Tz>() {
+            Ok(tz) if tz.name().starts_with("America/") || tz.name().starts_with("US/") => {
+                WeekStart::Sun
+            }
+            _ => WeekStart::Mon,
+        }
+    }
+
+    fn weekday(self) -> Weekday {
+        match self {
+            WeekStart::Mon => Weekday::Mon,
+            WeekStart::Sun => Weekday::Sun,
+        }
+    }
+
+    /// First day of the week containing `date`
+    pub fn week_containing(self, date: NaiveDate) -> NaiveDate {
+        let offset = (7 + date.weekday().num_days_from_monday()
+            - self.weekday().num_days_from_monday())
+            % 7;
+        date - Duration::days(offset as i64)
+    }
+}
+
+/// Aggregated statistics for one week
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct WeeklyStats {
+    /// First day of the week (YYYY-MM-DD)
+    pub week_start: NaiveDate,
+    /// Work sessions completed during the week
+    pub sessions: i64,
+    /// Seconds spent in work sessions during the week
+    pub focus_seconds: i64,
+    /// Focus score over all of the week's work sessions
+    pub focus_score: Option<f64>,
+}
+
+/// Bucket daily stats into weeks starting on `week_start`, ordered by week, scoring
+/// each week from the combined session metrics of its days.
+/// Rows with unparseable dates are skipped.
+pub fn weekly_rollup(
+    daily_stats: &[DailySessionStats],
+    day_metrics: &BTreeMap<NaiveDate, SessionMetrics>,
+    week_start: WeekStart,
+    weights: &FocusScoreWeights,
+) -> Vec<WeeklyStats> {
+    let mut weeks: BTreeMap<NaiveDate, WeeklyStats> = BTreeMap::new();
+
+    for stats in daily_stats {
+        let Ok(date) = NaiveDate::parse_from_str(&stats.date, "%Y-%m-%d") else {
+            continue;
+        };
+
+        let bucket = week_start.week_containing(date);
+        let week = weeks.entry(bucket).or_insert(WeeklyStats {
+            week_start: bucket,
+            sessions: 0,
+            focus_seconds: 0,
+            focus_score: None,
+        });
+        week.sessions += stats.work_sessions_completed;
+        week.focus_seconds += stats.total_work_seconds;
+    }
+
+    let mut week_metrics: BTreeMap<NaiveDate, SessionMetrics> = BTreeMap::new();
+    for (date, metrics) in day_metrics {
+        week_metrics
+            .entry(week_start.week_containing(*date))
+            .or_default()
+            .merge(metrics);
+    }
+    for (bucket, week) in weeks.iter_mut() {
+        week.focus_score = week_metrics.get(bucket).and_then(|metrics| metrics.focus_score(weights));
+    }
+
+    weeks.into_values().collect()
+}
+
+/// Runs of consecutive days with at least one completed work session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Streaks {
+    /// Days in the run ending today, or yesterday if nothing is done yet today
+    pub current: u32,
+    pub longest: u32,
+}
+
+/// Streaks over the archived days plus `today`, the day in progress, with
+/// `today_sessions` completed so far. A streak through yesterday stays current
+/// until today's reset: it is only broken once a whole day passes without a
+/// session. Rows with unparseable dates or dated after today are skipped.
+pub fn streaks(daily_stats: &[DailySessionStats], today: NaiveDate, today_sessions: i64) -> Streaks {
+    let mut days: BTreeSet<NaiveDate> = daily_stats
+        .iter()
+        .filter(|stats| stats.work_sessions_completed > 0)
+        .filter_map(|stats| NaiveDate::parse_from_str(&stats.date, "%Y-%m-%d").ok())
+        .filter(|date| *date <= today)
+        .collect();
+    if today_sessions > 0 {
+        days.insert(today);
+    }
+
+    let mut longest = 0;
+    let mut run = 0;
+    let mut previous: Option<NaiveDate> = None;
+    for day in &days {
+        run = match previous {
+            Some(previous) if *day - previous == Duration::days(1) => run + 1,
+            _ => 1,
+        };
+        longest = longest.max(run);
+        previous = Some(*day);
+    }
+
+    let mut current = 0;
+    let mut day = if days.contains(&today) { today } else { today - Duration::days(1) };
+    while days.contains(&day) {
+        current += 1;
+        day -= Duration::days(1);
+    }
+
+    Streaks { current, longest }
+}
+
+/// Today's progress toward the user's daily goal
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GoalProgress {
+    pub today_count: u32,
+    pub goal: Option<u32>,
+    /// Share of the goal done, 0-100; None without a goal
+    pub percent_complete: Option<f64>,
+}
+
+impl GoalProgress {
+    /// Progress of `today_count` sessions toward `goal`. A goal of zero counts as no goal.
+    pub fn new(today_count: u32, goal: Option<u32>) -> Self {
+        let goal = goal.filter(|goal| *goal > 0);
+        Self {
+            today_count,
+            goal,
+            percent_complete: goal.map(|goal| (today_count as f64 / goal as f64 * 100.0).min(100.0)),
+        }
+    }
+}
+
+/// Header row of the daily statistics CSV export
+pub const DAILY_STATS_CSV_HEADER: &str =
+    "date,timezone,work_sessions,total_work_seconds,break_seconds,manual_overrides,final_count\r\n";
+
+/// One day's statistics as a CSV row, CRLF-terminated
+pub fn daily_stats_csv_row(stats: &DailySessionStats) -> String {
+    format!(
+        "{},{},{},{},{},{},{}\r\n",
+        csv_field(&stats.date),
+        csv_field(&stats.timezone),
+        stats.work_sessions_completed,
+        stats.total_work_seconds,
+        stats.total_break_seconds,
+        stats.manual_overrides,
+        stats.final_session_count,
+    )
+}
+
+/// Quote a CSV field if it contains a delimiter, quote or line break
+fn csv_field(value: &str) -> std::borrow::Cow<'_, str> {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
+
+/// Period daily statistics are summed over
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl Granularity {
+    /// First day of the period containing `date`; weeks start on `week_start`
+    pub fn period_containing(self, date: NaiveDate, week_start: WeekStart) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => week_start.week_containing(date),
+            Granularity::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+}
+
+/// Work totals for one day, week or month
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AggregatedStats {
+    /// First day of the period (YYYY-MM-DD)
+    pub period_start: NaiveDate,
+    pub work_sessions: i64,
+    pub work_seconds: i64,
+}
+
+/// Sum daily stats into periods of `granularity`, ordered by period. Only
+/// periods with at least one row are returned; rows with unparseable dates are
+/// skipped.
+pub fn aggregate_daily_stats(
+    daily_stats: &[DailySessionStats],
+    granularity: Granularity,
+    week_start: WeekStart,
+) -> Vec<AggregatedStats> {
+    let mut periods: BTreeMap<NaiveDate, AggregatedStats> = BTreeMap::new();
+
+    for stats in daily_stats {
+        let Ok(date) = NaiveDate::parse_from_str(&stats.date, "%Y-%m-%d") else {
+            continue;
+        };
+
+        let bucket = granularity.period_containing(date, week_start);
+        let period = periods.entry(bucket).or_insert(AggregatedStats {
+            period_start: bucket,
+            work_sessions: 0,
+            work_seconds: 0,
+        });
+        period.work_sessions += stats.work_sessions_completed;
+        period.work_seconds += stats.total_work_seconds;
+    }
+
+    periods.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn daily(date: &str, sessions: i64, focus_seconds: i64) -> DailySessionStats {
+        let mut stats = DailySessionStats::new("user".to_string(), date.to_string(), "UTC".to_string());
+        stats.work_sessions_completed = sessions;
+        stats.total_work_seconds = focus_seconds;
+        stats
+    }
+
+    #[test]
+    fn test_weekly_rollup_respects_week_start() {
+        // 2024-03-09 is a Saturday, 2024-03-10 a Sunday, 2024-03-11 a Monday
+        let days = vec![
+            daily("2024-03-09", 1, 1500),
+            daily("2024-03-10", 2, 3000),
+            daily("2024-03-11", 4, 6000),
+        ];
+
+        let no_metrics = BTreeMap::new();
+        let weights = FocusScoreWeights::default();
+
+        let monday_weeks = weekly_rollup(&days, &no_metrics, WeekStart::Mon, &weights);
+        assert_eq!(
+            monday_weeks,
+            vec![
+                WeeklyStats {
+                    week_start: NaiveDate::from_ymd_opt(2024, 3, 4).unwrap(),
+                    sessions: 3,
+                    focus_seconds: 4500,
+                    focus_score: None,
+                },
+                WeeklyStats {
+                    week_start: NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+                    sessions: 4,
+                    focus_seconds: 6000,
+                    focus_score: None,
+                },
+            ]
+        );
+
+        let sunday_weeks = weekly_rollup(&days, &no_metrics, WeekStart::Sun, &weights);
+        assert_eq!(
+            sunday_weeks,
+            vec![
+                WeeklyStats {
+                    week_start: NaiveDate::from_ymd_opt(2024, 3, 3).unwrap(),
+                    sessions: 1,
+                    focus_seconds: 1500,
+                    focus_score: None,
+                },
+                WeeklyStats {
+                    week_start: NaiveDate::from_ymd_opt(2024, 3, 10).unwrap(),
+                    sessions: 6,
+                    focus_seconds: 9000,
+                    focus_score: None,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_default_week_start_for_timezone() {
+        assert_eq!(WeekStart::default_for_timezone("America/New_York"), WeekStart::Sun);
+        assert_eq!(WeekStart::default_for_timezone("Europe/Berlin"), WeekStart::Mon);
+        assert_eq!(WeekStart::default_for_timezone("UTC"), WeekStart::Mon);
+    }
+
+    fn session(timer_type: &str, duration: i64, elapsed: i64, outcome: &str, ended_at: i64) -> SessionRecordRow {
+        SessionRecordRow {
+            timer_type: timer_type.to_string(),
+            duration,
+            elapsed,
+            completed_at: (outcome == "completed").then_some(ended_at),
+            skipped_at: (outcome == "skipped").then_some(ended_at),
+            abandoned_at: (outcome == "abandoned").then_some(ended_at),
+        }
+    }
+
+    #[test]
+    fn test_focus_score_from_completed_skipped_and_abandoned_sessions() {
+        // 2024-03-11 09:00 UTC onwards
+        let base = 1_710_147_600;
+        let sessions = vec![
+            session("work", 1500, 1500, "completed", base),
+            session("short_break", 300, 300, "completed", base + 300),
+            session("work", 1500, 1500, "completed", base + 1800),
+            session("work", 1500, 600, "skipped", base + 2400),
+            session("work", 1500, 900, "abandoned", base + 3600),
+        ];
+        let weights = FocusScoreWeights {
+            skip_penalty: 0.05,
+            abandon_penalty: 0.1,
+        };
+
+        let metrics = session_metrics_by_day(&sessions, chrono_tz::UTC);
+        let day = metrics[&NaiveDate::from_ymd_opt(2024, 3, 11).unwrap()];
+        assert_eq!(day.completed_sessions, 2);
+        assert_eq!(day.completed_work_seconds, 3000);
+        assert_eq!(day.planned_work_seconds, 6000);
+        assert_eq!(day.skipped_sessions, 1);
+        assert_eq!(day.abandoned_sessions, 1);
+
+        // 3000 / 6000 - 0.05 * 1 - 0.1 * 1
+        let expected = 0.5 - 0.05 - 0.1;
+        let stats = daily_stats(&metrics, &weights);
+        assert_eq!(stats.len(), 1);
+        assert!((stats[0].focus_score.unwrap() - expected).abs() < 1e-9);
+
+        // Heavier penalties bottom out at zero
+        let harsh = FocusScoreWeights {
+            skip_penalty: 1.0,
+            abandon_penalty: 1.0,
+        };
+        assert_eq!(day.focus_score(&harsh), Some(0.0));
+
+        // The weekly rollup scores the week's combined sessions
+        let archived = vec![daily("2024-03-11", 2, 3000)];
+        let weeks = weekly_rollup(&archived, &metrics, WeekStart::Mon, &weights);
+        assert!((weeks[0].focus_score.unwrap() - expected).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_ten_days_aggregate_into_two_weeks() {
+        // Monday 2024-03-04 through Wednesday 2024-03-13
+        let start = NaiveDate::from_ymd_opt(2024, 3, 4).unwrap();
+        let days: Vec<_> = (0..10)
+            .map(|offset| {
+                let date = start + Duration::days(offset);
+                daily(&date.format("%Y-%m-%d").to_string(), 1, 1500)
+            })
+            .collect();
+
+        let weeks = aggregate_daily_stats(&days, Granularity::Week, WeekStart::Mon);
+        assert_eq!(
+            weeks,
+            vec![
+                AggregatedStats {
+                    period_start: start,
+                    work_sessions: 7,
+                    work_seconds: 7 * 1500,
+                },
+                AggregatedStats {
+                    period_start: NaiveDate::from_ymd_opt(2024, 3, 11).unwrap(),
+                    work_sessions: 3,
+                    work_seconds: 3 * 1500,
+                },
+            ]
+        );
+
+        // Sunday weeks split the same days differently
+        let sunday_weeks = aggregate_daily_stats(&days, Granularity::Week, WeekStart::Sun);
+        let sizes: Vec<_> = sunday_weeks.iter().map(|week| week.work_sessions).collect();
+        assert_eq!(sizes, vec![6, 4]);
+
+        assert_eq!(aggregate_daily_stats(&days, Granularity::Day, WeekStart::Mon).len(), 10);
+        let months = aggregate_daily_stats(&days, Granularity::Month, WeekStart::Mon);
+        assert_eq!(months.len(), 1);
+        assert_eq!(months[0].period_start, NaiveDate::from_ymd_opt(2024, 3, 1).unwrap());
+        assert_eq!(months[0].work_sessions, 10);
+    }
+
+    #[test]
+    fn test_streaks_survive_until_a_whole_day_is_missed() {
+        let date = |day: u32| NaiveDate::from_ymd_opt(2024, 5, day).unwrap();
+        // Worked the 1st-4th, skipped the 5th, then the 6th-8th (the 7th's row has no sessions)
+        let history = vec![
+            daily("2024-05-01", 2, 3000),
+            daily("2024-05-02", 1, 1500),
+            daily("2024-05-03", 4, 6000),
+            daily("2024-05-04", 1, 1500),
+            daily("2024-05-06", 3, 4500),
+            daily("2024-05-07", 0, 0),
+            daily("2024-05-08", 1, 1500),
+        ];
+
+        // The 7th broke the second run
+        assert_eq!(streaks(&history, date(8), 0), Streaks { current: 1, longest: 4 });
+
+        // Nothing done yet on the 9th: the run through the 8th is still alive
+        let ongoing = vec![
+            daily("2024-05-06", 3, 4500),
+            daily("2024-05-07", 2, 3000),
+            daily("2024-05-08", 1, 1500),
+        ];
+        assert_eq!(streaks(&ongoing, date(9), 0), Streaks { current: 3, longest: 3 });
+        // ...and a session today extends it
+        assert_eq!(streaks(&ongoing, date(9), 1), Streaks { current: 4, longest: 4 });
+        // Once the 9th passes without a session it is broken
+        assert_eq!(streaks(&ongoing, date(10), 0), Streaks { current: 0, longest: 3 });
+
+        assert_eq!(streaks(&[], date(1), 0), Streaks::default());
+    }
+
+    #[test]
+    fn test_goal_progress() {
+        let halfway = GoalProgress::new(3, Some(6));
+        assert_eq!(halfway.percent_complete, Some(50.0));
+
+        assert_eq!(GoalProgress::new(9, Some(6)).percent_complete, Some(100.0));
+
+        for goal in [None, Some(0)] {
+            let progress = GoalProgress::new(3, goal);
+            assert_eq!(progress.goal, None);
+            assert_eq!(progress.percent_complete, None);
+        }
+    }
+
+    #[test]
+    fn test_daily_stats_csv_row_quotes_fields() {
+        let mut stats = daily("2024-03-09", 3, 4500);
+        stats.total_break_seconds = 600;
+        stats.final_session_count = 3;
+        assert_eq!(daily_stats_csv_row(&stats), "2024-03-09,UTC,3,4500,600,0,3\r\n");
+
+        stats.timezone = "Odd,\"zone\"".to_string();
+        assert!(daily_stats_csv_row(&stats).starts_with("2024-03-09,\"Odd,\"\"zone\"\"\",3,"));
+    }
+}
@@ -6,10 +6,11 @@
 use chrono::{DateTime, Utc, TimeZone};
 use chrono_tz::Tz;
 use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 
 /// Trait for providing time functionality
 /// This enables dependency injection and testing with deterministic time
-pub trait TimeProvider: Send + Sync {
+pub trait TimeProvider: std::fmt::Debug + Send + Sync {
     /// Get the current UTC time
     fn now_utc(&self) -> DateTime<Utc>;
 
@@ -59,6 +60,32 @@ pub trait TimeProvider: Send + Sync {
     }
 }
 
+/// Current Unix time in seconds. A system clock set before 1970 is clamped
+/// to 0 with a warning instead of panicking.
+pub fn now_unix() -> u64 {
+    unix_seconds_at(SystemTime::now())
+}
+
+/// Current Unix time in milliseconds, clamped like `now_unix`
+pub fn now_unix_millis() -> u64 {
+    since_epoch(SystemTime::now()).as_millis() as u64
+}
+
+/// Unix seconds for `time`, or 0 if it is before the epoch
+pub fn unix_seconds_at(time: SystemTime) -> u64 {
+    since_epoch(time).as_secs()
+}
+
+fn since_epoch(time: SystemTime) -> Duration {
+    time.duration_since(UNIX_EPOCH).unwrap_or_else(|e| {
+        tracing::warn!(
+            "System clock is {}s before the Unix epoch, using 0",
+            e.duration().as_secs()
+        );
+        Duration::ZERO
+    })
+}
+
 /// System time provider for production use
 #[derive(Debug, Clone)]
 pub struct SystemTimeProvider;
@@ -175,6 +202,7 @@ impl TimeProvider for MockTimeProvider {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chrono::{Datelike, Timelike};
     use chrono_tz::America::New_York;
 
     #[test]
@@ -243,4 +271,30 @@ mod tests {
         assert_eq!(ny_time.second(), 0);
         assert_eq!(ny_time.timezone(), New_York);
     }
-}
\ No newline at end of file
+    #[test]
+    fn test_pre_epoch_clock_clamps_to_zero_with_warning() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use tracing_subscriber::layer::{Context, SubscriberExt};
+        use tracing_subscriber::{Layer, Registry};
+
+        struct CountWarnings(Arc<AtomicUsize>);
+
+        impl<S: tracing::Subscriber> Layer<S> for CountWarnings {
+            fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+                if *event.metadata().level() == tracing::Level::WARN {
+                    self.0.fetch_add(1, Ordering::SeqCst);
+                }
+            }
+        }
+
+        let warnings = Arc::new(AtomicUsize::new(0));
+        let subscriber = Registry::default().with(CountWarnings(warnings.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            let before_epoch = UNIX_EPOCH - Duration::from_secs(3600);
+            assert_eq!(unix_seconds_at(before_epoch), 0);
+            assert_eq!(unix_seconds_at(UNIX_EPOCH + Duration::from_secs(90)), 90);
+        });
+        assert_eq!(warnings.load(Ordering::SeqCst), 1);
+        assert!(now_unix() > 0);
+    }
+}
@@ -0,0 +1,1548 @@
+//! Timer Control Service
+//!
+//! Timer actions, the per-user ticker and session transitions.
+
+use std::env;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::api::timer_control::TimerRequest;
+use crate::config::TimerMode;
+use crate::database::DatabaseManager;
+use crate::models::timer_state::{PlanStep, SharedState, TimerState};
+use crate::models::user_configuration::UserConfiguration;
+use crate::services::time_provider::now_unix;
+use crate::services::webhook_service::{
+    get_webhook_retry_policy, notify_cycle_complete, notify_session_complete,
+};
+use crate::websocket::manager::{SharedWsManager, WsMessage};
+
+use axum::http::StatusCode;
+
+/// Check whether the current session may be skipped under the long break policy
+fn check_skip_allowed(timer_state: &TimerState, min_long_break_secs: Option<u32>) -> Result<(), String> {
+    if timer_state.session_type != "long_break" {
+        return Ok(());
+    }
+
+    if let Some(min_secs) = min_long_break_secs {
+        let elapsed = timer_state.elapsed_seconds();
+        if elapsed < min_secs {
+            return Err(format!(
+                "Long break can be skipped after {min_secs}s ({elapsed}s elapsed)"
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Optional cap on consecutive work sessions; once reached the next break is
+/// always a long break. Disabled when unset or zero.
+pub fn get_max_consecutive_work_sessions() -> Option<u32> {
+    env::var("ROMA_TIMER_MAX_CONSECUTIVE_WORK_SESSIONS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|cap| *cap > 0)
+}
+
+/// Least time between saving a running timer's ticks, from
+/// `ROMA_TIMER_STATE_PERSIST_INTERVAL_SECS`. Defaults to 5s; zero saves every tick.
+pub fn get_timer_persist_interval() -> Duration {
+    let secs = env::var("ROMA_TIMER_STATE_PERSIST_INTERVAL_SECS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(5);
+    Duration::from_secs(secs)
+}
+
+/// Why a timer action was refused
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum TimerError {
+    #[error("Unknown timer action '{0}'")]
+    UnknownAction(String),
+
+    #[error("{0}")]
+    SkipNotAllowed(String),
+}
+
+impl TimerError {
+    /// Code sent to WebSocket clients in an `Error` message
+    pub fn code(&self) -> &'static str {
+        match self {
+            TimerError::UnknownAction(_) => "unknown_action",
+            TimerError::SkipNotAllowed(_) => "skip_not_allowed",
+        }
+    }
+
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            TimerError::UnknownAction(_) => StatusCode::BAD_REQUEST,
+            TimerError::SkipNotAllowed(_) => StatusCode::CONFLICT,
+        }
+    }
+}
+
+/// Apply a start, pause, reset or skip action to `timer_state` under
+/// `user_config`'s skip and resume preferences. A skip moves on as
+/// `complete_session` does, with a long break forced after the server's
+/// `max_consecutive_work_sessions`. Starting a running timer changes nothing.
+fn apply_timer_action(
+    timer_state: &mut TimerState,
+    action: &str,
+    user_config: &UserConfiguration,
+    max_consecutive_work_sessions: Option<u32>,
+) -> Result<(), TimerError> {
+    let now = now_unix();
+    match action {
+        "start" => {
+            if !timer_state.is_running {
+                timer_state.start(now);
+            }
+        }
+        "pause" => {
+            timer_state.stop();
+            timer_state.last_updated = now;
+        }
+        "reset" => {
+            timer_state.stop();
+            timer_state.rewind();
+            timer_state.last_updated = now;
+        }
+        "skip" => {
+            check_skip_allowed(timer_state, user_config.min_long_break_before_skip)
+                .map_err(TimerError::SkipNotAllowed)?;
+            timer_state.stop();
+            let long_break_every = timer_state.long_break_every(max_consecutive_work_sessions);
+            transition_to_next_session(timer_state, long_break_every, user_config.resume_work_duration);
+            timer_state.last_updated = now;
+        }
+        other => return Err(TimerError::UnknownAction(other.to_string())),
+    }
+    Ok(())
+}
+
+/// Apply a timer action for `user_id`, from HTTP or WebSocket alike: update
+/// the state, record skipped sessions, and broadcast the change
+pub async fn control_user_timer(
+    state: &SharedState,
+    ws_manager: &SharedWsManager,
+    user_id: &str,
+    request: TimerRequest,
+) -> Result<TimerState, TimerError> {
+    if request.action == "start" {
+        let (started_state, _) = start_timer(state, ws_manager, user_id.to_string(), request.label).await;
+        return Ok(started_state);
+    }
+
+    let user_config = ws_manager.user_configuration(user_id).await;
+    let mut states = state.lock().await;
+    let timer_state = states.user(user_id);
+    if request.action == "reset" && timer_state.is_reset() {
+        // Nothing changes: nothing to persist or broadcast
+        return Ok(timer_state.clone());
+    }
+    let previous = timer_state.clone();
+    apply_timer_action(timer_state, &request.action, &user_config, ws_manager.max_consecutive_work_sessions)?;
+
+    if request.action == "skip" {
+        record_skipped_session(ws_manager.database.clone(), previous.clone(), user_id.to_string());
+    }
+    let completed_cycle = completed_cycle_length(&previous.session_type, timer_state);
+    let updated_state = timer_state.clone();
+    drop(states);
+
+    ws_manager.update_timer_state(user_id, updated_state.clone()).await;
+    if let Some(work_sessions) = completed_cycle {
+        notify_cycle_complete(ws_manager, user_id, work_sessions).await;
+    }
+    Ok(updated_state)
+}
+
+/// `timer_state` as of `now`. A running timer's remaining time is recomputed
+/// from its session end time, or from when it was last updated if it has none;
+/// a paused timer is returned unchanged. Completing a session that has run out
+/// is left to its ticker.
+pub fn timer_state_at(mut timer_state: TimerState, now: u64) -> TimerState {
+    if !timer_state.is_running {
+        return timer_state;
+    }
+    let ends_at = timer_state
+        .session_ends_at
+        .unwrap_or(timer_state.last_updated + timer_state.remaining_seconds as u64);
+    timer_state.remaining_seconds = ends_at.saturating_sub(now).min(u32::MAX as u64) as u32;
+    timer_state.last_updated = timer_state.last_updated.max(now);
+    timer_state
+}
+
+/// Start the timer on behalf of `user_id`. HTTP and WebSocket starts both go
+/// through here: the running check and transition happen under the state lock, so
+/// of any concurrent starts exactly one performs the transition, broadcasts it and
+/// spawns the ticker. Returns the resulting state and whether this call started it.
+pub async fn start_timer(
+    state: &SharedState,
+    ws_manager: &SharedWsManager,
+    user_id: String,
+    label: Option<String>,
+) -> (TimerState, bool) {
+    let mut states = state.lock().await;
+    let timer_state = states.user(&user_id);
+    if timer_state.is_running {
+        return (timer_state.clone(), false);
+    }
+    timer_state.start(now_unix());
+
+    if let Some(label) = label {
+        timer_state.label = normalize_label(&label);
+    }
+    let started_state = timer_state.clone();
+    drop(states);
+
+    // Broadcast the transition before the first tick so clients see it in order
+    ws_manager.update_timer_state(&user_id, started_state.clone()).await;
+    spawn_ticker(state.clone(), ws_manager.clone(), user_id).await;
+
+    (started_state, true)
+}
+
+/// Longest session label kept, in characters
+const MAX_LABEL_CHARS: usize = 64;
+
+/// Clean up a user-supplied session label: control characters are dropped,
+/// whitespace trimmed and the result truncated. Blank labels become `None`.
+fn normalize_label(raw: &str) -> Option<String> {
+    let cleaned: String = raw.chars().filter(|c| !c.is_control()).collect();
+    let label: String = cleaned.trim().chars().take(MAX_LABEL_CHARS).collect();
+    let label = label.trim_end().to_string();
+    (!label.is_empty()).then_some(label)
+}
+
+/// Spawn the server-side countdown for a timer started by `user_id`, aborting
+/// any ticker left over from an earlier start so only one task counts down each
+/// timer. In `ClientTick` mode the clients own the countdown, so no task is
+/// spawned. Returns whether a ticker was spawned.
+pub async fn spawn_ticker(state: SharedState, ws_manager: SharedWsManager, user_id: String) -> bool {
+    if let TimerMode::ClientTick = ws_manager.timer_mode {
+        return false;
+    }
+
+    let mut tickers = ws_manager.tickers.lock().await;
+    let ticker = tokio::spawn(tick_timer(state, ws_manager.clone(), user_id.clone()));
+    if let Some(previous) = tickers.insert(user_id, ticker) {
+        previous.abort();
+    }
+    true
+}
+
+/// Spawn the countdown for every running timer, e.g. for timers loaded at
+/// startup that were running when the server stopped. A session that ran out
+/// in the meantime completes on its first tick. Returns how many were resumed.
+pub async fn resume_running_timers(state: &SharedState, ws_manager: &SharedWsManager) -> usize {
+    let running: Vec<String> = state
+        .lock()
+        .await
+        .iter()
+        .filter(|(_, timer_state)| timer_state.is_running)
+        .map(|(user_id, _)| user_id.clone())
+        .collect();
+
+    let mut resumed = 0;
+    for user_id in running {
+        if spawn_ticker(state.clone(), ws_manager.clone(), user_id).await {
+            resumed += 1;
+        }
+    }
+    resumed
+}
+
+/// Record the session being skipped in the background so stats can score it
+fn record_skipped_session(database: Arc<DatabaseManager>, skipped_state: TimerState, user_id: String) {
+    tokio::spawn(async move {
+        let skipped_at = now_unix() as i64;
+        if let Err(e) = database
+            .record_skipped_session(&skipped_state, &user_id, "server", skipped_at)
+            .await
+        {
+            tracing::error!("Failed to record skipped session: {e}");
+        }
+    });
+}
+
+/// Count a completed work session through the daily reset service, which owns the
+/// daily session counter. Returns the new count, or None if it was not incremented
+/// (e.g. a manual override is active).
+pub async fn count_completed_work_session(ws_manager: &SharedWsManager, user_id: &str) -> Option<u32> {
+    let counted = ws_manager
+        .daily_reset_service
+        .increment_session_count_notifying(user_id, Some(ws_manager))
+        .await;
+    match counted {
+        Ok(count) => Some(count),
+        Err(e) => {
+            tracing::debug!("Daily session count for {user_id} not incremented: {e}");
+            None
+        }
+    }
+}
+
+pub async fn tick_timer(state: SharedState, ws_manager: SharedWsManager, user_id: String) {
+    let mut interval = tokio::time::interval(Duration::from_secs(1));
+    // Reloaded after each completion so a changed preference applies from the next session
+    let mut resume_work_duration = ws_manager.user_configuration(&user_id).await.resume_work_duration;
+
+    loop {
+        interval.tick().await;
+
+        let mut states = state.lock().await;
+        let timer_state = states.user(&user_id);
+
+        if timer_state.is_running {
+            // Read before a completion moves the timer on to the next session
+            let planned_duration = timer_state.session_duration();
+            let added_seconds = timer_state.added_seconds;
+            let now = now_unix();
+            let max_consecutive = ws_manager.max_consecutive_work_sessions;
+            let completed = advance_timer(timer_state, now, max_consecutive, resume_work_duration);
+            let updated_state = timer_state.clone();
+            drop(states);
+
+            // Plain ticks are saved at most once per persist interval
+            match completed {
+                Some((session_type, session_count)) => {
+                    let completed = CompletedSession {
+                        session_type,
+                        session_count,
+                        planned_duration,
+                        added_seconds,
+                        label: updated_state.label.clone(),
+                        completed_at: now,
+                    };
+                    finish_completed_session(&ws_manager, &user_id, completed, updated_state, "server").await;
+                    resume_work_duration = ws_manager.user_configuration(&user_id).await.resume_work_duration;
+                }
+                None => ws_manager.tick_timer_state(&user_id, updated_state).await,
+            }
+        } else {
+            tracing::debug!(target: "roma::tick", "Timer paused, stopping tick task");
+            break; // Exit the task if timer is paused
+        }
+    }
+}
+
+/// A session that just ran out, as it stood before the timer moved on
+#[derive(Debug, Clone)]
+pub struct CompletedSession {
+    pub session_type: String,
+    pub session_count: u32,
+    /// Planned length in seconds: the plan step's, or the configured one
+    pub planned_duration: u32,
+    /// Seconds added on top of `planned_duration`
+    pub added_seconds: u32,
+    pub label: Option<String>,
+    pub completed_at: u64,
+}
+
+/// Everything that follows a session running out, whether the server ticker or
+/// a client (`device_id`) counted it down. Call without holding the timer state
+/// lock: the session is recorded and counted toward the day in the background,
+/// completion webhooks are sent, then clients get `SessionCompleted` followed by
+/// `next_state`, and any cycle-complete or awaiting-start notices.
+pub async fn finish_completed_session(
+    ws_manager: &SharedWsManager,
+    user_id: &str,
+    completed: CompletedSession,
+    next_state: TimerState,
+    device_id: &'static str,
+) {
+    let completed_cycle = completed_cycle_length(&completed.session_type, &next_state);
+
+    let recorder = ws_manager.clone();
+    let record_user_id = user_id.to_string();
+    let record = completed.clone();
+    tokio::spawn(async move {
+        if let Err(e) = recorder
+            .database
+            .record_completed_session(
+                &record_user_id,
+                &record.session_type,
+                record.planned_duration,
+                record.added_seconds,
+                device_id,
+                record.completed_at as i64,
+                record.label.as_deref(),
+            )
+            .await
+        {
+            tracing::error!("Failed to record completed session: {e}");
+        }
+        if record.session_type == "work" {
+            count_completed_work_session(&recorder, &record_user_id).await;
+        }
+    });
+
+    let notifier = ws_manager.clone();
+    let notify_user_id = user_id.to_string();
+    let notify_session_type = completed.session_type.clone();
+    tokio::spawn(async move {
+        let target = notifier.completion_notify_target(&notify_user_id).await;
+        if target.urls.is_empty() {
+            return;
+        }
+        notify_session_complete(
+            &notifier,
+            &notify_user_id,
+            &target,
+            &notify_session_type,
+            completed.session_count,
+            get_webhook_retry_policy(),
+        )
+        .await;
+    });
+
+    // Announce the completion, then broadcast the state change
+    ws_manager
+        .broadcast_message(user_id, WsMessage::SessionCompleted {
+            completed_type: completed.session_type,
+            session_count: completed.session_count,
+            next_type: next_state.session_type.clone(),
+        })
+        .await;
+    ws_manager.update_timer_state(user_id, next_state.clone()).await;
+    if let Some(work_sessions) = completed_cycle {
+        notify_cycle_complete(ws_manager, user_id, work_sessions).await;
+    }
+    // The next session is loaded paused either way; users who confirm
+    // each session are told it's waiting for them
+    if ws_manager.waits_for_interaction(user_id).await {
+        ws_manager
+            .broadcast_message(user_id, WsMessage::AwaitingStart {
+                session_type: next_state.session_type,
+                session_count: next_state.session_count,
+            })
+            .await;
+    }
+}
+
+/// Bring a running timer's remaining time up to `now` from its session end time,
+/// so delayed or missed ticks don't make it drift from the wall clock. When the
+/// session reaches zero the timer stops and switches to the next session type
+/// (see `complete_session`); the completed session's type and count are
+/// returned so the caller can send notifications.
+pub fn advance_timer(
+    timer_state: &mut TimerState,
+    now: u64,
+    max_consecutive_work_sessions: Option<u32>,
+    resume_work_duration: bool,
+) -> Option<(String, u32)> {
+    let ends_at = *timer_state
+        .session_ends_at
+        .get_or_insert(now + timer_state.remaining_seconds as u64);
+    timer_state.remaining_seconds = ends_at.saturating_sub(now).min(u32::MAX as u64) as u32;
+    timer_state.last_updated = now;
+
+    tracing::trace!(
+        target: "roma::tick",
+        remaining_seconds = timer_state.remaining_seconds,
+        session_type = %timer_state.session_type,
+        "Tick"
+    );
+
+    if timer_state.remaining_seconds > 0 {
+        return None;
+    }
+
+    Some(complete_session(timer_state, max_consecutive_work_sessions, resume_work_duration))
+}
+
+/// Switch to the session following the current one and load its duration. A work
+/// session is followed by a short break unless `max_consecutive_work` work sessions
+/// have now run without a long break, in which case a long break is forced. The
+/// length of a finished work session is remembered; with `resume_work_duration`
+/// the next work session after the break gets that length again.
+pub fn transition_to_next_session(
+    timer_state: &mut TimerState,
+    max_consecutive_work: Option<u32>,
+    resume_work_duration: bool,
+) {
+    if timer_state.session_type == "work" {
+        timer_state.pre_break_work_duration = Some(timer_state.session_duration());
+    }
+    timer_state.added_seconds = 0;
+
+    if let Some(plan) = timer_state.plan.as_mut() {
+        plan.current += 1;
+        match plan.current_step().cloned() {
+            Some(step) => {
+                advance_to_planned_session(timer_state, step);
+                return;
+            }
+            None => {
+                tracing::info!("Session plan finished, resuming the default cycle");
+                timer_state.plan = None;
+            }
+        }
+    }
+
+    if timer_state.session_type == "work" {
+        timer_state.work_sessions_since_long_break += 1;
+        let cap_reached = max_consecutive_work
+            .is_some_and(|cap| timer_state.work_sessions_since_long_break >= cap);
+        if cap_reached {
+            tracing::info!(
+                "{} consecutive work sessions, forcing a long break",
+                timer_state.work_sessions_since_long_break
+            );
+            timer_state.session_type = "long_break".to_string();
+        } else {
+            timer_state.session_type = "short_break".to_string();
+        }
+    } else {
+        if timer_state.session_type == "long_break" {
+            timer_state.work_sessions_since_long_break = 0;
+        }
+        timer_state.session_type = "work".to_string();
+        timer_state.session_count += 1;
+    }
+
+    let remembered = timer_state.pre_break_work_duration.filter(|_| resume_work_duration);
+    timer_state.remaining_seconds = match remembered {
+        Some(duration) if timer_state.session_type == "work" => {
+            // Time beyond the configured duration counts as added, so normalize keeps it
+            timer_state.added_seconds = duration.saturating_sub(timer_state.session_duration());
+            duration
+        }
+        _ => timer_state.session_duration(),
+    };
+    if timer_state.session_type == "work" {
+        timer_state.pre_break_work_duration = None;
+    }
+    timer_state.normalize();
+}
+
+/// Switch to the next step of a session plan, keeping the work session and
+/// long break counters as the default cycle would
+fn advance_to_planned_session(timer_state: &mut TimerState, step: PlanStep) {
+    match timer_state.session_type.as_str() {
+        "work" => timer_state.work_sessions_since_long_break += 1,
+        "long_break" => timer_state.work_sessions_since_long_break = 0,
+        _ => {}
+    }
+    if step.session_type == "work" && timer_state.session_type != "work" {
+        timer_state.session_count += 1;
+    }
+
+    timer_state.session_type = step.session_type;
+    timer_state.remaining_seconds = step.duration;
+}
+
+/// Number of work sessions in the cycle just completed, if the timer has moved
+/// from a `previous_session_type` work session into a long break
+pub fn completed_cycle_length(previous_session_type: &str, timer_state: &TimerState) -> Option<u32> {
+    (previous_session_type == "work" && timer_state.session_type == "long_break")
+        .then_some(timer_state.work_sessions_since_long_break)
+}
+
+/// Stop a session that reached zero and switch to the next session type, with a
+/// long break forced after `max_consecutive_work_sessions` and, with
+/// `resume_work_duration`, the pre-break work duration restored. Returns the
+/// completed session's type and count.
+pub fn complete_session(
+    timer_state: &mut TimerState,
+    max_consecutive_work_sessions: Option<u32>,
+    resume_work_duration: bool,
+) -> (String, u32) {
+    timer_state.stop();
+
+    // Store the old session type for notifications
+    let completed_session_type = timer_state.session_type.clone();
+    let completed_session_count = timer_state.session_count;
+
+    let long_break_every = timer_state.long_break_every(max_consecutive_work_sessions);
+    transition_to_next_session(timer_state, long_break_every, resume_work_duration);
+
+    tracing::debug!(
+        target: "roma::tick",
+        completed = %completed_session_type,
+        next = %timer_state.session_type,
+        "Session complete"
+    );
+
+    (completed_session_type, completed_session_count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use tokio::sync::mpsc;
+    use crate::api::daily_reset::daily_reset_status_message;
+    use crate::api::json::ApiJson;
+    use crate::api::sessions::{CompletedSessionsQuery, list_completed_sessions};
+    use crate::api::stats::{DailyStatsQuery, goal_stats, label_stats};
+    use crate::api::timer_control::{SessionPlanRequest, clear_session_plan, control_timer, get_timer, set_session_plan};
+    use crate::models::timer_state::SessionPlan;
+    use crate::websocket::manager::WebSocketManager;
+    use axum::extract::State;
+    use axum::extract::ws::Message;
+    use axum::response::Json;
+
+    #[test]
+    fn test_long_break_skip_respects_minimum_elapsed() {
+        let mut state = TimerState {
+            is_running: true,
+            session_type: "long_break".to_string(),
+            remaining_seconds: 15 * 60 - 60, // 60s elapsed
+            ..test_timer_state()
+        };
+
+        assert!(check_skip_allowed(&state, Some(300)).is_err());
+
+        state.remaining_seconds = 15 * 60 - 300; // 300s elapsed
+        assert!(check_skip_allowed(&state, Some(300)).is_ok());
+
+        // Policy disabled, or not a long break
+        state.remaining_seconds = 15 * 60;
+        assert!(check_skip_allowed(&state, None).is_ok());
+        state.session_type = "short_break".to_string();
+        assert!(check_skip_allowed(&state, Some(300)).is_ok());
+    }
+
+    #[test]
+    fn test_long_break_skip_counts_plan_step_and_added_time() {
+        // A 30 minute planned long break, 60s in
+        let mut state = TimerState {
+            is_running: true,
+            session_type: "long_break".to_string(),
+            plan: Some(SessionPlan {
+                steps: vec![PlanStep { session_type: "long_break".to_string(), duration: 30 * 60 }],
+                current: 0,
+            }),
+            remaining_seconds: 30 * 60 - 60,
+            ..test_timer_state()
+        };
+        assert!(check_skip_allowed(&state, Some(300)).is_err());
+        state.remaining_seconds = 30 * 60 - 300;
+        assert!(check_skip_allowed(&state, Some(300)).is_ok());
+
+        // Five minutes added to a configured 15 minute long break, 60s in
+        state.plan = None;
+        state.added_seconds = 300;
+        state.remaining_seconds = 20 * 60 - 60;
+        assert!(check_skip_allowed(&state, Some(300)).is_err());
+        state.remaining_seconds = 20 * 60 - 300;
+        assert!(check_skip_allowed(&state, Some(300)).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_goal_reached_broadcast_once_when_crossed() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, today_session_count, daily_goal, created_at, updated_at)
+            VALUES ('alice', 1, 2, 0, 0), ('bob', 1, NULL, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let (alice_sender, mut alice_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("alice-conn".to_string(), "alice", None, alice_sender).await;
+        let (bob_sender, mut bob_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("bob-conn".to_string(), "bob", None, bob_sender).await;
+        while alice_receiver.try_recv().is_ok() {}
+        while bob_receiver.try_recv().is_ok() {}
+
+        fn goal_messages(receiver: &mut mpsc::UnboundedReceiver<Message>) -> Vec<(u32, u32)> {
+            let mut reached = Vec::new();
+            while let Ok(Message::Text(text)) = receiver.try_recv() {
+                if let Ok(WsMessage::GoalReached { goal, session_count }) = serde_json::from_str(&text) {
+                    reached.push((goal, session_count));
+                }
+            }
+            reached
+        }
+
+        let progress = |user: &'static str| {
+            goal_stats(State((state.clone(), ws_manager.clone())), current_user(user))
+        };
+        let Json(halfway) = progress("alice").await.unwrap();
+        assert_eq!((halfway.today_count, halfway.goal, halfway.percent_complete), (1, Some(2), Some(50.0)));
+
+        // The second session crosses the goal; the third goes past it quietly
+        assert_eq!(count_completed_work_session(&ws_manager, "alice").await, Some(2));
+        assert_eq!(goal_messages(&mut alice_receiver), vec![(2, 2)]);
+        assert_eq!(count_completed_work_session(&ws_manager, "alice").await, Some(3));
+        assert!(goal_messages(&mut alice_receiver).is_empty());
+        let Json(done) = progress("alice").await.unwrap();
+        assert_eq!(done.percent_complete, Some(100.0));
+
+        // Without a goal there is nothing to reach
+        assert_eq!(count_completed_work_session(&ws_manager, "bob").await, Some(2));
+        assert!(goal_messages(&mut bob_receiver).is_empty());
+        let Json(unset) = progress("bob").await.unwrap();
+        assert_eq!((unset.today_count, unset.goal, unset.percent_complete), (2, None, None));
+    }
+
+    #[tokio::test]
+    async fn test_work_completion_increments_daily_session_count() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, daily_reset_enabled, today_session_count, created_at, updated_at)
+            VALUES ('alice', 'UTC', TRUE, 3, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        let today_count = || async {
+            let (count,): (i64,) =
+                sqlx::query_as("SELECT today_session_count FROM user_configurations WHERE id = 'alice'")
+                    .fetch_one(pool)
+                    .await
+                    .unwrap();
+            count
+        };
+        let finish_work_session = || async {
+            {
+                let mut states = state.lock().await;
+                let timer_state = states.user("alice");
+                timer_state.is_running = true;
+                timer_state.session_type = "work".to_string();
+                timer_state.remaining_seconds = 1;
+            }
+            tick_timer(state.clone(), ws_manager.clone(), "alice".to_string()).await;
+        };
+
+        finish_work_session().await;
+        for _ in 0..50 {
+            if today_count().await == 4 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert_eq!(today_count().await, 4);
+
+        let WsMessage::DailyResetStatus(status) =
+            daily_reset_status_message(ws_manager.database.clone(), "alice").await
+        else {
+            panic!("expected a daily reset status");
+        };
+        assert_eq!(status.current_session_count, 4);
+
+        // A manual override suppresses automated counting
+        sqlx::query("UPDATE user_configurations SET manual_session_override = 7 WHERE id = 'alice'")
+            .execute(pool)
+            .await
+            .unwrap();
+        finish_work_session().await;
+        assert_eq!(count_completed_work_session(&ws_manager, "alice").await, None);
+        assert_eq!(today_count().await, 4);
+
+        let WsMessage::DailyResetStatus(status) =
+            daily_reset_status_message(ws_manager.database.clone(), "alice").await
+        else {
+            panic!("expected a daily reset status");
+        };
+        assert_eq!(status.current_session_count, 7);
+    }
+
+    #[tokio::test]
+    async fn test_ticks_saved_at_most_once_per_persist_interval() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, base_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), base_manager.database.clone())
+                .with_timer_persist_interval(Duration::from_secs(60)),
+        );
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("conn-1".to_string(), "alice", None, sender).await;
+
+        let now = now_unix();
+        let mut timer_state = TimerState {
+            remaining_seconds: 600,
+            last_updated: now,
+            session_ends_at: Some(now + 600),
+            ..test_timer_state()
+        };
+
+        // A burst of five ticks is broadcast every time but saved once
+        let mut saved_values = Vec::new();
+        for _ in 0..5 {
+            timer_state.remaining_seconds -= 1;
+            ws_manager.tick_timer_state("alice", timer_state.clone()).await;
+            let stored = ws_manager.database.get_timer_state("alice").await.unwrap().unwrap();
+            if saved_values.last() != Some(&stored.remaining_seconds) {
+                saved_values.push(stored.remaining_seconds);
+            }
+        }
+        assert_eq!(saved_values, vec![599]);
+        assert_eq!(state.lock().await.get("alice").remaining_seconds, 595);
+
+        let mut updates = 0;
+        while let Ok(message) = receiver.try_recv() {
+            if let Message::Text(text) = message {
+                if let Ok(WsMessage::TimerStateUpdate(_)) = serde_json::from_str(&text) {
+                    updates += 1;
+                }
+            }
+        }
+        assert_eq!(updates, 5);
+
+        // Pausing is saved straight away
+        let request = TimerRequest { action: "pause".to_string(), label: None };
+        let paused = control_user_timer(&state, &ws_manager, "alice", request).await.unwrap();
+        let stored = ws_manager.database.get_timer_state("alice").await.unwrap().unwrap();
+        assert!(!stored.is_running);
+        assert_eq!(stored.remaining_seconds, paused.remaining_seconds);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_http_and_websocket_start_spawn_one_ticker() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let initial_remaining = state.lock().await.user("alice").remaining_seconds;
+
+        let (http_result, (ws_state, _)) = tokio::join!(
+            control_timer(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(TimerRequest { action: "start".to_string(), label: None }),
+            ),
+            start_timer(&state, &ws_manager, "alice".to_string(), None),
+        );
+        let Json(http_state) = http_result.unwrap();
+        assert!(http_state.is_running);
+        assert!(ws_state.is_running);
+
+        // A later start is a no-op
+        let (_, started_again) = start_timer(&state, &ws_manager, "alice".to_string(), None).await;
+        assert!(!started_again);
+
+        // One ticker ticks immediately and again after a second; two would double that
+        tokio::time::sleep(Duration::from_millis(1500)).await;
+        let timer_state = state.lock().await.get("alice");
+        assert!(timer_state.is_running);
+        let elapsed = initial_remaining - timer_state.remaining_seconds;
+        assert!((1..=2).contains(&elapsed), "elapsed {elapsed}s implies more than one ticker");
+
+        state.lock().await.user("alice").is_running = false;
+    }
+
+    #[test]
+    fn test_long_break_forced_after_consecutive_work_cap() {
+        let mut state = TimerState {
+            session_type: "work".to_string(),
+            ..test_timer_state()
+        };
+        let mut sequence = Vec::new();
+        for _ in 0..6 {
+            transition_to_next_session(&mut state, Some(2), false);
+            sequence.push(state.session_type.clone());
+        }
+        assert_eq!(
+            sequence,
+            ["short_break", "work", "long_break", "work", "short_break", "work"]
+        );
+        assert_eq!(state.work_sessions_since_long_break, 1);
+
+        // The long break gets its full duration
+        let mut state = TimerState {
+            session_type: "work".to_string(),
+            work_sessions_since_long_break: 1,
+            ..test_timer_state()
+        };
+        transition_to_next_session(&mut state, Some(2), false);
+        assert_eq!(state.session_type, "long_break");
+        assert_eq!(state.remaining_seconds, state.long_break_duration);
+
+        // Disabled by default: never forced
+        let mut state = TimerState {
+            session_type: "work".to_string(),
+            work_sessions_since_long_break: 50,
+            ..test_timer_state()
+        };
+        transition_to_next_session(&mut state, None, false);
+        assert_eq!(state.session_type, "short_break");
+    }
+
+    #[test]
+    fn test_sessions_until_long_break_counts_down_through_cycle() {
+        let mut state = TimerState {
+            session_type: "work".to_string(),
+            work_sessions_since_long_break: 0,
+            ..test_timer_state()
+        };
+
+        let mut hints = Vec::new();
+        for _ in 0..10 {
+            hints.push((state.session_type.clone(), state.sessions_until_long_break(3)));
+            transition_to_next_session(&mut state, Some(3), false);
+        }
+
+        let expected = [
+            ("work", 3),
+            ("short_break", 2),
+            ("work", 2),
+            ("short_break", 1),
+            ("work", 1),
+            ("long_break", 3),
+            ("work", 3),
+            ("short_break", 2),
+            ("work", 2),
+            ("short_break", 1),
+        ];
+        let expected: Vec<(String, u32)> = expected
+            .iter()
+            .map(|(session_type, hint)| (session_type.to_string(), *hint))
+            .collect();
+        assert_eq!(hints, expected);
+    }
+
+    #[test]
+    fn test_normalize_label() {
+        assert_eq!(normalize_label("  project-x\n"), Some("project-x".to_string()));
+        assert_eq!(normalize_label(" \t "), None);
+        assert_eq!(normalize_label(&"x".repeat(100)).unwrap().chars().count(), MAX_LABEL_CHARS);
+    }
+
+    #[tokio::test]
+    async fn test_labeled_session_is_recorded_and_grouped() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        state.lock().await.user("alice").remaining_seconds = 1;
+
+        let Json(started) = control_timer(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(TimerRequest {
+                action: "start".to_string(),
+                label: Some(" project-x ".to_string()),
+            }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(started.label.as_deref(), Some("project-x"));
+
+        let now = chrono::Utc::now().timestamp();
+        let mut sessions = Vec::new();
+        for _ in 0..60 {
+            sessions = ws_manager
+                .database
+                .get_completed_sessions("alice", now - 60, now + 60, 10, 0)
+                .await
+                .unwrap();
+            if !sessions.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].label.as_deref(), Some("project-x"));
+
+        ws_manager
+            .database
+            .record_completed_session("alice", "work", 600, 0, "laptop", now, None)
+            .await
+            .unwrap();
+
+        let Json(rows) = label_stats(
+            State((state, ws_manager)),
+            current_user("alice"),
+            axum::extract::Query(DailyStatsQuery { from: None, to: None }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].label.as_deref(), Some("project-x"));
+        assert_eq!(rows[0].sessions, 1);
+        assert_eq!(rows[0].focus_seconds, 25 * 60);
+        assert_eq!(rows[1].label, None);
+        assert_eq!(rows[1].focus_seconds, 600);
+    }
+
+    #[tokio::test]
+    async fn test_session_plan_followed_then_default_cycle() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let step = |session_type: &str, minutes: u32| PlanStep {
+            session_type: session_type.to_string(),
+            duration: minutes * 60,
+        };
+        let plan = vec![step("work", 50), step("short_break", 10), step("work", 50), step("long_break", 20)];
+
+        let response = set_session_plan(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(SessionPlanRequest { steps: plan }),
+        )
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut followed = Vec::new();
+        for _ in 0..6 {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            followed.push((timer_state.session_type.clone(), timer_state.session_duration()));
+            assert_eq!(timer_state.remaining_seconds, timer_state.session_duration());
+            complete_session(timer_state, None, false);
+        }
+        assert_eq!(
+            followed,
+            [
+                ("work".to_string(), 50 * 60),
+                ("short_break".to_string(), 10 * 60),
+                ("work".to_string(), 50 * 60),
+                ("long_break".to_string(), 20 * 60),
+                // Plan exhausted: back to the configured durations
+                ("work".to_string(), 25 * 60),
+                ("short_break".to_string(), 5 * 60),
+            ]
+        );
+        assert!(state.lock().await.user("alice").plan.is_none());
+
+        // Invalid steps are rejected
+        for steps in [vec![], vec![step("lunch", 30)], vec![step("work", 0)]] {
+            let response = set_session_plan(
+                State((state.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(SessionPlanRequest { steps }),
+            )
+            .await
+            .unwrap();
+            assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        }
+
+        // Clearing a plan restores the default duration
+        set_session_plan(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(SessionPlanRequest { steps: vec![step("work", 50)] }),
+        )
+        .await
+        .unwrap();
+        let Json(cleared) = clear_session_plan(State((state.clone(), ws_manager.clone())), current_user("alice"))
+            .await
+            .unwrap();
+        assert!(cleared.plan.is_none());
+        assert_eq!(cleared.remaining_seconds, 25 * 60);
+    }
+
+    #[test]
+    fn test_resume_work_duration_after_break() {
+        let planned_work = |state: &mut TimerState| {
+            state.plan = Some(SessionPlan {
+                steps: vec![PlanStep { session_type: "work".to_string(), duration: 40 * 60 }],
+                current: 0,
+            });
+            state.remaining_seconds = state.session_duration();
+        };
+
+        // With the flag, the 40 minute work session comes back after the break
+        let mut state = test_timer_state();
+        planned_work(&mut state);
+        transition_to_next_session(&mut state, None, true);
+        assert_eq!(state.session_type, "short_break");
+        assert_eq!(state.remaining_seconds, 5 * 60);
+        assert_eq!(state.pre_break_work_duration, Some(40 * 60));
+        transition_to_next_session(&mut state, None, true);
+        assert_eq!(state.session_type, "work");
+        assert_eq!(state.remaining_seconds, 40 * 60);
+        assert_eq!(state.extended_duration(), 40 * 60);
+        assert_eq!(state.pre_break_work_duration, None);
+
+        // Without it, work after the break uses the configured duration
+        let mut state = test_timer_state();
+        planned_work(&mut state);
+        transition_to_next_session(&mut state, None, false);
+        transition_to_next_session(&mut state, None, false);
+        assert_eq!(state.session_type, "work");
+        assert_eq!(state.remaining_seconds, 25 * 60);
+    }
+
+    #[tokio::test]
+    async fn test_resume_work_duration_follows_user_configuration() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, resume_work_duration, created_at, updated_at)
+            VALUES ('alice', TRUE, 0, 0), ('bob', FALSE, 0, 0)
+            "#,
+        )
+        .execute(ws_manager.database.pool.sqlite().unwrap())
+        .await
+        .unwrap();
+
+        for (user_id, resumed_duration) in [("alice", 40 * 60), ("bob", 25 * 60)] {
+            {
+                let mut states = state.lock().await;
+                let timer_state = states.user(user_id);
+                timer_state.plan = Some(SessionPlan {
+                    steps: vec![PlanStep { session_type: "work".to_string(), duration: 40 * 60 }],
+                    current: 0,
+                });
+                timer_state.remaining_seconds = timer_state.session_duration();
+            }
+
+            // Skip the 40 minute work session, then its break
+            let mut skipped_to = Vec::new();
+            for _ in 0..2 {
+                let Json(skipped) = control_timer(
+                    State((state.clone(), ws_manager.clone())),
+                    current_user(user_id),
+                    ApiJson(TimerRequest {
+                        action: "skip".to_string(),
+                        label: None,
+                    }),
+                )
+                .await
+                .unwrap();
+                skipped_to.push((skipped.session_type, skipped.remaining_seconds));
+            }
+            assert_eq!(skipped_to, [("short_break".to_string(), 5 * 60), ("work".to_string(), resumed_duration)]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_timer_state_is_per_user() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (alice_sender, mut alice_receiver) = mpsc::unbounded_channel();
+        let (bob_sender, mut bob_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("alice-phone".to_string(), "alice", None, alice_sender).await;
+        ws_manager.add_connection("bob-phone".to_string(), "bob", None, bob_sender).await;
+        while alice_receiver.try_recv().is_ok() {}
+        while bob_receiver.try_recv().is_ok() {}
+        assert_eq!(ws_manager.devices("bob").await.len(), 1);
+
+        let Json(started) = control_timer(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(TimerRequest { action: "start".to_string(), label: None }),
+        )
+        .await
+        .unwrap();
+        assert!(started.is_running);
+
+        let Json(bob_timer) = get_timer(State((state.clone(), ws_manager.clone())), current_user("bob"))
+            .await
+            .unwrap();
+        assert!(!bob_timer.is_running);
+        assert_eq!(bob_timer.remaining_seconds, 25 * 60);
+
+        // Only alice's devices hear about her timer
+        assert!(matches!(alice_receiver.try_recv(), Ok(Message::Text(_))));
+        assert!(bob_receiver.try_recv().is_err());
+
+        // Each user's timer is saved under their own id
+        assert!(ws_manager.database.get_timer_state("alice").await.unwrap().unwrap().is_running);
+        assert!(ws_manager.database.get_timer_state("bob").await.unwrap().is_none());
+
+        state.lock().await.user("alice").is_running = false;
+    }
+
+    #[test]
+    fn test_advance_timer_follows_wall_clock_not_ticks() {
+        use crate::services::time_provider::{MockTimeProvider, TimeProvider};
+
+        let clock = MockTimeProvider::new_from_ymd_hms(2024, 3, 1, 9, 0, 0).unwrap();
+        let now = || clock.now_timestamp() as u64;
+        let mut state = TimerState {
+            is_running: false,
+            remaining_seconds: 25 * 60,
+            ..test_timer_state()
+        };
+        state.start(now());
+        assert_eq!(state.session_ends_at, Some(now() + 25 * 60));
+
+        // One tick after 90 seconds (the task was delayed) catches up fully
+        clock.advance_seconds(90);
+        assert_eq!(advance_timer(&mut state, now(), None, false), None);
+        assert_eq!(state.remaining_seconds, 25 * 60 - 90);
+
+        // Several ticks within the same second don't count extra time
+        for _ in 0..5 {
+            advance_timer(&mut state, now(), None, false);
+        }
+        assert_eq!(state.remaining_seconds, 25 * 60 - 90);
+
+        // Pausing keeps the remaining time; resuming sets a new deadline
+        state.stop();
+        clock.advance_seconds(600);
+        state.start(now());
+        clock.advance_seconds(10);
+        advance_timer(&mut state, now(), None, false);
+        assert_eq!(state.remaining_seconds, 25 * 60 - 100);
+
+        // Asleep past the end: the next tick completes the session
+        clock.advance_seconds(3 * 60 * 60);
+        assert_eq!(advance_timer(&mut state, now(), None, false), Some(("work".to_string(), 1)));
+        assert!(!state.is_running);
+        assert_eq!(state.session_ends_at, None);
+    }
+
+    #[tokio::test]
+    async fn test_long_break_every_frequency_sessions_on_skip_and_completion() {
+        let expected = [
+            "short_break", "work", "short_break", "work", "short_break", "work", "long_break", "work", "short_break",
+        ];
+
+        // Automatic completion, as in tick_timer
+        let mut state = TimerState {
+            session_type: "work".to_string(),
+            long_break_frequency: 4,
+            ..test_timer_state()
+        };
+        let mut completed = Vec::new();
+        for _ in 0..expected.len() {
+            complete_session(&mut state, None, false);
+            completed.push(state.session_type.clone());
+            if state.session_type == "long_break" {
+                assert_eq!(state.remaining_seconds, state.long_break_duration);
+                assert_eq!(state.work_sessions_since_long_break, 4);
+            }
+        }
+        assert_eq!(completed, expected);
+        assert_eq!(state.work_sessions_since_long_break, 1);
+
+        // Skipping over HTTP follows the same cycle
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (shared, ws_manager) = test_app_state(&temp_dir).await;
+        shared.lock().await.user("alice").long_break_frequency = 4;
+        let mut skipped = Vec::new();
+        for _ in 0..expected.len() {
+            let Json(state) = control_timer(
+                State((shared.clone(), ws_manager.clone())),
+                current_user("alice"),
+                ApiJson(TimerRequest {
+                    action: "skip".to_string(),
+                    label: None,
+                }),
+            )
+            .await
+            .unwrap();
+            skipped.push(state.session_type);
+        }
+        assert_eq!(skipped, expected);
+        assert_eq!(shared.lock().await.get("alice").work_sessions_since_long_break, 1);
+    }
+
+    #[test]
+    fn test_apply_timer_action_handles_each_action() {
+        let mut user_config = UserConfiguration::new();
+        let mut timer_state = test_timer_state();
+        timer_state.is_running = false;
+        let before = now_unix();
+
+        assert_eq!(apply_timer_action(&mut timer_state, "start", &user_config, None), Ok(()));
+        assert!(timer_state.is_running);
+        let ends_at = timer_state.session_ends_at.unwrap();
+        assert!((before + 10..=now_unix() + 10).contains(&ends_at));
+        // Starting a running timer leaves its session alone
+        assert_eq!(apply_timer_action(&mut timer_state, "start", &user_config, None), Ok(()));
+        assert_eq!(timer_state.session_ends_at, Some(ends_at));
+
+        assert_eq!(apply_timer_action(&mut timer_state, "pause", &user_config, None), Ok(()));
+        assert!(!timer_state.is_running);
+        assert_eq!(timer_state.session_ends_at, None);
+        assert!(timer_state.last_updated >= before);
+
+        assert_eq!(apply_timer_action(&mut timer_state, "reset", &user_config, None), Ok(()));
+        assert_eq!(timer_state.remaining_seconds, timer_state.work_duration);
+        assert!(timer_state.is_reset());
+
+        assert_eq!(apply_timer_action(&mut timer_state, "skip", &user_config, None), Ok(()));
+        assert_eq!(timer_state.session_type, "short_break");
+        assert_eq!(timer_state.remaining_seconds, timer_state.short_break_duration);
+
+        // The user's minimum long break holds back a skip until it has passed
+        timer_state.session_type = "long_break".to_string();
+        timer_state.remaining_seconds = timer_state.long_break_duration - 60;
+        user_config.min_long_break_before_skip = Some(300);
+        let error = apply_timer_action(&mut timer_state, "skip", &user_config, None).unwrap_err();
+        assert_eq!(error.code(), "skip_not_allowed");
+        assert_eq!(timer_state.session_type, "long_break");
+        timer_state.remaining_seconds = timer_state.long_break_duration - 300;
+        assert_eq!(apply_timer_action(&mut timer_state, "skip", &user_config, None), Ok(()));
+        assert_eq!(timer_state.session_type, "work");
+    }
+
+    #[test]
+    fn test_apply_timer_action_rejects_unknown_action() {
+        let mut timer_state = test_timer_state();
+
+        let error = apply_timer_action(&mut timer_state, "rewind", &UserConfiguration::new(), None).unwrap_err();
+        assert_eq!(error, TimerError::UnknownAction("rewind".to_string()));
+        assert_eq!(error.code(), "unknown_action");
+        assert_eq!(error.status_code(), StatusCode::BAD_REQUEST);
+        assert!(timer_state.is_running);
+        assert_eq!(timer_state.last_updated, 0);
+    }
+
+    #[tokio::test]
+    async fn test_control_user_timer_rejects_unknown_action() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("alice-phone".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        let result = control_user_timer(
+            &state,
+            &ws_manager,
+            "alice",
+            TimerRequest {
+                action: "rewind".to_string(),
+                label: None,
+            },
+        )
+        .await;
+
+        assert_eq!(result.unwrap_err().code(), "unknown_action");
+        assert!(receiver.try_recv().is_err(), "rejected action must not broadcast");
+    }
+
+    #[tokio::test]
+    async fn test_restarting_timer_keeps_a_single_ticker() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("alice-phone".to_string(), "alice", None, sender).await;
+
+        let control = |action: &str| TimerRequest {
+            action: action.to_string(),
+            label: None,
+        };
+        control_user_timer(&state, &ws_manager, "alice", control("start")).await.unwrap();
+        control_user_timer(&state, &ws_manager, "alice", control("pause")).await.unwrap();
+        let (started, _) = start_timer(&state, &ws_manager, "alice".to_string(), None).await;
+        while receiver.try_recv().is_ok() {}
+
+        tokio::time::sleep(Duration::from_millis(2500)).await;
+
+        // One ticker updates immediately and then once a second; a leftover
+        // ticker from the first start would double that
+        let updates = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter(|message| match message {
+                Message::Text(text) => matches!(
+                    serde_json::from_str::<WsMessage>(text),
+                    Ok(WsMessage::TimerStateUpdate(_))
+                ),
+                _ => false,
+            })
+            .count();
+        assert!(updates <= 3, "expected one ticker, got {updates} updates in 2.5s");
+        assert_eq!(ws_manager.tickers.lock().await.len(), 1);
+
+        let remaining = state.lock().await.get("alice").remaining_seconds;
+        assert!(started.remaining_seconds - remaining <= 3);
+    }
+
+    #[tokio::test]
+    async fn test_session_history_records_completions_and_skips_per_user() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        // A work session that runs to completion on the server ticker
+        state.lock().await.user("alice").remaining_seconds = 1;
+        start_timer(&state, &ws_manager, "alice".to_string(), None).await;
+        let history = |user_id: &'static str| {
+            let database = ws_manager.database.clone();
+            async move {
+                let now = chrono::Utc::now().timestamp();
+                database.get_session_history(user_id, now - 3600, now + 60, 10, 0).await.unwrap()
+            }
+        };
+        let mut sessions = Vec::new();
+        for _ in 0..60 {
+            sessions = history("alice").await;
+            if !sessions.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_type, "work");
+        assert_eq!(sessions[0].planned_duration, 25 * 60);
+        assert_eq!(sessions[0].actual_duration, 25 * 60);
+        assert_eq!(sessions[0].completed_at - sessions[0].started_at, 25 * 60);
+        assert!(!sessions[0].skipped);
+
+        // Skipping the following break is recorded as skipped
+        let skip = TimerRequest {
+            action: "skip".to_string(),
+            label: None,
+        };
+        control_user_timer(&state, &ws_manager, "alice", skip).await.unwrap();
+        for _ in 0..60 {
+            sessions = history("alice").await;
+            if sessions.len() > 1 {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sessions.len(), 2);
+        let skipped = sessions.iter().find(|session| session.skipped).unwrap();
+        assert_eq!(skipped.session_type, "short_break");
+        assert_eq!(skipped.planned_duration, 5 * 60);
+        assert_eq!(skipped.actual_duration, 0);
+
+        // The endpoint only lists the authenticated user's sessions
+        let query = || {
+            axum::extract::Query(CompletedSessionsQuery {
+                start: None,
+                end: None,
+                limit: None,
+                offset: None,
+            })
+        };
+        let Json(alice) = list_completed_sessions(State((state.clone(), ws_manager.clone())), current_user("alice"), query())
+            .await
+            .unwrap();
+        assert_eq!(alice.sessions.len(), 2);
+        let Json(bob) = list_completed_sessions(State((state, ws_manager)), current_user("bob"), query())
+            .await
+            .unwrap();
+        assert!(bob.sessions.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_completed_plan_step_recorded_with_its_length() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        // A 50 minute planned work session, two minutes added, about to run out
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.plan = Some(SessionPlan {
+                steps: vec![PlanStep { session_type: "work".to_string(), duration: 50 * 60 }],
+                current: 0,
+            });
+            timer_state.added_seconds = 120;
+            timer_state.is_running = true;
+            timer_state.remaining_seconds = 1;
+        }
+        tick_timer(state.clone(), ws_manager.clone(), "alice".to_string()).await;
+
+        let now = chrono::Utc::now().timestamp();
+        let mut sessions = Vec::new();
+        for _ in 0..60 {
+            sessions = ws_manager.database.get_session_history("alice", now - 3600, now + 60, 10, 0).await.unwrap();
+            if !sessions.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].planned_duration, 50 * 60);
+        assert_eq!(sessions[0].actual_duration, 50 * 60 + 120);
+    }
+
+    #[tokio::test]
+    async fn test_skipped_plan_step_recorded_with_time_spent() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        // Ten minutes into a 50 minute planned work session with two minutes added
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.plan = Some(SessionPlan {
+                steps: vec![PlanStep { session_type: "work".to_string(), duration: 50 * 60 }],
+                current: 0,
+            });
+            timer_state.added_seconds = 120;
+            timer_state.remaining_seconds = 42 * 60;
+        }
+        let skip = TimerRequest {
+            action: "skip".to_string(),
+            label: None,
+        };
+        control_user_timer(&state, &ws_manager, "alice", skip).await.unwrap();
+
+        let now = chrono::Utc::now().timestamp();
+        let mut sessions = Vec::new();
+        for _ in 0..60 {
+            sessions = ws_manager.database.get_session_history("alice", now - 3600, now + 60, 10, 0).await.unwrap();
+            if !sessions.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sessions.len(), 1);
+        assert!(sessions[0].skipped);
+        assert_eq!(sessions[0].planned_duration, 50 * 60);
+        assert_eq!(sessions[0].actual_duration, 10 * 60);
+    }
+
+    #[tokio::test]
+    async fn test_session_completed_sent_once_before_next_state() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("alice-phone".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.is_running = true;
+            timer_state.remaining_seconds = 1;
+        }
+        tick_timer(state.clone(), ws_manager.clone(), "alice".to_string()).await;
+
+        let messages: Vec<WsMessage> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                Message::Text(text) => serde_json::from_str(&text).ok(),
+                _ => None,
+            })
+            .collect();
+        let completions: Vec<usize> = messages
+            .iter()
+            .enumerate()
+            .filter(|(_, message)| matches!(message, WsMessage::SessionCompleted { .. }))
+            .map(|(index, _)| index)
+            .collect();
+        assert_eq!(completions.len(), 1);
+        match &messages[completions[0]] {
+            WsMessage::SessionCompleted { completed_type, session_count, next_type } => {
+                assert_eq!(completed_type, "work");
+                assert_eq!(*session_count, 1);
+                assert_eq!(next_type, "short_break");
+            }
+            _ => unreachable!(),
+        }
+
+        // The state update showing the break comes after the completion
+        let break_update = messages.iter().position(|message| {
+            matches!(message, WsMessage::TimerStateUpdate(broadcast) if broadcast.state.session_type == "short_break")
+        });
+        assert!(break_update.is_some_and(|index| index > completions[0]));
+    }
+
+    #[test]
+    fn test_tick_events_use_tick_target_and_filter_independently() {
+        let targets = capture_with_filter("trace", || {
+            advance_timer(&mut test_timer_state(), 0, None, false);
+        });
+        assert!(!targets.is_empty());
+        assert!(targets.iter().all(|t| t == "roma::tick"));
+
+        let targets = capture_with_filter("trace,roma::tick=off", || {
+            advance_timer(&mut test_timer_state(), 0, None, false);
+            tracing::info!(target: "roma::http", "request");
+        });
+        assert_eq!(targets, vec!["roma::http".to_string()]);
+    }
+}
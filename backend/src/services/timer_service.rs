@@ -8,7 +8,8 @@ use crate::models::user_configuration::UserConfiguration;
 use crate::services::configuration_service::ConfigurationService;
 use crate::services::daily_reset_service::DailyResetService;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
+use crate::services::time_provider::{now_unix, now_unix_millis};
 use tokio::sync::{Mutex, RwLock};
 
 /// Timer service for managing pomodoro sessions
@@ -26,7 +27,7 @@ pub struct TimerService {
     /// Work sessions completed in current cycle
     work_sessions_completed: Arc<Mutex<u32>>,
 
-    /// Last update timestamp for accurate time tracking
+    /// Last update timestamp in milliseconds, for accurate time tracking
     last_update: Arc<Mutex<u64>>,
 }
 
@@ -58,10 +59,7 @@ impl TimerService {
         // Set duration based on configuration
         session.duration = TimerType::Work.get_duration_from_config(&config);
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix_millis();
 
         Ok(Self {
             session: Arc::new(RwLock::new(session)),
@@ -72,26 +70,20 @@ impl TimerService {
         })
     }
 
-    /// Create a new timer service with configuration service (blocking version for tests)
+    /// Create a timer service for tests, backed by an in-memory database
     #[cfg(test)]
-    pub fn new_with_config(config: UserConfiguration) -> Self {
+    pub async fn new_with_config(config: UserConfiguration) -> Self {
         let mut session = TimerSession::new_work_session();
         session.duration = TimerType::Work.get_duration_from_config(&config);
 
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix_millis();
 
-        // Create a mock configuration service for testing
-        let pool = sqlx::SqlitePool::connect(":memory:").unwrap();
-        let websocket_service = crate::services::websocket_service::WebSocketService::new(pool.clone());
+        let database = Arc::new(
+            crate::database::DatabaseManager::new("sqlite::memory:").await.unwrap(),
+        );
+        database.migrate().await.unwrap();
         let configuration_service = Arc::new(
-            tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    ConfigurationService::new(pool, websocket_service).await.unwrap()
-                })
-            })
+            ConfigurationService::without_broadcast(database).await.unwrap(),
         );
 
         Self {
@@ -149,10 +141,7 @@ impl TimerService {
         session.start()?;
 
         // Update last update timestamp
-        *self.last_update.lock().await = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        *self.last_update.lock().await = now_unix_millis();
 
         Ok(())
     }
@@ -184,10 +173,7 @@ impl TimerService {
         session.duration = session.timer_type.default_duration();
 
         *self.work_sessions_completed.lock().await = 0;
-        *self.last_update.lock().await = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        *self.last_update.lock().await = now_unix_millis();
 
         Ok(())
     }
@@ -209,10 +195,7 @@ impl TimerService {
 
         session.skip_to_next_with_config(*work_sessions, &config);
 
-        *self.last_update.lock().await = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        *self.last_update.lock().await = now_unix_millis();
 
         Ok(())
     }
@@ -236,10 +219,7 @@ impl TimerService {
         session.elapsed = 0;
         session.is_running = false;
 
-        *self.last_update.lock().await = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        *self.last_update.lock().await = now_unix_millis();
 
         Ok(())
     }
@@ -307,10 +287,7 @@ impl TimerService {
 
         session.skip_to_next_with_config(*work_sessions, &config);
 
-        *self.last_update.lock().await = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        *self.last_update.lock().await = now_unix_millis();
 
         Ok(())
     }
@@ -343,17 +320,15 @@ impl TimerService {
     /// Update elapsed time based on current time
     async fn update_elapsed_time(&self, session: &mut TimerSession) -> bool {
         if session.is_running {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            let now = now_unix_millis();
 
             let last_update = *self.last_update.lock().await;
-            let elapsed_since_update = (now - last_update) as u32;
+            let elapsed_since_update = (now.saturating_sub(last_update) / 1000) as u32;
 
             if elapsed_since_update > 0 {
                 let completed = session.add_elapsed(elapsed_since_update);
-                *self.last_update.lock().await = now;
+                // Carry the partial second over to the next update
+                *self.last_update.lock().await = last_update + u64::from(elapsed_since_update) * 1000;
                 return completed;
             }
         }
@@ -407,10 +382,7 @@ impl TimerService {
         };
 
         // Update last update timestamp
-        let now = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
+        let now = now_unix();
         timer_state.updated_at = now;
 
         timer_state
@@ -459,10 +431,7 @@ impl TimerService {
                 }
 
                 // Update timestamp for conflict resolution
-                *self.last_update.lock().await = SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs();
+                *self.last_update.lock().await = now_unix_millis();
 
                 Ok(())
             }
@@ -539,7 +508,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_timer_service_creation() {
-        let service = TimerService::new();
+        let service = TimerService::new_with_config(UserConfiguration::new()).await;
 
         assert!(!service.is_running().await);
         assert_eq!(service.get_session_type().await, TimerType::Work);
@@ -547,7 +516,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_timer_start_stop() {
-        let service = TimerService::new();
+        let service = TimerService::new_with_config(UserConfiguration::new()).await;
 
         // Start timer
         assert!(service.start_timer().await.is_ok());
@@ -560,7 +529,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_timer_reset() {
-        let service = TimerService::new();
+        let service = TimerService::new_with_config(UserConfiguration::new()).await;
 
         // Start timer and let it run
         service.start_timer().await.unwrap();
@@ -574,7 +543,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_timer_skip() {
-        let service = TimerService::new();
+        let service = TimerService::new_with_config(UserConfiguration::new()).await;
 
         let initial_type = service.get_session_type().await;
         assert_eq!(initial_type, TimerType::Work);
@@ -586,7 +555,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_timer_state() {
-        let service = TimerService::new();
+        let service = TimerService::new_with_config(UserConfiguration::new()).await;
         let state = service.get_timer_state().await;
 
         assert_eq!(state.timer_type, "Work");
@@ -599,7 +568,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_elapsed_time_tracking() {
-        let service = TimerService::new();
+        let service = TimerService::new_with_config(UserConfiguration::new()).await;
 
         service.set_duration(2).await; // 2 seconds for testing
         service.start_timer().await.unwrap();
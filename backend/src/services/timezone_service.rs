@@ -212,7 +212,8 @@ impl TimezoneService {
 
         // Basic timezone format validation
         // Should match patterns like "UTC", "America/New_York", "Europe/London", etc.
-        let timezone_regex = Regex::new(r"^[A-Za-z_]+(/[A-Za-z_]+)*$")
+        // IANA names have at most three parts, e.g. "America/Argentina/Buenos_Aires"
+        let timezone_regex = Regex::new(r"^[A-Za-z_]+(/[A-Za-z_]+){0,2}$")
             .map_err(|_| TimezoneError::ValidationFailed {
                 reason: "Invalid regex pattern".to_string(),
             })?;
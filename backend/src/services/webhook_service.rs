@@ -0,0 +1,928 @@
+//! Webhook Service
+//!
+//! Delivers session and cycle completion notifications to user-configured webhooks.
+
+use std::env;
+use std::sync::atomic::Ordering;
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::Semaphore;
+
+use crate::models::user_configuration::{WebhookEvent, WebhookFormat};
+use crate::services::time_provider::now_unix;
+use crate::websocket::manager::{WebSocketManager, WsMessage};
+
+use rand::Rng;
+use reqwest::Client;
+
+/// Where a user's completion notifications are sent, and how their bodies are shaped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct NotifyTarget {
+    pub urls: Vec<String>,
+    pub format: WebhookFormat,
+    /// Template for the Custom format
+    pub template: Option<String>,
+}
+
+/// Default maximum number of webhook requests in flight server-wide
+const DEFAULT_WEBHOOK_CONCURRENCY: usize = 8;
+
+/// Semaphore bounding concurrent outbound webhook requests across the server,
+/// sized by `ROMA_TIMER_WEBHOOK_CONCURRENCY`
+fn webhook_limiter() -> &'static Semaphore {
+    static LIMITER: OnceLock<Semaphore> = OnceLock::new();
+    LIMITER.get_or_init(|| {
+        let permits = env::var("ROMA_TIMER_WEBHOOK_CONCURRENCY")
+            .ok()
+            .and_then(|value| value.parse().ok())
+            .filter(|permits: &usize| *permits > 0)
+            .unwrap_or(DEFAULT_WEBHOOK_CONCURRENCY);
+        Semaphore::new(permits)
+    })
+}
+
+/// Run an outbound request once a permit is available, so bursts queue up
+/// instead of all firing at once
+async fn with_webhook_permit<F, T>(limiter: &Semaphore, request: F) -> T
+where
+    F: std::future::Future<Output = T>,
+{
+    let _permit = limiter
+        .acquire()
+        .await
+        .expect("webhook semaphore is never closed");
+    request.await
+}
+
+pub async fn send_webhook_notification(
+    webhook_url: &str,
+    webhook_format: &WebhookFormat,
+    template: Option<&str>,
+    session_type: &str,
+    session_count: u32,
+    policy: WebhookRetryPolicy,
+) -> Result<WebhookDelivery, WebhookError> {
+    let message = match session_type {
+        "work" => format!("Work session #{session_count} complete! Time for a break."),
+        "short_break" => "Short break over! Ready to focus?".to_string(),
+        "long_break" => "Long break complete! Ready to be productive?".to_string(),
+        WEBHOOK_TEST_SESSION_TYPE => "Test notification from Roma Timer: your webhook works!".to_string(),
+        _ => "Timer session complete!".to_string(),
+    };
+
+    let event = WebhookEvent {
+        session_type: session_type.to_string(),
+        session_count,
+        message,
+        timestamp: now_unix(),
+    };
+    // Templates are checked when saved, so this only falls back for rows edited by hand
+    let payload = webhook_format.render(template, &event).unwrap_or_else(|e| {
+        tracing::warn!("Sending raw webhook body instead of {}: {e}", webhook_format.as_str());
+        WebhookFormat::Raw.render(None, &event).unwrap_or_default()
+    });
+
+    post_webhook(webhook_url, &payload, policy).await
+}
+
+/// Notify `webhook_url` that a long break began after `work_sessions` work
+/// sessions, in `target`'s format
+async fn send_cycle_complete_webhook(
+    webhook_url: &str,
+    target: &NotifyTarget,
+    work_sessions: u32,
+) -> Result<WebhookDelivery, WebhookError> {
+    let message = format!("Cycle complete! {work_sessions} work sessions done, enjoy a long break.");
+    let payload = match target.format {
+        WebhookFormat::Raw => serde_json::json!({
+            "title": "Roma Timer",
+            "message": message,
+            "event": "cycle_complete",
+            "work_sessions": work_sessions,
+            "timestamp": now_unix()
+        }),
+        ref format => {
+            let event = WebhookEvent {
+                session_type: "cycle_complete".to_string(),
+                session_count: work_sessions,
+                message,
+                timestamp: now_unix(),
+            };
+            format.render(target.template.as_deref(), &event).unwrap_or_else(|e| {
+                tracing::warn!("Sending raw webhook body instead of {}: {e}", format.as_str());
+                WebhookFormat::Raw.render(None, &event).unwrap_or_default()
+            })
+        }
+    };
+
+    post_webhook(webhook_url, &payload, get_webhook_retry_policy()).await
+}
+
+/// Session type of the synthetic session sent by `POST /api/webhook/test`
+pub const WEBHOOK_TEST_SESSION_TYPE: &str = "test";
+
+/// Deliver `payload` under `policy`, logging the final outcome
+pub async fn post_webhook(
+    webhook_url: &str,
+    payload: &serde_json::Value,
+    policy: WebhookRetryPolicy,
+) -> Result<WebhookDelivery, WebhookError> {
+    let client = webhook_client(get_webhook_max_redirects()).map_err(|e| WebhookError::Permanent {
+        attempts: 0,
+        status: None,
+        reason: e.to_string(),
+    })?;
+    match deliver_webhook(&client, webhook_url, payload, policy).await {
+        Ok(delivery) => {
+            tracing::info!("Webhook delivered to {webhook_url} (attempt {})", delivery.attempts);
+            Ok(delivery)
+        }
+        Err(e) => {
+            tracing::warn!("Webhook to {webhook_url} given up after {} attempt(s): {e}", e.attempts());
+            Err(e)
+        }
+    }
+}
+
+/// Default number of redirects followed for one webhook delivery
+const DEFAULT_WEBHOOK_MAX_REDIRECTS: usize = 5;
+
+/// Longest part of a rejected webhook's response body kept for the log
+const WEBHOOK_BODY_SNIPPET_CHARS: usize = 200;
+
+/// Redirects followed per webhook delivery, from `ROMA_TIMER_WEBHOOK_MAX_REDIRECTS`.
+/// Zero disables redirects.
+fn get_webhook_max_redirects() -> usize {
+    env::var("ROMA_TIMER_WEBHOOK_MAX_REDIRECTS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_WEBHOOK_MAX_REDIRECTS)
+}
+
+fn webhook_client(max_redirects: usize) -> reqwest::Result<Client> {
+    let policy = if max_redirects == 0 {
+        reqwest::redirect::Policy::none()
+    } else {
+        reqwest::redirect::Policy::limited(max_redirects)
+    };
+    Client::builder().redirect(policy).build()
+}
+
+/// How often a webhook is retried after a retryable failure
+#[derive(Debug, Clone, Copy)]
+pub struct WebhookRetryPolicy {
+    pub max_attempts: u32,
+    /// Wait before the first retry; multiplied by `multiplier` for each one after
+    pub initial_backoff: Duration,
+    pub multiplier: f64,
+    /// Fraction of each wait randomly added or taken off, so deliveries that
+    /// failed together don't all retry together
+    pub jitter: f64,
+}
+
+impl Default for WebhookRetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            multiplier: 2.0,
+            jitter: 0.1,
+        }
+    }
+}
+
+impl WebhookRetryPolicy {
+    /// Wait before retry number `retry`, the first being 1
+    fn backoff(&self, retry: u32) -> Duration {
+        let exponent = i32::try_from(retry.saturating_sub(1)).unwrap_or(i32::MAX);
+        let base = self.initial_backoff.as_secs_f64() * self.multiplier.powi(exponent);
+        let spread = if self.jitter > 0.0 {
+            rand::thread_rng().gen_range(-self.jitter..=self.jitter)
+        } else {
+            0.0
+        };
+        Duration::try_from_secs_f64(base * (1.0 + spread)).unwrap_or(Duration::MAX)
+    }
+}
+
+/// Webhook retry policy from `ROMA_TIMER_WEBHOOK_MAX_ATTEMPTS`,
+/// `ROMA_TIMER_WEBHOOK_BACKOFF_MS`, `ROMA_TIMER_WEBHOOK_BACKOFF_MULTIPLIER` and
+/// `ROMA_TIMER_WEBHOOK_BACKOFF_JITTER` (0-1). Unset or invalid values keep the default.
+pub fn get_webhook_retry_policy() -> WebhookRetryPolicy {
+    fn parse<T: std::str::FromStr>(name: &str) -> Option<T> {
+        env::var(name).ok().and_then(|value| value.parse().ok())
+    }
+
+    let defaults = WebhookRetryPolicy::default();
+    WebhookRetryPolicy {
+        max_attempts: parse("ROMA_TIMER_WEBHOOK_MAX_ATTEMPTS")
+            .filter(|attempts: &u32| *attempts > 0)
+            .unwrap_or(defaults.max_attempts),
+        initial_backoff: parse("ROMA_TIMER_WEBHOOK_BACKOFF_MS")
+            .map(Duration::from_millis)
+            .unwrap_or(defaults.initial_backoff),
+        multiplier: parse("ROMA_TIMER_WEBHOOK_BACKOFF_MULTIPLIER")
+            .filter(|multiplier: &f64| *multiplier >= 1.0)
+            .unwrap_or(defaults.multiplier),
+        jitter: parse("ROMA_TIMER_WEBHOOK_BACKOFF_JITTER")
+            .filter(|jitter: &f64| (0.0..=1.0).contains(jitter))
+            .unwrap_or(defaults.jitter),
+    }
+}
+
+/// A webhook the endpoint accepted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WebhookDelivery {
+    pub attempts: u32,
+    /// The accepting response's HTTP status
+    pub status: u16,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebhookError {
+    /// The endpoint rejected the request (4xx, or a redirect that couldn't be
+    /// followed); retrying won't help
+    #[error("webhook permanently failed: {reason}")]
+    Permanent { attempts: u32, status: Option<u16>, reason: String },
+    /// Server errors or network failures on every attempt
+    #[error("webhook failed after {attempts} attempt(s): {reason}")]
+    Exhausted { attempts: u32, status: Option<u16>, reason: String },
+}
+
+impl WebhookError {
+    /// Delivery attempts made before giving up
+    fn attempts(&self) -> u32 {
+        match self {
+            WebhookError::Permanent { attempts, .. } | WebhookError::Exhausted { attempts, .. } => *attempts,
+        }
+    }
+
+    /// HTTP status of the last response, if the endpoint answered at all
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            WebhookError::Permanent { status, .. } | WebhookError::Exhausted { status, .. } => *status,
+        }
+    }
+}
+
+/// POST `payload` to `webhook_url`, following redirects as the client allows.
+/// 2xx succeeds; 4xx and unfollowable redirects fail at once; 5xx and network
+/// errors are retried with jittered exponential backoff.
+async fn deliver_webhook(
+    client: &Client,
+    webhook_url: &str,
+    payload: &serde_json::Value,
+    policy: WebhookRetryPolicy,
+) -> Result<WebhookDelivery, WebhookError> {
+    let mut attempt = 1;
+
+    loop {
+        let result = with_webhook_permit(
+            webhook_limiter(),
+            client
+                .post(webhook_url)
+                .header("Content-Type", "application/json")
+                .header("User-Agent", "Roma-Timer/1.0")
+                .json(payload)
+                .send(),
+        )
+        .await;
+
+        let (status, reason) = match result {
+            Ok(response) if response.status().is_success() => {
+                return Ok(WebhookDelivery { attempts: attempt, status: response.status().as_u16() });
+            }
+            Ok(response) if response.status().is_server_error() => {
+                (Some(response.status().as_u16()), format!("server error {}", response.status()))
+            }
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let snippet: String = body.chars().take(WEBHOOK_BODY_SNIPPET_CHARS).collect();
+                tracing::warn!("Webhook to {webhook_url} rejected with {status}: {snippet}");
+                return Err(WebhookError::Permanent {
+                    attempts: attempt,
+                    status: Some(status.as_u16()),
+                    reason: format!("{status}: {snippet}"),
+                });
+            }
+            Err(e) if e.is_redirect() => {
+                return Err(WebhookError::Permanent { attempts: attempt, status: None, reason: e.to_string() });
+            }
+            Err(e) => (None, e.to_string()),
+        };
+
+        if attempt >= policy.max_attempts {
+            return Err(WebhookError::Exhausted { attempts: attempt, status, reason });
+        }
+
+        let backoff = policy.backoff(attempt);
+        tracing::debug!("Webhook to {webhook_url} failed ({reason}), retrying in {}ms", backoff.as_millis());
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+    }
+}
+
+/// Value of `ROMA_TIMER_FALLBACK_NOTIFY_URL` that writes notifications to the
+/// server log instead of posting them
+const LOG_NOTIFY_SINK: &str = "log";
+
+/// Server-wide destination for completion notifications of users who have
+/// notifications on but no webhook, e.g. a shared ntfy topic, or `log`.
+/// Unset (the default) sends those notifications nowhere.
+pub fn get_fallback_notify_url() -> Option<String> {
+    let value = env::var("ROMA_TIMER_FALLBACK_NOTIFY_URL").ok()?;
+    let value = value.trim();
+    if value.is_empty() {
+        return None;
+    }
+    if value != LOG_NOTIFY_SINK && url::Url::parse(value).is_err() {
+        tracing::warn!("Ignoring invalid ROMA_TIMER_FALLBACK_NOTIFY_URL: {value}");
+        return None;
+    }
+    Some(value.to_string())
+}
+
+/// Notify clients and the webhook when a long break begins. Off by default.
+pub fn get_cycle_complete_notifications() -> bool {
+    env::var("ROMA_TIMER_CYCLE_COMPLETE_NOTIFICATIONS")
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Send the session-complete webhook to each of `target`'s URLs concurrently;
+/// one URL failing doesn't stop delivery to the others. If any can't be
+/// delivered and `user_id` has `pause_on_webhook_failure` on, pause their timer
+/// and tell their clients why.
+pub async fn notify_session_complete(
+    ws_manager: &WebSocketManager,
+    user_id: &str,
+    target: &NotifyTarget,
+    session_type: &str,
+    session_count: u32,
+    policy: WebhookRetryPolicy,
+) {
+    let deliveries = target.urls.iter().map(|webhook_url| async move {
+        if webhook_url == LOG_NOTIFY_SINK {
+            tracing::info!(target: "roma::notify", "{session_type} session #{session_count} complete for {user_id}");
+            return None;
+        }
+        let result = send_webhook_notification(
+            webhook_url,
+            &target.format,
+            target.template.as_deref(),
+            session_type,
+            session_count,
+            policy,
+        )
+        .await;
+        let (attempts, failure) = match &result {
+            Ok(delivery) => (delivery.attempts, None),
+            Err(e) => (e.attempts(), Some(e.to_string())),
+        };
+        if let Err(e) = ws_manager
+            .database
+            .record_notification_delivery(user_id, session_type, webhook_url, attempts, failure.as_deref(), now_unix() as i64)
+            .await
+        {
+            tracing::warn!("Failed to record notification delivery for {user_id}: {e}");
+        }
+
+        let result = result.map(|_| ()).map_err(|e| e.to_string());
+        match &result {
+            Ok(()) => tracing::debug!("Delivered {session_type} notification for {user_id} to {webhook_url}"),
+            Err(reason) => tracing::warn!("Failed to deliver {session_type} notification for {user_id} to {webhook_url}: {reason}"),
+        }
+        Some(result)
+    });
+    let results: Vec<_> = futures_util::future::join_all(deliveries).await.into_iter().flatten().collect();
+
+    ws_manager.webhooks_sent.fetch_add(results.len() as u64, Ordering::Relaxed);
+    let failures: Vec<String> = results.into_iter().filter_map(Result::err).collect();
+    if failures.is_empty() {
+        return;
+    }
+    ws_manager.webhooks_failed.fetch_add(failures.len() as u64, Ordering::Relaxed);
+
+    let reason = failures.join("; ");
+    tracing::warn!("Failed to send webhook notification for {user_id}: {reason}");
+    if !ws_manager.user_configuration(user_id).await.pause_on_webhook_failure {
+        return;
+    }
+
+    let mut states = ws_manager.timer_states.lock().await;
+    let timer_state = states.user(user_id);
+    let was_running = timer_state.is_running;
+    timer_state.stop();
+    timer_state.last_updated = now_unix();
+    let paused_state = timer_state.clone();
+    drop(states);
+
+    tracing::warn!("Pausing timer: webhook notification for {session_type} session could not be delivered");
+    if was_running {
+        ws_manager.update_timer_state(user_id, paused_state).await;
+    }
+    ws_manager
+        .broadcast_message(user_id, WsMessage::Error {
+            code: "webhook_failed".to_string(),
+            message: format!("Timer paused because the completion notification could not be delivered: {reason}"),
+        })
+        .await;
+}
+
+/// Tell clients, and the user's completion notification target if they have
+/// one, that a long break began after `work_sessions` work sessions. Does
+/// nothing unless enabled.
+pub async fn notify_cycle_complete(ws_manager: &WebSocketManager, user_id: &str, work_sessions: u32) {
+    if !ws_manager.cycle_complete_notifications {
+        return;
+    }
+
+    tracing::info!("Cycle of {work_sessions} work sessions complete");
+    ws_manager
+        .broadcast_message(user_id, WsMessage::CycleComplete { work_sessions })
+        .await;
+
+    // Sent wherever the user's session completions go
+    let target = ws_manager.completion_notify_target(user_id).await;
+    if target.urls.is_empty() {
+        return;
+    }
+    let user_id = user_id.to_string();
+    tokio::spawn(async move {
+        let deliveries = target.urls.iter().map(|webhook_url| {
+            let (target, user_id) = (&target, &user_id);
+            async move {
+                if webhook_url == LOG_NOTIFY_SINK {
+                    tracing::info!(target: "roma::notify", "Cycle of {work_sessions} work sessions complete for {user_id}");
+                    return;
+                }
+                if let Err(e) = send_cycle_complete_webhook(webhook_url, target, work_sessions).await {
+                    tracing::warn!("Failed to send cycle complete webhook for {user_id} to {webhook_url}: {e}");
+                }
+            }
+        });
+        futures_util::future::join_all(deliveries).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::collections::HashMap;
+    use std::sync::Arc;
+    use std::sync::Mutex as StdMutex;
+    use tokio::net::TcpListener;
+    use tokio::sync::mpsc;
+    use crate::models::timer_state::TimerState;
+    use crate::services::timer_control_service::{completed_cycle_length, tick_timer, transition_to_next_session};
+    use crate::websocket::manager::SharedWsManager;
+    use axum::Router;
+    use axum::extract::ws::Message;
+    use axum::http::{StatusCode, header};
+    use axum::response::Json;
+    use axum::routing::post;
+
+    #[tokio::test]
+    async fn test_webhook_requests_respect_concurrency_cap() {
+        use std::sync::atomic::AtomicUsize;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let in_flight = Arc::new(AtomicUsize::new(0));
+        let max_in_flight = Arc::new(AtomicUsize::new(0));
+        let app = Router::new().route("/hook/:id", post({
+            let in_flight = in_flight.clone();
+            let max_in_flight = max_in_flight.clone();
+            move || async move {
+                let current = in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                max_in_flight.fetch_max(current, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_millis(50)).await;
+                in_flight.fetch_sub(1, Ordering::SeqCst);
+                StatusCode::OK
+            }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let urls: Vec<String> = (0..DEFAULT_WEBHOOK_CONCURRENCY * 3)
+            .map(|id| format!("http://{addr}/hook/{id}"))
+            .collect();
+        notify_session_complete(&ws_manager, "alice", &notify_target(&urls), "work", 1, WebhookRetryPolicy::default()).await;
+
+        assert_eq!(ws_manager.webhooks_sent.load(Ordering::Relaxed), urls.len() as u64);
+        assert_eq!(ws_manager.webhooks_failed.load(Ordering::Relaxed), 0);
+        // Fanned out concurrently, but never past the server-wide cap. Other
+        // tests share the limiter, so fewer than the cap may be reached.
+        let max_in_flight = max_in_flight.load(Ordering::SeqCst);
+        assert!(max_in_flight > 1, "deliveries ran one at a time");
+        assert!(max_in_flight <= DEFAULT_WEBHOOK_CONCURRENCY, "{max_in_flight} requests in flight at once");
+    }
+
+    #[tokio::test]
+    async fn test_cycle_complete_notified_once_at_long_break() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_cycle_complete_notifications(true),
+        );
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        // work, short break x3, then the 4th work session leads into the long break
+        let mut timer_state = TimerState {
+            session_type: "work".to_string(),
+            ..test_timer_state()
+        };
+        for _ in 0..8 {
+            let previous_session_type = timer_state.session_type.clone();
+            transition_to_next_session(&mut timer_state, Some(4), false);
+            if let Some(work_sessions) = completed_cycle_length(&previous_session_type, &timer_state) {
+                notify_cycle_complete(&ws_manager, "alice", work_sessions).await;
+            }
+        }
+        assert_eq!(timer_state.session_type, "work");
+
+        let notifications: Vec<u32> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                Message::Text(text) => match serde_json::from_str::<WsMessage>(&text) {
+                    Ok(WsMessage::CycleComplete { work_sessions }) => Some(work_sessions),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(notifications, [4]);
+
+        // Disabled by default
+        let (_, quiet_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        quiet_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+        notify_cycle_complete(&quiet_manager, "alice", 4).await;
+        assert!(receiver.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cycle_complete_sent_to_users_webhooks_in_their_format() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_cycle_complete_notifications(true),
+        );
+
+        let hits = Arc::new(StdMutex::new(Vec::<serde_json::Value>::new()));
+        let app = Router::new().route("/hook", post({
+            let hits = hits.clone();
+            move |Json(payload): Json<serde_json::Value>| async move {
+                hits.lock().unwrap().push(payload);
+                StatusCode::OK
+            }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, notifications_enabled, webhook_urls, webhook_format, created_at, updated_at)
+            VALUES ('alice', 'UTC', TRUE, ?, 'discord', 0, 0), ('bob', 'UTC', FALSE, ?, 'raw', 0, 0)
+            "#,
+        )
+        .bind(serde_json::json!([format!("http://{addr}/hook")]).to_string())
+        .bind(serde_json::json!([format!("http://{addr}/hook")]).to_string())
+        .execute(pool)
+        .await
+        .unwrap();
+
+        notify_cycle_complete(&ws_manager, "alice", 4).await;
+        // Bob turned notifications off
+        notify_cycle_complete(&ws_manager, "bob", 4).await;
+        for _ in 0..50 {
+            if !hits.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let payloads = hits.lock().unwrap().clone();
+        assert_eq!(payloads.len(), 1);
+        assert!(payloads[0]["content"].as_str().unwrap().contains("4 work sessions"));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_responses_handled_by_status_class() {
+        type Hits = Arc<StdMutex<HashMap<&'static str, usize>>>;
+        /// Count a request to `path`, returning how many it has had
+        fn count(hits: &Hits, path: &'static str) -> usize {
+            let mut hits = hits.lock().unwrap();
+            *hits.entry(path).or_default() += 1;
+            hits[path]
+        }
+
+        let hits = Hits::default();
+
+        let app = Router::new()
+            .route("/missing", post({
+                let hits = hits.clone();
+                move || async move {
+                    count(&hits, "missing");
+                    (StatusCode::NOT_FOUND, "no such hook")
+                }
+            }))
+            .route("/flaky", post({
+                let hits = hits.clone();
+                move || async move {
+                    if count(&hits, "flaky") == 1 {
+                        StatusCode::INTERNAL_SERVER_ERROR
+                    } else {
+                        StatusCode::OK
+                    }
+                }
+            }))
+            .route("/moved", post({
+                let hits = hits.clone();
+                move || async move {
+                    count(&hits, "moved");
+                    (StatusCode::MOVED_PERMANENTLY, [(header::LOCATION, "/hook")])
+                }
+            }))
+            .route("/hook", axum::routing::any({
+                let hits = hits.clone();
+                move || async move {
+                    count(&hits, "hook");
+                    StatusCode::OK
+                }
+            }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let client = webhook_client(DEFAULT_WEBHOOK_MAX_REDIRECTS).unwrap();
+        let policy = WebhookRetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..WebhookRetryPolicy::default()
+        };
+        let payload = serde_json::json!({ "title": "Roma Timer" });
+        let url = |path: &str| format!("http://{addr}{path}");
+
+        // 4xx: permanent, not retried, body kept for the log
+        match deliver_webhook(&client, &url("/missing"), &payload, policy).await {
+            Err(WebhookError::Permanent { reason, .. }) => assert!(reason.contains("no such hook"), "{reason}"),
+            other => panic!("expected a permanent failure, got {other:?}"),
+        }
+        assert_eq!(hits.lock().unwrap()["missing"], 1);
+
+        // 5xx: retried until it succeeds
+        assert_eq!(deliver_webhook(&client, &url("/flaky"), &payload, policy).await.unwrap().attempts, 2);
+        assert_eq!(hits.lock().unwrap()["flaky"], 2);
+
+        // 301: followed to the new location
+        assert_eq!(deliver_webhook(&client, &url("/moved"), &payload, policy).await.unwrap().attempts, 1);
+        assert_eq!(hits.lock().unwrap()["hook"], 1);
+
+        // With redirects disabled the 301 can't be delivered
+        let no_redirects = webhook_client(0).unwrap();
+        assert!(matches!(
+            deliver_webhook(&no_redirects, &url("/moved"), &payload, policy).await,
+            Err(WebhookError::Permanent { .. })
+        ));
+        assert_eq!(hits.lock().unwrap()["hook"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_failure_pauses_timer_when_enabled() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        sqlx::query(
+            "INSERT INTO user_configurations (id, pause_on_webhook_failure, created_at, updated_at) VALUES ('alice', TRUE, 0, 0)",
+        )
+        .execute(ws_manager.database.pool.sqlite().unwrap())
+        .await
+        .unwrap();
+
+        let attempts = Arc::new(StdMutex::new(0));
+        let app = Router::new().route("/hook", post({
+            let attempts = attempts.clone();
+            move || async move {
+                *attempts.lock().unwrap() += 1;
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        // The next session was started while the notification was retrying
+        state.lock().await.user("alice").is_running = true;
+
+        let policy = WebhookRetryPolicy {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(1),
+            ..WebhookRetryPolicy::default()
+        };
+        notify_session_complete(&ws_manager, "alice", &notify_target(&[format!("http://{addr}/hook")]), "work", 1, policy).await;
+
+        assert_eq!(*attempts.lock().unwrap(), 3);
+        assert!(!state.lock().await.user("alice").is_running);
+
+        let error_codes: Vec<String> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                Message::Text(text) => match serde_json::from_str::<WsMessage>(&text) {
+                    Ok(WsMessage::Error { code, .. }) => Some(code),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+        assert_eq!(error_codes, ["webhook_failed"]);
+
+        // Off by default: another user's timer keeps going
+        state.lock().await.user("bob").is_running = true;
+        notify_session_complete(&ws_manager, "bob", &notify_target(&[format!("http://{addr}/hook")]), "work", 1, policy).await;
+        assert!(state.lock().await.user("bob").is_running);
+    }
+
+    #[tokio::test]
+    async fn test_webhook_failure_does_not_block_other_urls() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let received = Arc::new(StdMutex::new(Vec::<serde_json::Value>::new()));
+        let app = Router::new()
+            .route("/broken", post(|| async { StatusCode::INTERNAL_SERVER_ERROR }))
+            .route("/hook", post({
+                let received = received.clone();
+                move |Json(payload): Json<serde_json::Value>| async move {
+                    received.lock().unwrap().push(payload);
+                    StatusCode::OK
+                }
+            }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let policy = WebhookRetryPolicy {
+            max_attempts: 2,
+            initial_backoff: Duration::from_millis(1),
+            ..WebhookRetryPolicy::default()
+        };
+        let urls = [format!("http://{addr}/broken"), format!("http://{addr}/hook")];
+        notify_session_complete(&ws_manager, "alice", &notify_target(&urls), "work", 3, policy).await;
+
+        let payloads = received.lock().unwrap().clone();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0]["session_type"], "work");
+        assert_eq!(payloads[0]["session_count"], 3);
+        assert_eq!(ws_manager.webhooks_sent.load(Ordering::Relaxed), 2);
+        assert_eq!(ws_manager.webhooks_failed.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn test_webhook_backoff_grows_by_multiplier_within_jitter() {
+        let policy = WebhookRetryPolicy {
+            max_attempts: 4,
+            initial_backoff: Duration::from_millis(100),
+            multiplier: 3.0,
+            jitter: 0.0,
+        };
+        assert_eq!(policy.backoff(1), Duration::from_millis(100));
+        assert_eq!(policy.backoff(2), Duration::from_millis(300));
+        assert_eq!(policy.backoff(3), Duration::from_millis(900));
+
+        let jittered = WebhookRetryPolicy { jitter: 0.5, ..policy };
+        for _ in 0..20 {
+            let backoff = jittered.backoff(2);
+            assert!((Duration::from_millis(150)..=Duration::from_millis(450)).contains(&backoff), "{backoff:?}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_webhook_retried_until_delivered_and_recorded() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let attempts = Arc::new(StdMutex::new(0));
+        let app = Router::new().route("/hook", post({
+            let attempts = attempts.clone();
+            move || async move {
+                let mut attempts = attempts.lock().unwrap();
+                *attempts += 1;
+                if *attempts <= 2 {
+                    StatusCode::BAD_GATEWAY
+                } else {
+                    StatusCode::OK
+                }
+            }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let policy = WebhookRetryPolicy {
+            max_attempts: 5,
+            initial_backoff: Duration::from_millis(1),
+            ..WebhookRetryPolicy::default()
+        };
+        let url = format!("http://{addr}/hook");
+        notify_session_complete(&ws_manager, "alice", &notify_target(&[url.clone()]), "work", 1, policy).await;
+
+        assert_eq!(*attempts.lock().unwrap(), 3);
+        assert_eq!(ws_manager.webhooks_failed.load(Ordering::Relaxed), 0);
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        let (event_type, delivered, recorded_attempts, webhook_url): (String, bool, i64, String) = sqlx::query_as(
+            "SELECT event_type, delivered, attempts, webhook_url FROM notification_events WHERE user_id = 'alice'"
+        )
+        .fetch_one(pool)
+        .await
+        .unwrap();
+        assert_eq!(event_type, "WorkSessionComplete");
+        assert!(delivered);
+        assert_eq!(recorded_attempts, 3);
+        assert_eq!(webhook_url, url);
+    }
+
+    #[tokio::test]
+    async fn test_completion_notification_uses_fallback_without_webhook() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, quiet_manager) = test_app_state(&temp_dir).await;
+
+        let hits = Arc::new(StdMutex::new(Vec::<serde_json::Value>::new()));
+        let app = Router::new().route("/fallback", post({
+            let hits = hits.clone();
+            move |Json(payload): Json<serde_json::Value>| async move {
+                hits.lock().unwrap().push(payload);
+                StatusCode::OK
+            }
+        }));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let pool = quiet_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, notifications_enabled, created_at, updated_at)
+            VALUES ('alice', 'UTC', TRUE, 0, 0), ('bob', 'UTC', FALSE, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        // Without a fallback, a user with no webhook has nowhere to send to
+        assert!(quiet_manager.completion_notify_target("alice").await.urls.is_empty());
+
+        let fallback_url = format!("http://{addr}/fallback");
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), quiet_manager.database.clone())
+                .with_fallback_notify_url(Some(fallback_url.clone())),
+        );
+        assert_eq!(ws_manager.completion_notify_target("alice").await.urls, [fallback_url]);
+        assert!(ws_manager.completion_notify_target("bob").await.urls.is_empty());
+
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.is_running = true;
+            timer_state.remaining_seconds = 1;
+        }
+        tick_timer(state.clone(), ws_manager.clone(), "alice".to_string()).await;
+        for _ in 0..50 {
+            if !hits.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        let payloads = hits.lock().unwrap().clone();
+        assert_eq!(payloads.len(), 1);
+        assert_eq!(payloads[0]["session_type"], "work");
+    }
+}
@@ -18,7 +18,8 @@ use futures_util::StreamExt;
 use std::collections::HashMap;
 use std::net::SocketAddr;
 use std::sync::Arc;
-use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::time::Duration;
+use crate::services::time_provider::{now_unix, now_unix_millis};
 use tokio::sync::{broadcast, RwLock};
 use tokio::time::interval;
 use uuid::Uuid;
@@ -116,10 +117,7 @@ impl WebSocketService {
 
     /// Broadcast timer state to all connected devices with performance optimization
     pub async fn broadcast_timer_state(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let start_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as f64;
+        let start_time = now_unix_millis() as f64;
 
         let timer_state = self.timer_service.get_timer_state().await;
 
@@ -149,10 +147,7 @@ impl WebSocketService {
     /// Batch broadcast multiple messages for efficiency
     pub async fn broadcast_batch(&self, messages: Vec<WebSocketMessage>) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
         let mut sent_count = 0;
-        let start_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as f64;
+        let start_time = now_unix_millis() as f64;
 
         for message in messages {
             if self.message_broadcast.send(message).is_ok() {
@@ -168,10 +163,7 @@ impl WebSocketService {
 
     /// Broadcast a single message to all connected clients
     pub async fn broadcast_message(&self, message: WebSocketMessage) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        let start_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as f64;
+        let start_time = now_unix_millis() as f64;
 
         // Broadcast and track performance
         let _ = self.message_broadcast.send(message);
@@ -184,10 +176,7 @@ impl WebSocketService {
 
     /// Update performance metrics after broadcast
     async fn update_broadcast_metrics(&self, start_time: f64) {
-        let end_time = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as f64;
+        let end_time = now_unix_millis() as f64;
 
         let latency = end_time - start_time;
 
@@ -219,10 +208,7 @@ impl WebSocketService {
 
         // Calculate messages per second (rolling average over last minute)
         if let Some(last_broadcast) = metrics.last_broadcast_time {
-            let now = SystemTime::now()
-                .duration_since(UNIX_EPOCH)
-                .unwrap()
-                .as_secs();
+            let now = now_unix();
 
             let time_diff = now.saturating_sub(last_broadcast) as f64;
             if time_diff > 0.0 {
@@ -548,13 +534,7 @@ mod tests {
         let config = UserConfiguration::new();
 
         // Create a mock timer service for testing
-        let timer_service = Arc::new(
-            tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    crate::services::timer_service::TimerService::new_with_config(config.clone())
-                })
-            })
-        );
+        let timer_service = Arc::new(TimerService::new_with_config(config).await);
         let ws_service = WebSocketService::new(timer_service);
 
         let stats = ws_service.get_connection_stats().await;
@@ -568,13 +548,7 @@ mod tests {
         let config = UserConfiguration::new();
 
         // Create a mock timer service for testing
-        let timer_service = Arc::new(
-            tokio::task::block_in_place(|| {
-                tokio::runtime::Handle::current().block_on(async {
-                    crate::services::timer_service::TimerService::new_with_config(config.clone())
-                })
-            })
-        );
+        let timer_service = Arc::new(TimerService::new_with_config(config).await);
         let _ws_service = WebSocketService::new(timer_service);
 
         let mut pool = ConnectionPool::new();
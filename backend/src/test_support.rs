@@ -0,0 +1,111 @@
+//! Test Support
+//!
+//! Helpers shared by the unit tests of the API, service and WebSocket modules.
+
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex as StdMutex};
+use tokio::sync::Mutex;
+
+use crate::database::DatabaseManager;
+use crate::models::timer_state::{SharedState, TimerState, TimerStates, DEFAULT_LONG_BREAK_FREQUENCY};
+use crate::services::auth_service::{authenticate, generate_auth_token, sign_claims, AuthClaims, CurrentUser};
+use crate::services::webhook_service::NotifyTarget;
+use crate::websocket::manager::{SharedWsManager, WebSocketManager};
+
+use axum::http::header;
+use axum::response::Response;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::{EnvFilter, Layer, Registry};
+
+pub fn test_timer_state() -> TimerState {
+    TimerState {
+        is_running: true,
+        remaining_seconds: 10,
+        session_type: "work".to_string(),
+        session_count: 1,
+        work_duration: 25 * 60,
+        short_break_duration: 5 * 60,
+        long_break_duration: 15 * 60,
+        long_break_frequency: DEFAULT_LONG_BREAK_FREQUENCY,
+        last_updated: 0,
+        work_sessions_since_long_break: 0,
+        label: None,
+        session_type_labels: BTreeMap::new(),
+        plan: None,
+        pre_break_work_duration: None,
+        added_seconds: 0,
+        session_ends_at: None,
+    }
+}
+
+pub async fn test_app_state(temp_dir: &tempfile::TempDir) -> (SharedState, SharedWsManager) {
+    let db_path = temp_dir.path().join("test_app.db");
+    let database = Arc::new(
+        DatabaseManager::new(&format!("sqlite:{}?mode=rwc", db_path.to_string_lossy()))
+            .await
+            .expect("Failed to create DatabaseManager"),
+    );
+    database.migrate().await.expect("Failed to run migrations");
+
+    let state = SharedState::new(Mutex::new(TimerStates::new(TimerState {
+        is_running: false,
+        remaining_seconds: 25 * 60,
+        ..test_timer_state()
+    })));
+    let ws_manager = SharedWsManager::new(WebSocketManager::new(state.clone(), database));
+    (state, ws_manager)
+}
+
+pub async fn response_json<T: serde::de::DeserializeOwned>(response: Response) -> T {
+    let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    serde_json::from_slice(&body).unwrap()
+}
+
+pub fn auth_headers(user_id: &str) -> axum::http::HeaderMap {
+    let token = generate_auth_token(user_id).unwrap();
+    let mut headers = axum::http::HeaderMap::new();
+    headers.insert(header::AUTHORIZATION, format!("Bearer {token}").parse().unwrap());
+    headers
+}
+
+/// The user a handler sees for a request carrying `user_id`'s token
+pub fn current_user(user_id: &str) -> CurrentUser {
+    CurrentUser(authenticate(&auth_headers(user_id)).unwrap())
+}
+
+/// Raw-format notifications to `urls`
+pub fn notify_target(urls: &[String]) -> NotifyTarget {
+    NotifyTarget {
+        urls: urls.to_vec(),
+        ..NotifyTarget::default()
+    }
+}
+
+pub fn token_with_times(iat: u64, exp: u64) -> String {
+    sign_claims(&AuthClaims {
+        sub: "alice".to_string(),
+        iat,
+        exp,
+        jti: None,
+    })
+    .unwrap()
+}
+
+/// Layer that records the target of every event it sees
+struct CaptureTargets(Arc<StdMutex<Vec<String>>>);
+
+impl<S: tracing::Subscriber> Layer<S> for CaptureTargets {
+    fn on_event(&self, event: &tracing::Event<'_>, _ctx: Context<'_, S>) {
+        self.0.lock().unwrap().push(event.metadata().target().to_string());
+    }
+}
+
+pub fn capture_with_filter(directives: &str, f: impl FnOnce()) -> Vec<String> {
+    let captured = Arc::new(StdMutex::new(Vec::new()));
+    let subscriber = Registry::default()
+        .with(EnvFilter::new(directives))
+        .with(CaptureTargets(captured.clone()));
+    tracing::subscriber::with_default(subscriber, f);
+    let targets = captured.lock().unwrap().clone();
+    targets
+}
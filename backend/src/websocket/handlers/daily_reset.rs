@@ -28,6 +28,9 @@ pub struct ConfigureDailyResetRequest {
     pub reset_time_type: DailyResetTimeType,
     /// Hour for daily reset (0-23) - only used if time_type is Hour
     pub reset_hour: Option<u8>,
+    /// Minutes past the hour (0-59) - only used if time_type is Hour
+    #[serde(default)]
+    pub reset_minute: Option<u8>,
     /// Custom time string (HH:MM) - only used if time_type is Custom
     pub custom_time: Option<String>,
     /// User timezone
@@ -142,6 +145,7 @@ impl DailyResetWebSocketHandler {
         config.daily_reset_enabled = request.enabled;
         config.daily_reset_time_type = request.reset_time_type;
         config.daily_reset_time_hour = request.reset_hour;
+        config.daily_reset_time_minute = request.reset_minute;
         config.daily_reset_time_custom = request.custom_time;
         config.timezone = request.timezone;
 
@@ -244,7 +248,11 @@ impl DailyResetWebSocketHandler {
             DailyResetTimeType::Midnight => Ok(DailyResetTime::midnight()),
             DailyResetTimeType::Hour => {
                 if let Some(hour) = request.reset_hour {
-                    DailyResetTime::hour(hour).map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
+                    match request.reset_minute {
+                        Some(minute) => DailyResetTime::hour_minute(hour, minute),
+                        None => DailyResetTime::hour(hour),
+                    }
+                    .map_err(|e| Box::new(e) as Box<dyn std::error::Error + Send + Sync>)
                 } else {
                     Err("Hour must be specified when time type is Hour".into())
                 }
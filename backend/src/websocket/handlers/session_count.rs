@@ -34,10 +34,10 @@ impl SessionCountWebSocketHandler {
     #[instrument(skip(self, message, response_tx))]
     pub async fn handle_get_session_count(
         &self,
-        message: &crate::services::websocket_service::WebSocketMessage,
+        message: &serde_json::Value,
         response_tx: mpsc::UnboundedSender<crate::services::websocket_service::WebSocketMessage>,
     ) -> Result<(), AppError> {
-        let request: GetSessionCountRequest = serde_json::from_value(message.data.clone())
+        let request: GetSessionCountRequest = serde_json::from_value(message.clone())
             .map_err(|e| AppError::Serialization(e))?;
 
         info!("Handling get session count request for user {}", request.user_id);
@@ -49,7 +49,7 @@ impl SessionCountWebSocketHandler {
                 error!("Failed to get daily reset status for user {}: {}", request.user_id, e);
 
                 let response = SessionCountResponse {
-                    message_id: request.message_id,
+                    message_id: request.message_id.clone(),
                     success: false,
                     current_session_count: 0,
                     manual_session_override: None,
@@ -77,7 +77,7 @@ impl SessionCountWebSocketHandler {
 
         // Create successful response
         let response = SessionCountResponse {
-            message_id: request.message_id,
+            message_id: request.message_id.clone(),
             success: true,
             current_session_count: status.current_session_count,
             manual_session_override: status.manual_session_override,
@@ -109,10 +109,10 @@ impl SessionCountWebSocketHandler {
     #[instrument(skip(self, message, response_tx))]
     pub async fn handle_set_session_count(
         &self,
-        message: &crate::services::websocket_service::WebSocketMessage,
+        message: &serde_json::Value,
         response_tx: mpsc::UnboundedSender<crate::services::websocket_service::WebSocketMessage>,
     ) -> Result<(), AppError> {
-        let request: SetSessionCountRequest = serde_json::from_value(message.data.clone())
+        let request: SetSessionCountRequest = serde_json::from_value(message.clone())
             .map_err(|e| AppError::Serialization(e))?;
 
         info!("Handling set session count request for user {}: {} (override: {})",
@@ -132,7 +132,7 @@ impl SessionCountWebSocketHandler {
                         error!("Failed to get updated status for user {}: {}", request.user_id, e);
 
                         let response = SessionSetResponse {
-                            message_id: request.message_id,
+                            message_id: request.message_id.clone(),
                             success: true, // The set succeeded even if we can't get status
                             current_session_count: request.session_count,
                             manual_session_override: if request.manual_override { Some(request.session_count) } else { None },
@@ -159,7 +159,7 @@ impl SessionCountWebSocketHandler {
 
                 // Create successful response
                 let response = SessionSetResponse {
-                    message_id: request.message_id,
+                    message_id: request.message_id.clone(),
                     success: true,
                     current_session_count: status.current_session_count,
                     manual_session_override: status.manual_session_override,
@@ -188,7 +188,7 @@ impl SessionCountWebSocketHandler {
                 error!("Failed to set session count for user {}: {}", request.user_id, e);
 
                 let response = SessionSetResponse {
-                    message_id: request.message_id,
+                    message_id: request.message_id.clone(),
                     success: false,
                     current_session_count: 0,
                     manual_session_override: None,
@@ -219,10 +219,10 @@ impl SessionCountWebSocketHandler {
     #[instrument(skip(self, message, response_tx))]
     pub async fn handle_reset_session(
         &self,
-        message: &crate::services::websocket_service::WebSocketMessage,
+        message: &serde_json::Value,
         response_tx: mpsc::UnboundedSender<crate::services::websocket_service::WebSocketMessage>,
     ) -> Result<(), AppError> {
-        let request: ResetSessionRequest = serde_json::from_value(message.data.clone())
+        let request: ResetSessionRequest = serde_json::from_value(message.clone())
             .map_err(|e| AppError::Serialization(e))?;
 
         info!("Handling reset session request for user {}", request.user_id);
@@ -234,7 +234,7 @@ impl SessionCountWebSocketHandler {
                 error!("Failed to get current status for user {}: {}", request.user_id, e);
 
                 let response = SessionResetResponse {
-                    message_id: request.message_id,
+                    message_id: request.message_id.clone(),
                     success: false,
                     previous_session_count: 0,
                     new_session_count: 0,
@@ -267,7 +267,7 @@ impl SessionCountWebSocketHandler {
             Ok(_) => {
                 // Create successful response
                 let response = SessionResetResponse {
-                    message_id: request.message_id,
+                    message_id: request.message_id.clone(),
                     success: true,
                     previous_session_count: previous_count,
                     new_session_count: 0,
@@ -296,7 +296,7 @@ impl SessionCountWebSocketHandler {
                 error!("Failed to reset session count for user {}: {}", request.user_id, e);
 
                 let response = SessionResetResponse {
-                    message_id: request.message_id,
+                    message_id: request.message_id.clone(),
                     success: false,
                     previous_session_count: previous_count,
                     new_session_count: previous_count,
@@ -329,19 +329,15 @@ impl SessionCountWebSocketHandler {
 mod tests {
     use super::*;
     use crate::services::time_provider::MockTimeProvider;
-    use tempfile::TempDir;
-    use std::time::SystemTime;
+    use crate::services::websocket_service::WebSocketMessage;
 
     async fn create_test_handler() -> (SessionCountWebSocketHandler, Arc<MockTimeProvider>) {
-        let temp_dir = TempDir::new().expect("Failed to create temp directory");
-        let db_path = temp_dir.path().join("test_session_count.db");
-        let db_manager = Arc::new(DatabaseManager::new(&db_path.to_string_lossy()).await
+        let db_manager = Arc::new(DatabaseManager::new("sqlite::memory:").await
             .expect("Failed to create DatabaseManager"));
+        db_manager.migrate().await.expect("Failed to migrate database");
 
-        let mock_time = Arc::new(MockTimeProvider::new());
-        mock_time.set_time(chrono::Utc::now());
+        let mock_time = Arc::new(MockTimeProvider::new(chrono::Utc::now()));
 
-        let timezone_service = Arc::new(crate::services::timezone_service::TimezoneService::new(mock_time.clone()));
         let daily_reset_service = Arc::new(DailyResetService::new(mock_time.clone(), db_manager.clone()));
 
         let handler = SessionCountWebSocketHandler::new(db_manager, daily_reset_service);
@@ -349,6 +345,14 @@ mod tests {
         (handler, mock_time)
     }
 
+    /// The `{ type, message_id, data }` envelope a handler sent back
+    fn response_payload(message: Option<WebSocketMessage>) -> serde_json::Value {
+        match message {
+            Some(WebSocketMessage::ConfigurationUpdate { payload }) => payload,
+            other => panic!("expected a ConfigurationUpdate response, got {other:?}"),
+        }
+    }
+
     #[tokio::test]
     async fn test_handle_get_session_count() -> Result<(), Box<dyn std::error::Error>> {
         let (handler, _) = create_test_handler().await;
@@ -361,22 +365,15 @@ mod tests {
 
         let (response_tx, mut response_rx) = mpsc::unbounded_channel();
 
-        let ws_message = crate::services::websocket_service::WebSocketMessage::ConfigurationUpdate {
-            payload: serde_json::json!({
-                "type": "get_session_count",
-                "message_id": "test_001",
-                "data": serde_json::to_value(&request)?,
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            }),
-        };
+        let ws_message = serde_json::to_value(&request)?;
 
         handler.handle_get_session_count(&ws_message, response_tx).await?;
 
-        let response = response_rx.recv().await?;
-        assert_eq!(response.type_, "session_count_response");
-        assert_eq!(response.message_id, "test_001");
+        let response = response_payload(response_rx.recv().await);
+        assert_eq!(response["type"], "session_count_response");
+        assert_eq!(response["message_id"], "test_001");
 
-        let response_data: SessionCountResponse = serde_json::from_value(response.data)?;
+        let response_data: SessionCountResponse = serde_json::from_value(response["data"].clone())?;
         assert_eq!(response_data.message_id, "test_001");
         // Note: Since user doesn't exist, it should return an error response
         assert!(!response_data.success);
@@ -399,22 +396,15 @@ mod tests {
 
         let (response_tx, mut response_rx) = mpsc::unbounded_channel();
 
-        let ws_message = crate::services::websocket_service::WebSocketMessage::ConfigurationUpdate {
-            payload: serde_json::json!({
-                "type": "set_session_count",
-                "message_id": "test_002",
-                "data": serde_json::to_value(&request)?,
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            }),
-        };
+        let ws_message = serde_json::to_value(&request)?;
 
         handler.handle_set_session_count(&ws_message, response_tx).await?;
 
-        let response = response_rx.recv().await?;
-        assert_eq!(response.type_, "session_set_response");
-        assert_eq!(response.message_id, "test_002");
+        let response = response_payload(response_rx.recv().await);
+        assert_eq!(response["type"], "session_set_response");
+        assert_eq!(response["message_id"], "test_002");
 
-        let response_data: SessionSetResponse = serde_json::from_value(response.data)?;
+        let response_data: SessionSetResponse = serde_json::from_value(response["data"].clone())?;
         assert_eq!(response_data.message_id, "test_002");
         // Note: Since user doesn't exist, it should return an error response
         assert!(!response_data.success);
@@ -435,22 +425,15 @@ mod tests {
 
         let (response_tx, mut response_rx) = mpsc::unbounded_channel();
 
-        let ws_message = crate::services::websocket_service::WebSocketMessage::ConfigurationUpdate {
-            payload: serde_json::json!({
-                "type": "reset_session",
-                "message_id": "test_003",
-                "data": serde_json::to_value(&request)?,
-                "timestamp": chrono::Utc::now().to_rfc3339()
-            }),
-        };
+        let ws_message = serde_json::to_value(&request)?;
 
         handler.handle_reset_session(&ws_message, response_tx).await?;
 
-        let response = response_rx.recv().await?;
-        assert_eq!(response.type_, "session_reset_response");
-        assert_eq!(response.message_id, "test_003");
+        let response = response_payload(response_rx.recv().await);
+        assert_eq!(response["type"], "session_reset_response");
+        assert_eq!(response["message_id"], "test_003");
 
-        let response_data: SessionResetResponse = serde_json::from_value(response.data)?;
+        let response_data: SessionResetResponse = serde_json::from_value(response["data"].clone())?;
         assert_eq!(response_data.message_id, "test_003");
         // Note: Since user doesn't exist, it should return an error response
         assert!(!response_data.success);
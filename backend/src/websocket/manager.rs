@@ -0,0 +1,936 @@
+//! WebSocket Connection Manager
+//!
+//! Tracks connected devices per user and fans timer and settings updates out to them.
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::env;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+use crate::api::timer_control::TimerRequest;
+use crate::config::TimerMode;
+use crate::database::DatabaseManager;
+use crate::models::timer_state::{SharedState, TimerState, TimerStateBroadcast};
+use crate::models::user_configuration::UserConfiguration;
+use crate::services::daily_reset_service::DailyResetService;
+use crate::services::settings_service::{SettingsRequest, SettingsThrottle};
+use crate::services::time_provider::{now_unix, SystemTimeProvider};
+use crate::services::timer_control_service::timer_state_at;
+use crate::services::webhook_service::NotifyTarget;
+
+use axum::extract::ws::Message;
+use serde::{Deserialize, Serialize};
+
+// WebSocket messages
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data")]
+pub enum WsMessage {
+    /// First message on every connection: who the client is authenticated as,
+    /// its connection id, and the server clock (Unix milliseconds) for offset correction
+    Welcome {
+        user_id: String,
+        connection_id: String,
+        server_time: u64,
+        device_count: usize,
+    },
+    TimerStateUpdate(TimerStateBroadcast),
+    TimerControl(TimerRequest),
+    SettingsUpdate(SettingsRequest),
+    ConnectionStatus {
+        connection_id: String,
+        connected: bool,
+        device_count: usize,
+        /// The user's connected devices after the change, oldest first
+        #[serde(default)]
+        device_list: Vec<DeviceInfo>,
+    },
+    Ping,
+    Pong,
+    ClientStateReport(ClientStateReport),
+    GetDailyResetStatus,
+    DailyResetStatus(crate::services::daily_reset_service::DailyResetStatusSnapshot),
+    /// Reset the daily session count now; answered with a `DailyResetStatus` broadcast
+    ResetDailySessions,
+    /// A session just ran out and the timer moved on to `next_type`; sent
+    /// before the `TimerStateUpdate` carrying the new session
+    SessionCompleted {
+        completed_type: String,
+        session_count: u32,
+        next_type: String,
+    },
+    /// A long break just began, completing a cycle of `work_sessions` work sessions
+    CycleComplete {
+        work_sessions: u32,
+    },
+    /// A session finished and the next one is loaded but waits for the user to
+    /// start it (`wait_for_interaction` is on)
+    AwaitingStart {
+        session_type: String,
+        session_count: u32,
+    },
+    /// Today's completed work sessions just reached the user's daily goal
+    GoalReached {
+        goal: u32,
+        session_count: u32,
+    },
+    /// The server is shutting down and about to close the connection; clients
+    /// should reconnect once it is back. Sent to every connection regardless
+    /// of its subscriptions.
+    ServerShutdown,
+    /// Only receive broadcasts of these message types; an empty list receives everything
+    Subscribe {
+        message_types: Vec<String>,
+    },
+    Error {
+        code: String,
+        message: String,
+    },
+}
+
+/// Broadcast message types a connection can subscribe to
+pub const SUBSCRIBABLE_MESSAGE_TYPES: &[&str] =
+    &["TimerStateUpdate", "ConnectionStatus", "SessionCompleted", "CycleComplete", "AwaitingStart", "GoalReached", "Error"];
+
+impl WsMessage {
+    /// The `type` tag this message is serialized with
+    pub fn message_type(&self) -> &'static str {
+        match self {
+            WsMessage::Welcome { .. } => "Welcome",
+            WsMessage::TimerStateUpdate(_) => "TimerStateUpdate",
+            WsMessage::TimerControl(_) => "TimerControl",
+            WsMessage::SettingsUpdate(_) => "SettingsUpdate",
+            WsMessage::ConnectionStatus { .. } => "ConnectionStatus",
+            WsMessage::Ping => "Ping",
+            WsMessage::Pong => "Pong",
+            WsMessage::ClientStateReport(_) => "ClientStateReport",
+            WsMessage::GetDailyResetStatus => "GetDailyResetStatus",
+            WsMessage::DailyResetStatus(_) => "DailyResetStatus",
+            WsMessage::ResetDailySessions => "ResetDailySessions",
+            WsMessage::SessionCompleted { .. } => "SessionCompleted",
+            WsMessage::CycleComplete { .. } => "CycleComplete",
+            WsMessage::AwaitingStart { .. } => "AwaitingStart",
+            WsMessage::GoalReached { .. } => "GoalReached",
+            WsMessage::ServerShutdown => "ServerShutdown",
+            WsMessage::Subscribe { .. } => "Subscribe",
+            WsMessage::Error { .. } => "Error",
+        }
+    }
+}
+
+/// Countdown state reported by a client in `ClientTick` mode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClientStateReport {
+    pub session_type: String,
+    pub remaining_seconds: u32,
+}
+
+// Connection info
+#[derive(Debug, Clone)]
+pub struct Connection {
+    pub id: String,
+    /// Authenticated user the connection belongs to
+    pub user_id: String,
+    pub user_agent: Option<String>,
+    pub connected_at: u64,
+    /// When a message was last received from the client (Unix seconds)
+    pub last_seen: u64,
+    /// Stable id the client gave for its device, if any
+    pub device_id: Option<String>,
+    /// Broadcast message types this connection receives; `None` receives all
+    pub subscriptions: Option<BTreeSet<String>>,
+}
+
+/// Longest user agent shown to other devices, in characters
+const MAX_DEVICE_USER_AGENT_CHARS: usize = 120;
+
+/// A connected device, as listed to the user's other devices
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceInfo {
+    pub connection_id: String,
+    /// Stable id the client gave for its device, if any
+    pub device_id: Option<String>,
+    /// The client's user agent, truncated to `MAX_DEVICE_USER_AGENT_CHARS`
+    pub user_agent: Option<String>,
+    pub connected_at: u64,
+}
+
+impl From<&Connection> for DeviceInfo {
+    fn from(connection: &Connection) -> Self {
+        Self {
+            connection_id: connection.id.clone(),
+            device_id: connection.device_id.clone(),
+            user_agent: connection
+                .user_agent
+                .as_ref()
+                .map(|user_agent| user_agent.chars().take(MAX_DEVICE_USER_AGENT_CHARS).collect()),
+            connected_at: connection.connected_at,
+        }
+    }
+}
+
+// WebSocket message sender type
+type WsSender = mpsc::UnboundedSender<Message>;
+
+// WebSocket manager
+#[derive(Debug)]
+pub struct WebSocketManager {
+    pub connections: Arc<Mutex<HashMap<String, Connection>>>,
+    pub senders: Arc<Mutex<HashMap<String, WsSender>>>,
+    pub timer_states: SharedState,
+    pub database: Arc<DatabaseManager>,
+    pub timer_mode: TimerMode,
+    /// Answer messages the server doesn't handle with an `unsupported_message` error
+    pub strict_messages: bool,
+    /// Send a `CycleComplete` notification when a long break begins
+    pub cycle_complete_notifications: bool,
+    /// Save each device's subscription and restore it when the device reconnects
+    pub persist_subscriptions: bool,
+    /// Where completion notifications go for users with notifications on but no webhook
+    pub fallback_notify_url: Option<String>,
+    /// Seconds without any message from a client, pongs included, before the
+    /// sweep drops it; `None` only drops connections whose channel has closed
+    pub heartbeat_timeout_secs: Option<u64>,
+    /// Total connections pruned by `sweep_dead_connections`
+    pub connections_swept: AtomicU64,
+    /// Session-complete webhook deliveries attempted and failed since startup
+    pub webhooks_sent: AtomicU64,
+    pub webhooks_failed: AtomicU64,
+    /// Minimum time between applied settings updates per user; zero disables the limit
+    pub settings_update_interval: Duration,
+    pub settings_throttles: Arc<Mutex<HashMap<String, SettingsThrottle>>>,
+    /// Limit on manual session-count changes per user; `None` disables it
+    pub count_change_limit: Option<CountChangeLimit>,
+    /// Recent manual session-count changes per user, oldest first
+    pub count_changes: Arc<Mutex<HashMap<String, VecDeque<std::time::Instant>>>>,
+    /// Limit on pairing-code redemption attempts per client address; `None` disables it
+    pub redeem_attempt_limit: Option<CountChangeLimit>,
+    /// Recent pairing-code redemption attempts per client address, oldest first
+    pub redeem_attempts: Arc<Mutex<HashMap<String, VecDeque<std::time::Instant>>>>,
+    /// Externally reachable base URL used in pairing deep links, without a trailing slash
+    pub public_base_url: Option<String>,
+    /// Most seconds add-time may extend one session by; `None` disables the cap
+    pub max_added_seconds: Option<u32>,
+    /// Consecutive work sessions after which a long break is forced; `None` disables the cap
+    pub max_consecutive_work_sessions: Option<u32>,
+    /// Server-side countdown task per user; starting a new one aborts the old
+    pub tickers: Arc<Mutex<HashMap<String, tokio::task::JoinHandle<()>>>>,
+    /// Least time between saving a running timer's ticks; zero saves every tick.
+    /// Other timer changes are always saved.
+    pub timer_persist_interval: Duration,
+    /// When each user's timer was last saved
+    pub timer_saved_at: Arc<Mutex<HashMap<String, std::time::Instant>>>,
+    /// Looks up user configurations and counts completed work sessions toward the day
+    pub daily_reset_service: DailyResetService,
+    /// When the server started, for the uptime reported by the health check
+    pub started_at: std::time::Instant,
+    /// Whether the scheduled task runner is running; the server isn't ready without it
+    pub scheduler_running: AtomicBool,
+}
+
+impl WebSocketManager {
+    pub fn new(timer_states: SharedState, database: Arc<DatabaseManager>) -> Self {
+        Self {
+            connections: Arc::new(Mutex::new(HashMap::new())),
+            senders: Arc::new(Mutex::new(HashMap::new())),
+            timer_states,
+            daily_reset_service: DailyResetService::new(Arc::new(SystemTimeProvider::new()), database.clone()),
+            database,
+            timer_mode: TimerMode::default(),
+            strict_messages: false,
+            cycle_complete_notifications: false,
+            persist_subscriptions: false,
+            fallback_notify_url: None,
+            heartbeat_timeout_secs: None,
+            connections_swept: AtomicU64::new(0),
+            webhooks_sent: AtomicU64::new(0),
+            webhooks_failed: AtomicU64::new(0),
+            settings_update_interval: Duration::ZERO,
+            settings_throttles: Arc::new(Mutex::new(HashMap::new())),
+            count_change_limit: None,
+            count_changes: Arc::new(Mutex::new(HashMap::new())),
+            redeem_attempt_limit: None,
+            redeem_attempts: Arc::new(Mutex::new(HashMap::new())),
+            public_base_url: None,
+            max_added_seconds: None,
+            max_consecutive_work_sessions: None,
+            tickers: Arc::new(Mutex::new(HashMap::new())),
+            timer_persist_interval: Duration::ZERO,
+            timer_saved_at: Arc::new(Mutex::new(HashMap::new())),
+            started_at: std::time::Instant::now(),
+            scheduler_running: AtomicBool::new(false),
+        }
+    }
+
+    pub fn with_timer_mode(mut self, timer_mode: TimerMode) -> Self {
+        self.timer_mode = timer_mode;
+        self
+    }
+
+    pub fn with_strict_messages(mut self, strict: bool) -> Self {
+        self.strict_messages = strict;
+        self
+    }
+
+    pub fn with_cycle_complete_notifications(mut self, enabled: bool) -> Self {
+        self.cycle_complete_notifications = enabled;
+        self
+    }
+
+    pub fn with_persist_subscriptions(mut self, enabled: bool) -> Self {
+        self.persist_subscriptions = enabled;
+        self
+    }
+
+    pub fn with_fallback_notify_url(mut self, url: Option<String>) -> Self {
+        self.fallback_notify_url = url;
+        self
+    }
+
+    pub fn with_heartbeat_timeout(mut self, timeout_secs: Option<u64>) -> Self {
+        self.heartbeat_timeout_secs = timeout_secs;
+        self
+    }
+
+    pub fn with_timer_persist_interval(mut self, interval: Duration) -> Self {
+        self.timer_persist_interval = interval;
+        self
+    }
+
+    pub fn with_settings_update_interval(mut self, interval: Duration) -> Self {
+        self.settings_update_interval = interval;
+        self
+    }
+
+    pub fn with_count_change_limit(mut self, limit: Option<CountChangeLimit>) -> Self {
+        self.count_change_limit = limit;
+        self
+    }
+
+    pub fn with_redeem_attempt_limit(mut self, limit: Option<CountChangeLimit>) -> Self {
+        self.redeem_attempt_limit = limit;
+        self
+    }
+
+    pub fn with_public_base_url(mut self, public_base_url: Option<String>) -> Self {
+        self.public_base_url = public_base_url;
+        self
+    }
+
+    pub fn with_max_added_seconds(mut self, max_added_seconds: Option<u32>) -> Self {
+        self.max_added_seconds = max_added_seconds;
+        self
+    }
+
+    pub fn with_max_consecutive_work_sessions(mut self, cap: Option<u32>) -> Self {
+        self.max_consecutive_work_sessions = cap;
+        self
+    }
+
+    /// `user_id`'s open connections, oldest first
+    pub async fn devices(&self, user_id: &str) -> Vec<DeviceInfo> {
+        let connections = self.connections.lock().await;
+        user_devices(&connections, user_id)
+    }
+
+    pub async fn add_connection(&self, id: String, user_id: &str, user_agent: Option<String>, sender: WsSender) {
+        let mut connections = self.connections.lock().await;
+        let mut senders = self.senders.lock().await;
+        let now = now_unix();
+
+        connections.insert(
+            id.clone(),
+            Connection {
+                id: id.clone(),
+                user_id: user_id.to_string(),
+                user_agent,
+                connected_at: now,
+                last_seen: now,
+                device_id: None,
+                subscriptions: None,
+            },
+        );
+
+        senders.insert(id.clone(), sender);
+
+        // Broadcast connection status to the user's devices
+        let device_list = user_devices(&connections, user_id);
+        drop(connections);
+        drop(senders);
+        self.broadcast_message(user_id, WsMessage::ConnectionStatus {
+            connection_id: id,
+            connected: true,
+            device_count: device_list.len(),
+            device_list,
+        })
+        .await;
+    }
+
+    pub async fn remove_connection(&self, id: String) {
+        let mut connections = self.connections.lock().await;
+        let mut senders = self.senders.lock().await;
+        let removed = connections.remove(&id);
+        senders.remove(&id);
+        let Some(removed) = removed else {
+            return;
+        };
+        let device_list = user_devices(&connections, &removed.user_id);
+        drop(connections);
+        drop(senders);
+
+        // Broadcast disconnection status to the user's remaining devices
+        self.broadcast_message(&removed.user_id, WsMessage::ConnectionStatus {
+            connection_id: id,
+            connected: false,
+            device_count: device_list.len(),
+            device_list,
+        })
+        .await;
+    }
+
+    /// Where `user_id`'s completion notifications go: their own webhooks in their
+    /// chosen format, else the server webhook, else the fallback, both raw. No
+    /// URLs if they turned notifications off or nothing is configured.
+    pub async fn completion_notify_target(&self, user_id: &str) -> NotifyTarget {
+        let config = self.user_configuration(user_id).await;
+        if !config.should_send_notifications() {
+            return NotifyTarget::default();
+        }
+        if !config.webhook_urls.is_empty() {
+            return NotifyTarget {
+                urls: config.webhook_urls,
+                format: config.webhook_format,
+                template: config.webhook_template,
+            };
+        }
+        NotifyTarget {
+            urls: env::var("ROMA_TIMER_WEBHOOK_URL")
+                .ok()
+                .or_else(|| self.fallback_notify_url.clone())
+                .into_iter()
+                .collect(),
+            ..NotifyTarget::default()
+        }
+    }
+
+    /// Whether `user_id` wants to start each session themselves once the
+    /// previous one completes
+    pub async fn waits_for_interaction(&self, user_id: &str) -> bool {
+        self.user_configuration(user_id).await.wait_for_interaction
+    }
+
+    /// `user_id`'s configuration, or the defaults if they have none or it
+    /// can't be loaded
+    pub async fn user_configuration(&self, user_id: &str) -> UserConfiguration {
+        match self.daily_reset_service.find_user_configuration(user_id).await {
+            Ok(config) => config,
+            Err(e) => {
+                tracing::warn!("Failed to load configuration for {user_id}: {e}");
+                None
+            }
+        }
+        .unwrap_or_else(|| UserConfiguration::with_id(user_id.to_string()))
+    }
+
+    /// Count a manual session-count change by `user_id` against their limit.
+    /// Returns how long until another change is allowed if the limit is reached.
+    pub async fn acquire_count_change(&self, user_id: &str) -> Result<(), Duration> {
+        let Some(limit) = self.count_change_limit else {
+            return Ok(());
+        };
+        let mut count_changes = self.count_changes.lock().await;
+        limit.acquire(count_changes.entry(user_id.to_string()).or_default())
+    }
+
+    /// Count a pairing-code redemption attempt from `client` against its limit.
+    /// Returns how long until another attempt is allowed if the limit is reached.
+    pub async fn acquire_redeem_attempt(&self, client: &str) -> Result<(), Duration> {
+        let Some(limit) = self.redeem_attempt_limit else {
+            return Ok(());
+        };
+        let mut redeem_attempts = self.redeem_attempts.lock().await;
+        limit.acquire(redeem_attempts.entry(client.to_string()).or_default())
+    }
+
+    /// Record that a message was just received on a connection
+    pub async fn touch_connection(&self, id: &str) {
+        if let Some(connection) = self.connections.lock().await.get_mut(id) {
+            connection.last_seen = now_unix();
+        }
+    }
+
+    /// Tie a connection to the client's stable device id, restoring the
+    /// subscription last saved for that device when persistence is enabled
+    pub async fn attach_device(&self, connection_id: &str, user_id: &str, device_id: &str) {
+        let saved = if self.persist_subscriptions {
+            self.database
+                .get_device_subscription(user_id, device_id)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!(target: "roma::ws", "Failed to load subscription for device {device_id}: {e}");
+                    None
+                })
+        } else {
+            None
+        };
+
+        if let Some(connection) = self.connections.lock().await.get_mut(connection_id) {
+            connection.device_id = Some(device_id.to_string());
+            if let Some(message_types) = saved.filter(|types| !types.is_empty()) {
+                tracing::debug!(target: "roma::ws", "Restored subscription {message_types:?} for device {device_id}");
+                connection.subscriptions = Some(message_types.into_iter().collect());
+            }
+        }
+    }
+
+    /// Limit the broadcasts a connection receives to `message_types`; an empty
+    /// list receives everything again. Saved for the connection's device, if any.
+    pub async fn subscribe(
+        &self,
+        connection_id: &str,
+        user_id: &str,
+        message_types: Vec<String>,
+    ) -> Result<(), String> {
+        if let Some(unknown) = message_types
+            .iter()
+            .find(|message_type| !SUBSCRIBABLE_MESSAGE_TYPES.contains(&message_type.as_str()))
+        {
+            return Err(format!("Cannot subscribe to unknown message type '{unknown}'"));
+        }
+
+        let device_id = {
+            let mut connections = self.connections.lock().await;
+            let Some(connection) = connections.get_mut(connection_id) else {
+                return Ok(());
+            };
+            connection.subscriptions =
+                (!message_types.is_empty()).then(|| message_types.iter().cloned().collect());
+            connection.device_id.clone()
+        };
+
+        if let Some(device_id) = device_id.filter(|_| self.persist_subscriptions) {
+            if let Err(e) = self
+                .database
+                .save_device_subscription(user_id, &device_id, &message_types)
+                .await
+            {
+                tracing::warn!(target: "roma::ws", "Failed to save subscription for device {device_id}: {e}");
+            }
+        }
+        Ok(())
+    }
+
+    /// Send a WebSocket ping to every connection. Clients answer with a pong,
+    /// which counts as activity, so only unresponsive ones go silent.
+    pub async fn ping_connections(&self) {
+        for sender in self.senders.lock().await.values() {
+            let _ = sender.send(Message::Ping(Vec::new()));
+        }
+    }
+
+    /// Remove connections whose channel has closed, or that have been silent for
+    /// longer than the heartbeat timeout, broadcasting the new device count for
+    /// each. Returns how many were removed.
+    pub async fn sweep_dead_connections(&self, now: u64) -> usize {
+        let mut dead: Vec<(bool, String)> = {
+            let connections = self.connections.lock().await;
+            let senders = self.senders.lock().await;
+            connections
+                .values()
+                .filter_map(|connection| {
+                    let closed = senders
+                        .get(&connection.id)
+                        .is_none_or(|sender| sender.is_closed());
+                    let silent = self
+                        .heartbeat_timeout_secs
+                        .is_some_and(|timeout| now.saturating_sub(connection.last_seen) > timeout);
+                    (closed || silent).then(|| (closed, connection.id.clone()))
+                })
+                .collect()
+        };
+        // Closed ones first: announcing a silent one's removal would otherwise
+        // prune them without the remaining devices hearing the final count
+        dead.sort_by_key(|(closed, _)| !closed);
+
+        let swept = dead.len();
+        for (_, connection_id) in dead {
+            self.remove_connection(connection_id).await;
+        }
+
+        let total = self.connections_swept.fetch_add(swept as u64, Ordering::Relaxed) + swept as u64;
+        if swept > 0 {
+            tracing::info!(target: "roma::ws", swept, total, "Swept dead WebSocket connections");
+        } else {
+            tracing::trace!(target: "roma::ws", total, "No dead WebSocket connections to sweep");
+        }
+        swept
+    }
+
+    /// Prepare for the server going away: stop the countdowns, tell every
+    /// connection with a `ServerShutdown`, save every timer as of now, then
+    /// close the connections and wait up to `drain_timeout` for them to go.
+    /// Returns how many timers were saved.
+    pub async fn shutdown(&self, drain_timeout: Duration) -> usize {
+        for (_, ticker) in self.tickers.lock().await.drain() {
+            ticker.abort();
+        }
+
+        if let Ok(shutdown_text) = serde_json::to_string(&WsMessage::ServerShutdown) {
+            for sender in self.senders.lock().await.values() {
+                let _ = sender.send(Message::Text(shutdown_text.clone()));
+            }
+        }
+
+        let now = now_unix();
+        let timers: Vec<(String, TimerState)> = self
+            .timer_states
+            .lock()
+            .await
+            .iter()
+            .map(|(user_id, timer_state)| (user_id.clone(), timer_state_at(timer_state.clone(), now)))
+            .collect();
+        let mut saved = 0;
+        for (user_id, timer_state) in &timers {
+            match self.database.save_timer_state(user_id, timer_state).await {
+                Ok(()) => saved += 1,
+                Err(e) => tracing::error!(target: "roma::ws", "Failed to save timer state for {user_id} on shutdown: {e}"),
+            }
+        }
+
+        for sender in self.senders.lock().await.values() {
+            let _ = sender.send(Message::Close(Some(axum::extract::ws::CloseFrame {
+                code: axum::extract::ws::close_code::AWAY,
+                reason: "server shutdown".into(),
+            })));
+        }
+
+        let drained = tokio::time::timeout(drain_timeout, async {
+            while !self.senders.lock().await.is_empty() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await;
+        if drained.is_err() {
+            let remaining = self.senders.lock().await.len();
+            tracing::warn!(target: "roma::ws", remaining, "WebSocket connections still open at shutdown");
+        }
+        tracing::info!(target: "roma::ws", saved, "Saved timer states for shutdown");
+
+        saved
+    }
+
+    pub async fn update_timer_state(&self, user_id: &str, state: TimerState) {
+        // Update the user's timer
+        self.timer_states.lock().await.insert(user_id, state.clone());
+
+        // Save to database
+        self.save_timer_state(user_id, &state).await;
+
+        // Broadcast to the user's connected clients
+        self.broadcast_message(user_id, WsMessage::TimerStateUpdate(TimerStateBroadcast::new(state, self.max_consecutive_work_sessions)))
+            .await;
+    }
+
+    /// Like `update_timer_state` for a countdown tick, but only saved if
+    /// `timer_persist_interval` has passed since the user's timer was last
+    /// saved. The saved end time lets a restart recover the remaining time.
+    pub async fn tick_timer_state(&self, user_id: &str, state: TimerState) {
+        self.timer_states.lock().await.insert(user_id, state.clone());
+
+        let save_due = self
+            .timer_saved_at
+            .lock()
+            .await
+            .get(user_id)
+            .is_none_or(|saved_at| saved_at.elapsed() >= self.timer_persist_interval);
+        if save_due {
+            self.save_timer_state(user_id, &state).await;
+        }
+
+        self.broadcast_message(user_id, WsMessage::TimerStateUpdate(TimerStateBroadcast::new(state, self.max_consecutive_work_sessions)))
+            .await;
+    }
+
+    async fn save_timer_state(&self, user_id: &str, state: &TimerState) {
+        if let Err(e) = self.database.save_timer_state(user_id, state).await {
+            tracing::error!(target: "roma::ws", "Failed to save timer state to database: {e}");
+            return;
+        }
+        self.timer_saved_at
+            .lock()
+            .await
+            .insert(user_id.to_string(), std::time::Instant::now());
+    }
+
+    /// Send a message to a single connection, such as a reply to something it
+    /// sent, regardless of its subscriptions
+    pub async fn send_to(&self, connection_id: &str, message: &WsMessage) {
+        let message_text = match serde_json::to_string(message) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::error!(target: "roma::ws", "Failed to serialize message: {e}");
+                return;
+            }
+        };
+        if let Some(sender) = self.senders.lock().await.get(connection_id) {
+            let _ = sender.send(Message::Text(message_text));
+        }
+    }
+
+    /// Send a message to every connection belonging to `user_id` that is
+    /// subscribed to its type
+    pub async fn broadcast_message(&self, user_id: &str, message: WsMessage) {
+        let connections = self.connections.lock().await;
+        let senders = self.senders.lock().await;
+        let message_type = message.message_type();
+        let message_text = match serde_json::to_string(&message) {
+            Ok(text) => text,
+            Err(e) => {
+                tracing::error!(target: "roma::ws", "Failed to serialize message: {e}");
+                return;
+            }
+        };
+
+        let mut disconnected_senders = Vec::new();
+        let mut recipients = 0;
+
+        for (connection_id, sender) in senders.iter() {
+            let Some(connection) = connections.get(connection_id) else {
+                continue;
+            };
+            let subscribed = connection
+                .subscriptions
+                .as_ref()
+                .is_none_or(|message_types| message_types.contains(message_type));
+            if connection.user_id != user_id || !subscribed {
+                continue;
+            }
+            recipients += 1;
+            if sender.send(Message::Text(message_text.clone())).is_err() {
+                // Connection is broken, mark for removal
+                disconnected_senders.push(connection_id.clone());
+            }
+        }
+
+        tracing::trace!(target: "roma::ws", recipients, "Broadcast message");
+
+        drop(senders);
+        drop(connections);
+
+        // Remove disconnected senders from both connections and senders maps
+        if !disconnected_senders.is_empty() {
+            tracing::debug!(
+                target: "roma::ws",
+                "Pruning {} disconnected sender(s)",
+                disconnected_senders.len()
+            );
+            let mut connections = self.connections.lock().await;
+            let mut senders = self.senders.lock().await;
+            for connection_id in disconnected_senders {
+                connections.remove(&connection_id);
+                senders.remove(&connection_id);
+            }
+        }
+    }
+}
+
+fn user_devices(connections: &HashMap<String, Connection>, user_id: &str) -> Vec<DeviceInfo> {
+    let mut devices: Vec<DeviceInfo> = connections
+        .values()
+        .filter(|connection| connection.user_id == user_id)
+        .map(DeviceInfo::from)
+        .collect();
+    devices.sort_by(|a, b| (a.connected_at, &a.connection_id).cmp(&(b.connected_at, &b.connection_id)));
+    devices
+}
+
+pub type SharedWsManager = Arc<WebSocketManager>;
+
+/// At most `max_changes` manual session-count changes per user in any `window`
+#[derive(Debug, Clone, Copy)]
+pub struct CountChangeLimit {
+    pub max_changes: u32,
+    pub window: Duration,
+}
+
+impl CountChangeLimit {
+    /// Record a change in `recent` if the limit allows it, otherwise return
+    /// how long until the oldest change leaves the window
+    fn acquire(&self, recent: &mut VecDeque<std::time::Instant>) -> Result<(), Duration> {
+        let now = std::time::Instant::now();
+        while recent.front().is_some_and(|at| now.duration_since(*at) >= self.window) {
+            recent.pop_front();
+        }
+        if recent.len() >= self.max_changes as usize {
+            let oldest = recent.front().copied().unwrap_or(now);
+            return Err(self.window.saturating_sub(now.duration_since(oldest)));
+        }
+        recent.push_back(now);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+
+    #[tokio::test]
+    async fn test_shutdown_notifies_clients_and_saves_timers() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        // Running since 60 seconds ago with 10 minutes left, not saved since
+        let now = now_unix();
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.is_running = true;
+            timer_state.remaining_seconds = 600;
+            timer_state.last_updated = now - 60;
+            timer_state.session_ends_at = Some(now - 60 + 600);
+        }
+        let ticker = tokio::spawn(std::future::pending::<()>());
+        ws_manager.tickers.lock().await.insert("alice".to_string(), ticker);
+
+        // A client that leaves when the server closes the connection
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("conn-1".to_string(), "alice", None, sender).await;
+        let client_manager = ws_manager.clone();
+        let client = tokio::spawn(async move {
+            let mut received = Vec::new();
+            while let Some(message) = receiver.recv().await {
+                match message {
+                    Message::Text(text) => received.push(serde_json::from_str::<WsMessage>(&text).unwrap().message_type()),
+                    Message::Close(frame) => {
+                        assert_eq!(frame.unwrap().code, axum::extract::ws::close_code::AWAY);
+                        client_manager.remove_connection("conn-1".to_string()).await;
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+            received
+        });
+
+        let saved = tokio::time::timeout(Duration::from_secs(1), ws_manager.shutdown(Duration::from_secs(1)))
+            .await
+            .expect("shutdown did not drain the closed connection");
+        assert_eq!(saved, 1);
+        assert!(ws_manager.tickers.lock().await.is_empty());
+        assert!(ws_manager.senders.lock().await.is_empty());
+        assert!(client.await.unwrap().contains(&"ServerShutdown"));
+
+        let stored = ws_manager.database.get_timer_state("alice").await.unwrap().unwrap();
+        assert!(stored.is_running);
+        assert!((538..=540).contains(&stored.remaining_seconds), "{}", stored.remaining_seconds);
+    }
+
+    #[tokio::test]
+    async fn test_timer_running_at_shutdown_resumes_after_restart() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        // alice has 10 minutes left; bob's session runs out while the server is down
+        let now = now_unix();
+        {
+            let mut states = state.lock().await;
+            let alice = states.user("alice");
+            alice.start(now);
+            alice.remaining_seconds = 600;
+            alice.session_ends_at = Some(now + 600);
+            let bob = states.user("bob");
+            bob.start(now);
+            bob.remaining_seconds = 2;
+            bob.session_ends_at = Some(now + 2);
+        }
+        assert_eq!(ws_manager.shutdown(Duration::from_secs(1)).await, 2);
+
+        // Restart: load the saved timers the way main does
+        let (restarted_state, restarted_manager) = test_app_state(&temp_dir).await;
+        for (user_id, timer_state) in restarted_manager.database.get_all_timer_states().await.unwrap() {
+            restarted_state.lock().await.insert(&user_id, timer_state);
+        }
+        let alice = restarted_state.lock().await.get("alice");
+        assert!(alice.is_running);
+        assert_eq!(alice.session_ends_at, Some(now + 600));
+
+        tokio::time::sleep(Duration::from_secs(3)).await;
+        assert_eq!(
+            crate::services::timer_control_service::resume_running_timers(&restarted_state, &restarted_manager).await,
+            2
+        );
+        tokio::time::sleep(Duration::from_millis(100)).await;
+
+        // alice keeps counting down from the saved end time, bob's session completed
+        let alice = restarted_state.lock().await.get("alice");
+        assert!(alice.is_running);
+        assert!((596..=597).contains(&alice.remaining_seconds), "{}", alice.remaining_seconds);
+        let bob = restarted_state.lock().await.get("bob");
+        assert!(!bob.is_running);
+        assert_eq!(bob.session_type, "short_break");
+
+        // Starting the completed session's successor works as usual
+        let started = crate::services::timer_control_service::control_user_timer(
+            &restarted_state,
+            &restarted_manager,
+            "bob",
+            TimerRequest { action: "start".to_string(), label: None },
+        )
+        .await
+        .unwrap();
+        assert!(started.is_running);
+        assert!(restarted_manager.tickers.lock().await.contains_key("bob"));
+    }
+
+    #[tokio::test]
+    async fn test_connection_status_lists_devices() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager
+            .add_connection("phone".to_string(), "alice", Some("iPhone Safari".to_string()), sender)
+            .await;
+        let long_agent = "MacBook ".repeat(40);
+        let (laptop_sender, _laptop_receiver) = mpsc::unbounded_channel();
+        ws_manager
+            .add_connection("laptop".to_string(), "alice", Some(long_agent.clone()), laptop_sender)
+            .await;
+        let (bob_sender, _bob_receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("bob-phone".to_string(), "bob", None, bob_sender).await;
+        ws_manager.remove_connection("laptop".to_string()).await;
+
+        let statuses: Vec<(String, bool, Vec<DeviceInfo>)> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                Message::Text(text) => match serde_json::from_str::<WsMessage>(&text) {
+                    Ok(WsMessage::ConnectionStatus {
+                        connection_id,
+                        connected,
+                        device_count,
+                        device_list,
+                    }) => {
+                        assert_eq!(device_count, device_list.len());
+                        Some((connection_id, connected, device_list))
+                    }
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect();
+
+        // Joined, then the laptop joined, then it left; bob's device never appears
+        assert_eq!(statuses.len(), 3);
+        let ids = |devices: &[DeviceInfo]| devices.iter().map(|d| d.connection_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&statuses[0].2), ["phone"]);
+        assert_eq!(statuses[0].2[0].user_agent.as_deref(), Some("iPhone Safari"));
+        assert_eq!((statuses[1].0.as_str(), statuses[1].1), ("laptop", true));
+        let mut joined = ids(&statuses[1].2);
+        joined.sort();
+        assert_eq!(joined, ["laptop", "phone"]);
+        let laptop = statuses[1].2.iter().find(|d| d.connection_id == "laptop").unwrap();
+        assert_eq!(laptop.user_agent.as_ref().unwrap().chars().count(), MAX_DEVICE_USER_AGENT_CHARS);
+        assert_eq!((statuses[2].0.as_str(), statuses[2].1), ("laptop", false));
+        assert_eq!(ids(&statuses[2].2), ["phone"]);
+    }
+}
@@ -3,4 +3,6 @@
 //! Provides WebSocket message handling and schemas for daily reset functionality.
 
 pub mod handlers;
-pub mod messages;
\ No newline at end of file
+pub mod manager;
+pub mod messages;
+pub mod server;
@@ -0,0 +1,990 @@
+//! WebSocket Server
+//!
+//! Upgrade handler, token authentication and the per-connection message loop.
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+use tokio::sync::mpsc;
+use tracing::Instrument;
+
+use crate::api::daily_reset::{daily_reset_status_message, reset_daily_sessions_for};
+use crate::config::TimerMode;
+use crate::models::session_reset_event::SessionResetTriggerSource;
+use crate::models::timer_state::{SharedState, TimerState, TimerStateBroadcast};
+use crate::services::auth_service::{AuthClaims, verify_auth_token};
+use crate::services::settings_service::{SettingsOutcome, submit_settings_update};
+use crate::services::time_provider::{now_unix, now_unix_millis};
+use crate::services::timer_control_service::{
+    complete_session, control_user_timer, finish_completed_session, start_timer, timer_state_at, CompletedSession,
+};
+use crate::websocket::manager::{ClientStateReport, SharedWsManager, WebSocketManager, WsMessage};
+
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::{header, StatusCode, Uri},
+    response::Response,
+};
+use axum_extra::typed_header::TypedHeader;
+use futures_util::{SinkExt, StreamExt};
+use headers::{authorization::Bearer, Authorization};
+use uuid::Uuid;
+
+/// Remember which message types each device subscribed to and restore them
+/// when it reconnects with the same `device_id`. Enabled by default.
+pub fn get_persist_subscriptions() -> bool {
+    env::var("ROMA_TIMER_PERSIST_SUBSCRIPTIONS")
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(true)
+}
+
+/// Reply to unknown or unhandled WebSocket messages with an error instead of
+/// ignoring them. Off by default so older clients keep working.
+pub fn get_ws_strict_messages() -> bool {
+    env::var("ROMA_TIMER_WS_STRICT_MESSAGES")
+        .map(|value| matches!(value.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Close code sent when a WebSocket is closed because of a token problem
+const WS_CLOSE_UNAUTHORIZED: u16 = 4401;
+
+/// Close code asking the client to reconnect (and re-authenticate) right away
+pub const WS_CLOSE_RECONNECT: u16 = 4000;
+
+/// Why a WebSocket connection could not be authenticated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum WsAuthError {
+    MissingToken,
+    InvalidToken,
+    TokenExpired,
+}
+
+impl WsAuthError {
+    /// Machine-readable reason, used as the close reason and in the rejection body
+    fn code(self) -> &'static str {
+        match self {
+            WsAuthError::MissingToken => "missing_token",
+            WsAuthError::InvalidToken => "invalid_token",
+            WsAuthError::TokenExpired => "token_expired",
+        }
+    }
+
+    fn message(self) -> &'static str {
+        match self {
+            WsAuthError::MissingToken => "Authorization required for WebSocket connection",
+            WsAuthError::InvalidToken => "Invalid token",
+            WsAuthError::TokenExpired => "Token expired",
+        }
+    }
+}
+
+fn authenticate_ws_token(token: Option<&str>) -> Result<AuthClaims, WsAuthError> {
+    let token = token.ok_or(WsAuthError::MissingToken)?;
+    verify_auth_token(token).map_err(|e| {
+        if e.to_string() == "Token expired" {
+            WsAuthError::TokenExpired
+        } else {
+            WsAuthError::InvalidToken
+        }
+    })
+}
+
+/// Reject a WebSocket handshake with 401, a Bearer challenge and a JSON reason
+fn reject_ws_upgrade(error: WsAuthError) -> Response {
+    let body = serde_json::json!({
+        "code": error.code(),
+        "message": error.message(),
+    });
+
+    Response::builder()
+        .status(StatusCode::UNAUTHORIZED)
+        .header(
+            header::WWW_AUTHENTICATE,
+            format!("Bearer error=\"{}\"", error.code()),
+        )
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(axum::body::Body::from(body.to_string()))
+        .unwrap()
+}
+
+pub async fn websocket_handler(
+    ws: WebSocketUpgrade,
+    State((state, ws_manager)): State<(SharedState, SharedWsManager)>,
+    auth_headers: Option<TypedHeader<Authorization<Bearer>>>,
+    user_agent: Option<TypedHeader<headers::UserAgent>>,
+    uri: Uri,
+) -> Response {
+    let params: HashMap<String, String> = uri
+        .query()
+        .map(|query| {
+            url::form_urlencoded::parse(query.as_bytes())
+                .into_owned()
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Try to get token from Authorization header first, falling back to the query
+    // parameter for the JavaScript WebSocket API
+    let token = match auth_headers {
+        Some(auth_headers) => Some((*auth_headers).token().to_string()),
+        None => params.get("token").cloned(),
+    };
+
+    match authenticate_ws_token(token.as_deref()) {
+        Ok(claims) => {
+            let user_id = claims.sub;
+            let device_id = params.get("device_id").cloned().filter(|id| !id.is_empty());
+            ws.on_upgrade(move |socket| {
+                handle_websocket(
+                    socket,
+                    state,
+                    ws_manager,
+                    user_agent.map(|ua| ua.to_string()),
+                    user_id,
+                    device_id,
+                )
+            })
+        }
+        // Browsers can't read a rejected handshake, so clients may ask for the
+        // upgrade to complete and be closed with a reason they can inspect
+        Err(error) if params.get("auth_errors").map(String::as_str) == Some("close") => {
+            tracing::info!(target: "roma::ws", "Closing unauthenticated WebSocket: {}", error.code());
+            ws.on_upgrade(move |mut socket| async move {
+                let _ = socket
+                    .send(Message::Close(Some(axum::extract::ws::CloseFrame {
+                        code: WS_CLOSE_UNAUTHORIZED,
+                        reason: error.code().into(),
+                    })))
+                    .await;
+            })
+        }
+        Err(error) => {
+            tracing::info!(target: "roma::ws", "Rejected WebSocket upgrade: {}", error.code());
+            reject_ws_upgrade(error)
+        }
+    }
+}
+
+/// Messages sent directly to a newly connected client, in order
+async fn initial_messages(
+    state: &SharedState,
+    ws_manager: &WebSocketManager,
+    connection_id: &str,
+    user_id: &str,
+) -> Vec<WsMessage> {
+    let device_list = ws_manager.devices(user_id).await;
+    let device_count = device_list.len();
+    let server_time = now_unix_millis();
+    // The saved state may be up to a tick behind; send the true remaining time
+    let timer_state = timer_state_at(state.lock().await.get(user_id), now_unix());
+
+    vec![
+        WsMessage::Welcome {
+            user_id: user_id.to_string(),
+            connection_id: connection_id.to_string(),
+            server_time,
+            device_count,
+        },
+        WsMessage::TimerStateUpdate(TimerStateBroadcast::new(timer_state, ws_manager.max_consecutive_work_sessions)),
+        WsMessage::ConnectionStatus {
+            connection_id: connection_id.to_string(),
+            connected: true,
+            device_count,
+            device_list,
+        },
+    ]
+}
+
+/// Serve a WebSocket connection inside a span carrying its connection id, so
+/// every log line about the connection can be traced back to it
+async fn handle_websocket(
+    socket: WebSocket,
+    state: SharedState,
+    ws_manager: SharedWsManager,
+    user_agent: Option<String>,
+    user_id: String,
+    device_id: Option<String>,
+) {
+    let connection_id = Uuid::new_v4().to_string();
+    let span = tracing::info_span!(target: "roma::ws", "ws_connection", %connection_id, %user_id);
+
+    serve_websocket(socket, state, ws_manager, user_agent, user_id, device_id, connection_id)
+        .instrument(span)
+        .await
+}
+
+async fn serve_websocket(
+    socket: WebSocket,
+    state: SharedState,
+    ws_manager: SharedWsManager,
+    user_agent: Option<String>,
+    user_id: String,
+    device_id: Option<String>,
+    connection_id: String,
+) {
+    tracing::info!(target: "roma::ws", "WebSocket connected: {connection_id} for user {user_id} (UA: {user_agent:?})");
+
+    // Create a channel for this connection
+    let (tx, mut rx) = mpsc::unbounded_channel::<Message>();
+
+    // Add connection to manager with the sender
+    ws_manager
+        .add_connection(connection_id.clone(), &user_id, user_agent.clone(), tx)
+        .await;
+    if let Some(device_id) = &device_id {
+        ws_manager
+            .attach_device(&connection_id, &user_id, device_id)
+            .await;
+    }
+    auto_start_on_first_connect(&state, &ws_manager, &user_id).await;
+
+    // Split the WebSocket into sender and receiver
+    let (mut ws_sender, mut ws_receiver) = socket.split();
+
+    // Send welcome, initial timer state and connection status
+    for initial_msg in initial_messages(&state, &ws_manager, &connection_id, &user_id).await {
+        if let Ok(msg_text) = serde_json::to_string(&initial_msg) {
+            let _ = ws_sender.send(Message::Text(msg_text)).await;
+        }
+    }
+
+    // Task to forward messages from the channel to the WebSocket
+    let connection_id_clone = connection_id.clone();
+    let forward_task = tokio::spawn(async move {
+        while let Some(msg) = rx.recv().await {
+            let closing = matches!(msg, Message::Close(_));
+            if ws_sender.send(msg).await.is_err() || closing {
+                break;
+            }
+        }
+        tracing::debug!(target: "roma::ws", "WebSocket forward task ended for: {connection_id_clone}");
+    }.in_current_span());
+
+    // Task to handle incoming messages from the WebSocket
+    let state_clone = state.clone();
+    let ws_manager_clone = ws_manager.clone();
+    let connection_id_clone2 = connection_id.clone();
+    let user_id_clone = user_id.clone();
+    let receive_task = tokio::spawn(async move {
+        while let Some(msg) = ws_receiver.next().await {
+            if let Ok(msg) = msg {
+                ws_manager_clone.touch_connection(&connection_id_clone2).await;
+                match msg {
+                    Message::Text(text) => {
+                        let reply = match serde_json::from_str::<WsMessage>(&text) {
+                            Ok(WsMessage::TimerControl(request)) => {
+                                control_user_timer(&state_clone, &ws_manager_clone, &user_id_clone, request)
+                                    .await
+                                    .err()
+                                    .map(|e| {
+                                        tracing::debug!(target: "roma::ws", "Rejected timer action from {connection_id_clone2}: {e}");
+                                        WsMessage::Error {
+                                            code: e.code().to_string(),
+                                            message: e.to_string(),
+                                        }
+                                    })
+                            }
+                            Ok(WsMessage::SettingsUpdate(request)) => {
+                                match submit_settings_update(&state_clone, &ws_manager_clone, &user_id_clone, request).await {
+                                    SettingsOutcome::Applied(_) => None,
+                                    SettingsOutcome::Rejected(reason) => Some(WsMessage::Error {
+                                        code: "invalid_settings".to_string(),
+                                        message: reason,
+                                    }),
+                                    SettingsOutcome::Deferred { retry_after } => Some(WsMessage::Error {
+                                        code: "settings_deferred".to_string(),
+                                        message: format!(
+                                            "Settings updates are limited; this one will be applied in {}ms",
+                                            retry_after.as_millis()
+                                        ),
+                                    }),
+                                }
+                            }
+                            Ok(WsMessage::ClientStateReport(report)) => {
+                                handle_client_state_report(&ws_manager_clone, &user_id_clone, &report)
+                                    .await
+                                    .err()
+                                    .map(|reason| {
+                                        tracing::debug!(target: "roma::ws", "Rejected state report from {connection_id_clone2}: {reason}");
+                                        WsMessage::Error {
+                                            code: "invalid_state_report".to_string(),
+                                            message: reason,
+                                        }
+                                    })
+                            }
+                            Ok(WsMessage::GetDailyResetStatus) => {
+                                Some(daily_reset_status_message(ws_manager_clone.database.clone(), &user_id_clone).await)
+                            }
+                            Ok(WsMessage::ResetDailySessions) => {
+                                reset_daily_sessions_for(
+                                    &ws_manager_clone,
+                                    &user_id_clone,
+                                    SessionResetTriggerSource::WebSocketMessage,
+                                )
+                                .await
+                                .err()
+                                .map(|e| WsMessage::Error {
+                                    code: e.error_code().to_string(),
+                                    message: e.to_string(),
+                                })
+                            }
+                            Ok(WsMessage::Subscribe { message_types }) => ws_manager_clone
+                                .subscribe(&connection_id_clone2, &user_id_clone, message_types)
+                                .await
+                                .err()
+                                .map(|reason| WsMessage::Error {
+                                    code: "invalid_subscription".to_string(),
+                                    message: reason,
+                                }),
+                            // Respond with pong directly to this client
+                            Ok(WsMessage::Ping) => Some(WsMessage::Pong),
+                            Ok(_) | Err(_) => unsupported_message_reply(ws_manager_clone.strict_messages, &text),
+                        };
+                        if let Some(reply) = reply {
+                            ws_manager_clone.send_to(&connection_id_clone2, &reply).await;
+                        }
+                    }
+                    Message::Close(_) => {
+                        break;
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }.in_current_span());
+
+    // Wait for any task to complete
+    tokio::select! {
+        _ = forward_task => {},
+        _ = receive_task => {},
+    }
+
+    // Remove connection when disconnected
+    let connection_id_clone = connection_id.clone();
+    ws_manager.remove_connection(connection_id).await;
+    tracing::info!(target: "roma::ws", "WebSocket disconnected: {connection_id_clone}");
+}
+
+/// Log a WebSocket message the server can't parse or doesn't handle, and in
+/// strict mode build the `unsupported_message` error to send back
+fn unsupported_message_reply(strict: bool, text: &str) -> Option<WsMessage> {
+    let message_type = serde_json::from_str::<serde_json::Value>(text)
+        .ok()
+        .and_then(|value| value.get("type").and_then(|t| t.as_str()).map(str::to_string))
+        .unwrap_or_else(|| "<none>".to_string());
+    tracing::debug!(target: "roma::ws", "Unsupported WebSocket message type: {message_type}");
+
+    strict.then(|| WsMessage::Error {
+        code: "unsupported_message".to_string(),
+        message: format!("Message type '{message_type}' is not supported"),
+    })
+}
+
+/// Whether a connection at `now` is the user's first since the daily reset on a
+/// new local day, given when the first-connect auto start last fired
+fn is_first_connect_of_day(
+    last_auto_start: Option<i64>,
+    last_daily_reset: Option<i64>,
+    tz: chrono_tz::Tz,
+    now: chrono::DateTime<chrono::Utc>,
+) -> bool {
+    let Some(last_auto_start) = last_auto_start else {
+        return true;
+    };
+    let Some(last_auto_start_at) = chrono::DateTime::from_timestamp(last_auto_start, 0) else {
+        return true;
+    };
+
+    let new_local_day = last_auto_start_at.with_timezone(&tz).date_naive() < now.with_timezone(&tz).date_naive();
+    let reset_since = !matches!(last_daily_reset, Some(reset) if reset <= last_auto_start);
+    new_local_day && reset_since
+}
+
+/// Start a work session when a user who opted in connects for the first time
+/// after their daily reset. Fires at most once per local day, and never
+/// interrupts a session that is already running. Returns true if it started one.
+async fn auto_start_on_first_connect(state: &SharedState, ws_manager: &SharedWsManager, user_id: &str) -> bool {
+    let service = crate::services::daily_reset_service::DailyResetService::new(
+        Arc::new(crate::services::time_provider::SystemTimeProvider::new()),
+        ws_manager.database.clone(),
+    );
+    let config = match service.find_user_configuration(user_id).await {
+        Ok(Some(config)) if config.auto_start_on_first_connect => config,
+        Ok(_) => return false,
+        Err(e) => {
+            tracing::warn!("Failed to load configuration for {user_id}: {e}");
+            return false;
+        }
+    };
+
+    let now = chrono::Utc::now();
+    let tz = config.timezone.parse().unwrap_or(chrono_tz::UTC);
+    let previous = match ws_manager.database.get_last_auto_start(user_id).await {
+        Ok(previous) => previous,
+        Err(e) => {
+            tracing::warn!("Failed to load last auto start for {user_id}: {e}");
+            return false;
+        }
+    };
+    if !is_first_connect_of_day(previous, config.last_daily_reset_utc, tz, now) {
+        return false;
+    }
+
+    // Claim today's auto start even if a session is running, so it can't fire later in the day
+    match ws_manager.database.claim_auto_start(user_id, previous, now.timestamp()).await {
+        Ok(true) => {}
+        Ok(false) => return false,
+        Err(e) => {
+            tracing::warn!("Failed to record auto start for {user_id}: {e}");
+            return false;
+        }
+    }
+
+    {
+        let mut states = state.lock().await;
+        let timer_state = states.user(user_id);
+        if timer_state.is_running {
+            return false;
+        }
+        if timer_state.session_type != "work" {
+            timer_state.reset_to("work");
+        }
+    }
+
+    let (_, started) = start_timer(state, ws_manager, user_id.to_string(), None).await;
+    if started {
+        tracing::info!("Auto-started work session on first connect of the day for {user_id}");
+    }
+    started
+}
+
+/// How far a client's reported remaining time may drift from the server's deadline
+const CLIENT_REPORT_TOLERANCE_SECS: u64 = 5;
+
+/// Validate a client-reported countdown against the deadline implied by the last
+/// accepted state and apply it. Returns the completed session when the report
+/// brings the timer to zero.
+fn apply_client_report(
+    timer_state: &mut TimerState,
+    report: &ClientStateReport,
+    now: u64,
+    max_consecutive_work_sessions: Option<u32>,
+    resume_work_duration: bool,
+) -> Result<Option<(String, u32)>, String> {
+    if !timer_state.is_running {
+        return Err("timer is not running".to_string());
+    }
+    if report.session_type != timer_state.session_type {
+        return Err(format!(
+            "reported session type '{}' does not match current '{}'",
+            report.session_type, timer_state.session_type
+        ));
+    }
+
+    let elapsed = now.saturating_sub(timer_state.last_updated);
+    let expected = (timer_state.remaining_seconds as u64).saturating_sub(elapsed);
+    if (report.remaining_seconds as u64).abs_diff(expected) > CLIENT_REPORT_TOLERANCE_SECS {
+        return Err(format!(
+            "reported {}s remaining, expected about {}s",
+            report.remaining_seconds, expected
+        ));
+    }
+
+    timer_state.remaining_seconds = report.remaining_seconds;
+    timer_state.session_ends_at = Some(now + report.remaining_seconds as u64);
+    timer_state.last_updated = now;
+
+    if timer_state.remaining_seconds > 0 {
+        return Ok(None);
+    }
+    Ok(Some(complete_session(timer_state, max_consecutive_work_sessions, resume_work_duration)))
+}
+
+/// Validate, persist and broadcast a client state report. A report that
+/// finishes the session is handled like a completion on the server ticker.
+async fn handle_client_state_report(
+    ws_manager: &SharedWsManager,
+    user_id: &str,
+    report: &ClientStateReport,
+) -> Result<(), String> {
+    if ws_manager.timer_mode != TimerMode::ClientTick {
+        return Err("state reports are only accepted in client tick mode".to_string());
+    }
+
+    // Only a report finishing the session needs the user's transition preference
+    let resume_work_duration =
+        report.remaining_seconds == 0 && ws_manager.user_configuration(user_id).await.resume_work_duration;
+    let now = now_unix();
+    let mut states = ws_manager.timer_states.lock().await;
+    let timer_state = states.user(user_id);
+    // Read before a completion moves the timer on to the next session
+    let planned_duration = timer_state.session_duration();
+    let added_seconds = timer_state.added_seconds;
+    let max_consecutive = ws_manager.max_consecutive_work_sessions;
+    let completed = apply_client_report(timer_state, report, now, max_consecutive, resume_work_duration)?;
+    let updated_state = timer_state.clone();
+    drop(states);
+
+    match completed {
+        Some((session_type, session_count)) => {
+            let completed = CompletedSession {
+                session_type,
+                session_count,
+                planned_duration,
+                added_seconds,
+                label: updated_state.label.clone(),
+                completed_at: now,
+            };
+            finish_completed_session(ws_manager, user_id, completed, updated_state, "client").await;
+        }
+        None => ws_manager.update_timer_state(user_id, updated_state).await,
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::*;
+    use std::time::Duration;
+    use tokio::net::TcpListener;
+    use crate::api::json::ApiJson;
+    use crate::api::timer_control::{TimerRequest, control_timer};
+    use crate::services::auth_service::generate_auth_token;
+    use crate::services::timer_control_service::{spawn_ticker, tick_timer};
+    use axum::Router;
+    use axum::response::Json;
+    use axum::routing::get;
+    use crate::models::timer_state::{PlanStep, SessionPlan};
+
+    #[tokio::test]
+    async fn test_client_tick_mode_validates_and_broadcasts_reports() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_timer_mode(TimerMode::ClientTick),
+        );
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        assert!(!spawn_ticker(state.clone(), ws_manager.clone(), "alice".to_string()).await);
+
+        let Json(started) = control_timer(
+            State((state.clone(), ws_manager.clone())),
+            current_user("alice"),
+            ApiJson(TimerRequest { action: "start".to_string(), label: None }),
+        )
+        .await
+        .unwrap();
+        assert!(started.is_running);
+        while receiver.try_recv().is_ok() {}
+
+        // A report far from the server's deadline is rejected
+        let bogus = ClientStateReport {
+            session_type: "work".to_string(),
+            remaining_seconds: 60,
+        };
+        assert!(handle_client_state_report(&ws_manager, "alice", &bogus).await.is_err());
+        assert!(receiver.try_recv().is_err());
+
+        let report = ClientStateReport {
+            session_type: "work".to_string(),
+            remaining_seconds: started.remaining_seconds - 1,
+        };
+        handle_client_state_report(&ws_manager, "alice", &report).await.unwrap();
+
+        assert_eq!(state.lock().await.user("alice").remaining_seconds, report.remaining_seconds);
+        let Message::Text(text) = receiver.try_recv().unwrap() else {
+            panic!("expected a text message");
+        };
+        match serde_json::from_str::<WsMessage>(&text).unwrap() {
+            WsMessage::TimerStateUpdate(broadcast) => {
+                assert_eq!(broadcast.state.remaining_seconds, report.remaining_seconds)
+            }
+            other => panic!("unexpected message: {other:?}"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_client_report_completing_session_is_recorded_and_announced() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = SharedWsManager::new(
+            WebSocketManager::new(state.clone(), ws_manager.database.clone())
+                .with_timer_mode(TimerMode::ClientTick),
+        );
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("client".to_string(), "alice", None, sender).await;
+        while receiver.try_recv().is_ok() {}
+
+        // A 50 minute planned work session, two minutes added, about to run out
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.plan = Some(SessionPlan {
+                steps: vec![PlanStep { session_type: "work".to_string(), duration: 50 * 60 }],
+                current: 0,
+            });
+            timer_state.added_seconds = 120;
+            timer_state.start(now_unix());
+            timer_state.remaining_seconds = 1;
+        }
+        let report = ClientStateReport {
+            session_type: "work".to_string(),
+            remaining_seconds: 0,
+        };
+        handle_client_state_report(&ws_manager, "alice", &report).await.unwrap();
+
+        let messages: Vec<WsMessage> = std::iter::from_fn(|| receiver.try_recv().ok())
+            .filter_map(|message| match message {
+                Message::Text(text) => serde_json::from_str(&text).ok(),
+                _ => None,
+            })
+            .collect();
+        assert!(matches!(
+            messages.first(),
+            Some(WsMessage::SessionCompleted { completed_type, .. }) if completed_type == "work"
+        ));
+
+        let now = chrono::Utc::now().timestamp();
+        let mut sessions = Vec::new();
+        for _ in 0..60 {
+            sessions = ws_manager.database.get_session_history("alice", now - 3600, now + 60, 10, 0).await.unwrap();
+            if !sessions.is_empty() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].planned_duration, 50 * 60);
+        assert_eq!(sessions[0].actual_duration, 50 * 60 + 120);
+    }
+
+    #[tokio::test]
+    async fn test_welcome_is_first_message_on_connect() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("conn-1".to_string(), "alice", None, sender).await;
+
+        let before = now_unix_millis();
+        let messages = initial_messages(&state, &ws_manager, "conn-1", "alice").await;
+        let after = now_unix_millis();
+
+        match &messages[0] {
+            WsMessage::Welcome {
+                user_id,
+                connection_id,
+                server_time,
+                device_count,
+            } => {
+                assert_eq!(user_id, "alice");
+                assert_eq!(connection_id, "conn-1");
+                assert!((before..=after).contains(server_time));
+                assert_eq!(*device_count, 1);
+            }
+            other => panic!("expected Welcome first, got {other:?}"),
+        }
+        assert!(matches!(messages[1], WsMessage::TimerStateUpdate(_)));
+
+        let json = serde_json::to_value(&messages[0]).unwrap();
+        assert_eq!(json["type"], "Welcome");
+        assert_eq!(json["data"]["user_id"], "alice");
+    }
+
+    #[tokio::test]
+    async fn test_reconnect_sees_remaining_time_elapsed_since_last_save() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        // Saved 90 seconds ago with 10 minutes left, no tick since
+        let now = now_unix();
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.is_running = true;
+            timer_state.remaining_seconds = 600;
+            timer_state.last_updated = now - 90;
+            timer_state.session_ends_at = Some(now - 90 + 600);
+        }
+
+        let (sender, _receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("conn-1".to_string(), "alice", None, sender).await;
+        let messages = initial_messages(&state, &ws_manager, "conn-1", "alice").await;
+        match &messages[1] {
+            WsMessage::TimerStateUpdate(broadcast) => {
+                assert!((508..=510).contains(&broadcast.state.remaining_seconds), "{}", broadcast.state.remaining_seconds);
+            }
+            other => panic!("expected TimerStateUpdate, got {other:?}"),
+        }
+
+        // Without an end time, last_updated is the reference
+        let mut timer_state = test_timer_state();
+        timer_state.remaining_seconds = 300;
+        timer_state.last_updated = 1_000;
+        assert_eq!(timer_state_at(timer_state.clone(), 1_120).remaining_seconds, 180);
+        assert_eq!(timer_state_at(timer_state.clone(), 2_000).remaining_seconds, 0);
+
+        // Paused timers don't move
+        timer_state.is_running = false;
+        assert_eq!(timer_state_at(timer_state, 1_120).remaining_seconds, 300);
+    }
+
+    /// Perform a raw WebSocket handshake against `path`, returning the response head
+    /// and any bytes received after it
+    async fn ws_handshake(addr: std::net::SocketAddr, path: &str) -> (String, Vec<u8>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+        let request = format!(
+            "GET {path} HTTP/1.1\r\nHost: localhost\r\nConnection: Upgrade\r\nUpgrade: websocket\r\n\
+             Sec-WebSocket-Version: 13\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\r\n"
+        );
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut received = Vec::new();
+        let mut buf = [0u8; 1024];
+        loop {
+            let read = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+                .await
+                .unwrap()
+                .unwrap();
+            received.extend_from_slice(&buf[..read]);
+            if let Some(end) = received.windows(4).position(|w| w == b"\r\n\r\n") {
+                let head = String::from_utf8_lossy(&received[..end]).to_string();
+                let mut rest = received[end + 4..].to_vec();
+                // Wait for the close frame header and code when upgrading
+                while head.starts_with("HTTP/1.1 101") && rest.len() < 4 && read > 0 {
+                    let read = tokio::time::timeout(Duration::from_secs(2), stream.read(&mut buf))
+                        .await
+                        .unwrap()
+                        .unwrap();
+                    if read == 0 {
+                        break;
+                    }
+                    rest.extend_from_slice(&buf[..read]);
+                }
+                return (head, rest);
+            }
+            if read == 0 {
+                panic!("connection closed before handshake completed");
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_websocket_upgrade_auth() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let app_state = test_app_state(&temp_dir).await;
+        let app = Router::new()
+            .route("/ws", get(websocket_handler))
+            .with_state(app_state);
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(listener, app).await.unwrap();
+        });
+
+        let (head, _) = ws_handshake(addr, "/ws?token=not-a-token").await;
+        assert!(head.starts_with("HTTP/1.1 401"), "{head}");
+        assert!(head.to_lowercase().contains("www-authenticate: bearer error=\"invalid_token\""));
+
+        let (head, _) = ws_handshake(addr, "/ws").await;
+        assert!(head.starts_with("HTTP/1.1 401"), "{head}");
+
+        let token = generate_auth_token("alice").unwrap();
+        let encoded: String = url::form_urlencoded::byte_serialize(token.as_bytes()).collect();
+        let (head, _) = ws_handshake(addr, &format!("/ws?token={encoded}")).await;
+        assert!(head.starts_with("HTTP/1.1 101"), "{head}");
+
+        // Opting in to close-on-error upgrades, then closes with the reason code
+        let (head, frame) = ws_handshake(addr, "/ws?token=not-a-token&auth_errors=close").await;
+        assert!(head.starts_with("HTTP/1.1 101"), "{head}");
+        assert_eq!(frame[0], 0x88, "expected a close frame");
+        assert_eq!(u16::from_be_bytes([frame[2], frame[3]]), WS_CLOSE_UNAUTHORIZED);
+        assert!(String::from_utf8_lossy(&frame[4..]).starts_with("invalid_token"));
+    }
+
+    #[test]
+    fn test_ws_token_errors_are_distinguished() {
+        assert_eq!(authenticate_ws_token(None).unwrap_err(), WsAuthError::MissingToken);
+        assert_eq!(authenticate_ws_token(Some("garbage")).unwrap_err(), WsAuthError::InvalidToken);
+
+        let now = now_unix();
+        let expired = token_with_times(now - 7200, now - 3600);
+        assert_eq!(authenticate_ws_token(Some(&expired)).unwrap_err(), WsAuthError::TokenExpired);
+    }
+
+    #[tokio::test]
+    async fn test_auto_start_on_first_connect_fires_once_per_day() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, timezone, daily_reset_enabled, auto_start_on_first_connect, last_daily_reset_utc, last_auto_start_utc, created_at, updated_at)
+            VALUES ('alice', 'UTC', TRUE, TRUE, ?, ?, 0, 0)
+            "#,
+        )
+        .bind(now - 60)
+        .bind(now - 86_400)
+        .execute(pool)
+        .await
+        .unwrap();
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            timer_state.session_type = "short_break".to_string();
+            timer_state.remaining_seconds = 100;
+        }
+
+        assert!(auto_start_on_first_connect(&state, &ws_manager, "alice").await);
+        {
+            let mut states = state.lock().await;
+            let timer_state = states.user("alice");
+            assert!(timer_state.is_running);
+            assert_eq!(timer_state.session_type, "work");
+            assert_eq!(timer_state.remaining_seconds, timer_state.work_duration);
+            timer_state.is_running = false;
+        }
+
+        // A second device connecting the same day leaves the paused timer alone
+        assert!(!auto_start_on_first_connect(&state, &ws_manager, "alice").await);
+        assert!(!state.lock().await.user("alice").is_running);
+    }
+
+    #[test]
+    fn test_unsupported_ws_messages() {
+        // Server-to-client variants and unknown types are both unsupported from a client
+        for text in [r#"{"type":"Pong"}"#, r#"{"type":"TimeTravel","data":{}}"#, "not json"] {
+            let reply = unsupported_message_reply(true, text);
+            assert!(
+                matches!(reply, Some(WsMessage::Error { ref code, .. }) if code == "unsupported_message"),
+                "{text}"
+            );
+        }
+
+        let mut reply = Some(WsMessage::Ping);
+        let targets = capture_with_filter("roma::ws=debug", || {
+            reply = unsupported_message_reply(false, r#"{"type":"TimeTravel"}"#);
+        });
+        assert!(reply.is_none());
+        assert_eq!(targets, vec!["roma::ws".to_string()]);
+    }
+
+    #[tokio::test]
+    async fn test_device_subscription_restored_on_reconnect() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (_, ws_manager) = test_app_state(&temp_dir).await;
+        let ws_manager = Arc::new(
+            WebSocketManager::new(ws_manager.timer_states.clone(), ws_manager.database.clone())
+                .with_persist_subscriptions(true),
+        );
+
+        fn received_types(receiver: &mut mpsc::UnboundedReceiver<Message>) -> Vec<String> {
+            let mut types = Vec::new();
+            while let Ok(Message::Text(text)) = receiver.try_recv() {
+                let message: serde_json::Value = serde_json::from_str(&text).unwrap();
+                types.push(message["type"].as_str().unwrap().to_string());
+            }
+            types
+        }
+
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("first".to_string(), "alice", None, sender).await;
+        ws_manager.attach_device("first", "alice", "phone").await;
+        ws_manager
+            .subscribe("first", "alice", vec!["CycleComplete".to_string()])
+            .await
+            .unwrap();
+        assert!(ws_manager
+            .subscribe("first", "alice", vec!["Welcome".to_string()])
+            .await
+            .is_err());
+        ws_manager.remove_connection("first".to_string()).await;
+        received_types(&mut receiver);
+
+        // Same device reconnects and never re-subscribes
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("second".to_string(), "alice", None, sender).await;
+        ws_manager.attach_device("second", "alice", "phone").await;
+        received_types(&mut receiver);
+
+        let state = ws_manager.timer_states.lock().await.get("alice");
+        ws_manager.update_timer_state("alice", state).await;
+        ws_manager.broadcast_message("alice", WsMessage::CycleComplete { work_sessions: 4 }).await;
+        assert_eq!(received_types(&mut receiver), ["CycleComplete"]);
+
+        // A device without a saved subscription still gets everything
+        let (sender, mut receiver) = mpsc::unbounded_channel();
+        ws_manager.add_connection("third".to_string(), "alice", None, sender).await;
+        ws_manager.attach_device("third", "alice", "laptop").await;
+        received_types(&mut receiver);
+        ws_manager.broadcast_message("alice", WsMessage::CycleComplete { work_sessions: 4 }).await;
+        assert_eq!(received_types(&mut receiver), ["CycleComplete"]);
+        let state = ws_manager.timer_states.lock().await.get("alice");
+        ws_manager.update_timer_state("alice", state).await;
+        assert_eq!(received_types(&mut receiver), ["TimerStateUpdate"]);
+    }
+
+    #[tokio::test]
+    async fn test_awaiting_start_sent_only_when_waiting_for_interaction() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let (state, ws_manager) = test_app_state(&temp_dir).await;
+
+        let pool = ws_manager.database.pool.sqlite().unwrap();
+        sqlx::query(
+            r#"
+            INSERT INTO user_configurations (id, wait_for_interaction, created_at, updated_at)
+            VALUES ('alice', TRUE, 0, 0), ('bob', FALSE, 0, 0)
+            "#,
+        )
+        .execute(pool)
+        .await
+        .unwrap();
+
+        for (user_id, waits) in [("alice", true), ("bob", false)] {
+            let (sender, mut receiver) = mpsc::unbounded_channel();
+            ws_manager.add_connection(format!("{user_id}-client"), user_id, None, sender).await;
+            while receiver.try_recv().is_ok() {}
+
+            {
+                let mut states = state.lock().await;
+                let timer_state = states.user(user_id);
+                timer_state.remaining_seconds = 1;
+                timer_state.start(now_unix());
+            }
+            tick_timer(state.clone(), ws_manager.clone(), user_id.to_string()).await;
+
+            let next = state.lock().await.get(user_id);
+            assert!(!next.is_running);
+            assert_eq!(next.session_type, "short_break");
+            assert_eq!(next.remaining_seconds, next.short_break_duration);
+
+            let awaiting: Vec<(String, u32)> = std::iter::from_fn(|| receiver.try_recv().ok())
+                .filter_map(|message| match message {
+                    Message::Text(text) => match serde_json::from_str::<WsMessage>(&text) {
+                        Ok(WsMessage::AwaitingStart { session_type, session_count }) => {
+                            Some((session_type, session_count))
+                        }
+                        _ => None,
+                    },
+                    _ => None,
+                })
+                .collect();
+            if waits {
+                assert_eq!(awaiting, [("short_break".to_string(), 1)]);
+            } else {
+                assert!(awaiting.is_empty());
+            }
+        }
+    }
+}